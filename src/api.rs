@@ -93,6 +93,14 @@ impl Gerb {
         }
     }
 
+    /// Return the currently loaded designspace.
+    #[getter(designspace)]
+    fn designspace(self_: PyRef<Self>) -> types::Designspace {
+        types::Designspace {
+            __gerb: self_.into(),
+        }
+    }
+
     /// Process API request.
     fn __send_rcv(&self, request: String, py: Python<'_>) -> PyResult<Py<PyAny>> {
         // [ref:python_api_main_loop_channel]
@@ -151,9 +159,54 @@ fn process_api_request(app: &Application, msg: String) -> Result<String, String>
         Request::ObjectProperty { type_name: _, kind } => {
             unimplemented!("Python {kind:?} functionality has not been implemented.")
         }
+        Request::Compatibility { glyph } => {
+            let Some(designspace) = app.window.designspace.borrow().clone() else {
+                return Ok(no_designspace());
+            };
+            Ok(serde_json::to_string(&Response::Object {
+                py_type: PyType::String,
+                value: designspace.compatibility(&glyph).to_string().into(),
+            })
+            .unwrap())
+        }
+        Request::Interpolate { glyph, location } => {
+            use crate::ufo::designspace::{Dimension, Location};
+
+            let Some(designspace) = app.window.designspace.borrow().clone() else {
+                return Ok(no_designspace());
+            };
+            let location = Location {
+                dimensions: location
+                    .into_iter()
+                    .map(|(name, xvalue)| Dimension {
+                        name,
+                        xvalue,
+                        yvalue: None,
+                    })
+                    .collect(),
+            };
+            Ok(match designspace.interpolate(&glyph, &location) {
+                Err(err) => serde_json::to_string(&Response::Error {
+                    message: err.to_string(),
+                })
+                .unwrap(),
+                Ok(glyph) => serde_json::to_string(&Response::Object {
+                    py_type: PyType::String,
+                    value: crate::ufo::glif::Glif::from(&glyph).to_xml().into(),
+                })
+                .unwrap(),
+            })
+        }
     }
 }
 
+fn no_designspace() -> String {
+    serde_json::to_string(&Response::Error {
+        message: "No designspace is loaded.".to_string(),
+    })
+    .unwrap()
+}
+
 /// Operations on properties of an object. See [`Request`].
 #[derive(Debug, Serialize, Deserialize)]
 pub enum Property {
@@ -166,7 +219,19 @@ pub enum Property {
 /// Request object from main thread to python thread that is serialized to JSON.
 #[derive(Debug, Serialize, Deserialize)]
 pub enum Request {
-    ObjectProperty { type_name: String, kind: Property },
+    ObjectProperty {
+        type_name: String,
+        kind: Property,
+    },
+    /// Check whether a glyph can be interpolated between the designspace masters.
+    Compatibility {
+        glyph: String,
+    },
+    /// Interpolate a glyph at a design space location, and return it as glif XML.
+    Interpolate {
+        glyph: String,
+        location: IndexMap<String, f64>,
+    },
 }
 
 /// Response object from main thread to python thread that is serialized to JSON.
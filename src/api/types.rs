@@ -156,3 +156,50 @@ impl Project {
         getter!(self, py, crate::prelude::Project, ITALIC_ANGLE)
     }
 }
+
+#[pyclass]
+pub struct Designspace {
+    #[pyo3(get)]
+    pub(in crate::api) __gerb: Py<Gerb>,
+}
+
+#[pymethods]
+impl Designspace {
+    fn __repr__(&self) -> PyResult<String> {
+        Ok("Designspace".to_string())
+    }
+
+    /// Check whether glyph `glyph_name` can be interpolated between the masters.
+    fn compatibility(&self, glyph_name: String, py: Python<'_>) -> PyResult<String> {
+        self.__gerb
+            .as_ref(py)
+            .borrow()
+            .__send_rcv(
+                serde_json::to_string(&Request::Compatibility { glyph: glyph_name }).unwrap(),
+                py,
+            )?
+            .extract(py)
+    }
+
+    /// Interpolate glyph `glyph_name` at `location`, a dict of axis names to design space values,
+    /// and return it as a glif XML string.
+    fn interpolate(
+        &self,
+        glyph_name: String,
+        location: std::collections::HashMap<String, f64>,
+        py: Python<'_>,
+    ) -> PyResult<String> {
+        self.__gerb
+            .as_ref(py)
+            .borrow()
+            .__send_rcv(
+                serde_json::to_string(&Request::Interpolate {
+                    glyph: glyph_name,
+                    location: location.into_iter().collect(),
+                })
+                .unwrap(),
+                py,
+            )?
+            .extract(py)
+    }
+}
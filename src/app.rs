@@ -261,6 +261,66 @@ impl ApplicationInner {
                 dialog.emit_close();
             }
         }));
+        let import_svg = gtk::gio::SimpleAction::new("project.import.svg", None);
+        import_svg.connect_activate(glib::clone!(@weak self.window as window => move |_, _| {
+            use crate::glyphs::svg;
+
+            let project = window.project().clone();
+            let Some((dir, options)) =
+                svg::import_dialog(window.upcast_ref(), &project, true)
+            else {
+                return;
+            };
+            let glyphs = project.default_layer.glyphs().clone();
+            let (changes, failed) = match svg::import_folder(&dir, &glyphs, options) {
+                Ok(result) => result,
+                Err(err) => {
+                    let dialog = crate::utils::widgets::new_simple_error_dialog(
+                        Some("Error: Could not import SVG files"),
+                        &err.to_string(),
+                        Some(&format!("Path: {}", dir.display())),
+                        window.upcast_ref(),
+                    );
+                    dialog.run();
+                    dialog.emit_close();
+                    return;
+                }
+            };
+            let imported = changes.len();
+            if imported > 0 {
+                let dialog = gtk::MessageDialog::builder()
+                    .transient_for(&window)
+                    .destroy_with_parent(true)
+                    .modal(true)
+                    .buttons(gtk::ButtonsType::YesNo)
+                    .message_type(gtk::MessageType::Question)
+                    .text("Replace contours?")
+                    .secondary_text(&format!("The contours of {imported} glyphs will be replaced by the paths of their SVG files. This can be undone."))
+                    .build();
+                let response = dialog.run();
+                dialog.emit_close();
+                if response != gtk::ResponseType::Yes {
+                    return;
+                }
+                window.replace_glyph_contours("import svg", changes);
+            }
+            let failed = failed
+                .iter()
+                .map(|(path, err)| {
+                    glib::markup_escape_text(&format!("{}: {err}", path.display()))
+                })
+                .collect::<Vec<_>>();
+            let dialog = crate::utils::widgets::new_simple_info_dialog(
+                Some("SVG import"),
+                &format!("Replaced the contours of {imported} glyphs."),
+                (!failed.is_empty())
+                    .then(|| format!("Could not import:\n{}", failed.join("\n")))
+                    .as_deref(),
+                window.upcast_ref(),
+            );
+            dialog.run();
+            dialog.emit_close();
+        }));
         let open = gtk::gio::SimpleAction::new("project.open", None);
         open.connect_activate(glib::clone!(@weak window => move |_, _| {
             let dialog = gtk::FileChooserNative::new(
@@ -277,6 +337,26 @@ impl ApplicationInner {
             window.emit_by_name::<()>("open-project", &[&path]);
             dialog.hide();
         }));
+        let open_designspace = gtk::gio::SimpleAction::new("project.open.designspace", None);
+        open_designspace.connect_activate(glib::clone!(@weak window => move |_, _| {
+            let dialog = gtk::FileChooserNative::new(
+                Some("Open .designspace file..."),
+                Some(&window),
+                gtk::FileChooserAction::Open,
+                None,
+                None
+            );
+            let filter = gtk::FileFilter::new();
+            filter.set_name(Some("Designspace documents"));
+            filter.add_pattern("*.designspace");
+            dialog.add_filter(&filter);
+            crate::return_if_not_ok_or_accept!(dialog.run());
+
+            let Some(f) = dialog.filename() else { return; };
+            let Some(path) = f.to_str() else { return; };
+            window.emit_by_name::<()>("open-project", &[&path]);
+            dialog.hide();
+        }));
         let new_project = gtk::gio::SimpleAction::new("project.new", None);
         {
             new_project.connect_activate(glib::clone!(@weak self.window as window => move |_, _| {
@@ -340,7 +420,42 @@ impl ApplicationInner {
         );
         let project_save = gtk::gio::SimpleAction::new("project.save", None);
         project_save.connect_activate(glib::clone!(@weak self.window as window => move |_, _| {
-            if let Err(err) = window.project.borrow().save() {
+            let projects = match window.designspace.borrow().as_ref() {
+                Some(designspace) => designspace.projects.borrow().clone(),
+                None => vec![window.project.borrow().clone()],
+            };
+            /* Saving edited feature code that was decoded lossily writes U+FFFD in place of the
+             * original bytes, so ask first. */
+            for project in projects {
+                {
+                    let features = project.features.borrow();
+                    if !(features.lossy() && features.modified()) {
+                        continue;
+                    }
+                }
+                let dialog = gtk::MessageDialog::builder()
+                    .transient_for(&window)
+                    .destroy_with_parent(true)
+                    .modal(true)
+                    .buttons(gtk::ButtonsType::YesNo)
+                    .message_type(gtk::MessageType::Warning)
+                    .text("Save feature code?")
+                    .secondary_text(&format!("The <tt>features.fea</tt> file of {} was not valid UTF-8 and its invalid bytes were replaced with U+FFFD when it was loaded. Saving it will write the replacement characters instead of the original bytes.", glib::markup_escape_text(&project.property::<String>(crate::prelude::Project::NAME))))
+                    .secondary_use_markup(true)
+                    .build();
+                let response = dialog.run();
+                dialog.emit_close();
+                if response != gtk::ResponseType::Yes {
+                    return;
+                }
+                project.features.borrow_mut().accept_lossy();
+            }
+            let result = if let Some(designspace) = window.designspace.borrow().as_ref() {
+                designspace.save()
+            } else {
+                window.project.borrow().save()
+            };
+            if let Err(err) = result {
                 let dialog = crate::utils::widgets::new_simple_error_dialog(
                     Some("Error: could not perform conversion to UFOv3 with glyphsLib"),
                     &err.to_string(),
@@ -354,19 +469,19 @@ impl ApplicationInner {
         let project_export = gtk::gio::SimpleAction::new("project.export", None);
         project_export
             .connect_activate(glib::clone!(@weak self.window as window => move |_, _| {
-            #[cfg(feature = "python")]
+            #[cfg(any(feature = "python", feature = "native-export"))]
             {
                 crate::ufo::export::ufo_compile::export_action_cb(
                     window.clone().upcast(),
                     window.project().clone(),
                 );
             }
-            #[cfg(not(feature = "python"))]
+            #[cfg(not(any(feature = "python", feature = "native-export")))]
             {
                 // [ref:needs_user_doc] Add compilation instructions and/or url to docs.
                 let dialog = crate::utils::widgets::new_simple_error_dialog(
                     None,
-                    "This application build doesn't include python support. <i>UFOv3</i> export is performed with the <tt>ufo2ft</tt> python3 library.\n\nCompile or install the app with <tt>python</tt> Cargo feature enabled.",
+                    "This application build doesn't include font export support. <i>UFOv3</i> export is performed either with the built-in compiler or the <tt>ufo2ft</tt> python3 library.\n\nCompile or install the app with the <tt>native-export</tt> or <tt>python</tt> Cargo feature enabled.",
                     None,
                     window.upcast_ref(),
                 );
@@ -374,6 +489,10 @@ impl ApplicationInner {
                 dialog.emit_close();
             }
         }));
+        let project_lint = gtk::gio::SimpleAction::new("project.lint", None);
+        project_lint.connect_activate(glib::clone!(@weak self.window as window => move |_, _| {
+            window.show_lint();
+        }));
         let bug_report = gtk::gio::SimpleAction::new("bug_report", None);
         bug_report.connect_activate(|_, _| {
             gtk::gio::AppInfo::launch_default_for_uri(
@@ -385,12 +504,15 @@ impl ApplicationInner {
         application.add_action(&project_properties);
         application.add_action(&project_save);
         application.add_action(&project_export);
+        application.add_action(&project_lint);
         application.add_action(&import_glyphs);
         application.add_action(&import_ufo2);
+        application.add_action(&import_svg);
         application.add_action(&settings);
         application.add_action(&about);
         application.add_action(&bug_report);
         application.add_action(&open);
+        application.add_action(&open_designspace);
         application.add_action(&new_project);
         application.add_action(&undo);
         application.add_action(&redo);
@@ -406,6 +528,10 @@ impl ApplicationInner {
             let import_menu = gio::Menu::new();
             file_menu.append(Some("_New"), Some("app.project.new"));
             file_menu.append(Some("_Open"), Some("app.project.open"));
+            file_menu.append(
+                Some("Open _Designspace"),
+                Some("app.project.open.designspace"),
+            );
             file_menu.append(Some("_Save"), Some("app.project.save"));
             import_menu.append(
                 Some("Import Glyphs file"),
@@ -415,10 +541,15 @@ impl ApplicationInner {
                 Some("Import UFOv2 directory"),
                 Some("app.project.import.ufo2"),
             );
+            import_menu.append(
+                Some("Import folder of SVGs by glyph name"),
+                Some("app.project.import.svg"),
+            );
             file_menu.append_submenu(Some("_Import"), &import_menu);
             file_menu.append(Some("_Export"), Some("app.project.export"));
             let project_section = gio::Menu::new();
             project_section.append(Some("_Properties"), Some("app.project.properties"));
+            project_section.append(Some("_Validate font"), Some("app.project.lint"));
             #[cfg(feature = "python")]
             {
                 project_section.append(Some("Open Python Shell"), Some("app.shell"));
@@ -48,6 +48,7 @@ pub struct SettingsInner {
     pub guideline_width: Cell<f64>,
     pub warp_cursor: Cell<bool>,
     pub mark_color: Cell<types::MarkColor>,
+    pub contour_direction: Cell<types::ContourDirection>,
     pub entries: RefCell<IndexMap<String, Vec<glib::object::WeakRef<glib::Object>>>>,
     #[allow(clippy::type_complexity)]
     pub file: Rc<RefCell<Option<(PathBuf, BufWriter<File>)>>>,
@@ -138,6 +139,7 @@ impl SettingsInner {
             document[Settings::GUIDELINE_WIDTH] = toml_value(self.guideline_width.get());
             document[Settings::WARP_CURSOR] = toml_value(self.warp_cursor.get());
             document[Settings::MARK_COLOR] = toml_value(self.mark_color.get().name());
+            document[Settings::CONTOUR_DIRECTION] = toml_value(self.contour_direction.get().name());
             file.rewind()?;
             file.get_mut().set_len(0)?;
             file.write_all(document.to_string().as_bytes())?;
@@ -285,6 +287,14 @@ impl SettingsInner {
                 save = true;
             }
         }
+        for (prop, field) in [(Settings::CONTOUR_DIRECTION, &self.contour_direction)] {
+            if let Some(v) = types::ContourDirection::deserialize(document.get(prop)) {
+                field.set(v);
+            } else {
+                document[prop] = toml_value(field.get().name());
+                save = true;
+            }
+        }
         drop(document);
         if save {
             self.save_settings()?;
@@ -428,6 +438,14 @@ impl ObjectImpl for SettingsInner {
                         types::MarkColor::None as i32,
                         glib::ParamFlags::READWRITE | UI_EDITABLE,
                     ),
+                    glib::ParamSpecEnum::new(
+                        Settings::CONTOUR_DIRECTION,
+                        Settings::CONTOUR_DIRECTION,
+                        "Direction convention of closed contours.",
+                        types::ContourDirection::static_type(),
+                        types::ContourDirection::PostScript as i32,
+                        glib::ParamFlags::READWRITE | UI_EDITABLE,
+                    ),
                     glib::ParamSpecBoxed::new(
                         Settings::UI_FONT,
                         Settings::UI_FONT,
@@ -447,6 +465,7 @@ impl ObjectImpl for SettingsInner {
             Settings::GUIDELINE_WIDTH => self.guideline_width.get().to_value(),
             Settings::WARP_CURSOR => self.warp_cursor.get().to_value(),
             Settings::MARK_COLOR => self.mark_color.get().to_value(),
+            Settings::CONTOUR_DIRECTION => self.contour_direction.get().to_value(),
             Settings::UI_FONT => self.ui_font.borrow().to_value(),
             _ => unimplemented!("{}", pspec.name()),
         }
@@ -480,6 +499,10 @@ impl ObjectImpl for SettingsInner {
                 self.mark_color.set(value.get().unwrap());
                 self.save_settings().unwrap();
             }
+            Settings::CONTOUR_DIRECTION => {
+                self.contour_direction.set(value.get().unwrap());
+                self.save_settings().unwrap();
+            }
             Settings::UI_FONT => {
                 *self.ui_font.borrow_mut() = value.get().unwrap();
                 self.save_settings().unwrap();
@@ -501,6 +524,7 @@ impl Settings {
     pub const GUIDELINE_WIDTH: &str = "guideline-width";
     pub const WARP_CURSOR: &str = "warp-cursor";
     pub const MARK_COLOR: &str = "mark-color";
+    pub const CONTOUR_DIRECTION: &str = "contour-direction";
     pub const UI_FONT: &str = "ui-font";
 
     pub fn new() -> Self {
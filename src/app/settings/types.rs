@@ -65,6 +65,20 @@ pub enum ShowMinimap {
 
 impl EnumValue for ShowMinimap {}
 
+/// Which way closed contours should run.
+#[derive(Debug, Deserialize, Serialize, Default, Copy, Clone, PartialEq, Eq, glib::Enum)]
+#[enum_type(name = "ContourDirection")]
+#[serde(rename_all = "kebab-case")]
+pub enum ContourDirection {
+    /// Outer contours run counter-clockwise and counters clockwise, as in UFO and CFF outlines.
+    #[default]
+    PostScript,
+    /// Outer contours run clockwise and counters counter-clockwise.
+    TrueType,
+}
+
+impl EnumValue for ContourDirection {}
+
 #[test]
 fn test_parse_toml() {
     use crate::prelude::Color;
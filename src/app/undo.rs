@@ -58,6 +58,28 @@ pub struct Action {
     pub undo: Box<dyn FnMut()>,
 }
 
+impl Action {
+    /// Combine `actions` into one action that redoes them in order and undoes them in reverse.
+    pub fn compound(stamp: EventStamp, actions: Vec<Self>) -> Self {
+        let (mut redos, mut undos): (Vec<_>, Vec<_>) =
+            actions.into_iter().map(|a| (a.redo, a.undo)).unzip();
+        Self {
+            stamp,
+            compress: false,
+            redo: Box::new(move || {
+                for redo in redos.iter_mut() {
+                    redo();
+                }
+            }),
+            undo: Box::new(move || {
+                for undo in undos.iter_mut().rev() {
+                    undo();
+                }
+            }),
+        }
+    }
+}
+
 impl std::fmt::Debug for Action {
     fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
         fmt.debug_struct("Action")
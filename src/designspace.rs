@@ -0,0 +1,415 @@
+/*
+ * gerb
+ *
+ * Copyright 2022 - Manos Pitsidianakis
+ *
+ * This file is part of gerb.
+ *
+ * gerb is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * gerb is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with gerb. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::glyphs::{
+    interpolation::{self, VariationModel},
+    Glyph,
+};
+use crate::prelude::*;
+use crate::ufo::designspace::{DesignspaceDocument, Location};
+
+/// A source of a designspace: a UFO project, or one of its layers, at a design space location.
+#[derive(Debug, Clone)]
+pub struct Master {
+    pub name: String,
+    pub project: Project,
+    pub layer: ufo::objects::Layer,
+    /// Design space location with every axis specified.
+    pub location: Location,
+    /// Layer sources only contain the glyphs that need an extra master.
+    pub sparse: bool,
+}
+
+/// Whether a glyph can be interpolated between the masters of a designspace.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Compatibility {
+    Compatible,
+    /// The glyph is missing from these (non sparse) masters.
+    Missing(Vec<String>),
+    /// The glyph of `master` doesn't have the same structure as the one of the default master.
+    Incompatible {
+        master: String,
+        reason: String,
+    },
+}
+
+impl std::fmt::Display for Compatibility {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Compatible => write!(fmt, "compatible"),
+            Self::Missing(masters) => write!(fmt, "missing from {}", masters.join(", ")),
+            Self::Incompatible { master, reason } => {
+                write!(fmt, "incompatible: {master} {reason}")
+            }
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct DesignspaceInner {
+    name: RefCell<String>,
+    modified: Cell<bool>,
+    pub path: RefCell<PathBuf>,
+    document: RefCell<DesignspaceDocument>,
+    /// Set when `document` is changed, so that saving only rewrites the `.designspace` file then.
+    document_modified: Cell<bool>,
+    /// UFO projects of the sources. A project can be used by more than one source.
+    pub projects: RefCell<Vec<Project>>,
+    /// One master for each source of `document`, in the same order.
+    pub masters: RefCell<Vec<Master>>,
+    default_master: Cell<usize>,
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for DesignspaceInner {
+    const NAME: &'static str = "Designspace";
+    type Type = Designspace;
+    type ParentType = glib::Object;
+    type Interfaces = ();
+}
+
+impl ObjectImpl for DesignspaceInner {
+    fn properties() -> &'static [ParamSpec] {
+        static PROPERTIES: once_cell::sync::Lazy<Vec<ParamSpec>> =
+            once_cell::sync::Lazy::new(|| {
+                vec![
+                    ParamSpecString::new(
+                        Designspace::NAME,
+                        Designspace::NAME,
+                        Designspace::NAME,
+                        None,
+                        ParamFlags::READWRITE,
+                    ),
+                    ParamSpecBoolean::new(
+                        Designspace::MODIFIED,
+                        Designspace::MODIFIED,
+                        Designspace::MODIFIED,
+                        false,
+                        ParamFlags::READWRITE,
+                    ),
+                ]
+            });
+        PROPERTIES.as_ref()
+    }
+
+    fn property(&self, _obj: &Self::Type, _id: usize, pspec: &ParamSpec) -> Value {
+        match pspec.name() {
+            Designspace::NAME => self.name.borrow().to_value(),
+            Designspace::MODIFIED => self.modified.get().to_value(),
+            _ => unimplemented!("{}", pspec.name()),
+        }
+    }
+
+    fn set_property(&self, _obj: &Self::Type, _id: usize, value: &Value, pspec: &ParamSpec) {
+        match pspec.name() {
+            Designspace::NAME => {
+                *self.name.borrow_mut() = value.get().unwrap();
+            }
+            Designspace::MODIFIED => {
+                self.modified.set(value.get().unwrap());
+            }
+            _ => unimplemented!("{}", pspec.name()),
+        }
+    }
+}
+
+glib::wrapper! {
+    pub struct Designspace(ObjectSubclass<DesignspaceInner>);
+}
+
+impl std::ops::Deref for Designspace {
+    type Target = DesignspaceInner;
+
+    fn deref(&self) -> &Self::Target {
+        self.imp()
+    }
+}
+
+impl Designspace {
+    pub const NAME: &str = Project::NAME;
+    pub const MODIFIED: &str = Project::MODIFIED;
+
+    pub fn new() -> Self {
+        let ret: Self = glib::Object::new::<Self>(&[]).unwrap();
+        ret
+    }
+
+    /// Load a `.designspace` document and the UFO projects of its sources.
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, Box<dyn std::error::Error>> {
+        let path: PathBuf = std::fs::canonicalize(path.as_ref())?;
+        if !path.is_file() {
+            return Err(format!("Path {} is not a file.", path.display()).into());
+        }
+        let document = DesignspaceDocument::from_path(&path)
+            .map_err(|err| format!("couldn't read designspace {}:\n\n{}", path.display(), err))?;
+        if document.sources.sources.is_empty() {
+            return Err(format!("Designspace {} has no sources.", path.display()).into());
+        }
+        let ret: Self = Self::new();
+        let prefix = path.parent().unwrap_or_else(|| Path::new("/"));
+        let mut projects: Vec<Project> = vec![];
+        let mut masters = Vec::with_capacity(document.sources.sources.len());
+        for source in document.sources.sources.iter() {
+            let name = source.display_name();
+            let ufo_path = std::fs::canonicalize(prefix.join(&source.filename))
+                .map_err(|err| format!("couldn't find source {name}:\n\n{err}"))?;
+            let project = if let Some(p) = projects.iter().find(|p| *p.path.borrow() == ufo_path) {
+                p.clone()
+            } else {
+                let p = Project::from_path(&ufo_path)
+                    .map_err(|err| format!("couldn't load source {name}:\n\n{err}"))?;
+                p.connect_notify_local(
+                    Some(Project::MODIFIED),
+                    clone!(@weak ret => move |p, _| {
+                        if p.property::<bool>(Project::MODIFIED) {
+                            ret.set_property(Self::MODIFIED, true);
+                        }
+                    }),
+                );
+                projects.push(p.clone());
+                p
+            };
+            let layer = match source.layer.as_ref() {
+                None => project.default_layer.clone(),
+                Some(layer_name) => project
+                    .all_layers
+                    .borrow()
+                    .iter()
+                    .find(|l| *l.name.borrow() == *layer_name)
+                    .cloned()
+                    .ok_or_else(|| {
+                        format!("Source {name} refers to layer {layer_name} which does not exist in {}.", ufo_path.display())
+                    })?,
+            };
+            masters.push(Master {
+                name,
+                project,
+                layer,
+                location: document.normalize_location(&source.location),
+                sparse: source.layer.is_some(),
+            });
+        }
+        /* Fall back to the first full master if no source is at the default location. */
+        let default_master = document
+            .default_source()
+            .or_else(|| masters.iter().position(|m| !m.sparse))
+            .unwrap_or(0);
+        if let Some(name) = path.file_stem() {
+            ret.set_property(Self::NAME, name.to_string_lossy().to_string());
+        }
+        ret.default_master.set(default_master);
+        *ret.masters.borrow_mut() = masters;
+        *ret.projects.borrow_mut() = projects;
+        *ret.document.borrow_mut() = document;
+        *ret.path.borrow_mut() = path;
+        ret.set_property(Self::MODIFIED, false);
+        Ok(ret)
+    }
+
+    /// Save modified source projects, and the designspace document if it was changed.
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        for project in self
+            .projects
+            .borrow()
+            .iter()
+            .filter(|p| p.property::<bool>(Project::MODIFIED))
+        {
+            project.save()?;
+        }
+        let path = self.path.borrow();
+        if self.document_modified.get() {
+            self.document
+                .borrow()
+                .save(&path)
+                .map_err(|err| format!("Could not save {}:\n\n{err}", path.display()))?;
+            self.document_modified.set(false);
+        }
+        self.set_property(Self::MODIFIED, false);
+        Ok(())
+    }
+
+    pub fn document(&self) -> FieldRef<'_, DesignspaceDocument> {
+        self.document.borrow().into()
+    }
+
+    /// Change the designspace document, which is written back on the next [`Self::save`].
+    pub fn document_mut(&self) -> std::cell::RefMut<'_, DesignspaceDocument> {
+        self.document_modified.set(true);
+        self.set_property(Self::MODIFIED, true);
+        self.document.borrow_mut()
+    }
+
+    /// The master at the default location of every axis.
+    pub fn default_master(&self) -> Master {
+        self.masters.borrow()[self.default_master.get()].clone()
+    }
+
+    /// The project that contains `glyph` in any of its layers.
+    pub fn glyph_project(&self, glyph: &Rc<RefCell<Glyph>>) -> Option<Project> {
+        self.projects
+            .borrow()
+            .iter()
+            .find(|p| {
+                p.all_layers
+                    .borrow()
+                    .iter()
+                    .any(|l| l.glyphs().values().any(|g| Rc::ptr_eq(g, glyph)))
+            })
+            .cloned()
+    }
+
+    /// Names of the glyphs of all masters, in the glyph order of the default master.
+    pub fn glyph_names(&self) -> IndexSet<String> {
+        let masters = self.masters.borrow();
+        let default = &masters[self.default_master.get()];
+        let mut ret: IndexSet<String> = default.layer.glyphs().keys().cloned().collect();
+        for m in masters.iter() {
+            ret.extend(m.layer.glyphs().keys().cloned());
+        }
+        ret
+    }
+
+    /// Check whether glyph `name` can be interpolated between the masters.
+    pub fn compatibility(&self, name: &str) -> Compatibility {
+        let masters = self.masters.borrow();
+        let missing = masters
+            .iter()
+            .filter(|m| !m.sparse && !m.layer.glyphs().contains_key(name))
+            .map(|m| m.name.clone())
+            .collect::<Vec<String>>();
+        if !missing.is_empty() {
+            return Compatibility::Missing(missing);
+        }
+        let default = &masters[self.default_master.get()];
+        let Some(reference) = default.layer.glyphs().get(name).cloned() else {
+            return Compatibility::Missing(vec![default.name.clone()]);
+        };
+        let reference = reference.borrow();
+        for m in masters.iter() {
+            let Some(glyph) = m.layer.glyphs().get(name).cloned() else {
+                continue;
+            };
+            if Rc::ptr_eq(&glyph, &default.layer.glyphs()[name]) {
+                continue;
+            }
+            let reason = incompatibility(&reference, &glyph.borrow());
+            if let Some(reason) = reason {
+                return Compatibility::Incompatible {
+                    master: m.name.clone(),
+                    reason,
+                };
+            }
+        }
+        Compatibility::Compatible
+    }
+
+    /// Interpolate glyph `name` at design space `location`. Axes that `location` doesn't specify
+    /// are at their default value.
+    pub fn interpolate(
+        &self,
+        name: &str,
+        location: &Location,
+    ) -> Result<Glyph, Box<dyn std::error::Error>> {
+        let document = self.document.borrow();
+        let masters = self.masters.borrow();
+        let default_master = self.default_master.get();
+        /* The default master comes first, so that it is the reference for compatibility. */
+        let mut sources = vec![];
+        for m in std::iter::once(&masters[default_master]).chain(
+            masters
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| *i != default_master)
+                .map(|(_, m)| m),
+        ) {
+            match m.layer.glyphs().get(name) {
+                Some(glyph) => sources.push((
+                    m.name.as_str(),
+                    glyph.clone(),
+                    document.normalized_coordinates(&m.location),
+                )),
+                None if m.sparse => {}
+                None => {
+                    return Err(format!("Glyph {name} is missing from master {}.", m.name).into())
+                }
+            }
+        }
+        let model = VariationModel::new(
+            &sources
+                .iter()
+                .map(|(_, _, l)| l.clone())
+                .collect::<Vec<Vec<f64>>>(),
+        )?;
+        let weights = model.weights(&document.normalized_coordinates(location));
+        let glyphs = sources
+            .iter()
+            .map(|(_, g, _)| g.borrow())
+            .collect::<Vec<_>>();
+        for ((master, _, _), glyph) in sources.iter().zip(glyphs.iter()).skip(1) {
+            interpolation::check_compatibility(&glyphs[0], glyph)
+                .map_err(|err| format!("Glyph {name} of master {master} {err}."))?;
+        }
+        Ok(interpolation::interpolate(
+            &glyphs
+                .iter()
+                .zip(weights)
+                .map(|(g, w)| (&**g, w))
+                .collect::<Vec<(&Glyph, f64)>>(),
+        )?)
+    }
+}
+
+impl Default for Designspace {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl_modified!(Designspace);
+
+/// Describe how the outline structure of `other` differs from that of `reference`, if it does.
+///
+/// Glyphs are compatible if they have the same number of contours with the same kind of
+/// segments, the same components in the same order and the same anchors. See
+/// [`interpolation::check_compatibility`] for the structured result.
+pub fn incompatibility(reference: &Glyph, other: &Glyph) -> Option<String> {
+    interpolation::check_compatibility(reference, other)
+        .err()
+        .map(|err| err.to_string())
+}
+
+#[test]
+fn test_incompatibility() {
+    use crate::ufo::glif::{self, Glif};
+
+    let glyph = |src: &str| -> Glyph { Glif::from_str(src).unwrap().try_into().unwrap() };
+    let a = glyph(glif::_UPPERCASE_A_GLIF);
+    let b = glyph(glif::_LOWERCASE_B_GLIF);
+    assert_eq!(incompatibility(&a, &glyph(glif::_UPPERCASE_A_GLIF)), None);
+    assert_eq!(incompatibility(&b, &b), None);
+    assert!(incompatibility(&a, &b)
+        .unwrap()
+        .starts_with("contour 0 has "));
+    assert_eq!(
+        incompatibility(&a, &glyph(glif::_QUADRATIC_GLIF)).as_deref(),
+        Some("has 3 contours instead of 2")
+    );
+}
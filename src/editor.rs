@@ -25,23 +25,31 @@ use once_cell::unsync::OnceCell;
 use std::collections::HashSet;
 
 use crate::app::settings::types::ShowMinimap;
-use crate::glyphs::{Contour, Glyph, GlyphDrawingOptions, GlyphPointIndex, Guideline};
+use crate::glyphs::{
+    Anchor, Component, Contour, Glyph, GlyphDrawingOptions, GlyphPointIndex, Guideline,
+};
 use crate::prelude::*;
 use crate::views::{
     canvas::{Layer, LayerBuilder},
     overlay::Child,
 };
 
+mod align;
+mod clipboard;
 mod layers;
 mod menu;
 mod shortcuts;
 mod state;
 mod tools;
+pub use align::Alignment;
 pub use state::State;
+pub use tools::SelectionModifier;
 
-use tools::{PanningTool, SelectionModifier, Tool, ToolImpl};
+use tools::{ImageTool, PanningTool, Tool, ToolImpl};
 
 type StatusBarMessage = u32;
+/// A design space location and the glyph interpolated there, or why it couldn't be.
+type InterpolationCache = (crate::ufo::designspace::Location, Result<Glyph, String>);
 
 #[derive(Debug, Default)]
 pub struct EditorInner {
@@ -75,6 +83,17 @@ pub struct EditorInner {
     precision: Cell<(Option<StatusBarMessage>, tools::constraints::Precision)>,
     shortcuts: Rc<RefCell<Vec<ShortcutAction>>>,
     shortcut_status: gtk::Box,
+    /// Set if the glyph belongs to a master of a designspace.
+    designspace: RefCell<Option<Designspace>>,
+    /// Design space location of the interpolation preview.
+    interpolation_location: RefCell<crate::ufo::designspace::Location>,
+    /// Interpolated glyph at `interpolation_location`, cleared when a master's glyph is modified.
+    interpolation_cache: RefCell<Option<InterpolationCache>>,
+    /// Handlers that clear `interpolation_cache`, disconnected when the editor is disposed.
+    interpolation_handlers: RefCell<Vec<(glib::Object, glib::SignalHandlerId)>>,
+    interpolation_layer: OnceCell<Layer>,
+    /// Result of an operation that is previewed before it is applied.
+    preview_contours: RefCell<Vec<Contour>>,
 }
 
 #[glib::object_subclass]
@@ -163,6 +182,36 @@ impl ObjectImpl for EditorInner {
                 }))))
                 .build(),
         );
+        self.viewport.add_layer(
+            LayerBuilder::new()
+                .set_name(Some("anchors"))
+                .set_active(true)
+                .set_hidden(false)
+                .set_callback(Some(Box::new(clone!(@weak obj => @default-return Inhibit(false), move |viewport: &Canvas, mut cr: ContextRef<'_, '_>| {
+                    layers::draw_anchors(viewport, cr.push(), obj)
+                }))))
+                .build(),
+        );
+        let interpolation_layer = LayerBuilder::new()
+            .set_name(Some("interpolation"))
+            .set_active(false)
+            .set_hidden(false)
+            .set_callback(Some(Box::new(clone!(@weak obj => @default-return Inhibit(false), move |viewport: &Canvas, mut cr: ContextRef<'_, '_>| {
+                layers::draw_interpolation(viewport, cr.push(), obj)
+            }))))
+            .build();
+        self.viewport.add_layer(interpolation_layer.clone());
+        self.interpolation_layer.set(interpolation_layer).unwrap();
+        self.viewport.add_layer(
+            LayerBuilder::new()
+                .set_name(Some("operation preview"))
+                .set_active(true)
+                .set_hidden(true)
+                .set_callback(Some(Box::new(clone!(@weak obj => @default-return Inhibit(false), move |viewport: &Canvas, mut cr: ContextRef<'_, '_>| {
+                    layers::draw_preview_contours(viewport, cr.push(), obj)
+                }))))
+                .build(),
+        );
         self.viewport.add_pre_layer(
             LayerBuilder::new()
                 .set_name(Some("guidelines"))
@@ -487,6 +536,10 @@ impl ObjectImpl for EditorInner {
             _ => unimplemented!("{}", pspec.name()),
         }
     }
+
+    fn dispose(&self, _obj: &Self::Type) {
+        self.disconnect_interpolation_handlers();
+    }
 }
 
 impl WidgetImpl for EditorInner {}
@@ -494,6 +547,12 @@ impl ContainerImpl for EditorInner {}
 impl BinImpl for EditorInner {}
 
 impl EditorInner {
+    fn disconnect_interpolation_handlers(&self) {
+        for (obj, handler) in self.interpolation_handlers.take() {
+            obj.disconnect(handler);
+        }
+    }
+
     pub fn app(&self) -> &Application {
         self.app.get().unwrap()
     }
@@ -668,6 +727,37 @@ impl Editor {
         ret
     }
 
+    /// Enable interpolation preview between the masters of `designspace`.
+    pub fn set_designspace(&self, designspace: &Designspace) {
+        *self.designspace.borrow_mut() = Some(designspace.clone());
+        *self.interpolation_location.borrow_mut() = designspace.document().default_location();
+        self.interpolation_cache.take();
+        self.disconnect_interpolation_handlers();
+        let mut handlers = vec![];
+        let name = self.glyph().borrow().name().to_string();
+        for master in designspace.masters.borrow().iter() {
+            let Some(glyph) = master.layer.glyphs().get(&name).cloned() else {
+                continue;
+            };
+            let metadata = glyph.borrow().metadata.clone();
+            let handler = metadata.connect_notify_local(
+                Some(GlyphMetadata::MODIFIED),
+                clone!(@weak self as obj => move |_, _| {
+                    obj.interpolation_cache.take();
+                }),
+            );
+            handlers.push((metadata.upcast(), handler));
+        }
+        let handler = designspace.connect_notify_local(
+            Some(Designspace::MODIFIED),
+            clone!(@weak self as obj => move |_, _| {
+                obj.interpolation_cache.take();
+            }),
+        );
+        handlers.push((designspace.clone().upcast(), handler));
+        *self.interpolation_handlers.borrow_mut() = handlers;
+    }
+
     pub fn set_selection(&self, selection: &[GlyphPointIndex], modifier: SelectionModifier) {
         use SelectionModifier::*;
         {
@@ -0,0 +1,241 @@
+/*
+ * gerb
+ *
+ * Copyright 2022 - Manos Pitsidianakis
+ *
+ * This file is part of gerb.
+ *
+ * gerb is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * gerb is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with gerb. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Aligning and distributing the selection.
+//!
+//! The selection is split into items that move as a unit: contours whose points are all
+//! selected, on-curve points, anchors and components. Handles of partially selected contours
+//! follow their on-curve points and are not aligned on their own.
+
+use super::*;
+use crate::utils::curves::Bezier;
+
+/// Lower left and upper right corners of a bounding box.
+pub type BBox = (Point, Point);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Alignment {
+    Left,
+    Right,
+    Top,
+    Bottom,
+    /// Horizontal centers on a vertical line.
+    HorizontalCenter,
+    /// Vertical centers on a horizontal line.
+    VerticalCenter,
+}
+
+impl Alignment {
+    /// The coordinate that `bboxes` are aligned to when aligning them to each other: the
+    /// outermost edge, or the center of the box that bounds all of them.
+    pub fn target(self, bboxes: &[BBox]) -> Option<f64> {
+        let (min, max) = bounds(bboxes.iter().copied())?;
+        Some(match self {
+            Self::Left => min.x,
+            Self::Right => max.x,
+            Self::Top => max.y,
+            Self::Bottom => min.y,
+            Self::HorizontalCenter => 0.5 * (min.x + max.x),
+            Self::VerticalCenter => 0.5 * (min.y + max.y),
+        })
+    }
+
+    /// The translation that aligns `bbox` to `target`, which is an x coordinate for left,
+    /// right and horizontal center alignments and a y coordinate for the rest.
+    pub fn offset(self, (min, max): BBox, target: f64) -> Point {
+        let center = 0.5 * (min + max);
+        match self {
+            Self::Left => (target - min.x, 0.0),
+            Self::Right => (target - max.x, 0.0),
+            Self::Top => (0.0, target - max.y),
+            Self::Bottom => (0.0, target - min.y),
+            Self::HorizontalCenter => (target - center.x, 0.0),
+            Self::VerticalCenter => (0.0, target - center.y),
+        }
+        .into()
+    }
+}
+
+/// The box that bounds all of `bboxes`.
+pub fn bounds(bboxes: impl IntoIterator<Item = BBox>) -> Option<BBox> {
+    bboxes.into_iter().reduce(|(amin, amax), (bmin, bmax)| {
+        (
+            (amin.x.min(bmin.x), amin.y.min(bmin.y)).into(),
+            (amax.x.max(bmax.x), amax.y.max(bmax.y)).into(),
+        )
+    })
+}
+
+/// The translations that space the centers of `bboxes` evenly, horizontally or vertically,
+/// between the two outermost ones, which stay in place.
+pub fn distribute(bboxes: &[BBox], horizontally: bool) -> Vec<Point> {
+    let center = |(min, max): BBox| {
+        let c = 0.5 * (min + max);
+        if horizontally {
+            c.x
+        } else {
+            c.y
+        }
+    };
+    let mut ret = vec![Point::default(); bboxes.len()];
+    let mut order = (0..bboxes.len()).collect::<Vec<usize>>();
+    order.sort_by(|&a, &b| center(bboxes[a]).total_cmp(&center(bboxes[b])));
+    let (Some(&first), Some(&last)) = (order.first(), order.last()) else {
+        return ret;
+    };
+    let gaps = order.iter().skip(1).fold(0.0, |n, _| n + 1.0);
+    if gaps < 2.0 {
+        return ret;
+    }
+    let step = (center(bboxes[last]) - center(bboxes[first])) / gaps;
+    let mut position = center(bboxes[first]);
+    for i in order {
+        let delta = position - center(bboxes[i]);
+        ret[i] = if horizontally {
+            (delta, 0.0)
+        } else {
+            (0.0, delta)
+        }
+        .into();
+        position += step;
+    }
+    ret
+}
+
+impl State {
+    /// The items of the selection that are aligned as a unit, and their bounding boxes.
+    pub fn alignment_items(&self) -> Vec<(Vec<GlyphPointIndex>, BBox)> {
+        let mut items = vec![];
+        let mut contour_points: IndexMap<usize, Vec<GlyphPointIndex>> = IndexMap::default();
+        for idx in &self.selection {
+            if idx.is_contour_point() {
+                contour_points
+                    .entry(idx.contour_index)
+                    .or_default()
+                    .push(*idx);
+            } else if let Some(p) = self.point_position(idx) {
+                items.push((vec![*idx], (p, p)));
+            }
+        }
+        let glyph = self.glyph.borrow();
+        for (contour_index, selected) in contour_points {
+            let Some(contour) = glyph.contours.get(contour_index) else {
+                continue;
+            };
+            let curves = contour.curves();
+            let all = curves
+                .iter()
+                .enumerate()
+                .flat_map(|(curve_index, curve)| {
+                    curve
+                        .points()
+                        .iter()
+                        .map(|cp| (cp.glyph_index(contour_index, curve_index), cp.position))
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<(GlyphPointIndex, Point)>>();
+            if all.iter().all(|(idx, _)| selected.contains(idx)) {
+                if let Some(bbox) = bounds(all.iter().map(|&(_, p)| (p, p))) {
+                    items.push((all.into_iter().map(|(idx, _)| idx).collect(), bbox));
+                }
+                continue;
+            }
+            /* Curves share their end points, so coincident on-curve points are one item. */
+            let mut on_curve: Vec<(Vec<GlyphPointIndex>, Point)> = vec![];
+            for idx in selected {
+                let Some(points) = curves.get(idx.curve_index).map(Bezier::points) else {
+                    continue;
+                };
+                let Some(i) = points.iter().position(|cp| cp.uuid == idx.uuid) else {
+                    continue;
+                };
+                if i != 0 && i + 1 != points.len() {
+                    continue;
+                }
+                let p = points[i].position;
+                match on_curve
+                    .iter_mut()
+                    .find(|(_, q)| q.distance(p) < f64::EPSILON)
+                {
+                    Some((idxs, _)) => idxs.push(idx),
+                    None => on_curve.push((vec![idx], p)),
+                }
+            }
+            items.extend(on_curve.into_iter().map(|(idxs, p)| (idxs, (p, p))));
+        }
+        items
+    }
+
+    /// Move each group of points by its offset, as a single action.
+    pub fn translate_items(&self, items: &[(Vec<GlyphPointIndex>, Point)]) -> Action {
+        let actions = items
+            .iter()
+            .map(|(idxs, d)| self.transform_points(idxs, Matrix::new(1.0, 0.0, 0.0, 1.0, d.x, d.y)))
+            .collect();
+        Action::compound(
+            EventStamp {
+                t: std::any::TypeId::of::<Self>(),
+                property: "align",
+                id: Box::new([]),
+            },
+            actions,
+        )
+    }
+}
+
+#[test]
+fn test_align_distribute() {
+    let bboxes: [BBox; 3] = [
+        ((0.0, 0.0).into(), (10.0, 10.0).into()),
+        ((30.0, 5.0).into(), (50.0, 25.0).into()),
+        ((100.0, -5.0).into(), (110.0, 0.0).into()),
+    ];
+    let target = Alignment::Left.target(&bboxes).unwrap();
+    assert_eq!(target, 0.0);
+    for bbox in bboxes {
+        let offset = Alignment::Left.offset(bbox, target);
+        assert_eq!(offset.y, 0.0);
+        assert_eq!(bbox.0.x + offset.x, 0.0);
+    }
+    let target = Alignment::Top.target(&bboxes).unwrap();
+    assert_eq!(target, 25.0);
+    assert_eq!(Alignment::Top.offset(bboxes[2], target), (0.0, 25.0).into());
+    let target = Alignment::HorizontalCenter.target(&bboxes).unwrap();
+    assert_eq!(target, 55.0);
+    assert_eq!(
+        Alignment::HorizontalCenter.offset(bboxes[1], target),
+        (15.0, 0.0).into()
+    );
+
+    /* Centers are 5, 40 and 105; the middle one moves to 55. */
+    let offsets = distribute(&bboxes, true);
+    assert_eq!(
+        offsets,
+        vec![(0.0, 0.0).into(), (15.0, 0.0).into(), (0.0, 0.0).into()]
+    );
+    /* Vertical centers are 5, 15 and -2.5, so the first box is the middle one. */
+    let offsets = distribute(&bboxes, false);
+    assert_eq!(
+        offsets,
+        vec![(0.0, 1.25).into(), (0.0, 0.0).into(), (0.0, 0.0).into()]
+    );
+    assert_eq!(distribute(&bboxes[..2], true), vec![Point::default(); 2]);
+}
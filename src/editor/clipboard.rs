@@ -0,0 +1,380 @@
+/*
+ * gerb
+ *
+ * Copyright 2022 - Manos Pitsidianakis
+ *
+ * This file is part of gerb.
+ *
+ * gerb is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * gerb is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with gerb. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Copying, cutting and pasting outlines.
+//!
+//! Contours, components and anchors are put on the clipboard as a `.glif` document, which pastes
+//! into another glyph without losing anything, and as SVG for other applications. SVG documents
+//! and path data copied from other applications, e.g. Inkscape, are pasted as contours.
+
+use super::*;
+use crate::glyphs::svg;
+use crate::ufo::glif;
+use crate::views::canvas::UnitPoint;
+
+/// Clipboard target of the `.glif` document.
+const GLIF_TARGET: &str = "application/x-gerb-glif";
+const SVG_TARGET: &str = "image/svg+xml";
+
+/// Targets offered when copying, with the `info` value that identifies each format.
+const TARGETS: [(&str, u32); 5] = [
+    (GLIF_TARGET, 0),
+    (SVG_TARGET, 1),
+    ("UTF8_STRING", 2),
+    ("text/plain;charset=utf-8", 2),
+    ("text/plain", 2),
+];
+
+type Outline = (Vec<Contour>, Vec<Component>, Vec<Anchor>);
+
+impl State {
+    /// Indices of the contours with selected points and of the selected components and
+    /// anchors.
+    pub fn selected_outline(&self) -> (Vec<usize>, Vec<usize>, Vec<usize>) {
+        let glyph = self.glyph.borrow();
+        let anchors = glyph.metadata.anchors();
+        let (mut contours, mut components, mut anchor_idxs) = (vec![], vec![], vec![]);
+        for idx in &self.selection {
+            let (list, i) = if idx.is_anchor() {
+                (
+                    &mut anchor_idxs,
+                    anchors.iter().position(|a| a.uuid == idx.uuid),
+                )
+            } else if idx.is_component() {
+                (
+                    &mut components,
+                    glyph.components.iter().position(|c| c.uuid == idx.uuid),
+                )
+            } else {
+                (
+                    &mut contours,
+                    (idx.contour_index < glyph.contours.len()).then_some(idx.contour_index),
+                )
+            };
+            if let Some(i) = i.filter(|i| !list.contains(i)) {
+                list.push(i);
+            }
+        }
+        contours.sort_unstable();
+        components.sort_unstable();
+        anchor_idxs.sort_unstable();
+        (contours, components, anchor_idxs)
+    }
+
+    /// Append contours, components and anchors to the glyph, as one action.
+    pub fn add_outline(
+        &self,
+        contours: Vec<Contour>,
+        components: Vec<Component>,
+        anchors: Vec<Anchor>,
+    ) -> Action {
+        let mut actions = vec![];
+        if !contours.is_empty() {
+            let mut all = self.glyph.borrow().contours.clone();
+            all.extend(contours);
+            actions.push(self.replace_contours(all));
+        }
+        actions.extend(components.into_iter().map(|c| self.add_component(c)));
+        actions.extend(anchors.into_iter().map(|a| self.add_anchor(a)));
+        Action::compound(
+            EventStamp {
+                t: std::any::TypeId::of::<Self>(),
+                property: "paste",
+                id: Box::new([]),
+            },
+            actions,
+        )
+    }
+
+    /// Delete the contours, components and anchors at the given sorted indices, as one action.
+    pub fn delete_outline(
+        &self,
+        contours: &[usize],
+        components: &[usize],
+        anchors: &[usize],
+    ) -> Action {
+        let mut actions = vec![];
+        if !contours.is_empty() {
+            let remaining = self
+                .glyph
+                .borrow()
+                .contours
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| !contours.contains(i))
+                .map(|(_, c)| c.clone())
+                .collect();
+            actions.push(self.replace_contours(remaining));
+        }
+        /* Delete from the end so that the remaining indices stay valid. */
+        actions.extend(components.iter().rev().map(|&i| self.delete_component(i)));
+        actions.extend(anchors.iter().rev().map(|&i| self.delete_anchor(i)));
+        Action::compound(
+            EventStamp {
+                t: std::any::TypeId::of::<Self>(),
+                property: "cut",
+                id: Box::new([]),
+            },
+            actions,
+        )
+    }
+}
+
+impl Editor {
+    /// Put the contours with selected points and the selected components and anchors on the
+    /// clipboard. Returns `false` if there is nothing to copy.
+    pub fn copy_selection(&self) -> bool {
+        let state = self.state().borrow();
+        let (contours, components, anchors) = state.selected_outline();
+        if contours.is_empty() && components.is_empty() && anchors.is_empty() {
+            return false;
+        }
+        let glyph = state.glyph.borrow();
+        let contours = contours
+            .iter()
+            .map(|&i| glyph.contours[i].clone())
+            .collect::<Vec<Contour>>();
+        let mut outline = components
+            .iter()
+            .map(|&i| glif::OutlineEntry::Component((&glyph.components[i]).into()))
+            .collect::<Vec<_>>();
+        outline.extend(
+            contours
+                .iter()
+                .map(|c| glif::OutlineEntry::Contour(c.into())),
+        );
+        let anchors = {
+            let all = glyph.metadata.anchors();
+            anchors.iter().map(|&i| (&all[i]).into()).collect()
+        };
+        let xml = glif::Glif {
+            name: glyph.name().to_string(),
+            format: Some("2".to_string()),
+            unicode: vec![],
+            image: None,
+            advance: None,
+            outline: Some(glif::Outline { contours: outline }),
+            anchors,
+            guidelines: vec![],
+            lib: IndexMap::default(),
+            lib_source: None,
+        }
+        .to_xml();
+        let document = svg::document(&contours);
+        let path_data = svg::path_data(&contours);
+        let targets = TARGETS
+            .iter()
+            .map(|&(target, info)| gtk::TargetEntry::new(target, gtk::TargetFlags::empty(), info))
+            .collect::<Vec<_>>();
+        gtk::Clipboard::get(&gtk::gdk::SELECTION_CLIPBOARD).set_with_data(
+            &targets,
+            move |_, selection, info| match info {
+                0 => selection.set(&selection.target(), 8, xml.as_bytes()),
+                1 => selection.set(&selection.target(), 8, document.as_bytes()),
+                _ => {
+                    selection.set_text(&path_data);
+                }
+            },
+        );
+        true
+    }
+
+    /// Copy the selection to the clipboard and delete it from the glyph.
+    pub fn cut_selection(&self) {
+        if !self.copy_selection() {
+            return;
+        }
+        let (contours, components, anchors) = self.state().borrow().selected_outline();
+        self.set_selection(&[], SelectionModifier::Replace);
+        let state = self.state().borrow();
+        let mut action = state.delete_outline(&contours, &components, &anchors);
+        (action.redo)();
+        state.add_undo_action(action);
+    }
+
+    /// Paste outlines from the clipboard and select them. They keep their coordinates if
+    /// `in_place` is true, otherwise they are centered on the mouse pointer.
+    pub fn paste(&self, in_place: bool) {
+        let clipboard = gtk::Clipboard::get(&gtk::gdk::SELECTION_CLIPBOARD);
+        let contents = |target: &str| {
+            clipboard
+                .wait_for_contents(&gtk::gdk::Atom::intern(target))
+                .map(|s| s.data())
+                .filter(|data| !data.is_empty())
+                .and_then(|data| String::from_utf8(data).ok())
+        };
+        let cursor = (!in_place).then(|| {
+            let viewport = &self.imp().viewport;
+            let UnitPoint(p) = viewport.view_to_unit_point(viewport.get_mouse());
+            p
+        });
+        let outline = if let Some(xml) = contents(GLIF_TARGET) {
+            self.glif_outline(&xml, cursor)
+        } else if let Some(document) = contents(SVG_TARGET) {
+            svg::parse_document(&document).map(|d| svg_outline(d.subpaths, cursor))
+        } else if let Some(text) = clipboard.wait_for_text() {
+            let text = text.trim();
+            if text.starts_with('<') {
+                svg::parse_document(text).map(|d| d.subpaths)
+            } else {
+                svg::parse_path_data(text)
+            }
+            .map(|subpaths| svg_outline(subpaths, cursor))
+        } else {
+            return;
+        };
+        let (contours, components, anchors) = match outline {
+            Ok(outline) => outline,
+            Err(err) => {
+                let dialog = crate::utils::widgets::new_simple_error_dialog(
+                    Some("Error: Could not paste."),
+                    &err.to_string(),
+                    None,
+                    self.app().window.upcast_ref(),
+                );
+                dialog.run();
+                dialog.emit_close();
+                return;
+            }
+        };
+        let selection = {
+            let state = self.state().borrow();
+            let (contours_start, components_start, anchors_start) = {
+                let glyph = state.glyph.borrow();
+                let anchors_len = glyph.metadata.anchors().len();
+                (glyph.contours.len(), glyph.components.len(), anchors_len)
+            };
+            let mut action = state.add_outline(contours, components, anchors);
+            (action.redo)();
+            state.add_undo_action(action);
+            let glyph = state.glyph.borrow();
+            let mut selection = vec![];
+            for (contour_index, contour) in glyph.contours.iter().enumerate().skip(contours_start) {
+                for (curve_index, curve) in contour.curves().iter().enumerate() {
+                    selection.extend(
+                        curve
+                            .points()
+                            .iter()
+                            .map(|p| p.glyph_index(contour_index, curve_index)),
+                    );
+                }
+            }
+            selection.extend(
+                glyph
+                    .components
+                    .iter()
+                    .enumerate()
+                    .skip(components_start)
+                    .map(|(i, c)| c.glyph_index(i)),
+            );
+            selection.extend(
+                glyph
+                    .metadata
+                    .anchors()
+                    .iter()
+                    .enumerate()
+                    .skip(anchors_start)
+                    .map(|(i, a)| a.glyph_index(i)),
+            );
+            selection
+        };
+        self.set_selection(&selection, SelectionModifier::Replace);
+    }
+
+    /// Read a `.glif` document put on the clipboard by [`Editor::copy_selection`], centered on
+    /// `cursor` if given. Components are linked to the glyphs of the edited glyph's layer,
+    /// except for components of the edited glyph itself which are dropped.
+    fn glif_outline(
+        &self,
+        xml: &str,
+        cursor: Option<Point>,
+    ) -> Result<Outline, Box<dyn std::error::Error>> {
+        let mut glif = xml.parse::<glif::Glif>()?;
+        if let (Some(cursor), Some(outline)) = (cursor, glif.outline.as_mut()) {
+            let positions =
+                glif.anchors
+                    .iter()
+                    .map(|a| (a.x, a.y))
+                    .chain(outline.contours.iter().flat_map(|entry| match entry {
+                        glif::OutlineEntry::Contour(c) => {
+                            c.point.iter().map(|p| (p.x, p.y)).collect()
+                        }
+                        glif::OutlineEntry::Component(c) => vec![(c.x_offset, c.y_offset)],
+                    }));
+            if let Some((min, max)) = align::bounds(positions.map(|p| (p.into(), p.into()))) {
+                let d = cursor - 0.5 * (min + max);
+                for entry in &mut outline.contours {
+                    match entry {
+                        glif::OutlineEntry::Contour(c) => {
+                            for p in &mut c.point {
+                                p.x += d.x;
+                                p.y += d.y;
+                            }
+                        }
+                        glif::OutlineEntry::Component(c) => {
+                            c.x_offset += d.x;
+                            c.y_offset += d.y;
+                        }
+                    }
+                }
+                for a in &mut glif.anchors {
+                    a.x += d.x;
+                    a.y += d.y;
+                }
+            }
+        }
+        let mut pasted = Glyph::try_from(glif)?;
+        let glyphs = self.layer_glyphs();
+        let edited = self.glyph();
+        let components = std::mem::take(&mut pasted.components)
+            .into_iter()
+            .filter_map(|mut c| {
+                if let Some(base) = glyphs.get(&c.base_name) {
+                    if Rc::ptr_eq(base, edited) {
+                        return None;
+                    }
+                    c.base = Rc::downgrade(base);
+                }
+                Some(c)
+            })
+            .collect();
+        let anchors = pasted.metadata.anchors().iter().cloned().collect();
+        Ok((std::mem::take(&mut pasted.contours), components, anchors))
+    }
+}
+
+/// Convert SVG subpaths to contours in font units, centered on `cursor` if given.
+fn svg_outline(mut subpaths: Vec<svg::Subpath>, cursor: Option<Point>) -> Outline {
+    for s in &mut subpaths {
+        s.transform(svg::flip_y());
+    }
+    if let Some((cursor, (min, max))) = cursor.zip(svg::bbox(&subpaths)) {
+        let d = cursor - 0.5 * (min + max);
+        for s in &mut subpaths {
+            s.transform(Matrix::new(1.0, 0.0, 0.0, 1.0, d.x, d.y));
+        }
+    }
+    (
+        subpaths.iter().map(svg::Subpath::to_contour).collect(),
+        vec![],
+        vec![],
+    )
+}
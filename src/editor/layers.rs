@@ -123,6 +123,10 @@ pub fn draw_glyph_layer(viewport: &Canvas, mut cr: ContextRef, obj: Editor) -> I
                 corner: handle,
                 smooth_corner: handle,
                 direction_arrow,
+                direction_convention: Some(
+                    obj.app_settings()
+                        .property::<ContourDirection>(Settings::CONTOUR_DIRECTION),
+                ),
                 selection: Some(state.get_selection_set()),
             }
         };
@@ -297,6 +301,126 @@ pub fn draw_guidelines(viewport: &Canvas, mut cr: ContextRef, obj: Editor) -> In
     Inhibit(false)
 }
 
+pub fn draw_anchors(viewport: &Canvas, mut cr: ContextRef, obj: Editor) -> Inhibit {
+    let preview = obj.property::<bool>(Editor::PREVIEW);
+    if preview {
+        return Inhibit(false);
+    }
+    let scale: f64 = viewport
+        .transformation
+        .property::<f64>(Transformation::SCALE);
+    let ppu: f64 = viewport
+        .transformation
+        .property::<f64>(Transformation::PIXELS_PER_UNIT);
+    let matrix = viewport.transformation.matrix();
+    let state = obj.state().borrow();
+    let selection = state.get_selection_set();
+    let glyph = state.glyph.borrow();
+    let anchors = glyph.metadata.anchors();
+    if anchors.is_empty() {
+        return Inhibit(false);
+    }
+    cr.transform(matrix);
+    cr.set_line_width(obj.app_settings().property::<f64>(Settings::LINE_WIDTH) / (scale * ppu));
+    for anchor in anchors.iter() {
+        anchor.draw(
+            cr.push(),
+            6.0 / (scale * ppu),
+            selection.contains(&anchor.uuid),
+        );
+    }
+    Inhibit(false)
+}
+
+/// Draw the outline and advance width of the glyph interpolated at the preview location.
+pub fn draw_interpolation(viewport: &Canvas, mut cr: ContextRef, obj: Editor) -> Inhibit {
+    let preview = obj.property::<bool>(Editor::PREVIEW);
+    if preview {
+        return Inhibit(false);
+    }
+    let Some(designspace) = obj.designspace.borrow().clone() else {
+        return Inhibit(false);
+    };
+    let location = obj.interpolation_location.borrow().clone();
+    let mut cache = obj.interpolation_cache.borrow_mut();
+    if cache.as_ref().map(|(l, _)| l) != Some(&location) {
+        let name = obj.glyph().borrow().name().to_string();
+        let result = designspace
+            .interpolate(&name, &location)
+            .map_err(|err| err.to_string());
+        *cache = Some((location, result));
+    }
+    let glyph = match cache.as_ref().map(|(_, result)| result) {
+        Some(Ok(glyph)) => glyph,
+        None => return Inhibit(false),
+        Some(Err(err)) => {
+            cr.set_source_color(Color::RED);
+            let ruler = viewport.property::<f64>(Canvas::RULER_BREADTH_PIXELS);
+            cr.move_to(2.0 * ruler, 3.0 * ruler);
+            cr.show_text(&format!("Interpolation: {err}")).unwrap();
+            return Inhibit(false);
+        }
+    };
+    let scale: f64 = viewport
+        .transformation
+        .property::<f64>(Transformation::SCALE);
+    let ppu: f64 = viewport
+        .transformation
+        .property::<f64>(Transformation::PIXELS_PER_UNIT);
+    let units_per_em = obj.property::<f64>(Editor::UNITS_PER_EM);
+    let color = Color::from_hex("#1e88e5"); // [ref:hardcoded_color_value]
+    let line_width = obj.app_settings().property::<f64>(Settings::LINE_WIDTH);
+    cr.transform(viewport.transformation.matrix());
+    glyph.draw(
+        cr.push(),
+        GlyphDrawingOptions {
+            outline: <DrawOptions>::from((color, line_width)).scale(scale * ppu),
+            units_per_em,
+            ..Default::default()
+        },
+    );
+    if let Some(width) = glyph.width() {
+        let cr1 = cr.push();
+        cr1.set_source_color(color);
+        cr1.set_line_width(line_width / (scale * ppu));
+        cr1.set_dash(&[4.0 / (scale * ppu)], 0.0);
+        cr1.move_to(width, obj.property::<f64>(Editor::DESCENDER));
+        cr1.line_to(width, obj.property::<f64>(Editor::ASCENDER));
+        cr1.stroke().unwrap();
+    }
+    Inhibit(false)
+}
+
+/// Draw the result of an operation that is being previewed before it is applied, such as path
+/// offsetting.
+pub fn draw_preview_contours(viewport: &Canvas, mut cr: ContextRef, obj: Editor) -> Inhibit {
+    if obj.preview_contours.borrow().is_empty() {
+        return Inhibit(false);
+    }
+    let mut glyph = obj.glyph().borrow().clone();
+    glyph.contours = obj.preview_contours.borrow().clone();
+    glyph.components.clear();
+    let scale: f64 = viewport
+        .transformation
+        .property::<f64>(Transformation::SCALE);
+    let ppu: f64 = viewport
+        .transformation
+        .property::<f64>(Transformation::PIXELS_PER_UNIT);
+    let units_per_em = obj.property::<f64>(Editor::UNITS_PER_EM);
+    let color = Color::from_hex("#1e88e5"); // [ref:hardcoded_color_value]
+    let line_width = obj.app_settings().property::<f64>(Settings::LINE_WIDTH);
+    cr.transform(viewport.transformation.matrix());
+    glyph.draw(
+        cr.push(),
+        GlyphDrawingOptions {
+            outline: <DrawOptions>::from((color, line_width)).scale(scale * ppu),
+            units_per_em,
+            ..Default::default()
+        },
+    );
+    Inhibit(false)
+}
+
 impl EditorInner {
     pub fn create_layer_widget(&self) -> gtk::ListBox {
         let listbox = gtk::ListBox::builder()
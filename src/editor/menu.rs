@@ -19,11 +19,13 @@
  * along with gerb. If not, see <http://www.gnu.org/licenses/>.
  */
 
-use super::{Editor, EditorInner};
-use crate::glyphs::Contour;
+use super::{Alignment, Editor, EditorInner};
+use crate::glyphs::{boolean::BooleanOperation, Anchor, Component, Contour};
 use crate::prelude::*;
-use crate::views::Canvas;
+use crate::views::{canvas::Layer, Canvas};
+use gtk::cairo::Matrix;
 use gtk::{gio, glib::subclass::prelude::*, prelude::*};
+use indexmap::IndexMap;
 
 fn new_accel_item(menu: &gio::Menu, app: &Application, label: &str, detailed_action_name: &str) {
     let item = gio::MenuItem::new(Some(label), Some(detailed_action_name));
@@ -49,7 +51,27 @@ impl EditorInner {
             new_accel_item(&glyph_menu, app, "Save", "glyph.save");
             new_accel_item(&glyph_menu, app, "Properties", "glyph.properties");
             new_accel_item(&glyph_menu, app, "Inspect", "glyph.inspect");
+            new_accel_item(&glyph_menu, app, "Import SVG…", "glyph.import.svg");
+            new_accel_item(
+                &glyph_menu,
+                app,
+                "Import SVG as background image…",
+                "glyph.import.svg-image",
+            );
             new_accel_item(&glyph_menu, app, "Export to SVG", "glyph.export.svg");
+            {
+                let edit_glyph_menu = gio::Menu::new();
+                new_accel_item(&edit_glyph_menu, app, "Cut", "view.cut");
+                new_accel_item(&edit_glyph_menu, app, "Copy", "view.copy");
+                new_accel_item(&edit_glyph_menu, app, "Paste", "view.paste");
+                new_accel_item(
+                    &edit_glyph_menu,
+                    app,
+                    "Paste in place",
+                    "view.paste.in-place",
+                );
+                glyph_menu.append_section(None, &edit_glyph_menu);
+            }
             {
                 let view_glyph_menu = gio::Menu::new();
                 new_accel_item(&view_glyph_menu, app, "Show grid", "glyph.show.grid");
@@ -105,8 +127,202 @@ impl EditorInner {
             let contour_menu = gio::Menu::new();
             new_accel_item(&contour_menu, app, "Properties", "glyph.contour.properties");
             new_accel_item(&contour_menu, app, "Reverse", "glyph.contour.reverse");
+            {
+                let boolean_menu = gio::Menu::new();
+                for (label, action_name, operation) in [
+                    (
+                        "Remove overlap",
+                        "remove-overlap",
+                        BooleanOperation::RemoveOverlap,
+                    ),
+                    ("Union", "union", BooleanOperation::Union),
+                    ("Difference", "difference", BooleanOperation::Difference),
+                    (
+                        "Intersection",
+                        "intersection",
+                        BooleanOperation::Intersection,
+                    ),
+                    ("Exclusion", "exclusion", BooleanOperation::Xor),
+                ] {
+                    new_accel_item(
+                        &boolean_menu,
+                        app,
+                        label,
+                        &format!("glyph.contour.{action_name}"),
+                    );
+                    let action =
+                        gtk::gio::SimpleAction::new(&format!("contour.{action_name}"), None);
+                    action.connect_activate(glib::clone!(@weak obj => move |_, _| {
+                        obj.boolean_operation(operation);
+                    }));
+                    action_group.add_action(&action);
+                }
+                contour_menu.append_section(None, &boolean_menu);
+            }
+            {
+                let path_menu = gio::Menu::new();
+                new_accel_item(&path_menu, app, "Offset path…", "glyph.contour.offset");
+                let action = gtk::gio::SimpleAction::new("contour.offset", None);
+                action.connect_activate(glib::clone!(@weak obj => move |_, _| {
+                    obj.offset_dialog();
+                }));
+                action_group.add_action(&action);
+                new_accel_item(&path_menu, app, "Simplify…", "glyph.contour.simplify");
+                let action = gtk::gio::SimpleAction::new("contour.simplify", None);
+                action.connect_activate(glib::clone!(@weak obj => move |_, _| {
+                    obj.simplify_dialog();
+                }));
+                action_group.add_action(&action);
+                for (label, action_name, f) in [
+                    (
+                        "Add points at extrema",
+                        "add-extrema",
+                        Contour::with_extrema as fn(&Contour) -> Option<Contour>,
+                    ),
+                    (
+                        "Remove collinear points",
+                        "remove-collinear-points",
+                        Contour::without_collinear_points,
+                    ),
+                ] {
+                    new_accel_item(
+                        &path_menu,
+                        app,
+                        label,
+                        &format!("glyph.contour.{action_name}"),
+                    );
+                    let action =
+                        gtk::gio::SimpleAction::new(&format!("contour.{action_name}"), None);
+                    action.connect_activate(glib::clone!(@weak obj => move |_, _| {
+                        obj.map_operand_contours(f);
+                    }));
+                    action_group.add_action(&action);
+                }
+                contour_menu.append_section(None, &path_menu);
+            }
+            {
+                let direction_menu = gio::Menu::new();
+                new_accel_item(
+                    &direction_menu,
+                    app,
+                    "Correct direction",
+                    "glyph.contour.correct-direction",
+                );
+                let action = gtk::gio::SimpleAction::new("contour.correct-direction", None);
+                action.connect_activate(glib::clone!(@weak obj => move |_, _| {
+                    obj.correct_direction();
+                }));
+                action_group.add_action(&action);
+                contour_menu.append_section(None, &direction_menu);
+            }
             menumodel.append_submenu(Some("_Contour"), &contour_menu);
         }
+        {
+            let transform_menu = gio::Menu::new();
+            new_accel_item(&transform_menu, app, "Transform…", "glyph.transform.dialog");
+            let action = gtk::gio::SimpleAction::new("transform.dialog", None);
+            action.connect_activate(glib::clone!(@weak obj => move |_, _| {
+                obj.transform_dialog();
+            }));
+            action_group.add_action(&action);
+            {
+                let flip_menu = gio::Menu::new();
+                for (label, action_name, horizontally) in [
+                    ("Flip horizontally", "flip-horizontal", true),
+                    ("Flip vertically", "flip-vertical", false),
+                ] {
+                    new_accel_item(
+                        &flip_menu,
+                        app,
+                        label,
+                        &format!("glyph.transform.{action_name}"),
+                    );
+                    let action =
+                        gtk::gio::SimpleAction::new(&format!("transform.{action_name}"), None);
+                    action.connect_activate(glib::clone!(@weak obj => move |_, _| {
+                        obj.flip_selection(horizontally);
+                    }));
+                    action_group.add_action(&action);
+                }
+                transform_menu.append_section(None, &flip_menu);
+            }
+            {
+                let align_menu = gio::Menu::new();
+                for (label, action_name, alignment) in [
+                    ("Align left", "left", Alignment::Left),
+                    (
+                        "Align horizontal centers",
+                        "horizontal-center",
+                        Alignment::HorizontalCenter,
+                    ),
+                    ("Align right", "right", Alignment::Right),
+                    ("Align top", "top", Alignment::Top),
+                    (
+                        "Align vertical centers",
+                        "vertical-center",
+                        Alignment::VerticalCenter,
+                    ),
+                    ("Align bottom", "bottom", Alignment::Bottom),
+                ] {
+                    new_accel_item(
+                        &align_menu,
+                        app,
+                        label,
+                        &format!("glyph.align.{action_name}"),
+                    );
+                    let action = gtk::gio::SimpleAction::new(&format!("align.{action_name}"), None);
+                    action.connect_activate(glib::clone!(@weak obj => move |_, _| {
+                        obj.align_selection(alignment);
+                    }));
+                    action_group.add_action(&action);
+                }
+                transform_menu.append_section(None, &align_menu);
+            }
+            {
+                let distribute_menu = gio::Menu::new();
+                for (label, action_name, horizontally) in [
+                    ("Distribute horizontally", "horizontal", true),
+                    ("Distribute vertically", "vertical", false),
+                ] {
+                    new_accel_item(
+                        &distribute_menu,
+                        app,
+                        label,
+                        &format!("glyph.distribute.{action_name}"),
+                    );
+                    let action =
+                        gtk::gio::SimpleAction::new(&format!("distribute.{action_name}"), None);
+                    action.connect_activate(glib::clone!(@weak obj => move |_, _| {
+                        obj.distribute_selection(horizontally);
+                    }));
+                    action_group.add_action(&action);
+                }
+                new_accel_item(
+                    &distribute_menu,
+                    app,
+                    "Align to metric…",
+                    "glyph.align.metric",
+                );
+                let action = gtk::gio::SimpleAction::new("align.metric", None);
+                action.connect_activate(glib::clone!(@weak obj => move |_, _| {
+                    obj.align_to_metric_dialog();
+                }));
+                action_group.add_action(&action);
+                new_accel_item(
+                    &distribute_menu,
+                    app,
+                    "Center in advance width",
+                    "glyph.align.advance-center",
+                );
+                let action = gtk::gio::SimpleAction::new("align.advance-center", None);
+                action.connect_activate(glib::clone!(@weak obj => move |_, _| {
+                    obj.center_in_advance();
+                }));
+                action_group.add_action(&action);
+                transform_menu.append_section(None, &distribute_menu);
+            }
+            menumodel.append_submenu(Some("_Transform"), &transform_menu);
+        }
         {
             let guideline_menu = gio::Menu::new();
             new_accel_item(
@@ -178,6 +394,65 @@ impl EditorInner {
                 gtk::gio::PropertyAction::new("guideline.lock", obj, Editor::LOCK_GUIDELINES);
             action_group.add_action(&prop_action);
         }
+        {
+            let anchor_menu = gio::Menu::new();
+            new_accel_item(&anchor_menu, app, "Add anchor", "glyph.anchor.add");
+            menumodel.append_submenu(Some("_Anchors"), &anchor_menu);
+            let add_anchor = gtk::gio::SimpleAction::new("anchor.add", None);
+            add_anchor.connect_activate(glib::clone!(@weak obj, @weak app => move |_, _| {
+                let viewport = &obj.imp().viewport;
+                let UnitPoint(position) = viewport.view_to_unit_point(viewport.get_mouse());
+                let anchor = Anchor::new_at(String::new(), position);
+                let w = anchor.new_property_window(&app, true);
+                if let PropertyWindowButtons::Create { cancel: _, ref save } = w.imp().buttons.get().unwrap() {
+                    save.connect_clicked(clone!(@weak anchor, @weak w, @weak obj => move |_| {
+                        let state = obj.state().borrow();
+                        let mut action = state.add_anchor(anchor);
+                        (action.redo)();
+                        state.add_undo_action(action);
+                        w.close();
+                    }));
+                }
+                w.present();
+            }));
+            action_group.add_action(&add_anchor);
+        }
+        {
+            let component_menu = gio::Menu::new();
+            new_accel_item(&component_menu, app, "Add component", "glyph.component.add");
+            menumodel.append_submenu(Some("Co_mponents"), &component_menu);
+            let add_component = gtk::gio::SimpleAction::new("component.add", None);
+            add_component.connect_activate(glib::clone!(@weak obj => move |_, _| {
+                obj.add_component_dialog();
+            }));
+            action_group.add_action(&add_component);
+        }
+        {
+            let masters_menu = gio::Menu::new();
+            new_accel_item(
+                &masters_menu,
+                app,
+                "Interpolation preview",
+                "glyph.interpolation.preview",
+            );
+            new_accel_item(
+                &masters_menu,
+                app,
+                "Check compatibility",
+                "glyph.interpolation.check",
+            );
+            menumodel.append_submenu(Some("_Masters"), &masters_menu);
+            let preview = gtk::gio::SimpleAction::new("interpolation.preview", None);
+            preview.connect_activate(glib::clone!(@weak obj => move |_, _| {
+                obj.interpolation_dialog();
+            }));
+            action_group.add_action(&preview);
+            let check = gtk::gio::SimpleAction::new("interpolation.check", None);
+            check.connect_activate(glib::clone!(@weak obj => move |_, _| {
+                obj.check_compatibility_dialog();
+            }));
+            action_group.add_action(&check);
+        }
         {
             let layer_menu = gio::Menu::new();
             new_accel_item(&layer_menu, app, "Properties", "glyph.layer.properties");
@@ -241,6 +516,16 @@ impl EditorInner {
                 dialog.emit_close();
             }));
             action_group.add_action(&export_svg);
+            let import_svg = gtk::gio::SimpleAction::new("import.svg", None);
+            import_svg.connect_activate(clone!(@weak obj => move |_, _| {
+                obj.import_svg_dialog();
+            }));
+            action_group.add_action(&import_svg);
+            let import_svg_image = gtk::gio::SimpleAction::new("import.svg-image", None);
+            import_svg_image.connect_activate(clone!(@weak obj => move |_, _| {
+                obj.import_svg_image_dialog();
+            }));
+            action_group.add_action(&import_svg_image);
             self.menubar
                 .insert_action_group("glyph", Some(&action_group));
             obj.insert_action_group("glyph", Some(&action_group));
@@ -250,6 +535,995 @@ impl EditorInner {
 }
 
 impl Editor {
+    /// Glyphs of the layer the edited glyph belongs to.
+    pub(super) fn layer_glyphs(&self) -> IndexMap<String, Rc<RefCell<Glyph>>> {
+        let layer = self
+            .glyph()
+            .borrow()
+            .metadata
+            .layer()
+            .clone()
+            .unwrap_or_else(|| self.project().default_layer.clone());
+        let glyphs = layer.glyphs().clone();
+        glyphs
+    }
+
+    /// Ask for an SVG file and add its paths to the glyph's contours.
+    pub fn import_svg_dialog(&self) {
+        use crate::glyphs::svg;
+
+        let Some((path, options)) =
+            svg::import_dialog(self.app().window.upcast_ref(), self.project(), false)
+        else {
+            return;
+        };
+        let contours = match std::fs::read_to_string(&path)
+            .map_err(Into::into)
+            .and_then(|s| svg::import(&s, options))
+        {
+            Ok(contours) => contours,
+            Err(err) => {
+                let dialog = crate::utils::widgets::new_simple_error_dialog(
+                    Some("Error: Could not import SVG file"),
+                    &err.to_string(),
+                    Some(&format!("Path: {}", path.display())),
+                    self.app().window.upcast_ref(),
+                );
+                dialog.run();
+                dialog.emit_close();
+                return;
+            }
+        };
+        let state = self.state().borrow();
+        let mut action = state.add_outline(contours, vec![], vec![]);
+        (action.redo)();
+        state.add_undo_action(action);
+    }
+
+    /// Ask for an SVG file and show it behind the glyph as its image. Since UFO images are PNG
+    /// files, the document is rendered to one in the project's `images` directory.
+    pub fn import_svg_image_dialog(&self) {
+        use crate::glyphs::svg;
+
+        let window = self.app().window.clone();
+        let dialog = gtk::FileChooserNative::builder()
+            .title("Import SVG file as background image")
+            .accept_label("Import")
+            .action(gtk::FileChooserAction::Open)
+            .transient_for(&window)
+            .modal(true)
+            .build();
+        let filter = gtk::FileFilter::new();
+        filter.set_name(Some("SVG files"));
+        filter.add_pattern("*.svg");
+        dialog.add_filter(&filter);
+        let response = dialog.run();
+        dialog.hide();
+        if response != gtk::ResponseType::Accept {
+            return;
+        }
+        let Some(path) = dialog.filename() else {
+            return;
+        };
+        let project = self.project();
+        let width = self
+            .viewport
+            .transformation
+            .property::<f64>(Transformation::CONTENT_WIDTH);
+        let height = project.property::<f64>(Project::ASCENDER)
+            - project.property::<f64>(Project::DESCENDER);
+        /* Don't overwrite existing images, which undoing may bring back. */
+        let stem = std::path::Path::new(&*self.glyph().borrow().metadata.filename.borrow())
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .filter(|s| !s.is_empty())
+            .unwrap_or_else(|| "image".to_string());
+        let images = project.path.borrow().join("images");
+        let file_name = std::iter::once(format!("{stem}.png"))
+            .chain((1..).map(|i| format!("{stem}.{i}.png")))
+            .find(|f| !images.join(f).exists())
+            .unwrap();
+        let result = svg::render_image(&path, width, height).and_then(|pixbuf| {
+            project.save_image(&file_name, &pixbuf)?;
+            project.load_image(&file_name)
+        });
+        let surface = match result {
+            Ok(surface) => surface,
+            Err(err) => {
+                let dialog = crate::utils::widgets::new_simple_error_dialog(
+                    Some("Error: Could not import SVG file"),
+                    &err.to_string(),
+                    Some(&format!("Path: {}", path.display())),
+                    window.upcast_ref(),
+                );
+                dialog.run();
+                dialog.emit_close();
+                return;
+            }
+        };
+        let image_ref = ufo::glif::ImageRef {
+            file_name: Some(file_name),
+            x_scale: 1.0,
+            y_scale: 1.0,
+            ..Default::default()
+        };
+        let state = self.state().borrow();
+        let mut action = state.set_image(Some((image_ref, surface)));
+        (action.redo)();
+        state.add_undo_action(action);
+    }
+
+    /// Ask for a glyph name and add a component of that glyph.
+    pub fn add_component_dialog(&self) {
+        let dialog = gtk::Dialog::builder()
+            .attached_to(&self.app().window)
+            .application(self.app())
+            .border_width(10)
+            .destroy_with_parent(true)
+            .modal(true)
+            .title("Add component")
+            .build();
+        dialog.add_button("Add", gtk::ResponseType::Accept);
+        dialog.add_button("Cancel", gtk::ResponseType::Close);
+        dialog.set_default_response(gtk::ResponseType::Accept);
+        let glyphs = self.layer_glyphs();
+        let self_name = self.glyph().borrow().name().to_string();
+        let store = gtk::ListStore::new(&[String::static_type()]);
+        for name in glyphs.keys().filter(|n| **n != self_name) {
+            store.set(&store.append(), &[(0, name)]);
+        }
+        let completion = gtk::EntryCompletion::builder()
+            .model(&store)
+            .text_column(0)
+            .inline_completion(true)
+            .popup_completion(true)
+            .build();
+        let entry = gtk::Entry::builder()
+            .visible(true)
+            .activates_default(true)
+            .placeholder_text("base glyph name")
+            .completion(&completion)
+            .build();
+        dialog.content_area().pack_start(&entry, true, true, 5);
+        loop {
+            match dialog.run() {
+                gtk::ResponseType::Accept => {
+                    let name = entry.text().trim().to_string();
+                    let msg = match glyphs.get(&name) {
+                        None => Some(format!("Glyph <tt>{name}</tt> does not exist.")),
+                        Some(_) if name == self_name => {
+                            Some("A glyph cannot be a component of itself.".to_string())
+                        }
+                        Some(_) => None,
+                    };
+                    if let Some(msg) = msg {
+                        let err_dialog = crate::utils::widgets::new_simple_error_dialog(
+                            Some("Error: Could not add component."),
+                            &msg,
+                            None,
+                            dialog.upcast_ref(),
+                        );
+                        err_dialog.run();
+                        err_dialog.emit_close();
+                        continue;
+                    }
+                    let component = Component::new(name.clone(), Rc::downgrade(&glyphs[&name]));
+                    let state = self.state().borrow();
+                    let mut action = state.add_component(component);
+                    (action.redo)();
+                    state.add_undo_action(action);
+                    dialog.emit_close();
+                    break;
+                }
+                _ => {
+                    dialog.emit_close();
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Edit the affine transformation fields of the component at `idx`.
+    pub fn component_properties_dialog(&self, idx: usize) {
+        let (base_name, m) = {
+            let state = self.state().borrow();
+            let glyph = state.glyph.borrow();
+            let Some(c) = glyph.components.get(idx) else {
+                return;
+            };
+            (c.base_name.clone(), c.matrix())
+        };
+        let dialog = gtk::Dialog::builder()
+            .attached_to(&self.app().window)
+            .application(self.app())
+            .border_width(10)
+            .destroy_with_parent(true)
+            .modal(true)
+            .title(&format!("Component {base_name}"))
+            .build();
+        dialog.add_button("Apply", gtk::ResponseType::Accept);
+        dialog.add_button("Cancel", gtk::ResponseType::Close);
+        let grid = gtk::Grid::builder()
+            .row_spacing(5)
+            .column_spacing(5)
+            .visible(true)
+            .build();
+        let fields = [
+            ("x offset", m.x0, 1.0),
+            ("y offset", m.y0, 1.0),
+            ("x scale", m.xx, 0.01),
+            ("xy scale", m.yx, 0.01),
+            ("yx scale", m.xy, 0.01),
+            ("y scale", m.yy, 0.01),
+        ]
+        .map(|(label, value, step)| {
+            let entry = gtk::SpinButton::with_range(-10000.0, 10000.0, step);
+            entry.set_digits(if step < 1.0 { 3 } else { 1 });
+            entry.set_visible(true);
+            entry.set_value(value);
+            (label, entry)
+        });
+        for (row, (label, widget)) in (0..).zip(fields.iter()) {
+            grid.attach(
+                &gtk::Label::builder()
+                    .label(label)
+                    .halign(gtk::Align::End)
+                    .visible(true)
+                    .build(),
+                0,
+                row,
+                1,
+                1,
+            );
+            grid.attach(widget, 1, row, 1, 1);
+        }
+        dialog.content_area().pack_start(&grid, true, true, 5);
+        if dialog.run() == gtk::ResponseType::Accept {
+            let [x0, y0, xx, yx, xy, yy] = fields.map(|(_, e)| e.value());
+            let new = Matrix::new(xx, yx, xy, yy, x0, y0);
+            let state = self.state().borrow();
+            let mut action = state.set_component_matrix(idx, new);
+            (action.redo)();
+            state.add_undo_action(action);
+        }
+        dialog.emit_close();
+    }
+
+    /// Indices of the contours with selected points in selection order, or of all contours if
+    /// nothing is selected.
+    fn operand_contours(&self) -> Vec<usize> {
+        let state = self.state().borrow();
+        let len = state.glyph.borrow().contours.len();
+        let mut operands: Vec<usize> = vec![];
+        for idx in state.get_selection() {
+            if idx.contour_index < len && !operands.contains(&idx.contour_index) {
+                operands.push(idx.contour_index);
+            }
+        }
+        if operands.is_empty() {
+            operands.extend(0..len);
+        }
+        operands
+    }
+
+    /// Replace the contours at `operands` with `result`, placed where the first of them was, as
+    /// one undoable action.
+    fn replace_operand_contours(&self, operands: &[usize], result: &[Contour]) {
+        let contours = self.state().borrow().glyph.borrow().contours.clone();
+        let first = operands.iter().copied().min().unwrap_or(0);
+        let mut new_contours = vec![];
+        for (i, contour) in contours.into_iter().enumerate() {
+            if i == first {
+                new_contours.extend(result.iter().cloned());
+            }
+            if !operands.contains(&i) {
+                new_contours.push(contour);
+            }
+        }
+        self.set_selection(&[], super::tools::SelectionModifier::Replace);
+        let state = self.state().borrow();
+        let mut action = state.replace_contours(new_contours);
+        (action.redo)();
+        state.add_undo_action(action);
+    }
+
+    /// Replace the contour at each index of `replacements` as one undoable action.
+    fn replace_contours_at(&self, replacements: Vec<(usize, Contour)>) {
+        if replacements.is_empty() {
+            return;
+        }
+        let mut new_contours = self.state().borrow().glyph.borrow().contours.clone();
+        for (i, contour) in replacements {
+            new_contours[i] = contour;
+        }
+        self.set_selection(&[], super::tools::SelectionModifier::Replace);
+        let state = self.state().borrow();
+        let mut action = state.replace_contours(new_contours);
+        (action.redo)();
+        state.add_undo_action(action);
+    }
+
+    /// Replace the selected contours, or all contours if nothing is selected, for which `f`
+    /// returns a new contour.
+    pub fn map_operand_contours(&self, f: impl Fn(&Contour) -> Option<Contour>) {
+        let contours = self.state().borrow().glyph.borrow().contours.clone();
+        self.replace_contours_at(
+            self.operand_contours()
+                .into_iter()
+                .filter_map(|i| Some((i, f(&contours[i])?)))
+                .collect(),
+        );
+    }
+
+    /// Reverse the selected contours, or all contours if nothing is selected, that run against
+    /// the contour direction convention of the settings.
+    pub fn correct_direction(&self) {
+        let convention = self
+            .app_settings()
+            .property::<ContourDirection>(Settings::CONTOUR_DIRECTION);
+        let (contours, wrong) = {
+            let state = self.state().borrow();
+            let glyph = state.glyph.borrow();
+            (glyph.contours.clone(), glyph.wrong_directions(convention))
+        };
+        self.replace_contours_at(
+            self.operand_contours()
+                .into_iter()
+                .filter(|i| wrong.contains(i))
+                .map(|i| (i, contours[i].reversed()))
+                .collect(),
+        );
+    }
+
+    /// Apply `operation` to the selected contours, or to all contours if nothing is selected.
+    /// The last selected contour is the second operand of the operation.
+    pub fn boolean_operation(&self, operation: BooleanOperation) {
+        let contours = self.state().borrow().glyph.borrow().contours.clone();
+        let mut operands = self.operand_contours();
+        operands.retain(|&i| !contours[i].property::<bool>(Contour::OPEN));
+        let error = |msg: &str| {
+            let dialog = crate::utils::widgets::new_simple_error_dialog(
+                Some(&format!("Error: Could not apply {operation}.")),
+                msg,
+                None,
+                self.app().window.upcast_ref(),
+            );
+            dialog.run();
+            dialog.emit_close();
+        };
+        let (a, b) = match operation {
+            BooleanOperation::RemoveOverlap if operands.is_empty() => {
+                error("There are no closed contours.");
+                return;
+            }
+            BooleanOperation::RemoveOverlap => (operands.as_slice(), &[][..]),
+            _ if operands.len() < 2 => {
+                error("Select at least two closed contours.");
+                return;
+            }
+            _ => operands.split_at(operands.len() - 1),
+        };
+        let get = |idxs: &[usize]| {
+            idxs.iter()
+                .map(|&i| contours[i].clone())
+                .collect::<Vec<_>>()
+        };
+        let result = match crate::glyphs::boolean::apply(&get(a), &get(b), operation) {
+            Ok(v) => v,
+            Err(err) => {
+                error(&err.to_string());
+                return;
+            }
+        };
+        self.replace_operand_contours(&operands, &result);
+    }
+
+    /// Offset the selected contours, or all contours if nothing is selected, showing the result
+    /// in a preview layer until it is applied. Open contours are expanded to strokes.
+    pub fn offset_dialog(&self) {
+        use crate::glyphs::offset::{self, Cap, Join, OffsetOptions};
+
+        let operands = self.operand_contours();
+        if operands.is_empty() {
+            return;
+        }
+        let contours = {
+            let state = self.state().borrow();
+            let glyph = state.glyph.borrow();
+            operands
+                .iter()
+                .map(|&i| glyph.contours[i].clone())
+                .collect::<Vec<Contour>>()
+        };
+        let dialog = gtk::Dialog::builder()
+            .attached_to(&self.app().window)
+            .application(self.app())
+            .border_width(10)
+            .destroy_with_parent(true)
+            .modal(true)
+            .title("Offset path")
+            .build();
+        dialog.add_button("Apply", gtk::ResponseType::Accept);
+        dialog.add_button("Cancel", gtk::ResponseType::Close);
+        dialog.set_default_response(gtk::ResponseType::Accept);
+        let defaults = OffsetOptions::default();
+        let distance = gtk::SpinButton::with_range(-500.0, 500.0, 1.0);
+        distance.set_digits(1);
+        distance.set_value(defaults.distance);
+        distance.set_activates_default(true);
+        let join = gtk::ComboBoxText::new();
+        for (id, label) in [("miter", "Miter"), ("round", "Round"), ("bevel", "Bevel")] {
+            join.append(Some(id), label);
+        }
+        join.set_active_id(Some(match defaults.join {
+            Join::Miter => "miter",
+            Join::Round => "round",
+            Join::Bevel => "bevel",
+        }));
+        let cap = gtk::ComboBoxText::new();
+        for (id, label) in [("butt", "Butt"), ("round", "Round"), ("square", "Square")] {
+            cap.append(Some(id), label);
+        }
+        cap.set_active_id(Some(match defaults.cap {
+            Cap::Butt => "butt",
+            Cap::Round => "round",
+            Cap::Square => "square",
+        }));
+        let message = gtk::Label::builder()
+            .wrap(true)
+            .visible(true)
+            .halign(gtk::Align::Start)
+            .build();
+        let grid = gtk::Grid::builder()
+            .row_spacing(5)
+            .column_spacing(5)
+            .visible(true)
+            .build();
+        for (row, (label, widget)) in (0..).zip([
+            ("Distance", distance.upcast_ref::<gtk::Widget>()),
+            ("Join", join.upcast_ref()),
+            ("Cap of open contours", cap.upcast_ref()),
+        ]) {
+            widget.set_visible(true);
+            grid.attach(
+                &gtk::Label::builder()
+                    .label(label)
+                    .halign(gtk::Align::End)
+                    .visible(true)
+                    .build(),
+                0,
+                row,
+                1,
+                1,
+            );
+            grid.attach(widget, 1, row, 1, 1);
+        }
+        grid.attach(&message, 0, 3, 2, 1);
+        dialog.content_area().pack_start(&grid, true, true, 5);
+        let compute = Rc::new(
+            clone!(@weak self as obj, @weak distance, @weak join, @weak cap, @weak message => @default-return None, move || {
+                let options = OffsetOptions {
+                    distance: distance.value(),
+                    join: match join.active_id().as_deref() {
+                        Some("miter") => Join::Miter,
+                        Some("bevel") => Join::Bevel,
+                        _ => Join::Round,
+                    },
+                    cap: match cap.active_id().as_deref() {
+                        Some("round") => Cap::Round,
+                        Some("square") => Cap::Square,
+                        _ => Cap::Butt,
+                    },
+                    ..OffsetOptions::default()
+                };
+                let result = offset::offset_contours(&contours, &options);
+                match result {
+                    Ok(ref result) => {
+                        message.set_text("");
+                        *obj.imp().preview_contours.borrow_mut() = result.clone();
+                    }
+                    Err(ref err) => {
+                        message.set_text(&err.to_string());
+                        obj.imp().preview_contours.borrow_mut().clear();
+                    }
+                }
+                obj.imp().viewport.queue_draw();
+                result.ok()
+            }),
+        );
+        distance.connect_value_changed(clone!(@strong compute => move |_| {
+            compute();
+        }));
+        for combo in [&join, &cap] {
+            combo.connect_changed(clone!(@strong compute => move |_| {
+                compute();
+            }));
+        }
+        compute();
+        loop {
+            if dialog.run() != gtk::ResponseType::Accept {
+                break;
+            }
+            if let Some(result) = compute() {
+                self.replace_operand_contours(&operands, &result);
+                break;
+            }
+        }
+        self.imp().preview_contours.borrow_mut().clear();
+        self.imp().viewport.queue_draw();
+        dialog.emit_close();
+    }
+
+    /// Refit the curves with selected points, or all curves if nothing is selected, with fewer
+    /// cubics. The result is shown in a preview layer until it is applied.
+    pub fn simplify_dialog(&self) {
+        use crate::glyphs::simplify;
+
+        let (contours, selection) = {
+            let state = self.state().borrow();
+            let contours = state.glyph.borrow().contours.clone();
+            (contours, state.get_selection().to_vec())
+        };
+        let dialog = gtk::Dialog::builder()
+            .attached_to(&self.app().window)
+            .application(self.app())
+            .border_width(10)
+            .destroy_with_parent(true)
+            .modal(true)
+            .title("Simplify")
+            .build();
+        dialog.add_button("Apply", gtk::ResponseType::Accept);
+        dialog.add_button("Cancel", gtk::ResponseType::Close);
+        dialog.set_default_response(gtk::ResponseType::Accept);
+        let tolerance = gtk::SpinButton::with_range(0.1, 100.0, 0.1);
+        tolerance.set_digits(1);
+        tolerance.set_value(1.0);
+        tolerance.set_activates_default(true);
+        tolerance.set_visible(true);
+        let message = gtk::Label::builder()
+            .wrap(true)
+            .visible(true)
+            .halign(gtk::Align::Start)
+            .build();
+        let grid = gtk::Grid::builder()
+            .row_spacing(5)
+            .column_spacing(5)
+            .visible(true)
+            .build();
+        grid.attach(
+            &gtk::Label::builder()
+                .label("Tolerance")
+                .halign(gtk::Align::End)
+                .visible(true)
+                .build(),
+            0,
+            0,
+            1,
+            1,
+        );
+        grid.attach(&tolerance, 1, 0, 1, 1);
+        grid.attach(&message, 0, 1, 2, 1);
+        dialog.content_area().pack_start(&grid, true, true, 5);
+        let compute = Rc::new(
+            clone!(@weak self as obj, @weak tolerance, @weak message => @default-return vec![], move || {
+                let selected = |contour_index: usize, curve_index: usize| {
+                    selection.is_empty()
+                        || selection
+                            .iter()
+                            .any(|i| i.contour_index == contour_index && i.curve_index == curve_index)
+                };
+                let result = contours
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(ci, contour)| {
+                        let new = simplify::simplify_contour(
+                            contour,
+                            |curve_index| selected(ci, curve_index),
+                            tolerance.value(),
+                        )?;
+                        Some((ci, new))
+                    })
+                    .collect::<Vec<(usize, Contour)>>();
+                let count = |c: &Contour| c.curves().len();
+                let (before, after) = result.iter().fold((0, 0), |(before, after), (ci, new)| {
+                    (before + count(&contours[*ci]), after + count(new))
+                });
+                message.set_text(&if result.is_empty() {
+                    "Nothing can be simplified within this tolerance.".to_string()
+                } else {
+                    format!("{before} curves will be replaced by {after}.")
+                });
+                *obj.imp().preview_contours.borrow_mut() =
+                    result.iter().map(|(_, c)| c.clone()).collect();
+                obj.imp().viewport.queue_draw();
+                result
+            }),
+        );
+        tolerance.connect_value_changed(clone!(@strong compute => move |_| {
+            compute();
+        }));
+        compute();
+        if dialog.run() == gtk::ResponseType::Accept {
+            self.replace_contours_at(compute());
+        }
+        self.imp().preview_contours.borrow_mut().clear();
+        self.imp().viewport.queue_draw();
+        dialog.emit_close();
+    }
+
+    /// Move, scale, rotate and skew the selection by exact amounts around an origin that
+    /// starts at the center of the selection.
+    pub fn transform_dialog(&self) {
+        use super::tools::TransformParameters;
+
+        let Some((min, max)) = self.state().borrow().selection_bbox() else {
+            return;
+        };
+        let dialog = gtk::Dialog::builder()
+            .attached_to(&self.app().window)
+            .application(self.app())
+            .border_width(10)
+            .destroy_with_parent(true)
+            .modal(true)
+            .title("Transform")
+            .build();
+        dialog.add_button("Apply", gtk::ResponseType::Accept);
+        dialog.add_button("Cancel", gtk::ResponseType::Close);
+        dialog.set_default_response(gtk::ResponseType::Accept);
+        let spin_button = |min: f64, max: f64, value: f64| {
+            let ret = gtk::SpinButton::with_range(min, max, 1.0);
+            ret.set_digits(2);
+            ret.set_value(value);
+            ret.set_activates_default(true);
+            ret
+        };
+        let center = 0.5 * (min + max);
+        let move_x = spin_button(-10000.0, 10000.0, 0.0);
+        let move_y = spin_button(-10000.0, 10000.0, 0.0);
+        let scale_x = spin_button(-10000.0, 10000.0, 100.0);
+        let scale_y = spin_button(-10000.0, 10000.0, 100.0);
+        let rotate = spin_button(-360.0, 360.0, 0.0);
+        let skew_x = spin_button(-89.0, 89.0, 0.0);
+        let skew_y = spin_button(-89.0, 89.0, 0.0);
+        let origin_x = spin_button(-10000.0, 10000.0, center.x);
+        let origin_y = spin_button(-10000.0, 10000.0, center.y);
+        let message = gtk::Label::builder()
+            .wrap(true)
+            .visible(true)
+            .halign(gtk::Align::Start)
+            .build();
+        let grid = gtk::Grid::builder()
+            .row_spacing(5)
+            .column_spacing(5)
+            .visible(true)
+            .build();
+        for (row, (label, x, y)) in (0..).zip([
+            ("Move", &move_x, &move_y),
+            ("Scale (%)", &scale_x, &scale_y),
+            ("Skew (°)", &skew_x, &skew_y),
+            ("Origin", &origin_x, &origin_y),
+        ]) {
+            x.set_visible(true);
+            y.set_visible(true);
+            grid.attach(
+                &gtk::Label::builder()
+                    .label(label)
+                    .halign(gtk::Align::End)
+                    .visible(true)
+                    .build(),
+                0,
+                row,
+                1,
+                1,
+            );
+            grid.attach(x, 1, row, 1, 1);
+            grid.attach(y, 2, row, 1, 1);
+        }
+        rotate.set_visible(true);
+        grid.attach(
+            &gtk::Label::builder()
+                .label("Rotate (°)")
+                .halign(gtk::Align::End)
+                .visible(true)
+                .build(),
+            0,
+            4,
+            1,
+            1,
+        );
+        grid.attach(&rotate, 1, 4, 1, 1);
+        grid.attach(&message, 0, 5, 3, 1);
+        dialog.content_area().pack_start(&grid, true, true, 5);
+        while dialog.run() == gtk::ResponseType::Accept {
+            let m = TransformParameters {
+                origin: (origin_x.value(), origin_y.value()).into(),
+                translate: (move_x.value(), move_y.value()).into(),
+                scale: (scale_x.value() / 100.0, scale_y.value() / 100.0),
+                skew: (skew_x.value(), skew_y.value()),
+                rotate: rotate.value(),
+            }
+            .matrix();
+            /* Undoing needs the inverse transformation. */
+            if m.try_invert().is_err() {
+                message.set_text("Scaling by zero cannot be undone.");
+                continue;
+            }
+            self.state().borrow().transform_selection(m, false);
+            break;
+        }
+        dialog.emit_close();
+    }
+
+    /// Mirror the selection around the center of its bounding box.
+    pub fn flip_selection(&self, horizontally: bool) {
+        use super::tools::TransformParameters;
+
+        let state = self.state().borrow();
+        let Some((min, max)) = state.selection_bbox() else {
+            return;
+        };
+        let m = TransformParameters {
+            origin: 0.5 * (min + max),
+            scale: if horizontally {
+                (-1.0, 1.0)
+            } else {
+                (1.0, -1.0)
+            },
+            ..TransformParameters::default()
+        }
+        .matrix();
+        state.transform_selection(m, false);
+    }
+
+    /// Move the alignment items of the selection by their offsets, as one undo action.
+    fn translate_alignment_items(&self, mut moves: Vec<(Vec<GlyphPointIndex>, Point)>) {
+        moves.retain(|(_, d)| d.norm() > f64::EPSILON);
+        if moves.is_empty() {
+            return;
+        }
+        let state = self.state().borrow();
+        let mut action = state.translate_items(&moves);
+        (action.redo)();
+        state.add_undo_action(action);
+    }
+
+    /// Align the contours, points, anchors and components of the selection to each other.
+    pub fn align_selection(&self, alignment: Alignment) {
+        let items = self.state().borrow().alignment_items();
+        let bboxes = items.iter().map(|(_, b)| *b).collect::<Vec<_>>();
+        let Some(target) = alignment.target(&bboxes) else {
+            return;
+        };
+        self.translate_alignment_items(
+            items
+                .into_iter()
+                .map(|(idxs, bbox)| (idxs, alignment.offset(bbox, target)))
+                .collect(),
+        );
+    }
+
+    /// Space the centers of the selection's items evenly between the outermost ones.
+    pub fn distribute_selection(&self, horizontally: bool) {
+        let items = self.state().borrow().alignment_items();
+        let bboxes = items.iter().map(|(_, b)| *b).collect::<Vec<_>>();
+        self.translate_alignment_items(
+            items
+                .into_iter()
+                .map(|(idxs, _)| idxs)
+                .zip(super::align::distribute(&bboxes, horizontally))
+                .collect(),
+        );
+    }
+
+    /// Center the whole selection in the advance width of the glyph.
+    pub fn center_in_advance(&self) {
+        let (items, width) = {
+            let state = self.state().borrow();
+            let width = state.glyph.borrow().width();
+            (state.alignment_items(), width)
+        };
+        let (Some(width), Some(bbox)) =
+            (width, super::align::bounds(items.iter().map(|(_, b)| *b)))
+        else {
+            return;
+        };
+        let offset = Alignment::HorizontalCenter.offset(bbox, 0.5 * width);
+        self.translate_alignment_items(items.into_iter().map(|(idxs, _)| (idxs, offset)).collect());
+    }
+
+    /// Align the items of the selection to the baseline or a metric guideline of the project.
+    pub fn align_to_metric_dialog(&self) {
+        let items = self.state().borrow().alignment_items();
+        if items.is_empty() {
+            return;
+        }
+        let metrics = std::iter::once(("Baseline".to_string(), 0.0))
+            .chain(self.project().metric_guidelines.borrow().iter().map(|g| {
+                (
+                    g.name().unwrap_or_else(|| "Unnamed guideline".to_string()),
+                    g.y(),
+                )
+            }))
+            .collect::<Vec<(String, f64)>>();
+        let dialog = gtk::Dialog::builder()
+            .attached_to(&self.app().window)
+            .application(self.app())
+            .border_width(10)
+            .destroy_with_parent(true)
+            .modal(true)
+            .title("Align to metric")
+            .build();
+        dialog.add_button("Apply", gtk::ResponseType::Accept);
+        dialog.add_button("Cancel", gtk::ResponseType::Close);
+        dialog.set_default_response(gtk::ResponseType::Accept);
+        let metric = gtk::ComboBoxText::new();
+        for (name, y) in &metrics {
+            metric.append(None, &format!("{name} ({y})"));
+        }
+        metric.set_active(Some(0));
+        let edge = gtk::ComboBoxText::new();
+        for label in ["Bottom", "Center", "Top"] {
+            edge.append(None, label);
+        }
+        edge.set_active(Some(0));
+        let grid = gtk::Grid::builder()
+            .row_spacing(5)
+            .column_spacing(5)
+            .visible(true)
+            .build();
+        for (row, (label, widget)) in (0..).zip([("Metric", &metric), ("Edge", &edge)]) {
+            widget.set_visible(true);
+            grid.attach(
+                &gtk::Label::builder()
+                    .label(label)
+                    .halign(gtk::Align::End)
+                    .visible(true)
+                    .build(),
+                0,
+                row,
+                1,
+                1,
+            );
+            grid.attach(widget, 1, row, 1, 1);
+        }
+        dialog.content_area().pack_start(&grid, true, true, 5);
+        if dialog.run() == gtk::ResponseType::Accept {
+            let alignment = match edge.active() {
+                Some(1) => Alignment::VerticalCenter,
+                Some(2) => Alignment::Top,
+                _ => Alignment::Bottom,
+            };
+            let target = metric
+                .active()
+                .and_then(|i| metrics.get(usize::try_from(i).ok()?))
+                .map(|&(_, y)| y);
+            if let Some(target) = target {
+                self.translate_alignment_items(
+                    items
+                        .into_iter()
+                        .map(|(idxs, bbox)| (idxs, alignment.offset(bbox, target)))
+                        .collect(),
+                );
+            }
+        }
+        dialog.emit_close();
+    }
+
+    fn no_designspace_dialog(&self) {
+        let dialog = crate::utils::widgets::new_simple_error_dialog(
+            Some("Error: Glyph is not part of a designspace."),
+            "Interpolation needs the masters of a designspace. Open one with <i>Open Designspace</i>.",
+            None,
+            self.app().window.upcast_ref(),
+        );
+        dialog.run();
+        dialog.emit_close();
+    }
+
+    /// Show whether the glyph can be interpolated between the designspace masters.
+    pub fn check_compatibility_dialog(&self) {
+        let Some(designspace) = self.imp().designspace.borrow().clone() else {
+            self.no_designspace_dialog();
+            return;
+        };
+        let name = self.glyph().borrow().name().to_string();
+        let compatibility = designspace.compatibility(&name);
+        let dialog = gtk::MessageDialog::builder()
+            .attached_to(&self.app().window)
+            .transient_for(&self.app().window)
+            .destroy_with_parent(true)
+            .modal(true)
+            .buttons(gtk::ButtonsType::Close)
+            .message_type(
+                if compatibility == crate::designspace::Compatibility::Compatible {
+                    gtk::MessageType::Info
+                } else {
+                    gtk::MessageType::Warning
+                },
+            )
+            .text(&format!("Glyph {name} is {compatibility}."))
+            .build();
+        dialog.run();
+        dialog.emit_close();
+    }
+
+    /// Choose the design space location of the interpolation preview layer.
+    pub fn interpolation_dialog(&self) {
+        let Some(designspace) = self.imp().designspace.borrow().clone() else {
+            self.no_designspace_dialog();
+            return;
+        };
+        if let Some(layer) = self.imp().interpolation_layer.get() {
+            layer.set_property(Layer::ACTIVE, true);
+        }
+        let dialog = gtk::Dialog::builder()
+            .attached_to(&self.app().window)
+            .application(self.app())
+            .border_width(10)
+            .destroy_with_parent(true)
+            .title("Interpolation preview")
+            .build();
+        dialog.add_button("Close", gtk::ResponseType::Close);
+        let grid = gtk::Grid::builder()
+            .row_spacing(5)
+            .column_spacing(5)
+            .visible(true)
+            .build();
+        let axes = designspace.document().axes.axes.clone();
+        for (row, axis) in (0..).zip(axes) {
+            let (minimum, default, maximum) = axis.design_range();
+            let value = self
+                .imp()
+                .interpolation_location
+                .borrow()
+                .get(&axis.name)
+                .unwrap_or(default);
+            let scale = gtk::Scale::with_range(
+                gtk::Orientation::Horizontal,
+                minimum,
+                maximum,
+                ((maximum - minimum) / 100.0).max(f64::EPSILON),
+            );
+            scale.set_value(value);
+            scale.add_mark(default, gtk::PositionType::Bottom, None);
+            scale.set_hexpand(true);
+            scale.set_width_request(250);
+            scale.set_visible(true);
+            scale.connect_value_changed(clone!(@weak self as obj, @strong axis => move |scale| {
+                use crate::ufo::designspace::Dimension;
+
+                let mut location = obj.imp().interpolation_location.borrow_mut();
+                let value = scale.value();
+                if let Some(d) = location.dimensions.iter_mut().find(|d| d.name == axis.name) {
+                    d.xvalue = value;
+                } else {
+                    location.dimensions.push(Dimension {
+                        name: axis.name.clone(),
+                        xvalue: value,
+                        yvalue: None,
+                    });
+                }
+                obj.imp().viewport.queue_draw();
+            }));
+            grid.attach(
+                &gtk::Label::builder()
+                    .label(&axis.name)
+                    .halign(gtk::Align::End)
+                    .visible(true)
+                    .build(),
+                0,
+                row,
+                1,
+                1,
+            );
+            grid.attach(&scale, 1, row, 1, 1);
+        }
+        dialog.content_area().pack_start(&grid, true, true, 5);
+        dialog.connect_response(|dialog, _| dialog.close());
+        dialog.present();
+        self.imp().viewport.queue_draw();
+    }
+
     pub fn make_debug_window(&self) {
         let state = self.state().borrow();
         let glyph = state.glyph.borrow();
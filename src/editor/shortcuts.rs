@@ -49,6 +49,10 @@ impl Editor {
     pub const SELECT_ALL_ACTION: &str = "select.all";
     pub const SELECT_NONE_ACTION: &str = "select.none";
     pub const SELECT_INVERT_ACTION: &str = "select.invert";
+    pub const COPY_ACTION: &str = "copy";
+    pub const CUT_ACTION: &str = "cut";
+    pub const PASTE_ACTION: &str = "paste";
+    pub const PASTE_IN_PLACE_ACTION: &str = "paste.in-place";
 }
 
 impl EditorInner {
@@ -202,6 +206,34 @@ impl EditorInner {
                 }),
                 None,
             ));
+            for (desc, shortcut, action_name) in [
+                (
+                    "copy",
+                    Shortcut::empty().control().char('c'),
+                    A::COPY_ACTION,
+                ),
+                ("cut", Shortcut::empty().control().char('x'), A::CUT_ACTION),
+                (
+                    "paste",
+                    Shortcut::empty().control().char('v'),
+                    A::PASTE_ACTION,
+                ),
+                (
+                    "paste in place",
+                    Shortcut::empty().control().shift().char('V'),
+                    A::PASTE_IN_PLACE_ACTION,
+                ),
+            ] {
+                sh.push(ShortcutAction::new(
+                    desc.into(),
+                    shortcut,
+                    Box::new(|group| {
+                        group.activate_action(action_name, None);
+                        true
+                    }),
+                    None,
+                ));
+            }
             for (name, dir) in [
                 (A::MOVE_UP_ACTION, MoveDirection::Up),
                 (A::MOVE_DOWN_ACTION, MoveDirection::Down),
@@ -226,6 +258,23 @@ impl EditorInner {
                 }));
                 obj.action_group.add_action(&a);
             }
+            let copy = gtk::gio::SimpleAction::new(A::COPY_ACTION, None);
+            copy.connect_activate(glib::clone!(@weak obj => move |_, _| {
+                obj.copy_selection();
+            }));
+            obj.action_group.add_action(&copy);
+            let cut = gtk::gio::SimpleAction::new(A::CUT_ACTION, None);
+            cut.connect_activate(glib::clone!(@weak obj => move |_, _| {
+                obj.cut_selection();
+            }));
+            obj.action_group.add_action(&cut);
+            for (name, in_place) in [(A::PASTE_ACTION, false), (A::PASTE_IN_PLACE_ACTION, true)] {
+                let a = gtk::gio::SimpleAction::new(name, None);
+                a.connect_activate(glib::clone!(@weak obj => move |_, _| {
+                    obj.paste(in_place);
+                }));
+                obj.action_group.add_action(&a);
+            }
             for (name, key, num) in [
                 ("precision 1", '!', Precision::EMPTY),
                 ("precision 3", '@', Precision::_1),
@@ -51,9 +51,156 @@ impl State {
         for (contour_index, contour) in glyph.borrow().contours.iter().enumerate() {
             (ret.add_contour(contour, contour_index).redo)();
         }
+        for anchor in glyph.borrow().metadata.anchors().iter() {
+            ret.watch_anchor(anchor);
+        }
+        Self::sync_anchors(&ret.glyph, &ret.kd_tree);
         ret
     }
 
+    /// Replace all anchor entries in `kd_tree` with the current anchor positions.
+    ///
+    /// Anchor indices are positions in [`GlyphMetadata::anchors`], so they have to be
+    /// re-added whenever an anchor is inserted, removed or moved.
+    fn sync_anchors(
+        glyph: &Rc<RefCell<Glyph>>,
+        kd_tree: &Rc<RefCell<crate::utils::range_query::KdTree>>,
+    ) {
+        let mut kd_tree = kd_tree.borrow_mut();
+        for idx in kd_tree.all().into_iter().filter(GlyphPointIndex::is_anchor) {
+            kd_tree.remove(idx);
+        }
+        for (i, anchor) in glyph.borrow().metadata.anchors().iter().enumerate() {
+            kd_tree.add(anchor.glyph_index(i), anchor.position());
+        }
+    }
+
+    /// Keep `kd_tree` up to date when the anchor is edited outside the editor's tools, e.g. by
+    /// its property window.
+    fn watch_anchor(&self, anchor: &Anchor) {
+        for property in [Anchor::X, Anchor::Y] {
+            anchor.connect_notify_local(
+                Some(property),
+                clone!(@weak self.glyph as glyph, @weak self.kd_tree as kd_tree, @weak self.viewport as viewport => move |_, _| {
+                    Self::sync_anchors(&glyph, &kd_tree);
+                    viewport.queue_draw();
+                }),
+            );
+        }
+        anchor.connect_notify_local(
+            Some(Anchor::NAME),
+            clone!(@weak self.viewport as viewport => move |_, _| {
+                viewport.queue_draw();
+            }),
+        );
+    }
+
+    /// Return the anchor `idx` refers to, if any.
+    pub fn anchor(&self, idx: &GlyphPointIndex) -> Option<Anchor> {
+        if !idx.is_anchor() {
+            return None;
+        }
+        self.glyph
+            .borrow()
+            .metadata
+            .anchors()
+            .iter()
+            .find(|a| a.uuid == idx.uuid)
+            .cloned()
+    }
+
+    /// Return the position of a contour point, anchor or component origin.
+    pub fn point_position(&self, idx: &GlyphPointIndex) -> Option<Point> {
+        if idx.is_anchor() {
+            return self.anchor(idx).map(|a| a.position());
+        }
+        let glyph = self.glyph.borrow();
+        if idx.is_component() {
+            return glyph
+                .components
+                .iter()
+                .find(|c| c.uuid == idx.uuid)
+                .map(|c| (c.x_offset, c.y_offset).into());
+        }
+        glyph
+            .contours
+            .get(idx.contour_index)
+            .and_then(|c| c.get_point(*idx))
+    }
+
+    /// Return the lower left and upper right corners of the box that bounds the positions of
+    /// the selected points, anchors and component origins.
+    pub fn selection_bbox(&self) -> Option<(Point, Point)> {
+        self.selection
+            .iter()
+            .filter_map(|idx| self.point_position(idx))
+            .fold(None, |acc, p| match acc {
+                None => Some((p, p)),
+                Some((min, max)) => Some((
+                    (min.x.min(p.x), min.y.min(p.y)).into(),
+                    (max.x.max(p.x), max.y.max(p.y)).into(),
+                )),
+            })
+    }
+
+    pub fn add_anchor(&self, anchor: Anchor) -> Action {
+        self.watch_anchor(&anchor);
+        Action {
+            stamp: EventStamp {
+                t: std::any::TypeId::of::<Self>(),
+                property: Anchor::static_type().name(),
+                id: Box::new([]),
+            },
+            compress: false,
+            redo: Box::new(
+                clone!(@weak self.glyph as glyph, @weak self.kd_tree as kd_tree, @weak self.viewport as viewport, @strong anchor => move || {
+                    glyph.borrow().metadata.add_anchor(anchor.clone());
+                    Self::sync_anchors(&glyph, &kd_tree);
+                    viewport.queue_draw();
+                }),
+            ),
+            undo: Box::new(
+                clone!(@weak self.glyph as glyph, @weak self.kd_tree as kd_tree, @weak self.viewport as viewport, @strong anchor => move || {
+                    {
+                        let glyph = glyph.borrow();
+                        let i = glyph.metadata.anchors().iter().position(|a| a == &anchor);
+                        if let Some(i) = i {
+                            glyph.metadata.remove_anchor(i);
+                        }
+                    }
+                    Self::sync_anchors(&glyph, &kd_tree);
+                    viewport.queue_draw();
+                }),
+            ),
+        }
+    }
+
+    pub fn delete_anchor(&self, idx: usize) -> Action {
+        let anchor = self.glyph.borrow().metadata.anchors()[idx].clone();
+        Action {
+            stamp: EventStamp {
+                t: std::any::TypeId::of::<Self>(),
+                property: Anchor::static_type().name(),
+                id: unsafe { std::mem::transmute::<&[usize], &[u8]>(&[idx]).into() },
+            },
+            compress: false,
+            redo: Box::new(
+                clone!(@weak self.glyph as glyph, @weak self.kd_tree as kd_tree, @weak self.viewport as viewport => move || {
+                    glyph.borrow().metadata.remove_anchor(idx);
+                    Self::sync_anchors(&glyph, &kd_tree);
+                    viewport.queue_draw();
+                }),
+            ),
+            undo: Box::new(
+                clone!(@weak self.glyph as glyph, @weak self.kd_tree as kd_tree, @weak self.viewport as viewport, @strong anchor => move || {
+                    glyph.borrow().metadata.insert_anchor(idx, anchor.clone());
+                    Self::sync_anchors(&glyph, &kd_tree);
+                    viewport.queue_draw();
+                }),
+            ),
+        }
+    }
+
     pub fn add_contour(&self, contour: &Contour, contour_index: usize) -> Action {
         Action {
             stamp: EventStamp {
@@ -87,7 +234,7 @@ impl State {
 
     pub fn reverse_contour(&self, contour: &Contour, contour_index: usize) -> Action {
         let cl = Box::new(
-            clone!(@weak self.kd_tree as kd_tree, @weak contour as contour  => move || {
+            clone!(@weak self.kd_tree as kd_tree, @weak self.glyph as glyph, @weak contour as contour  => move || {
                 let mut kd_tree = kd_tree.borrow_mut();
                 for (curve_index, curve) in contour.curves().iter().enumerate() {
                     for idx in curve.points().iter().map(|p| p.glyph_index(contour_index, curve_index)) {
@@ -100,6 +247,7 @@ impl State {
                         kd_tree.add(idx, pos);
                     }
                 }
+                glyph.borrow().metadata.set_property(GlyphMetadata::MODIFIED, true);
             }),
         );
         Action {
@@ -246,35 +394,304 @@ impl State {
             compress: false,
             redo: Box::new(
                 clone!(@strong idxs, @weak self.kd_tree as kd_tree, @weak self.glyph as glyph, @weak viewport => move || {
-                    let mut kd_tree = kd_tree.borrow_mut();
-                    let glyph = glyph.borrow();
-                    for contour_index in idxs.iter().map(|i| i.contour_index).collect::<HashSet<usize>>() {
-                        let contour = &glyph.contours[contour_index];
-                        for (idx, new_pos) in contour.transform_points(contour_index, &idxs, m) {
-                            kd_tree.add(idx, new_pos);
+                    {
+                        let mut kd_tree = kd_tree.borrow_mut();
+                        let glyph = glyph.borrow();
+                        for contour_index in idxs.iter().filter(|i| i.is_contour_point()).map(|i| i.contour_index).collect::<HashSet<usize>>() {
+                            let contour = &glyph.contours[contour_index];
+                            for (idx, new_pos) in contour.transform_points(contour_index, &idxs, m) {
+                                kd_tree.add(idx, new_pos);
+                            }
                         }
                     }
+                    if idxs.iter().any(GlyphPointIndex::is_anchor) {
+                        Self::transform_anchors(&glyph.borrow(), &idxs, m);
+                        Self::sync_anchors(&glyph, &kd_tree);
+                    }
+                    Self::transform_components(&mut glyph.borrow_mut(), &idxs, m);
+                    glyph.borrow().metadata.set_property(GlyphMetadata::MODIFIED, true);
                     viewport.queue_draw();
                 }),
             ),
             undo: Box::new(
                 clone!(@strong idxs, @weak self.kd_tree as kd_tree, @weak self.glyph as glyph, @weak viewport => move || {
                     let m = if let Ok(m) = m.try_invert() {m} else {return;};
-                    let mut kd_tree = kd_tree.borrow_mut();
-                    let glyph = glyph.borrow();
-                    for contour_index in idxs.iter().map(|i| i.contour_index).collect::<HashSet<usize>>() {
-                        let contour = &glyph.contours[contour_index];
-                        for (idx, new_pos) in contour.transform_points(contour_index, &idxs, m) {
-                            /* update kd_tree */
-                            kd_tree.add(idx, new_pos);
+                    {
+                        let mut kd_tree = kd_tree.borrow_mut();
+                        let glyph = glyph.borrow();
+                        for contour_index in idxs.iter().filter(|i| i.is_contour_point()).map(|i| i.contour_index).collect::<HashSet<usize>>() {
+                            let contour = &glyph.contours[contour_index];
+                            for (idx, new_pos) in contour.transform_points(contour_index, &idxs, m) {
+                                /* update kd_tree */
+                                kd_tree.add(idx, new_pos);
+                            }
                         }
                     }
+                    if idxs.iter().any(GlyphPointIndex::is_anchor) {
+                        Self::transform_anchors(&glyph.borrow(), &idxs, m);
+                        Self::sync_anchors(&glyph, &kd_tree);
+                    }
+                    Self::transform_components(&mut glyph.borrow_mut(), &idxs, m);
+                    glyph.borrow().metadata.set_property(GlyphMetadata::MODIFIED, true);
+                    viewport.queue_draw();
+                }),
+            ),
+        }
+    }
+
+    /// Transform the anchors in `idxs`.
+    ///
+    /// Anchors are looked up by `uuid`, so indices that went stale after an anchor was removed
+    /// are skipped.
+    fn transform_anchors(glyph: &Glyph, idxs: &[GlyphPointIndex], m: Matrix) {
+        let anchors = glyph.metadata.anchors();
+        for idx in idxs.iter().filter(|i| i.is_anchor()) {
+            if let Some(anchor) = anchors.iter().find(|a| a.uuid == idx.uuid) {
+                let Point { x, y } = anchor.position();
+                let (x, y) = m.transform_point(x, y);
+                anchor.set_property(Anchor::X, x);
+                anchor.set_property(Anchor::Y, y);
+            }
+        }
+    }
+
+    /// Transform the components in `idxs`.
+    ///
+    /// Like anchors, components are looked up by `uuid`.
+    fn transform_components(glyph: &mut Glyph, idxs: &[GlyphPointIndex], m: Matrix) {
+        if !idxs.iter().any(GlyphPointIndex::is_component) {
+            return;
+        }
+        for idx in idxs.iter().filter(|i| i.is_component()) {
+            if let Some(c) = glyph.components.iter_mut().find(|c| c.uuid == idx.uuid) {
+                c.set_matrix(Matrix::multiply(&c.matrix(), &m));
+            }
+        }
+        glyph.metadata.set_property(GlyphMetadata::MODIFIED, true);
+    }
+
+    /// Return the index of the component `idx` refers to, if it still exists.
+    pub fn component_index(&self, idx: &GlyphPointIndex) -> Option<usize> {
+        if !idx.is_component() {
+            return None;
+        }
+        self.glyph
+            .borrow()
+            .components
+            .iter()
+            .position(|c| c.uuid == idx.uuid)
+    }
+
+    pub fn set_component_matrix(&self, idx: usize, new: Matrix) -> Action {
+        let (old, uuid) = {
+            let glyph = self.glyph.borrow();
+            let c = &glyph.components[idx];
+            (c.matrix(), c.uuid)
+        };
+        let set = move |glyph: &Rc<RefCell<Glyph>>, m: Matrix| {
+            let mut glyph = glyph.borrow_mut();
+            if let Some(c) = glyph.components.iter_mut().find(|c| c.uuid == uuid) {
+                c.set_matrix(m);
+            }
+            glyph.metadata.set_property(GlyphMetadata::MODIFIED, true);
+        };
+        Action {
+            stamp: EventStamp {
+                t: std::any::TypeId::of::<Self>(),
+                property: "component",
+                id: unsafe { std::mem::transmute::<&[usize], &[u8]>(&[idx]).into() },
+            },
+            compress: false,
+            redo: Box::new(
+                clone!(@weak self.glyph as glyph, @weak self.viewport as viewport => move || {
+                    set(&glyph, new);
+                    viewport.queue_draw();
+                }),
+            ),
+            undo: Box::new(
+                clone!(@weak self.glyph as glyph, @weak self.viewport as viewport => move || {
+                    set(&glyph, old);
+                    viewport.queue_draw();
+                }),
+            ),
+        }
+    }
+
+    pub fn add_component(&self, component: Component) -> Action {
+        Action {
+            stamp: EventStamp {
+                t: std::any::TypeId::of::<Self>(),
+                property: "component",
+                id: Box::new([]),
+            },
+            compress: false,
+            redo: Box::new(
+                clone!(@weak self.glyph as glyph, @weak self.viewport as viewport => move || {
+                    let mut glyph = glyph.borrow_mut();
+                    glyph.components.push(component.clone());
+                    glyph.metadata.set_property(GlyphMetadata::MODIFIED, true);
+                    viewport.queue_draw();
+                }),
+            ),
+            undo: Box::new(
+                clone!(@weak self.glyph as glyph, @weak self.viewport as viewport => move || {
+                    let mut glyph = glyph.borrow_mut();
+                    glyph.components.pop();
+                    glyph.metadata.set_property(GlyphMetadata::MODIFIED, true);
                     viewport.queue_draw();
                 }),
             ),
         }
     }
 
+    pub fn delete_component(&self, idx: usize) -> Action {
+        let component = self.glyph.borrow().components[idx].clone();
+        Action {
+            stamp: EventStamp {
+                t: std::any::TypeId::of::<Self>(),
+                property: "component",
+                id: unsafe { std::mem::transmute::<&[usize], &[u8]>(&[idx]).into() },
+            },
+            compress: false,
+            redo: Box::new(
+                clone!(@weak self.glyph as glyph, @weak self.viewport as viewport => move || {
+                    let mut glyph = glyph.borrow_mut();
+                    glyph.components.remove(idx);
+                    glyph.metadata.set_property(GlyphMetadata::MODIFIED, true);
+                    viewport.queue_draw();
+                }),
+            ),
+            undo: Box::new(
+                clone!(@weak self.glyph as glyph, @weak self.viewport as viewport => move || {
+                    let mut glyph = glyph.borrow_mut();
+                    glyph.components.insert(idx, component.clone());
+                    glyph.metadata.set_property(GlyphMetadata::MODIFIED, true);
+                    viewport.queue_draw();
+                }),
+            ),
+        }
+    }
+
+    /// Replace the component at `idx` with copies of its base glyph's contours.
+    pub fn decompose_component(&self, idx: usize) -> Action {
+        let delete = Rc::new(RefCell::new(self.delete_component(idx)));
+        let contours = self.glyph.borrow().components[idx].decompose();
+        let first_index = self.glyph.borrow().contours.len();
+        let subactions = Rc::new(RefCell::new(
+            contours
+                .iter()
+                .enumerate()
+                .map(|(i, c)| self.add_contour(c, first_index + i))
+                .collect::<Vec<Action>>(),
+        ));
+        Action {
+            stamp: EventStamp {
+                t: std::any::TypeId::of::<Self>(),
+                property: "decompose component",
+                id: unsafe { std::mem::transmute::<&[usize], &[u8]>(&[idx]).into() },
+            },
+            compress: false,
+            redo: Box::new(
+                clone!(@weak self.glyph as glyph, @strong delete, @strong subactions => move || {
+                    (delete.borrow_mut().redo)();
+                    glyph.borrow_mut().contours.extend(contours.iter().cloned());
+                    for a in subactions.borrow_mut().iter_mut() {
+                        (a.redo)();
+                    }
+                }),
+            ),
+            undo: Box::new(
+                clone!(@weak self.glyph as glyph, @strong delete, @strong subactions => move || {
+                    for a in subactions.borrow_mut().iter_mut() {
+                        (a.undo)();
+                    }
+                    glyph.borrow_mut().contours.truncate(first_index);
+                    (delete.borrow_mut().undo)();
+                }),
+            ),
+        }
+    }
+
+    /// Replace all contours of the glyph with `contours`, e.g. with the result of a boolean
+    /// operation.
+    pub fn replace_contours(&self, contours: Vec<Contour>) -> Action {
+        let old = Rc::new(RefCell::new(self.glyph.borrow().contours.clone()));
+        let new = Rc::new(RefCell::new(contours));
+        let swap = |contours: &Rc<RefCell<Vec<Contour>>>| {
+            Box::new(
+                clone!(@weak self.glyph as glyph, @weak self.kd_tree as kd_tree, @weak self.viewport as viewport, @strong contours => move || {
+                    let mut glyph = glyph.borrow_mut();
+                    let mut kd_tree = kd_tree.borrow_mut();
+                    for (contour_index, contour) in glyph.contours.iter().enumerate() {
+                        for (curve_index, curve) in contour.curves().iter().enumerate() {
+                            for idx in curve.points().iter().map(|p| p.glyph_index(contour_index, curve_index)) {
+                                kd_tree.remove(idx);
+                            }
+                        }
+                    }
+                    glyph.contours = contours.borrow().clone();
+                    for (contour_index, contour) in glyph.contours.iter().enumerate() {
+                        for (curve_index, curve) in contour.curves().iter().enumerate() {
+                            for (idx, pos) in curve.points().iter().map(|p| (p.glyph_index(contour_index, curve_index), p.position)) {
+                                kd_tree.add(idx, pos);
+                            }
+                        }
+                    }
+                    glyph.metadata.set_property(GlyphMetadata::MODIFIED, true);
+                    viewport.queue_draw();
+                }),
+            )
+        };
+        Action {
+            stamp: EventStamp {
+                t: std::any::TypeId::of::<Self>(),
+                property: "replace contours",
+                id: Box::new([]),
+            },
+            compress: false,
+            redo: swap(&new),
+            undo: swap(&old),
+        }
+    }
+
+    /// Replace the image reference of the glyph and the image shown behind it by the
+    /// [`ImageTool`] with `image`, or remove them if it is `None`.
+    pub fn set_image(&self, image: Option<(ufo::glif::ImageRef, cairo::ImageSurface)>) -> Action {
+        let tool = self.tools[&ImageTool::static_type()]
+            .clone()
+            .downcast::<ImageTool>()
+            .unwrap();
+        let old = self
+            .glyph
+            .borrow()
+            .metadata
+            .image
+            .borrow()
+            .clone()
+            .zip(tool.image());
+        let swap = |image: Option<(ufo::glif::ImageRef, cairo::ImageSurface)>| {
+            Box::new(
+                clone!(@weak self.glyph as glyph, @weak self.viewport as viewport, @weak tool => move || {
+                    let glyph = glyph.borrow();
+                    *glyph.metadata.image.borrow_mut() = image.as_ref().map(|(r, _)| r.clone());
+                    tool.set_image(image.as_ref().map(|(r, s)| (r, s.clone())));
+                    glyph.metadata.set_property(GlyphMetadata::MODIFIED, true);
+                    viewport.queue_draw();
+                }),
+            )
+        };
+        Action {
+            stamp: EventStamp {
+                t: std::any::TypeId::of::<Self>(),
+                property: "image",
+                id: Box::new([]),
+            },
+            compress: false,
+            redo: swap(image),
+            undo: swap(old),
+        }
+    }
+
     pub fn set_selection(&mut self, selection: &[GlyphPointIndex], modifier: SelectionModifier) {
         use SelectionModifier::*;
         match modifier {
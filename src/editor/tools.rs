@@ -24,16 +24,22 @@ use super::*;
 mod bezier;
 mod bspline;
 mod image;
+mod knife;
+mod measure;
 mod panning;
 mod shapes;
 mod tool_impl;
+mod transform;
 mod zoom;
 pub use bezier::*;
 pub use bspline::*;
 pub use image::*;
+pub use knife::*;
+pub use measure::*;
 pub use panning::*;
 pub use shapes::*;
 pub use tool_impl::*;
+pub use transform::*;
 pub use zoom::*;
 
 pub struct Tool;
@@ -173,7 +179,10 @@ impl Tool {
         for t in [
             PanningTool::new().upcast::<ToolImpl>(),
             BezierTool::new().upcast::<ToolImpl>(),
-            //BSplineTool::new().upcast::<ToolImpl>(),
+            BSplineTool::new().upcast::<ToolImpl>(),
+            KnifeTool::new().upcast::<ToolImpl>(),
+            MeasureTool::new().upcast::<ToolImpl>(),
+            TransformTool::new().upcast::<ToolImpl>(),
             QuadrilateralTool::new().upcast::<ToolImpl>(),
             EllipseTool::new().upcast::<ToolImpl>(),
             ImageTool::new(glyph, obj.project.get().unwrap().clone()).upcast::<ToolImpl>(),
@@ -334,12 +343,14 @@ pub fn new_contour_action(
             clone!(@strong glyph, @strong contour, @strong subaction => move || {
                 glyph.borrow_mut().contours.push(contour.clone());
                 (subaction.borrow_mut().redo)();
+                glyph.borrow().metadata.set_property(GlyphMetadata::MODIFIED, true);
             }),
         ),
         undo: Box::new(
             clone!(@strong glyph, @strong contour, @strong subaction => move || {
                 (subaction.borrow_mut().undo)();
                 glyph.borrow_mut().contours.pop();
+                glyph.borrow().metadata.set_property(GlyphMetadata::MODIFIED, true);
             }),
         ),
     }
@@ -19,13 +19,32 @@
  * along with gerb. If not, see <http://www.gnu.org/licenses/>.
  */
 
-use super::tool_impl::*;
+//! Placing the control points of a uniform cubic B-spline, which becomes a contour of cubic
+//! Bézier curves once it is finished.
+//!
+//! Primary clicks add control points, and clicking the first point again closes the spline. The
+//! control points can be dragged until the spline is committed with a secondary click.
+
+use super::{new_contour_action, tool_impl::*};
 use gtk::Inhibit;
 
+use crate::glyphs::bspline;
 use crate::prelude::*;
+use crate::views::canvas::{Layer, LayerBuilder};
 
 #[derive(Default)]
-pub struct BSplineToolInner;
+pub struct BSplineToolInner {
+    layer: OnceCell<Layer>,
+    active: Cell<bool>,
+    cursor: OnceCell<Option<gtk::gdk_pixbuf::Pixbuf>>,
+    /// Control points placed so far.
+    control: RefCell<Vec<Point>>,
+    closed: Cell<bool>,
+    /// Index in `control` of the point being dragged.
+    dragging: Cell<Option<usize>>,
+    /// Pointer position, previewed as the next control point.
+    pointer: Cell<Option<Point>>,
+}
 
 #[glib::object_subclass]
 impl ObjectSubclass for BSplineToolInner {
@@ -37,11 +56,49 @@ impl ObjectSubclass for BSplineToolInner {
 impl ObjectImpl for BSplineToolInner {
     fn constructed(&self, obj: &Self::Type) {
         self.parent_constructed(obj);
+        obj.set_property::<bool>(BSplineTool::ACTIVE, false);
         obj.set_property::<String>(ToolImpl::NAME, "Create b-spline curve".to_string());
         obj.set_property::<gtk::Image>(
             ToolImpl::ICON,
             crate::resources::icons::BSPLINE_ICON.to_image_widget(),
         );
+        self.cursor
+            .set(crate::resources::cursors::PEN_CURSOR.to_pixbuf())
+            .unwrap();
+    }
+
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: once_cell::sync::Lazy<Vec<glib::ParamSpec>> =
+            once_cell::sync::Lazy::new(|| {
+                vec![glib::ParamSpecBoolean::new(
+                    BSplineTool::ACTIVE,
+                    BSplineTool::ACTIVE,
+                    BSplineTool::ACTIVE,
+                    true,
+                    glib::ParamFlags::READWRITE,
+                )]
+            });
+        PROPERTIES.as_ref()
+    }
+
+    fn property(&self, _obj: &Self::Type, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        match pspec.name() {
+            BSplineTool::ACTIVE => self.active.get().to_value(),
+            _ => unimplemented!("{}", pspec.name()),
+        }
+    }
+
+    fn set_property(
+        &self,
+        _obj: &Self::Type,
+        _id: usize,
+        value: &glib::Value,
+        pspec: &glib::ParamSpec,
+    ) {
+        match pspec.name() {
+            BSplineTool::ACTIVE => self.active.set(value.get().unwrap()),
+            _ => unimplemented!("{}", pspec.name()),
+        }
     }
 }
 
@@ -49,35 +106,168 @@ impl ToolImplImpl for BSplineToolInner {
     fn on_button_press_event(
         &self,
         _obj: &ToolImpl,
-        _view: Editor,
-        _viewport: &Canvas,
-        _event: &gtk::gdk::EventButton,
+        view: Editor,
+        viewport: &Canvas,
+        event: &gtk::gdk::EventButton,
     ) -> Inhibit {
-        Inhibit(false)
+        if !self.active.get() {
+            return Inhibit(false);
+        }
+        match event.button() {
+            gtk::gdk::BUTTON_PRIMARY => {
+                let UnitPoint(point) =
+                    viewport.view_to_unit_point(ViewPoint(event.position().into()));
+                let grab_distance = Self::grab_distance(&view, viewport);
+                let mut control = self.control.borrow_mut();
+                let nearest = control
+                    .iter()
+                    .enumerate()
+                    .map(|(i, p)| (i, p.distance(point)))
+                    .filter(|&(_, d)| d <= grab_distance)
+                    .min_by(|a, b| a.1.total_cmp(&b.1))
+                    .map(|(i, _)| i);
+                match nearest {
+                    Some(0) if !self.closed.get() && control.len() >= 3 => {
+                        self.closed.set(true);
+                    }
+                    Some(i) => {
+                        self.dragging.set(Some(i));
+                    }
+                    None if !self.closed.get() => {
+                        control.push(point);
+                    }
+                    None => return Inhibit(false),
+                }
+                viewport.queue_draw();
+            }
+            gtk::gdk::BUTTON_SECONDARY => {
+                self.finish(&view, viewport, true);
+            }
+            _ => return Inhibit(false),
+        }
+        Inhibit(true)
     }
 
     fn on_button_release_event(
         &self,
         _obj: &ToolImpl,
         _view: Editor,
-        _viewport: &Canvas,
+        viewport: &Canvas,
         _event: &gtk::gdk::EventButton,
     ) -> Inhibit {
-        Inhibit(false)
+        if !self.active.get() || self.dragging.take().is_none() {
+            return Inhibit(false);
+        }
+        viewport.queue_draw();
+        Inhibit(true)
     }
 
     fn on_motion_notify_event(
         &self,
         _obj: &ToolImpl,
         _view: Editor,
-        _viewport: &Canvas,
-        _event: &gtk::gdk::EventMotion,
+        viewport: &Canvas,
+        event: &gtk::gdk::EventMotion,
     ) -> Inhibit {
-        Inhibit(false)
+        if !self.active.get() {
+            return Inhibit(false);
+        }
+        let UnitPoint(point) = viewport.view_to_unit_point(ViewPoint(event.position().into()));
+        self.pointer.set(Some(point));
+        let mut control = self.control.borrow_mut();
+        if let Some(i) = self.dragging.get() {
+            control[i] = point;
+        }
+        if control.is_empty() {
+            return Inhibit(false);
+        }
+        viewport.queue_draw();
+        Inhibit(true)
+    }
+
+    fn setup_toolbox(&self, obj: &ToolImpl, toolbar: &gtk::Toolbar, view: &Editor) {
+        let layer =
+            LayerBuilder::new()
+                .set_name(Some("b-spline"))
+                .set_active(false)
+                .set_hidden(true)
+                .set_callback(Some(Box::new(clone!(@weak view => @default-return Inhibit(false), move |viewport: &Canvas, cr: ContextRef| {
+                    BSplineTool::draw_layer(viewport, cr, view)
+                }))))
+                .build();
+        self.instance()
+            .bind_property(BSplineTool::ACTIVE, &layer, Layer::ACTIVE)
+            .flags(glib::BindingFlags::SYNC_CREATE)
+            .build();
+        self.layer.set(layer.clone()).unwrap();
+        view.viewport.add_post_layer(layer);
+        self.instance()
+            .bind_property(BSplineTool::ACTIVE, view, Editor::MODIFYING_IN_PROCESS)
+            .build();
+
+        self.parent_setup_toolbox(obj, toolbar, view)
+    }
+
+    fn on_activate(&self, obj: &ToolImpl, view: &Editor) {
+        self.instance()
+            .set_property::<bool>(BSplineTool::ACTIVE, true);
+        if let Some(pixbuf) = self.cursor.get().unwrap().clone() {
+            view.viewport.set_cursor_from_pixbuf(pixbuf);
+        } else {
+            view.viewport.set_cursor("grab");
+        }
+        self.parent_on_activate(obj, view)
+    }
+
+    fn on_deactivate(&self, obj: &ToolImpl, view: &Editor) {
+        self.reset();
+        self.instance()
+            .set_property::<bool>(BSplineTool::ACTIVE, false);
+        view.viewport.set_cursor("default");
+        self.parent_on_deactivate(obj, view)
     }
 }
 
-impl BSplineToolInner {}
+impl BSplineToolInner {
+    /// Distance in units within which a click grabs a control point.
+    fn grab_distance(view: &Editor, viewport: &Canvas) -> f64 {
+        let scale: f64 = viewport
+            .transformation
+            .property::<f64>(Transformation::SCALE);
+        let ppu: f64 = viewport
+            .transformation
+            .property::<f64>(Transformation::PIXELS_PER_UNIT);
+        view.app_settings().property::<f64>(Settings::HANDLE_SIZE) / (scale * ppu)
+    }
+
+    fn reset(&self) {
+        self.control.borrow_mut().clear();
+        self.closed.set(false);
+        self.dragging.set(None);
+        self.pointer.set(None);
+    }
+
+    /// Add the spline to the glyph if `commit` is true and it has enough control points, and
+    /// stop using the tool.
+    fn finish(&self, view: &Editor, viewport: &Canvas, commit: bool) {
+        let contour = commit
+            .then(|| bspline::to_contour(&self.control.borrow(), self.closed.get()))
+            .flatten();
+        self.reset();
+        let mut state = view.state().borrow_mut();
+        if let Some(contour) = contour {
+            let contour_index = state.glyph.borrow().contours.len();
+            let subaction = state.add_contour(&contour, contour_index);
+            let mut action = new_contour_action(state.glyph.clone(), contour, subaction);
+            (action.redo)();
+            state.add_undo_action(action);
+        }
+        self.instance()
+            .set_property::<bool>(BSplineTool::ACTIVE, false);
+        state.active_tool = glib::types::Type::INVALID;
+        viewport.set_cursor("default");
+    }
+}
 
 glib::wrapper! {
     pub struct BSplineTool(ObjectSubclass<BSplineToolInner>)
@@ -91,7 +281,85 @@ impl Default for BSplineTool {
 }
 
 impl BSplineTool {
+    pub const ACTIVE: &str = "active";
+
     pub fn new() -> Self {
         glib::Object::new(&[]).unwrap()
     }
+
+    /// Draw the control polygon and a preview of the curve, which includes the pointer as the
+    /// next control point while the spline is open.
+    pub fn draw_layer(viewport: &Canvas, cr: ContextRef, obj: Editor) -> Inhibit {
+        let state = obj.state().borrow();
+        if Self::static_type() != state.active_tool {
+            return Inhibit(false);
+        }
+        let t = state.tools[&state.active_tool]
+            .clone()
+            .downcast::<Self>()
+            .unwrap();
+        let t = t.imp();
+        if !t.active.get() {
+            return Inhibit(false);
+        }
+        let closed = t.closed.get();
+        let mut control = t.control.borrow().clone();
+        if control.is_empty() {
+            return Inhibit(false);
+        }
+        let fixed = control.len();
+        if let (false, None, Some(pointer)) = (closed, t.dragging.get(), t.pointer.get()) {
+            control.push(pointer);
+        }
+        let scale: f64 = viewport
+            .transformation
+            .property::<f64>(Transformation::SCALE);
+        let ppu: f64 = viewport
+            .transformation
+            .property::<f64>(Transformation::PIXELS_PER_UNIT);
+        let line_width = obj.app_settings().property::<f64>(Settings::LINE_WIDTH) / (scale * ppu);
+        let handle_size = obj.app_settings().property::<f64>(Settings::HANDLE_SIZE) / (scale * ppu);
+        cr.transform(viewport.transformation.matrix());
+        cr.set_line_width(line_width);
+
+        /* control polygon */
+        cr.set_source_rgba(0.2, 0.2, 0.2, 0.6);
+        cr.set_dash(&[4.0 / (scale * ppu)], 0.0);
+        cr.move_to(control[0].x, control[0].y);
+        for p in control.iter().skip(1) {
+            cr.line_to(p.x, p.y);
+        }
+        if closed {
+            cr.close_path();
+        }
+        cr.stroke().unwrap();
+        cr.set_dash(&[], 0.0);
+
+        /* curve */
+        let segments = bspline::bezier_segments(&control, closed);
+        if let Some(first) = segments.first() {
+            cr.set_source_color_alpha(Color::from_hex("#3333FF").with_alpha(204));
+            cr.move_to(first[0].x, first[0].y);
+            for [_, p1, p2, p3] in segments {
+                cr.curve_to(p1.x, p1.y, p2.x, p2.y, p3.x, p3.y);
+            }
+            cr.stroke().unwrap();
+        }
+
+        /* control points */
+        for (i, p) in control.iter().take(fixed).enumerate() {
+            if t.dragging.get() == Some(i) {
+                cr.set_source_rgba(0.0, 0.0, 1.0, 0.8);
+            } else {
+                cr.set_source_rgba(0.9, 0.9, 0.9, 1.0);
+            }
+            cr.arc(p.x, p.y, handle_size / 2.0, 0.0, 2.0 * std::f64::consts::PI);
+            cr.fill().unwrap();
+            cr.set_source_rgba(0.0, 0.0, 0.0, 1.0);
+            cr.arc(p.x, p.y, handle_size / 2.0, 0.0, 2.0 * std::f64::consts::PI);
+            cr.stroke().unwrap();
+        }
+
+        Inhibit(true)
+    }
 }
@@ -209,18 +209,7 @@ impl ImageTool {
         if let Some(image_ref) = glyph.borrow().image.borrow().as_ref() {
             if let Some(file_name) = image_ref.file_name.as_ref() {
                 // [ref:FIXME] error handling
-                *ret.imp().image_data.borrow_mut() = Some(project.load_image(file_name).unwrap());
-                ret.imp().active.set(true);
-                ret.imp().color.set(image_ref.color);
-                let xx = image_ref.x_scale;
-                let yy = image_ref.y_scale;
-                let xy = image_ref.xy_scale;
-                let yx = image_ref.yx_scale;
-                let x0 = image_ref.x_offset;
-                let y0 = image_ref.y_offset;
-                ret.imp()
-                    .matrix
-                    .set(cairo::Matrix::new(xx, yx, xy, yy, x0, y0));
+                ret.set_image(Some((image_ref, project.load_image(file_name).unwrap())));
             }
         }
         ret.imp().glyph.set(glyph).unwrap();
@@ -228,6 +217,33 @@ impl ImageTool {
         ret
     }
 
+    /// The image currently shown behind the glyph.
+    pub fn image(&self) -> Option<ImageSurface> {
+        self.imp().image_data.borrow().clone()
+    }
+
+    /// Show `image`, a glyph image reference along with its loaded contents, behind the glyph, or
+    /// stop showing an image if it is `None`.
+    pub fn set_image(&self, image: Option<(&ufo::glif::ImageRef, ImageSurface)>) {
+        let Some((image_ref, surface)) = image else {
+            *self.imp().image_data.borrow_mut() = None;
+            self.set_property::<bool>(Self::ACTIVE, false);
+            return;
+        };
+        *self.imp().image_data.borrow_mut() = Some(surface);
+        self.set_property::<bool>(Self::ACTIVE, true);
+        self.imp().color.set(image_ref.color);
+        let xx = image_ref.x_scale;
+        let yy = image_ref.y_scale;
+        let xy = image_ref.xy_scale;
+        let yx = image_ref.yx_scale;
+        let x0 = image_ref.x_offset;
+        let y0 = image_ref.y_offset;
+        self.imp()
+            .matrix
+            .set(cairo::Matrix::new(xx, yx, xy, yy, x0, y0));
+    }
+
     pub fn draw_layer(viewport: &Canvas, cr: ContextRef, obj: Editor) -> Inhibit {
         let t = obj.state().borrow().tools[&Self::static_type()]
             .clone()
@@ -0,0 +1,291 @@
+/*
+ * gerb
+ *
+ * Copyright 2022 - Manos Pitsidianakis
+ *
+ * This file is part of gerb.
+ *
+ * gerb is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * gerb is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with gerb. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Cutting contours along a line that is dragged across the glyph. The cut is applied with
+//! [`knife::cut`] when the button is released.
+
+use super::tool_impl::*;
+use gtk::Inhibit;
+
+use crate::glyphs::knife;
+use crate::prelude::*;
+use crate::views::canvas::{Layer, LayerBuilder};
+
+#[derive(Default)]
+pub struct KnifeToolInner {
+    layer: OnceCell<Layer>,
+    active: Cell<bool>,
+    /// Start and end of the line being dragged.
+    line: Cell<Option<(Point, Point)>>,
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for KnifeToolInner {
+    const NAME: &'static str = "KnifeTool";
+    type ParentType = ToolImpl;
+    type Type = KnifeTool;
+}
+
+impl ObjectImpl for KnifeToolInner {
+    fn constructed(&self, obj: &Self::Type) {
+        self.parent_constructed(obj);
+        obj.set_property::<bool>(KnifeTool::ACTIVE, false);
+        obj.set_property::<String>(ToolImpl::NAME, "knife".to_string());
+        obj.set_property::<String>(
+            ToolImpl::DESCRIPTION,
+            "Cut contours along a line".to_string(),
+        );
+        obj.set_property::<gtk::Image>(
+            ToolImpl::ICON,
+            crate::resources::icons::KNIFE_ICON.to_image_widget(),
+        );
+    }
+
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: once_cell::sync::Lazy<Vec<glib::ParamSpec>> =
+            once_cell::sync::Lazy::new(|| {
+                vec![glib::ParamSpecBoolean::new(
+                    KnifeTool::ACTIVE,
+                    KnifeTool::ACTIVE,
+                    KnifeTool::ACTIVE,
+                    true,
+                    glib::ParamFlags::READWRITE,
+                )]
+            });
+        PROPERTIES.as_ref()
+    }
+
+    fn property(&self, _obj: &Self::Type, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        match pspec.name() {
+            KnifeTool::ACTIVE => self.active.get().to_value(),
+            _ => unimplemented!("{}", pspec.name()),
+        }
+    }
+
+    fn set_property(
+        &self,
+        _obj: &Self::Type,
+        _id: usize,
+        value: &glib::Value,
+        pspec: &glib::ParamSpec,
+    ) {
+        match pspec.name() {
+            KnifeTool::ACTIVE => self.active.set(value.get().unwrap()),
+            _ => unimplemented!("{}", pspec.name()),
+        }
+    }
+}
+
+impl ToolImplImpl for KnifeToolInner {
+    fn on_button_press_event(
+        &self,
+        _obj: &ToolImpl,
+        view: Editor,
+        viewport: &Canvas,
+        event: &gtk::gdk::EventButton,
+    ) -> Inhibit {
+        if !self.active.get() {
+            return Inhibit(false);
+        }
+        match event.button() {
+            gtk::gdk::BUTTON_PRIMARY => {
+                let UnitPoint(point) =
+                    viewport.view_to_unit_point(ViewPoint(event.position().into()));
+                self.line.set(Some((point, point)));
+            }
+            gtk::gdk::BUTTON_SECONDARY => {
+                self.finish(&view, viewport);
+            }
+            _ => return Inhibit(false),
+        }
+        Inhibit(true)
+    }
+
+    fn on_button_release_event(
+        &self,
+        _obj: &ToolImpl,
+        view: Editor,
+        viewport: &Canvas,
+        event: &gtk::gdk::EventButton,
+    ) -> Inhibit {
+        if !self.active.get() || event.button() != gtk::gdk::BUTTON_PRIMARY {
+            return Inhibit(false);
+        }
+        let Some((start, _)) = self.line.get() else {
+            return Inhibit(false);
+        };
+        let UnitPoint(end) = viewport.view_to_unit_point(ViewPoint(event.position().into()));
+        self.finish(&view, viewport);
+        let contours = view.state().borrow().glyph.borrow().contours.clone();
+        match knife::cut(&contours, (start, end)) {
+            Ok(Some(new_contours)) => {
+                view.set_selection(&[], SelectionModifier::Replace);
+                let state = view.state().borrow();
+                let mut action = state.replace_contours(new_contours);
+                (action.redo)();
+                state.add_undo_action(action);
+            }
+            Ok(None) => {}
+            Err(err) => {
+                let dialog = crate::utils::widgets::new_simple_error_dialog(
+                    Some("Error: Could not cut contours."),
+                    &err.to_string(),
+                    None,
+                    view.app().window.upcast_ref(),
+                );
+                dialog.run();
+                dialog.emit_close();
+            }
+        }
+        Inhibit(true)
+    }
+
+    fn on_motion_notify_event(
+        &self,
+        _obj: &ToolImpl,
+        _view: Editor,
+        viewport: &Canvas,
+        event: &gtk::gdk::EventMotion,
+    ) -> Inhibit {
+        if !self.active.get() {
+            return Inhibit(false);
+        }
+        let Some((start, _)) = self.line.get() else {
+            return Inhibit(false);
+        };
+        let UnitPoint(end) = viewport.view_to_unit_point(ViewPoint(event.position().into()));
+        self.line.set(Some((start, end)));
+        viewport.queue_draw();
+        Inhibit(true)
+    }
+
+    fn setup_toolbox(&self, obj: &ToolImpl, toolbar: &gtk::Toolbar, view: &Editor) {
+        let layer =
+            LayerBuilder::new()
+                .set_name(Some("knife"))
+                .set_active(false)
+                .set_hidden(true)
+                .set_callback(Some(Box::new(clone!(@weak view => @default-return Inhibit(false), move |viewport: &Canvas, cr: ContextRef| {
+                    KnifeTool::draw_layer(viewport, cr, view)
+                }))))
+                .build();
+        self.instance()
+            .bind_property(KnifeTool::ACTIVE, &layer, Layer::ACTIVE)
+            .flags(glib::BindingFlags::SYNC_CREATE)
+            .build();
+        self.layer.set(layer.clone()).unwrap();
+        view.viewport.add_post_layer(layer);
+
+        self.parent_setup_toolbox(obj, toolbar, view)
+    }
+
+    fn on_activate(&self, obj: &ToolImpl, view: &Editor) {
+        self.instance()
+            .set_property::<bool>(KnifeTool::ACTIVE, true);
+        view.viewport.set_cursor("crosshair");
+        self.parent_on_activate(obj, view)
+    }
+
+    fn on_deactivate(&self, obj: &ToolImpl, view: &Editor) {
+        self.line.set(None);
+        self.instance()
+            .set_property::<bool>(KnifeTool::ACTIVE, false);
+        view.viewport.set_cursor("default");
+        self.parent_on_deactivate(obj, view)
+    }
+}
+
+impl KnifeToolInner {
+    /// Stop using the tool.
+    fn finish(&self, view: &Editor, viewport: &Canvas) {
+        self.line.set(None);
+        self.instance()
+            .set_property::<bool>(KnifeTool::ACTIVE, false);
+        view.state().borrow_mut().active_tool = glib::types::Type::INVALID;
+        viewport.set_cursor("default");
+        viewport.queue_draw();
+    }
+}
+
+glib::wrapper! {
+    pub struct KnifeTool(ObjectSubclass<KnifeToolInner>)
+        @extends ToolImpl;
+}
+
+impl Default for KnifeTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl KnifeTool {
+    pub const ACTIVE: &str = "active";
+
+    pub fn new() -> Self {
+        glib::Object::new(&[]).unwrap()
+    }
+
+    /// Draw the cutting line and the points where it crosses the contours.
+    pub fn draw_layer(viewport: &Canvas, cr: ContextRef, obj: Editor) -> Inhibit {
+        let state = obj.state().borrow();
+        if Self::static_type() != state.active_tool {
+            return Inhibit(false);
+        }
+        let t = state.tools[&state.active_tool]
+            .clone()
+            .downcast::<Self>()
+            .unwrap();
+        if !t.imp().active.get() {
+            return Inhibit(false);
+        }
+        let Some((start, end)) = t.imp().line.get() else {
+            return Inhibit(false);
+        };
+        let scale: f64 = viewport
+            .transformation
+            .property::<f64>(Transformation::SCALE);
+        let ppu: f64 = viewport
+            .transformation
+            .property::<f64>(Transformation::PIXELS_PER_UNIT);
+        let line_width = obj.app_settings().property::<f64>(Settings::LINE_WIDTH) / (scale * ppu);
+        let handle_size = obj.app_settings().property::<f64>(Settings::HANDLE_SIZE) / (scale * ppu);
+        cr.transform(viewport.transformation.matrix());
+        cr.set_line_width(line_width);
+        cr.set_source_color_alpha(Color::RED.with_alpha(204)); // [ref:hardcoded_color_value]
+        cr.set_dash(&[4.0 / (scale * ppu)], 0.0);
+        cr.move_to(start.x, start.y);
+        cr.line_to(end.x, end.y);
+        cr.stroke().unwrap();
+        cr.set_dash(&[], 0.0);
+
+        for contour in state.glyph.borrow().contours.iter() {
+            for curve in contour.curves().iter() {
+                for (t, _) in curve.line_intersections((start, end)) {
+                    let p = curve.compute(t);
+                    cr.arc(p.x, p.y, handle_size / 2.0, 0.0, 2.0 * std::f64::consts::PI);
+                    cr.fill().unwrap();
+                }
+            }
+        }
+
+        Inhibit(true)
+    }
+}
@@ -0,0 +1,349 @@
+/*
+ * gerb
+ *
+ * Copyright 2022 - Manos Pitsidianakis
+ *
+ * This file is part of gerb.
+ *
+ * gerb is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * gerb is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with gerb. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Measuring the outline along a line that is dragged across the glyph. The distances between
+//! consecutive intersections of the line with the outline are shown along with the length,
+//! angle and deltas of the line. The ends of the line snap to nearby points, and holding Shift
+//! constrains the angle to multiples of 45 degrees.
+
+use super::tool_impl::*;
+use gtk::Inhibit;
+
+use crate::glyphs::Component;
+use crate::prelude::*;
+use crate::views::canvas::{Layer, LayerBuilder};
+
+#[derive(Default)]
+pub struct MeasureToolInner {
+    layer: OnceCell<Layer>,
+    active: Cell<bool>,
+    /// Start and end of the measurement, which stays visible after the button is released.
+    line: Cell<Option<(Point, Point)>>,
+    dragging: Cell<bool>,
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for MeasureToolInner {
+    const NAME: &'static str = "MeasureTool";
+    type ParentType = ToolImpl;
+    type Type = MeasureTool;
+}
+
+impl ObjectImpl for MeasureToolInner {
+    fn constructed(&self, obj: &Self::Type) {
+        self.parent_constructed(obj);
+        obj.set_property::<bool>(MeasureTool::ACTIVE, false);
+        obj.set_property::<String>(ToolImpl::NAME, "measure".to_string());
+        obj.set_property::<String>(
+            ToolImpl::DESCRIPTION,
+            "Measure distances and angles".to_string(),
+        );
+        obj.set_property::<gtk::Image>(
+            ToolImpl::ICON,
+            crate::resources::icons::MEASURE_ICON.to_image_widget(),
+        );
+    }
+
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: once_cell::sync::Lazy<Vec<glib::ParamSpec>> =
+            once_cell::sync::Lazy::new(|| {
+                vec![glib::ParamSpecBoolean::new(
+                    MeasureTool::ACTIVE,
+                    MeasureTool::ACTIVE,
+                    MeasureTool::ACTIVE,
+                    true,
+                    glib::ParamFlags::READWRITE,
+                )]
+            });
+        PROPERTIES.as_ref()
+    }
+
+    fn property(&self, _obj: &Self::Type, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        match pspec.name() {
+            MeasureTool::ACTIVE => self.active.get().to_value(),
+            _ => unimplemented!("{}", pspec.name()),
+        }
+    }
+
+    fn set_property(
+        &self,
+        _obj: &Self::Type,
+        _id: usize,
+        value: &glib::Value,
+        pspec: &glib::ParamSpec,
+    ) {
+        match pspec.name() {
+            MeasureTool::ACTIVE => self.active.set(value.get().unwrap()),
+            _ => unimplemented!("{}", pspec.name()),
+        }
+    }
+}
+
+impl ToolImplImpl for MeasureToolInner {
+    fn on_button_press_event(
+        &self,
+        _obj: &ToolImpl,
+        view: Editor,
+        viewport: &Canvas,
+        event: &gtk::gdk::EventButton,
+    ) -> Inhibit {
+        if !self.active.get() {
+            return Inhibit(false);
+        }
+        match event.button() {
+            gtk::gdk::BUTTON_PRIMARY => {
+                let UnitPoint(point) =
+                    viewport.view_to_unit_point(ViewPoint(event.position().into()));
+                let point = Self::snap(&view, viewport, point);
+                self.line.set(Some((point, point)));
+                self.dragging.set(true);
+                viewport.queue_draw();
+            }
+            gtk::gdk::BUTTON_SECONDARY => {
+                self.line.set(None);
+                self.dragging.set(false);
+                self.instance()
+                    .set_property::<bool>(MeasureTool::ACTIVE, false);
+                view.state().borrow_mut().active_tool = glib::types::Type::INVALID;
+                viewport.set_cursor("default");
+                viewport.queue_draw();
+            }
+            _ => return Inhibit(false),
+        }
+        Inhibit(true)
+    }
+
+    fn on_button_release_event(
+        &self,
+        _obj: &ToolImpl,
+        _view: Editor,
+        _viewport: &Canvas,
+        event: &gtk::gdk::EventButton,
+    ) -> Inhibit {
+        if !self.active.get() || event.button() != gtk::gdk::BUTTON_PRIMARY {
+            return Inhibit(false);
+        }
+        Inhibit(self.dragging.replace(false))
+    }
+
+    fn on_motion_notify_event(
+        &self,
+        _obj: &ToolImpl,
+        view: Editor,
+        viewport: &Canvas,
+        event: &gtk::gdk::EventMotion,
+    ) -> Inhibit {
+        if !self.active.get() || !self.dragging.get() {
+            return Inhibit(false);
+        }
+        let Some((start, _)) = self.line.get() else {
+            return Inhibit(false);
+        };
+        let UnitPoint(point) = viewport.view_to_unit_point(ViewPoint(event.position().into()));
+        let end = if event.state().intersects(gtk::gdk::ModifierType::SHIFT_MASK) {
+            let step = std::f64::consts::FRAC_PI_4;
+            let angle = ((point - start).atan2() / step).round() * step;
+            let direction: Point = (angle.cos(), angle.sin()).into();
+            start + (point - start).dot(direction) * direction
+        } else {
+            Self::snap(&view, viewport, point)
+        };
+        self.line.set(Some((start, end)));
+        viewport.queue_draw();
+        Inhibit(true)
+    }
+
+    fn setup_toolbox(&self, obj: &ToolImpl, toolbar: &gtk::Toolbar, view: &Editor) {
+        let layer =
+            LayerBuilder::new()
+                .set_name(Some("measure"))
+                .set_active(false)
+                .set_hidden(true)
+                .set_callback(Some(Box::new(clone!(@weak view => @default-return Inhibit(false), move |viewport: &Canvas, cr: ContextRef| {
+                    MeasureTool::draw_layer(viewport, cr, view)
+                }))))
+                .build();
+        self.instance()
+            .bind_property(MeasureTool::ACTIVE, &layer, Layer::ACTIVE)
+            .flags(glib::BindingFlags::SYNC_CREATE)
+            .build();
+        self.layer.set(layer.clone()).unwrap();
+        view.viewport.add_post_layer(layer);
+
+        self.parent_setup_toolbox(obj, toolbar, view)
+    }
+
+    fn on_activate(&self, obj: &ToolImpl, view: &Editor) {
+        self.instance()
+            .set_property::<bool>(MeasureTool::ACTIVE, true);
+        view.viewport.set_cursor("crosshair");
+        self.parent_on_activate(obj, view)
+    }
+
+    fn on_deactivate(&self, obj: &ToolImpl, view: &Editor) {
+        self.line.set(None);
+        self.dragging.set(false);
+        self.instance()
+            .set_property::<bool>(MeasureTool::ACTIVE, false);
+        view.viewport.set_cursor("default");
+        self.parent_on_deactivate(obj, view)
+    }
+}
+
+impl MeasureToolInner {
+    /// The position of the point nearest to `point` within snapping distance, or `point`.
+    fn snap(view: &Editor, viewport: &Canvas, point: Point) -> Point {
+        let scale: f64 = viewport
+            .transformation
+            .property::<f64>(Transformation::SCALE);
+        let ppu: f64 = viewport
+            .transformation
+            .property::<f64>(Transformation::PIXELS_PER_UNIT);
+        let state = view.state().borrow();
+        let pts = state
+            .kd_tree
+            .borrow()
+            .query_point(point, (10.0 / (scale * ppu)).ceil() as i64);
+        pts.iter()
+            .filter_map(|idx| state.point_position(idx))
+            .min_by(|a, b| a.distance(point).total_cmp(&b.distance(point)))
+            .unwrap_or(point)
+    }
+}
+
+glib::wrapper! {
+    pub struct MeasureTool(ObjectSubclass<MeasureToolInner>)
+        @extends ToolImpl;
+}
+
+impl Default for MeasureTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MeasureTool {
+    pub const ACTIVE: &str = "active";
+
+    pub fn new() -> Self {
+        glib::Object::new(&[]).unwrap()
+    }
+
+    /// Intersections of the line from `start` to `end` with the contours and components of
+    /// `glyph`, in order along the line.
+    pub fn intersections(glyph: &Glyph, (start, end): (Point, Point)) -> Vec<Point> {
+        let mut found = glyph
+            .contours
+            .iter()
+            .cloned()
+            .chain(glyph.components.iter().flat_map(Component::decompose))
+            .flat_map(|contour| {
+                contour
+                    .curves()
+                    .iter()
+                    .flat_map(|curve| {
+                        curve
+                            .line_intersections((start, end))
+                            .into_iter()
+                            .map(|(t, s)| (s, curve.compute(t)))
+                            .collect::<Vec<(f64, Point)>>()
+                    })
+                    .collect::<Vec<(f64, Point)>>()
+            })
+            .collect::<Vec<(f64, Point)>>();
+        found.sort_by(|a, b| a.0.total_cmp(&b.0));
+        /* Points where curves meet are found on both of them. */
+        found.dedup_by(|a, b| a.1.distance(b.1) < 1e-6);
+        found.into_iter().map(|(_, p)| p).collect()
+    }
+
+    /// Draw the line, its intersections with the outline and the distances between them in
+    /// view coordinates, so that the labels are not distorted.
+    pub fn draw_layer(viewport: &Canvas, mut cr: ContextRef, obj: Editor) -> Inhibit {
+        let state = obj.state().borrow();
+        if Self::static_type() != state.active_tool {
+            return Inhibit(false);
+        }
+        let t = state.tools[&state.active_tool]
+            .clone()
+            .downcast::<Self>()
+            .unwrap();
+        if !t.imp().active.get() {
+            return Inhibit(false);
+        }
+        let Some((start, end)) = t.imp().line.get() else {
+            return Inhibit(false);
+        };
+        let intersections = Self::intersections(&state.glyph.borrow(), (start, end));
+        let view = |p: Point| viewport.unit_to_view_point(UnitPoint(p)).0;
+        let color = Color::from_hex("#1e88e5"); // [ref:hardcoded_color_value]
+        let line_width = obj.app_settings().property::<f64>(Settings::LINE_WIDTH);
+        let (view_start, view_end) = (view(start), view(end));
+
+        let cr1 = cr.push();
+        cr1.set_source_color(color);
+        cr1.set_line_width(line_width);
+        cr1.move_to(view_start.x, view_start.y);
+        cr1.line_to(view_end.x, view_end.y);
+        cr1.stroke().unwrap();
+        for p in [view_start, view_end] {
+            cr1.move_to(p.x - 4.0, p.y);
+            cr1.line_to(p.x + 4.0, p.y);
+            cr1.move_to(p.x, p.y - 4.0);
+            cr1.line_to(p.x, p.y + 4.0);
+        }
+        cr1.stroke().unwrap();
+        for p in intersections.iter().map(|&p| view(p)) {
+            cr1.arc(p.x, p.y, 3.0, 0.0, 2.0 * std::f64::consts::PI);
+            cr1.fill().unwrap();
+        }
+
+        /* Label the distances between consecutive intersections beside the line. */
+        let direction = (view_end - view_start).unit();
+        let normal: Point = (-direction.y, direction.x).into();
+        cr1.set_source_rgba(0.0, 0.0, 0.0, 1.0);
+        for w in intersections.windows(2) {
+            let label = format!("{:.1}", w[0].distance(w[1]));
+            let extents = cr1.text_extents(&label).unwrap();
+            let middle = 0.5 * (view(w[0]) + view(w[1])) + 8.0 * normal;
+            cr1.move_to(
+                extents.width.mul_add(-0.5, middle.x),
+                extents.height.mul_add(0.5, middle.y),
+            );
+            cr1.show_text(&label).unwrap();
+        }
+
+        let delta = end - start;
+        let line_height = cr1.text_extents("Measure").unwrap().height * 1.5;
+        let mut y = view_end.y + line_height;
+        for line in [
+            format!("Distance: {:.1}", delta.norm()),
+            format!("Angle: {:.1}°", delta.atan2().to_degrees()),
+            format!("Δx: {:.1}  Δy: {:.1}", delta.x, delta.y),
+        ] {
+            cr1.move_to(view_end.x + 10.0, y);
+            cr1.show_text(&line).unwrap();
+            y += line_height;
+        }
+
+        Inhibit(true)
+    }
+}
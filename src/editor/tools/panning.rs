@@ -313,6 +313,24 @@ impl ToolImplImpl for PanningToolInner {
                                 return Inhibit(true);
                             }
                         }
+                        let component = {
+                            let state = view.state().borrow();
+                            let glyph = state.glyph.borrow();
+                            glyph
+                                .component_query(position)
+                                .map(|i| glyph.components[i].glyph_index(i))
+                        };
+                        if let Some(idx) = component {
+                            self.is_selection_empty.set(false);
+                            view.set_selection(&[idx], event.state().into());
+                            self.instance()
+                                .set_property::<bool>(PanningTool::ACTIVE, true);
+                            self.mode.set(Mode::Drag);
+                            view.set_property(Editor::MODIFYING_IN_PROCESS, true);
+                            view.hovering.set(None);
+                            viewport.set_cursor("grab");
+                            return Inhibit(true);
+                        }
                         view.hovering.set(None);
                         self.instance()
                             .set_property::<bool>(PanningTool::ACTIVE, true);
@@ -571,7 +589,54 @@ impl ToolImplImpl for PanningToolInner {
             }
             _ if event_button == gtk::gdk::BUTTON_SECONDARY => {
                 self.set_default_cursor(&view);
-                let (is_corner, on_curve_query) = {
+                let anchor = {
+                    let scale: f64 = viewport
+                        .transformation
+                        .property::<f64>(Transformation::SCALE);
+                    let ppu: f64 = viewport
+                        .transformation
+                        .property::<f64>(Transformation::PIXELS_PER_UNIT);
+                    let state = view.state().borrow();
+                    let UnitPoint(position) =
+                        viewport.view_to_unit_point(ViewPoint(event.position().into()));
+                    let pts = state
+                        .kd_tree
+                        .borrow()
+                        .query_point(position, (10.0 / (scale * ppu)).ceil() as i64);
+                    pts.iter()
+                        .find_map(|p| Some((p.curve_index, state.anchor(p)?)))
+                };
+                if let Some((i, a)) = anchor {
+                    crate::utils::menu::Menu::new()
+                        .title(Some(std::borrow::Cow::from(format!(
+                            "Anchor {}",
+                            a.name()
+                        ))))
+                        .separator()
+                        .add_button_cb(
+                            "Edit",
+                            clone!(@weak a, @weak view =>  move |_| {
+                                let w = a.new_property_window(view.app(), false);
+                                w.present();
+                            }),
+                        )
+                        .add_button_cb(
+                            "Delete",
+                            clone!(@weak view, @weak a =>  move |_| {
+                                view.set_selection(&[a.glyph_index(i)], SelectionModifier::Remove);
+                                let state = view.state().borrow();
+                                let i = state.glyph.borrow().metadata.anchors().iter().position(|el| el == &a);
+                                if let Some(i) = i {
+                                    let mut action = state.delete_anchor(i);
+                                    (action.redo)();
+                                    state.add_undo_action(action);
+                                }
+                            }),
+                        )
+                        .popup(event.time());
+                    return Inhibit(true);
+                }
+                let (is_corner, on_curve_query, component) = {
                     let scale: f64 = viewport
                         .transformation
                         .property::<f64>(Transformation::SCALE);
@@ -589,7 +654,7 @@ impl ToolImplImpl for PanningToolInner {
                     let is_corner = if pts.is_empty() {
                         None
                     } else {
-                        pts.iter().find_map(|p| {
+                        pts.iter().filter(|p| p.is_contour_point()).find_map(|p| {
                             state.glyph.borrow().contours[p.contour_index].curves()[p.curve_index]
                                 .points()
                                 .iter()
@@ -598,7 +663,8 @@ impl ToolImplImpl for PanningToolInner {
                         })
                     };
                     let on_curve_query = glyph.on_curve_query(position, &[]);
-                    (is_corner, on_curve_query)
+                    let component = glyph.component_query(position);
+                    (is_corner, on_curve_query, component)
                 };
                 if let Some((idx, corner_continuity)) = is_corner {
                     let mut menu = crate::utils::menu::Menu::new()
@@ -670,6 +736,65 @@ impl ToolImplImpl for PanningToolInner {
                         )
                         .popup(event.time());
                     return Inhibit(true);
+                } else if let Some(i) = component {
+                    let (base_name, base) = {
+                        let state = view.state().borrow();
+                        let glyph = state.glyph.borrow();
+                        let c = &glyph.components[i];
+                        (c.base_name.clone(), c.base.clone())
+                    };
+                    crate::utils::menu::Menu::new()
+                        .title(Some(std::borrow::Cow::from(format!(
+                            "Component {base_name}"
+                        ))))
+                        .separator()
+                        .add_button_cb(
+                            "Edit",
+                            clone!(@weak view => move |_| {
+                                view.component_properties_dialog(i);
+                            }),
+                        )
+                        .add_button_cb(
+                            "Decompose",
+                            clone!(@weak view => move |_| {
+                                view.set_selection(&[], SelectionModifier::Replace);
+                                let state = view.state().borrow();
+                                if state.glyph.borrow().components.get(i).is_some() {
+                                    let mut action = state.decompose_component(i);
+                                    (action.redo)();
+                                    state.add_undo_action(action);
+                                }
+                            }),
+                        )
+                        .add_button_cb(
+                            "Open base glyph in new tab",
+                            clone!(@weak view => move |_| {
+                                if let Some(base) = base.upgrade() {
+                                    view.app().window.edit_glyph(&base);
+                                }
+                            }),
+                        )
+                        .add_button_cb(
+                            "Delete",
+                            clone!(@weak view => move |_| {
+                                view.set_selection(&[], SelectionModifier::Replace);
+                                let state = view.state().borrow();
+                                if state.glyph.borrow().components.get(i).is_some() {
+                                    let mut action = state.delete_component(i);
+                                    (action.redo)();
+                                    state.add_undo_action(action);
+                                }
+                            }),
+                        )
+                        .separator()
+                        .add_button_cb(
+                            "Add component",
+                            clone!(@weak view => move |_| {
+                                view.add_component_dialog();
+                            }),
+                        )
+                        .popup(event.time());
+                    return Inhibit(true);
                 }
                 return Inhibit(false);
             }
@@ -748,7 +873,7 @@ impl ToolImplImpl for PanningToolInner {
                 } else {
                     let glyph = state.glyph.borrow();
 
-                    pts.iter().find_map(|p| {
+                    pts.iter().filter(|p| p.is_contour_point()).find_map(|p| {
                         let contour = &glyph.contours[p.contour_index];
                         let curves = contour.curves();
                         let ret = curves[p.curve_index]
@@ -839,7 +964,7 @@ impl ToolImplImpl for PanningToolInner {
                     Some(Lock::LOCAL) => {
                         // [ref:FIXME] ugly, wobbly but mostly works
                         let selection = state.get_selection();
-                        if !selection.is_empty() {
+                        if !selection.is_empty() && selection[0].is_contour_point() {
                             let UnitPoint(upos) = viewport
                                 .view_to_unit_point(ViewPoint(Point::from(event.position())));
                             let lock_delta = {
@@ -1297,10 +1422,9 @@ impl PanningTool {
             cr2.set_line_width(3.0);
             cr2.set_dash(&[2.0 * f, 2.0 * f], 0.5 * f);
             let selection = state.get_selection();
-            let contours = &state.glyph.borrow().contours;
             let points = selection
                 .iter()
-                .map(|g| contours[g.contour_index].get_point(*g).unwrap())
+                .filter_map(|g| state.point_position(g))
                 .collect::<Vec<Point>>();
             let kd = state.kd_tree.borrow();
             let mut x_set =
@@ -1316,7 +1440,11 @@ impl PanningTool {
                     .map(|p| (p, Coordinate::X))
                     .chain(query_y.into_iter().map(|p| (p, Coordinate::Y)))
                 {
-                    let p = contours[p.contour_index].get_point(p).unwrap();
+                    let p = if let Some(p) = state.point_position(&p) {
+                        p
+                    } else {
+                        continue;
+                    };
 
                     match ax {
                         Coordinate::X => {
@@ -0,0 +1,672 @@
+/*
+ * gerb
+ *
+ * Copyright 2022 - Manos Pitsidianakis
+ *
+ * This file is part of gerb.
+ *
+ * gerb is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * gerb is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with gerb. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Scaling, rotating and skewing the selection with handles on its bounding box.
+//!
+//! Dragging a corner handle scales from the opposite corner, uniformly if Shift is held, and
+//! dragging an edge handle scales from the opposite edge or, with Ctrl held, skews along it. The
+//! round handles outside the corners rotate around the pivot, which starts at the center of the
+//! box and can be dragged elsewhere; Shift constrains rotations to multiples of 15 degrees.
+//! Pressing anywhere else selects and moves points like the panning tool does.
+
+use super::tool_impl::*;
+use gtk::cairo::Matrix;
+use gtk::Inhibit;
+
+use crate::prelude::*;
+use crate::views::canvas::{Layer, LayerBuilder};
+
+/// Size of the handles in view coordinates.
+const HANDLE_SIZE: f64 = 8.0;
+/// Distance of the rotation handles from the corners in view coordinates.
+const ROTATION_HANDLE_OFFSET: f64 = 16.0;
+
+/// Affine transformation given by its components, as entered in the transform dialog.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TransformParameters {
+    /// Fixed point of scaling, skewing and rotation.
+    pub origin: Point,
+    pub translate: Point,
+    pub scale: (f64, f64),
+    /// Skew angles in degrees. The first slants vertical lines horizontally and the second
+    /// slants horizontal lines vertically.
+    pub skew: (f64, f64),
+    /// Counter-clockwise rotation angle in degrees.
+    pub rotate: f64,
+}
+
+impl Default for TransformParameters {
+    fn default() -> Self {
+        Self {
+            origin: Point::default(),
+            translate: Point::default(),
+            scale: (1.0, 1.0),
+            skew: (0.0, 0.0),
+            rotate: 0.0,
+        }
+    }
+}
+
+impl TransformParameters {
+    /// The transformation that scales, skews and rotates around `origin`, in that order, and
+    /// then moves by `translate`.
+    pub fn matrix(&self) -> Matrix {
+        let (sin, cos) = self.rotate.to_radians().sin_cos();
+        [
+            Matrix::new(1.0, 0.0, 0.0, 1.0, -self.origin.x, -self.origin.y),
+            Matrix::new(self.scale.0, 0.0, 0.0, self.scale.1, 0.0, 0.0),
+            Matrix::new(
+                1.0,
+                self.skew.1.to_radians().tan(),
+                self.skew.0.to_radians().tan(),
+                1.0,
+                0.0,
+                0.0,
+            ),
+            Matrix::new(cos, sin, -sin, cos, 0.0, 0.0),
+            Matrix::new(
+                1.0,
+                0.0,
+                0.0,
+                1.0,
+                self.origin.x + self.translate.x,
+                self.origin.y + self.translate.y,
+            ),
+        ]
+        .iter()
+        .fold(Matrix::identity(), |acc, m| Matrix::multiply(&acc, m))
+    }
+}
+
+/// A handle of the bounding box. Positions are given as `-1`, `0` or `1` for the left, center
+/// or right and the bottom, center or top of the box.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Handle {
+    Scale(i8, i8),
+    Rotate(i8, i8),
+    Pivot,
+}
+
+const CORNERS: [(i8, i8); 4] = [(-1, -1), (1, -1), (1, 1), (-1, 1)];
+const EDGES: [(i8, i8); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+
+/// Lower left and upper right corners of a bounding box.
+type BBox = (Point, Point);
+
+/// Position in unit coordinates of the handle at `(x, y)` of `bbox`.
+fn handle_position((min, max): BBox, (x, y): (i8, i8)) -> Point {
+    let center = 0.5 * (min + max);
+    (
+        (0.5 * f64::from(x)).mul_add(max.x - min.x, center.x),
+        (0.5 * f64::from(y)).mul_add(max.y - min.y, center.y),
+    )
+        .into()
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Drag {
+    handle: Handle,
+    /// Edge handles skew instead of scaling.
+    skew: bool,
+    start: Point,
+    bbox: BBox,
+    pivot: Point,
+    /// Transformation applied to the selection since the drag started.
+    applied: Matrix,
+}
+
+impl Drag {
+    /// The transformation of the whole drag when the pointer is at `point`.
+    fn matrix(&self, point: Point, constrain: bool) -> Matrix {
+        /* Ratio of the pointer's displacement to its distance from a fixed line. */
+        let ratio =
+            |moved: f64, distance: f64| (distance.abs() >= f64::EPSILON).then(|| moved / distance);
+        let parameters = match self.handle {
+            Handle::Pivot => return Matrix::identity(),
+            Handle::Rotate(_, _) => {
+                let mut angle = (point - self.pivot).atan2() - (self.start - self.pivot).atan2();
+                if constrain {
+                    let step = 15.0_f64.to_radians();
+                    angle = (angle / step).round() * step;
+                }
+                TransformParameters {
+                    origin: self.pivot,
+                    rotate: angle.to_degrees(),
+                    ..TransformParameters::default()
+                }
+            }
+            Handle::Scale(x, y) if self.skew && (x == 0) != (y == 0) => {
+                let anchor = handle_position(self.bbox, (-x, -y));
+                let skew = if y == 0 {
+                    (
+                        0.0,
+                        ratio(point.y - self.start.y, self.start.x - anchor.x)
+                            .unwrap_or(0.0)
+                            .atan()
+                            .to_degrees(),
+                    )
+                } else {
+                    (
+                        ratio(point.x - self.start.x, self.start.y - anchor.y)
+                            .unwrap_or(0.0)
+                            .atan()
+                            .to_degrees(),
+                        0.0,
+                    )
+                };
+                TransformParameters {
+                    origin: anchor,
+                    skew,
+                    ..TransformParameters::default()
+                }
+            }
+            Handle::Scale(x, y) => {
+                let anchor = handle_position(self.bbox, (-x, -y));
+                let mut scale = (
+                    if x == 0 {
+                        1.0
+                    } else {
+                        ratio(point.x - anchor.x, self.start.x - anchor.x).unwrap_or(1.0)
+                    },
+                    if y == 0 {
+                        1.0
+                    } else {
+                        ratio(point.y - anchor.y, self.start.y - anchor.y).unwrap_or(1.0)
+                    },
+                );
+                if constrain && x != 0 && y != 0 {
+                    let s = if scale.0.abs() > scale.1.abs() {
+                        scale.0
+                    } else {
+                        scale.1
+                    };
+                    scale = (s, s);
+                }
+                TransformParameters {
+                    origin: anchor,
+                    scale,
+                    ..TransformParameters::default()
+                }
+            }
+        };
+        parameters.matrix()
+    }
+}
+
+#[derive(Default)]
+pub struct TransformToolInner {
+    layer: OnceCell<Layer>,
+    active: Cell<bool>,
+    /// Position of the pivot relative to the bounding box, or `None` for its center.
+    pivot: Cell<Option<(f64, f64)>>,
+    drag: Cell<Option<Drag>>,
+    hovering: Cell<bool>,
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for TransformToolInner {
+    const NAME: &'static str = "TransformTool";
+    type ParentType = ToolImpl;
+    type Type = TransformTool;
+}
+
+impl ObjectImpl for TransformToolInner {
+    fn constructed(&self, obj: &Self::Type) {
+        self.parent_constructed(obj);
+        obj.set_property::<bool>(TransformTool::ACTIVE, false);
+        obj.set_property::<String>(ToolImpl::NAME, "transform".to_string());
+        obj.set_property::<String>(
+            ToolImpl::DESCRIPTION,
+            "Scale, rotate and skew selection".to_string(),
+        );
+        obj.set_property::<gtk::Image>(
+            ToolImpl::ICON,
+            crate::resources::icons::TRANSFORM_ICON.to_image_widget(),
+        );
+    }
+
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: once_cell::sync::Lazy<Vec<glib::ParamSpec>> =
+            once_cell::sync::Lazy::new(|| {
+                vec![glib::ParamSpecBoolean::new(
+                    TransformTool::ACTIVE,
+                    TransformTool::ACTIVE,
+                    TransformTool::ACTIVE,
+                    true,
+                    glib::ParamFlags::READWRITE,
+                )]
+            });
+        PROPERTIES.as_ref()
+    }
+
+    fn property(&self, _obj: &Self::Type, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        match pspec.name() {
+            TransformTool::ACTIVE => self.active.get().to_value(),
+            _ => unimplemented!("{}", pspec.name()),
+        }
+    }
+
+    fn set_property(
+        &self,
+        _obj: &Self::Type,
+        _id: usize,
+        value: &glib::Value,
+        pspec: &glib::ParamSpec,
+    ) {
+        match pspec.name() {
+            TransformTool::ACTIVE => self.active.set(value.get().unwrap()),
+            _ => unimplemented!("{}", pspec.name()),
+        }
+    }
+}
+
+impl ToolImplImpl for TransformToolInner {
+    fn on_button_press_event(
+        &self,
+        _obj: &ToolImpl,
+        view: Editor,
+        viewport: &Canvas,
+        event: &gtk::gdk::EventButton,
+    ) -> Inhibit {
+        if !self.active.get() {
+            return Inhibit(false);
+        }
+        match event.button() {
+            gtk::gdk::BUTTON_PRIMARY => {
+                let Some(bbox) = view.state().borrow().selection_bbox() else {
+                    return Inhibit(false);
+                };
+                let position: Point = event.position().into();
+                let Some(handle) = self.handle_at(viewport, bbox, position) else {
+                    return Inhibit(false);
+                };
+                let UnitPoint(start) = viewport.view_to_unit_point(ViewPoint(position));
+                self.drag.set(Some(Drag {
+                    handle,
+                    skew: event
+                        .state()
+                        .intersects(gtk::gdk::ModifierType::CONTROL_MASK),
+                    start,
+                    bbox,
+                    pivot: self.pivot_position(bbox),
+                    applied: Matrix::identity(),
+                }));
+                view.set_property(Editor::MODIFYING_IN_PROCESS, true);
+                viewport.set_cursor("grab");
+            }
+            gtk::gdk::BUTTON_SECONDARY => {
+                self.reset();
+                self.instance()
+                    .set_property::<bool>(TransformTool::ACTIVE, false);
+                view.state().borrow_mut().active_tool = glib::types::Type::INVALID;
+                viewport.set_cursor("default");
+                viewport.queue_draw();
+            }
+            _ => return Inhibit(false),
+        }
+        Inhibit(true)
+    }
+
+    fn on_button_release_event(
+        &self,
+        _obj: &ToolImpl,
+        view: Editor,
+        viewport: &Canvas,
+        event: &gtk::gdk::EventButton,
+    ) -> Inhibit {
+        if !self.active.get() || event.button() != gtk::gdk::BUTTON_PRIMARY {
+            return Inhibit(false);
+        }
+        let Some(drag) = self.drag.take() else {
+            return Inhibit(false);
+        };
+        if matches!(drag.handle, Handle::Rotate(_, _)) {
+            /* Keep the pivot in place although the bounding box of the rotated selection
+             * changed. */
+            if let Some(bbox) = view.state().borrow().selection_bbox() {
+                self.set_pivot(bbox, drag.pivot);
+            }
+        }
+        view.set_property(Editor::MODIFYING_IN_PROCESS, false);
+        viewport.queue_draw();
+        Inhibit(true)
+    }
+
+    fn on_motion_notify_event(
+        &self,
+        _obj: &ToolImpl,
+        view: Editor,
+        viewport: &Canvas,
+        event: &gtk::gdk::EventMotion,
+    ) -> Inhibit {
+        if !self.active.get() {
+            return Inhibit(false);
+        }
+        let position: Point = event.position().into();
+        let Some(mut drag) = self.drag.get() else {
+            let bbox = view.state().borrow().selection_bbox();
+            let over_handle =
+                bbox.is_some_and(|bbox| self.handle_at(viewport, bbox, position).is_some());
+            if over_handle {
+                viewport.set_cursor("grab");
+            } else if self.hovering.get() {
+                viewport.set_cursor("default");
+            }
+            self.hovering.set(over_handle);
+            return Inhibit(over_handle);
+        };
+        let UnitPoint(point) = viewport.view_to_unit_point(ViewPoint(position));
+        if drag.handle == Handle::Pivot {
+            self.set_pivot(drag.bbox, point);
+            viewport.queue_draw();
+            return Inhibit(true);
+        }
+        let new = drag.matrix(
+            point,
+            event.state().intersects(gtk::gdk::ModifierType::SHIFT_MASK),
+        );
+        /* A degenerate transformation could not be undone. */
+        if new.try_invert().is_err() {
+            return Inhibit(true);
+        }
+        let Ok(inverse) = drag.applied.try_invert() else {
+            return Inhibit(true);
+        };
+        view.state()
+            .borrow()
+            .transform_selection(Matrix::multiply(&inverse, &new), true);
+        drag.applied = new;
+        self.drag.set(Some(drag));
+        viewport.queue_draw();
+        Inhibit(true)
+    }
+
+    fn setup_toolbox(&self, obj: &ToolImpl, toolbar: &gtk::Toolbar, view: &Editor) {
+        let layer =
+            LayerBuilder::new()
+                .set_name(Some("transform"))
+                .set_active(false)
+                .set_hidden(true)
+                .set_callback(Some(Box::new(clone!(@weak view => @default-return Inhibit(false), move |viewport: &Canvas, cr: ContextRef| {
+                    TransformTool::draw_layer(viewport, cr, view)
+                }))))
+                .build();
+        self.instance()
+            .bind_property(TransformTool::ACTIVE, &layer, Layer::ACTIVE)
+            .flags(glib::BindingFlags::SYNC_CREATE)
+            .build();
+        self.layer.set(layer.clone()).unwrap();
+        view.viewport.add_post_layer(layer);
+
+        self.parent_setup_toolbox(obj, toolbar, view)
+    }
+
+    fn on_activate(&self, obj: &ToolImpl, view: &Editor) {
+        self.instance()
+            .set_property::<bool>(TransformTool::ACTIVE, true);
+        view.viewport.set_cursor("default");
+        self.parent_on_activate(obj, view)
+    }
+
+    fn on_deactivate(&self, obj: &ToolImpl, view: &Editor) {
+        if self.drag.get().is_some() {
+            view.set_property(Editor::MODIFYING_IN_PROCESS, false);
+        }
+        self.reset();
+        self.instance()
+            .set_property::<bool>(TransformTool::ACTIVE, false);
+        view.viewport.set_cursor("default");
+        self.parent_on_deactivate(obj, view)
+    }
+}
+
+impl TransformToolInner {
+    fn reset(&self) {
+        self.pivot.set(None);
+        self.drag.set(None);
+        self.hovering.set(false);
+    }
+
+    fn pivot_position(&self, bbox: BBox) -> Point {
+        let (min, max) = bbox;
+        self.pivot.get().map_or_else(
+            || 0.5 * (min + max),
+            |(x, y)| {
+                (
+                    x.mul_add(max.x - min.x, min.x),
+                    y.mul_add(max.y - min.y, min.y),
+                )
+                    .into()
+            },
+        )
+    }
+
+    /// Move the pivot to `point`, relative to `bbox` so that it follows the selection.
+    fn set_pivot(&self, (min, max): BBox, point: Point) {
+        let relative = |p: f64, min: f64, max: f64| {
+            if (max - min).abs() < f64::EPSILON {
+                0.5
+            } else {
+                (p - min) / (max - min)
+            }
+        };
+        self.pivot.set(Some((
+            relative(point.x, min.x, max.x),
+            relative(point.y, min.y, max.y),
+        )));
+    }
+
+    /// The handles of `bbox` and their positions in view coordinates, in the order they are
+    /// hit.
+    ///
+    /// A box that bounds a single point has no handles.
+    fn handles(&self, viewport: &Canvas, bbox: BBox) -> Vec<(Handle, Point)> {
+        let (min, max) = bbox;
+        if min.distance(max) < f64::EPSILON {
+            return vec![];
+        }
+        let view = |p: Point| viewport.unit_to_view_point(UnitPoint(p)).0;
+        std::iter::once((Handle::Pivot, view(self.pivot_position(bbox))))
+            .chain(
+                CORNERS
+                    .iter()
+                    .chain(EDGES.iter())
+                    .map(|&(x, y)| (Handle::Scale(x, y), view(handle_position(bbox, (x, y))))),
+            )
+            .chain(CORNERS.iter().map(|&(x, y)| {
+                /* View coordinates grow downwards. */
+                let direction = Point::from((f64::from(x), -f64::from(y))).unit();
+                (
+                    Handle::Rotate(x, y),
+                    view(handle_position(bbox, (x, y))) + ROTATION_HANDLE_OFFSET * direction,
+                )
+            }))
+            .collect()
+    }
+
+    /// The handle of `bbox` under `position` in view coordinates.
+    fn handle_at(&self, viewport: &Canvas, bbox: BBox, position: Point) -> Option<Handle> {
+        self.handles(viewport, bbox)
+            .into_iter()
+            .find(|(_, p)| p.distance(position) <= HANDLE_SIZE)
+            .map(|(h, _)| h)
+    }
+}
+
+glib::wrapper! {
+    pub struct TransformTool(ObjectSubclass<TransformToolInner>)
+        @extends ToolImpl;
+}
+
+impl Default for TransformTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TransformTool {
+    pub const ACTIVE: &str = "active";
+
+    pub fn new() -> Self {
+        glib::Object::new(&[]).unwrap()
+    }
+
+    /// Draw the bounding box of the selection and its handles in view coordinates. While
+    /// dragging, the box follows the transformation instead.
+    pub fn draw_layer(viewport: &Canvas, mut cr: ContextRef, obj: Editor) -> Inhibit {
+        let state = obj.state().borrow();
+        if Self::static_type() != state.active_tool {
+            return Inhibit(false);
+        }
+        let t = state.tools[&state.active_tool]
+            .clone()
+            .downcast::<Self>()
+            .unwrap();
+        if !t.imp().active.get() {
+            return Inhibit(false);
+        }
+        let drag = t.imp().drag.get();
+        let Some(bbox) = drag.map(|d| d.bbox).or_else(|| state.selection_bbox()) else {
+            return Inhibit(false);
+        };
+        let applied = drag.map_or_else(Matrix::identity, |d| d.applied);
+        let view = |p: Point| {
+            let (x, y) = applied.transform_point(p.x, p.y);
+            viewport.unit_to_view_point(UnitPoint((x, y).into())).0
+        };
+        let line_width = obj.app_settings().property::<f64>(Settings::LINE_WIDTH);
+
+        let cr1 = cr.push();
+        cr1.set_source_color(Color::BLACK);
+        cr1.set_line_width(line_width);
+        cr1.set_dash(&[4.0, 4.0], 0.0);
+        for (i, &corner) in CORNERS.iter().enumerate() {
+            let p = view(handle_position(bbox, corner));
+            if i == 0 {
+                cr1.move_to(p.x, p.y);
+            } else {
+                cr1.line_to(p.x, p.y);
+            }
+        }
+        cr1.close_path();
+        cr1.stroke().unwrap();
+        cr1.set_dash(&[], 0.0);
+
+        let pivot = drag.map_or_else(|| t.imp().pivot_position(bbox), |d| d.pivot);
+        let pivot = viewport.unit_to_view_point(UnitPoint(pivot)).0;
+        cr1.arc(
+            pivot.x,
+            pivot.y,
+            0.5 * HANDLE_SIZE,
+            0.0,
+            2.0 * std::f64::consts::PI,
+        );
+        cr1.move_to(pivot.x - HANDLE_SIZE, pivot.y);
+        cr1.line_to(pivot.x + HANDLE_SIZE, pivot.y);
+        cr1.move_to(pivot.x, pivot.y - HANDLE_SIZE);
+        cr1.line_to(pivot.x, pivot.y + HANDLE_SIZE);
+        cr1.stroke().unwrap();
+
+        if drag.is_some() {
+            return Inhibit(true);
+        }
+        for (handle, p) in t.imp().handles(viewport, bbox) {
+            match handle {
+                Handle::Pivot => continue,
+                Handle::Scale(_, _) => {
+                    let corner = p - Point::from((0.5 * HANDLE_SIZE, 0.5 * HANDLE_SIZE));
+                    cr1.rectangle(corner.x, corner.y, HANDLE_SIZE, HANDLE_SIZE);
+                }
+                Handle::Rotate(_, _) => {
+                    cr1.new_sub_path();
+                    cr1.arc(p.x, p.y, 0.5 * HANDLE_SIZE, 0.0, 2.0 * std::f64::consts::PI);
+                }
+            }
+            cr1.set_source_color(Color::WHITE);
+            cr1.fill_preserve().unwrap();
+            cr1.set_source_color(Color::BLACK);
+            cr1.stroke().unwrap();
+        }
+
+        Inhibit(true)
+    }
+}
+
+#[test]
+fn test_transform_parameters() {
+    let apply = |parameters: TransformParameters, (x, y): (f64, f64)| {
+        let (x, y) = parameters.matrix().transform_point(x, y);
+        Point::from((x, y))
+    };
+    let close = |a: Point, b: (f64, f64)| a.distance(b.into()) < 1e-9;
+
+    assert!(close(
+        apply(TransformParameters::default(), (3.0, 4.0)),
+        (3.0, 4.0)
+    ));
+    let origin = Point::from((10.0, 10.0));
+    assert!(close(
+        apply(
+            TransformParameters {
+                origin,
+                scale: (2.0, 0.5),
+                ..TransformParameters::default()
+            },
+            (20.0, 20.0)
+        ),
+        (30.0, 15.0)
+    ));
+    assert!(close(
+        apply(
+            TransformParameters {
+                origin,
+                rotate: 90.0,
+                ..TransformParameters::default()
+            },
+            (20.0, 10.0)
+        ),
+        (10.0, 20.0)
+    ));
+    assert!(close(
+        apply(
+            TransformParameters {
+                origin,
+                skew: (45.0, 0.0),
+                translate: (5.0, -5.0).into(),
+                ..TransformParameters::default()
+            },
+            (10.0, 20.0)
+        ),
+        (25.0, 15.0)
+    ));
+    /* Mirroring around the origin keeps it in place. */
+    assert!(close(
+        apply(
+            TransformParameters {
+                origin,
+                scale: (-1.0, 1.0),
+                ..TransformParameters::default()
+            },
+            (10.0, 0.0)
+        ),
+        (10.0, 0.0)
+    ));
+}
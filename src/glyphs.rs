@@ -36,14 +36,29 @@ use uuid::Uuid;
 mod guidelines;
 pub use guidelines::*;
 
-pub use crate::ufo::glif::{self, Advance, Anchor, ImageRef, Unicode};
+mod anchors;
+pub use anchors::*;
+
+pub use crate::ufo::glif::{self, Advance, ImageRef, Unicode};
 
 mod contours;
 pub use contours::*;
 
+pub mod boolean;
+pub mod bspline;
+pub mod interpolation;
+pub mod knife;
+pub mod offset;
+pub mod simplify;
+pub mod svg;
+
 pub mod obj;
 pub use obj::GlyphMetadata;
 
+/// Glyphs and their new contours, computed by a batch operation but not applied yet. See
+/// [`WindowInner::replace_glyph_contours`](crate::window::WindowInner::replace_glyph_contours).
+pub type ContourChanges = Vec<(Rc<RefCell<Glyph>>, Vec<Contour>)>;
+
 #[derive(Debug, Clone)]
 pub struct Component {
     pub base_name: String,
@@ -54,6 +69,127 @@ pub struct Component {
     pub xy_scale: f64,
     pub yx_scale: f64,
     pub y_scale: f64,
+    /// Identifies the component in the editor's selection.
+    pub uuid: Uuid,
+}
+
+impl Component {
+    pub fn new(base_name: String, base: Weak<RefCell<Glyph>>) -> Self {
+        Self {
+            base_name,
+            base,
+            x_offset: 0.0,
+            y_offset: 0.0,
+            x_scale: 1.0,
+            xy_scale: 0.0,
+            yx_scale: 0.0,
+            y_scale: 1.0,
+            uuid: Uuid::new_v4(),
+        }
+    }
+
+    /// Transformation from the base glyph's coordinates to the coordinates of the glyph that
+    /// contains the component.
+    pub fn matrix(&self) -> Matrix {
+        Matrix::new(
+            self.x_scale,
+            self.xy_scale,
+            self.yx_scale,
+            self.y_scale,
+            self.x_offset,
+            self.y_offset,
+        )
+    }
+
+    pub fn set_matrix(&mut self, m: Matrix) {
+        self.x_scale = m.xx;
+        self.xy_scale = m.yx;
+        self.yx_scale = m.xy;
+        self.y_scale = m.yy;
+        self.x_offset = m.x0;
+        self.y_offset = m.y0;
+    }
+
+    /// Index of the component in the editor's selection.
+    ///
+    /// `index` is the position of the component in [`Glyph::components`].
+    pub fn glyph_index(&self, index: usize) -> GlyphPointIndex {
+        GlyphPointIndex {
+            contour_index: GlyphPointIndex::COMPONENT,
+            curve_index: index,
+            uuid: self.uuid,
+        }
+    }
+
+    /// Bounding box of the base glyph's points, with the component transformation applied.
+    pub fn bbox(&self) -> Option<(Point, Point)> {
+        let contours = self.decompose();
+        let mut points = contours
+            .iter()
+            .flat_map(|c| {
+                c.curves()
+                    .iter()
+                    .flat_map(|b| b.points().iter().map(|p| p.position).collect::<Vec<_>>())
+                    .collect::<Vec<_>>()
+            })
+            .peekable();
+        let first = *points.peek()?;
+        Some(points.fold((first, first), |(min, max), p| {
+            (
+                (min.x.min(p.x), min.y.min(p.y)).into(),
+                (max.x.max(p.x), max.y.max(p.y)).into(),
+            )
+        }))
+    }
+
+    /// Copy the base glyph's contours, and those of its own components, with the component
+    /// transformation applied.
+    pub fn decompose(&self) -> Vec<Contour> {
+        let mut ret = vec![];
+        self.decompose_with(Matrix::identity(), 0, &mut ret);
+        ret
+    }
+
+    fn decompose_with(&self, m: Matrix, depth: usize, ret: &mut Vec<Contour>) {
+        /* Guard against components that reference themselves. */
+        const MAX_DEPTH: usize = 32;
+
+        let Some(base) = self.base.upgrade() else {
+            return;
+        };
+        if depth > MAX_DEPTH {
+            return;
+        }
+        let m = Matrix::multiply(&self.matrix(), &m);
+        let base = base.borrow();
+        for contour in base.contours.iter() {
+            let new = Contour::new();
+            for curve in contour.curves().iter() {
+                let points = curve
+                    .points()
+                    .iter()
+                    .map(|p| m.transform_point(p.position.x, p.position.y).into())
+                    .collect::<Vec<Point>>();
+                let new_curve = Bezier::new(points);
+                new_curve.set_property(Bezier::SMOOTH, curve.property::<bool>(Bezier::SMOOTH));
+                new_curve
+                    .imp()
+                    .implied_start
+                    .set(curve.imp().implied_start.get());
+                new.push_curve(new_curve);
+            }
+            new.imp()
+                .leading_offcurves
+                .set(contour.imp().leading_offcurves.get());
+            if !contour.property::<bool>(Contour::OPEN) {
+                new.close();
+            }
+            ret.push(new);
+        }
+        for c in base.components.iter() {
+            c.decompose_with(m, depth + 1, ret);
+        }
+    }
 }
 
 #[derive(Debug, Ord, PartialOrd, Clone, PartialEq, Eq)]
@@ -150,7 +286,29 @@ pub struct Glyph {
     pub components: Vec<Component>,
     guidelines: Vec<Guideline>,
     pub lib: IndexMap<String, plist::Value>,
+    /// See [`glif::Glif::lib_source`].
+    pub lib_source: Option<String>,
     pub metadata: GlyphMetadata,
+    wrong_directions: RefCell<Option<WrongDirections>>,
+}
+
+/// Result of [`Glyph::wrong_directions`], along with the contours and the revisions it was
+/// computed from.
+#[derive(Debug, Clone)]
+struct WrongDirections {
+    convention: ContourDirection,
+    contours: Vec<(Contour, Vec<u64>)>,
+    indices: Vec<usize>,
+}
+
+impl WrongDirections {
+    /// The [`Contour::revision`] of `contour` followed by the [`Bezier::revision`] of each of
+    /// its curves, since curves can be edited in place.
+    fn revisions(contour: &Contour) -> Vec<u64> {
+        std::iter::once(contour.revision())
+            .chain(contour.curves().iter().map(Bezier::revision))
+            .collect()
+    }
 }
 
 impl std::ops::Deref for Glyph {
@@ -210,6 +368,8 @@ pub struct GlyphDrawingOptions<'a> {
     pub corner: Option<DrawOptions>,
     pub smooth_corner: Option<DrawOptions>,
     pub direction_arrow: Option<DrawOptions>,
+    /// Draw the direction arrows of contours that run against this convention in red.
+    pub direction_convention: Option<ContourDirection>,
     pub selection: Option<&'a HashSet<Uuid>>,
 }
 
@@ -231,41 +391,54 @@ impl Default for GlyphDrawingOptions<'_> {
             corner: None,
             smooth_corner: None,
             direction_arrow: None,
+            direction_convention: None,
             selection: None,
         }
     }
 }
 
 impl Glyph {
+    /// Load the glyphs of `contents` from the glif files in `root_path`.
+    ///
+    /// Glyphs that fail to load are left out and their errors returned alongside the loaded
+    /// glyphs, so that one broken file does not prevent the rest of the font from opening. The
+    /// warnings of [`Glyph::from_glif`] are returned last.
     #[allow(clippy::type_complexity)]
     pub fn from_ufo(
         root_path: PathBuf,
         contents: &ufo::Contents,
-    ) -> Result<IndexMap<String, Rc<RefCell<Self>>>, Box<dyn std::error::Error>> {
+    ) -> (
+        IndexMap<String, Rc<RefCell<Self>>>,
+        Vec<glif::GlifError>,
+        Vec<glif::GlifError>,
+    ) {
+        use glif::{GlifElement, GlifError, GlifErrorKind};
+
         let mut ret: IndexMap<String, Rc<RefCell<Self>>> = IndexMap::default();
+        let mut errors = vec![];
+        let mut warnings = vec![];
         let mut glyphs_with_refs: Vec<Rc<_>> = vec![];
         let mut path = root_path;
 
         for (name, filename) in contents.glyphs().iter() {
             path.push(filename);
-            use std::fs::File;
-            use std::io::prelude::*;
-            let mut file = match File::open(&path) {
-                Err(err) => return Err(format!("Couldn't open {}: {}", path.display(), err).into()),
-                Ok(file) => file,
+            let s = match std::fs::read_to_string(&path) {
+                Err(err) => {
+                    errors.push(GlifError {
+                        file: Some(path.clone()),
+                        ..GlifError::new(GlifElement::Glyph, GlifErrorKind::Io(err.to_string()))
+                    });
+                    path.pop();
+                    continue;
+                }
+                Ok(s) => s,
             };
-
-            let mut s = String::new();
-            if let Err(err) = file.read_to_string(&mut s) {
-                return Err(format!("Couldn't read {}: {}", path.display(), err).into());
-            }
-            let g: Result<glif::Glif, _> = glif::Glif::from_str(&s);
-            match g {
+            match glif::Glif::from_str(&s).and_then(Self::from_glif) {
                 Err(err) => {
-                    eprintln!("couldn't parse {}: {}", path.display(), err);
+                    errors.push(err.locate(&path, &s));
                 }
-                Ok(g) => {
-                    let glyph: Self = g.into();
+                Ok((glyph, glyph_warnings)) => {
+                    warnings.extend(glyph_warnings.into_iter().map(|w| w.locate(&path, &s)));
                     *glyph.metadata.filename.borrow_mut() = filename.clone();
                     *glyph.metadata.glif_source.borrow_mut() = s;
                     let has_components = !glyph.components.is_empty();
@@ -287,7 +460,7 @@ impl Glyph {
                 }
             }
         }
-        Ok(ret)
+        (ret, errors, warnings)
     }
 
     pub fn new(name: String, char: char, curves: Vec<Bezier>) -> Self {
@@ -304,7 +477,9 @@ impl Glyph {
             components: vec![],
             guidelines: vec![],
             lib: IndexMap::default(),
+            lib_source: None,
             metadata,
+            wrong_directions: RefCell::new(None),
         }
     }
 
@@ -354,6 +529,7 @@ impl Glyph {
             corner,
             smooth_corner: _,
             direction_arrow,
+            direction_convention,
             selection,
         } = options;
 
@@ -558,11 +734,16 @@ impl Glyph {
                     cr.stroke().unwrap();
                 }
             };
-            let draw_tangent = |cr: ContextRef, curv: &Bezier| {
+            let wrong_directions = direction_arrow
+                .and(direction_convention)
+                .map(|convention| self.wrong_directions(convention))
+                .unwrap_or_default();
+            let draw_tangent = |cr: ContextRef, curv: &Bezier, wrong: bool| {
                 if let Some(opts) = direction_arrow {
                     let (t, p) = curv.emptiest_t(0.3);
                     let tangent = curv.tangent(t);
-                    cr.set_source_color_alpha(opts.color);
+                    /* [ref:hardcoded_color_value] */
+                    cr.set_source_color_alpha(if wrong { Color::RED } else { opts.color });
                     cr.set_line_width(outline.size);
                     cr.translate(p.x, p.y);
                     cr.rotate(tangent.atan2());
@@ -583,7 +764,8 @@ impl Glyph {
                     cr.stroke().unwrap();
                 }
             };
-            for contour in self.contours.iter() {
+            for (contour_index, contour) in self.contours.iter().enumerate() {
+                let wrong = wrong_directions.contains(&contour_index);
                 let curves = contour.curves();
                 let biggest = contour.property::<u64>(Contour::BIGGEST_CURVE) as usize;
                 for (i, curv) in curves.iter().enumerate() {
@@ -606,7 +788,7 @@ impl Glyph {
                         1 => {
                             /* Line. */
                             if i == biggest {
-                                draw_tangent(cr1.push(), curv);
+                                draw_tangent(cr1.push(), curv, wrong);
                             }
                             draw_oncurve(
                                 cr1.push(),
@@ -622,7 +804,7 @@ impl Glyph {
                         2 => {
                             /* Quadratic. */
                             if i == biggest {
-                                draw_tangent(cr1.push(), curv);
+                                draw_tangent(cr1.push(), curv, wrong);
                             }
                             let handle = &curv_points[1];
                             let ep1 = &curv_points[0];
@@ -636,7 +818,7 @@ impl Glyph {
                         3 => {
                             /* Cubic */
                             if i == biggest {
-                                draw_tangent(cr1.push(), curv);
+                                draw_tangent(cr1.push(), curv, wrong);
                             }
                             let handle1 = &curv_points[1];
                             let handle2 = &curv_points[2];
@@ -663,26 +845,35 @@ impl Glyph {
                 let glyph = rc.borrow();
                 let crc = cr.push();
                 crc.transform(matrix);
-                let matrix = Matrix::new(
-                    component.x_scale,
-                    component.xy_scale,
-                    component.yx_scale,
-                    component.y_scale,
-                    component.x_offset,
-                    component.y_offset,
-                );
                 glyph.draw(
                     crc,
                     GlyphDrawingOptions {
-                        matrix,
+                        matrix: component.matrix(),
                         handle: None,
                         corner: None,
                         smooth_corner: None,
                         direction_arrow: None,
+                        direction_convention: None,
                         selection: None,
                         ..options
                     },
                 );
+                drop(glyph);
+                if options
+                    .selection
+                    .map(|s| s.contains(&component.uuid))
+                    .unwrap_or(false)
+                {
+                    if let Some((min, max)) = component.bbox() {
+                        let crc = cr.push();
+                        crc.transform(matrix);
+                        crc.set_line_width(options.outline.size);
+                        crc.set_source_color_alpha(options.outline.color);
+                        crc.set_dash(&[4.0 * options.outline.size], 0.0);
+                        crc.rectangle(min.x, min.y, max.x - min.x, max.y - min.y);
+                        crc.stroke().unwrap();
+                    }
+                }
             }
         }
     }
@@ -757,6 +948,58 @@ impl Glyph {
         Ok(())
     }
 
+    /// Indices of the closed contours that run against `convention`. Contours inside an odd
+    /// number of other contours are counters, which run the opposite way of outer contours.
+    ///
+    /// The result is cached until the contours change, since it is needed on every redraw.
+    pub fn wrong_directions(&self, convention: ContourDirection) -> Vec<usize> {
+        let mut cache = self.wrong_directions.borrow_mut();
+        let valid = cache.as_ref().is_some_and(|cache| {
+            cache.convention == convention
+                && cache.contours.len() == self.contours.len()
+                && cache.contours.iter().zip(self.contours.iter()).all(
+                    |((c, revisions), contour)| {
+                        c == contour && *revisions == WrongDirections::revisions(contour)
+                    },
+                )
+        });
+        if !valid {
+            *cache = Some(WrongDirections {
+                convention,
+                contours: self
+                    .contours
+                    .iter()
+                    .map(|c| (c.clone(), WrongDirections::revisions(c)))
+                    .collect(),
+                indices: self.find_wrong_directions(convention),
+            });
+        }
+        cache.as_ref().unwrap().indices.clone()
+    }
+
+    fn find_wrong_directions(&self, convention: ContourDirection) -> Vec<usize> {
+        let closed = self
+            .contours
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| !c.property::<bool>(Contour::OPEN))
+            .collect::<Vec<(usize, &Contour)>>();
+        closed
+            .iter()
+            .filter(|&&(i, contour)| {
+                let area = contour.area();
+                let depth = closed
+                    .iter()
+                    .filter(|&&(j, other)| j != i && other.contains(contour))
+                    .count();
+                let counter_clockwise =
+                    (depth % 2 == 0) == matches!(convention, ContourDirection::PostScript);
+                area != 0.0 && (area > 0.0) != counter_clockwise
+            })
+            .map(|&(i, _)| i)
+            .collect()
+    }
+
     pub fn is_empty(&self) -> bool {
         (self.contours.is_empty() || self.contours.iter().all(|c| c.curves().is_empty()))
             && self.components.is_empty()
@@ -774,6 +1017,15 @@ impl Glyph {
         }
     }
 
+    /// Return the index of the topmost component whose bounding box contains `position`.
+    pub fn component_query(&self, position: Point) -> Option<usize> {
+        self.components.iter().enumerate().rev().find_map(|(i, c)| {
+            let (min, max) = c.bbox()?;
+            ((min.x..=max.x).contains(&position.x) && (min.y..=max.y).contains(&position.y))
+                .then_some(i)
+        })
+    }
+
     pub fn on_curve_query(
         &self,
         position: Point,
@@ -818,13 +1070,32 @@ impl Glyph {
         for g in self.guidelines.iter().filter(|obj| obj.modified()) {
             g.set_property(Guideline::MODIFIED, false);
         }
+        for a in self
+            .metadata
+            .anchors
+            .borrow()
+            .iter()
+            .filter(|obj| obj.modified())
+        {
+            a.set_property(Anchor::MODIFIED, false);
+        }
         Ok(())
     }
 }
 
-impl From<glif::Glif> for Glyph {
-    fn from(val: glif::Glif) -> Self {
-        use glif::{Component, OutlineEntry, Point, PointKind};
+impl TryFrom<glif::Glif> for Glyph {
+    type Error = glif::GlifError;
+
+    fn try_from(val: glif::Glif) -> Result<Self, Self::Error> {
+        Self::from_glif(val).map(|(glyph, _)| glyph)
+    }
+}
+
+impl Glyph {
+    /// Convert `val` to a glyph, along with warnings about data that was left out: `<unicode>`
+    /// elements with invalid values are skipped instead of failing the whole glyph.
+    pub fn from_glif(val: glif::Glif) -> Result<(Self, Vec<glif::GlifError>), glif::GlifError> {
+        use glif::{Component, GlifElement, GlifError, GlifErrorKind, OutlineEntry};
         let glif::Glif {
             name,
             outline,
@@ -835,18 +1106,28 @@ impl From<glif::Glif> for Glyph {
             unicode,
             format: _,
             lib,
+            lib_source,
         } = val;
 
-        let kinds = if unicode.is_empty() {
+        let mut warnings = vec![];
+        let mut chars = Vec::with_capacity(unicode.len());
+        for (i, u) in unicode.iter().enumerate() {
+            match u32::from_str_radix(u.hex(), 16)
+                .ok()
+                .and_then(|n| char::try_from(n).ok())
+            {
+                Some(c) => chars.push(GlyphKind::Char(c)),
+                None => warnings.push(GlifError::new(
+                    GlifElement::Unicode(i),
+                    GlifErrorKind::InvalidUnicode(u.hex().to_string()),
+                )),
+            }
+        }
+        let kinds = if chars.is_empty() {
             (GlyphKind::Component(name.clone()), vec![])
         } else {
-            let mut iter = unicode
-                .iter()
-                .filter_map(|unicode| u32::from_str_radix(unicode.hex(), 16).ok())
-                .filter_map(|n| n.try_into().ok())
-                .map(GlyphKind::Char);
-            let first = iter.next().unwrap();
-            (first, iter.collect::<Vec<_>>())
+            let first = chars.remove(0);
+            (first, chars)
         };
         let mut ret = Self {
             guidelines: guidelines
@@ -863,17 +1144,23 @@ impl From<glif::Glif> for Glyph {
                 })
                 .collect::<Vec<_>>(),
             lib,
+            lib_source,
             ..Self::default()
         };
         *ret.metadata.name.borrow_mut() = name;
         *ret.metadata.kinds.borrow_mut() = kinds;
         *ret.metadata.unicode.borrow_mut() = unicode;
-        *ret.metadata.anchors.borrow_mut() = anchors;
+        for anchor in anchors.into_iter().map(Anchor::from) {
+            ret.metadata.add_anchor(anchor);
+        }
+        /* Adding anchors marks the glyph as modified, but nothing has changed yet. */
+        ret.metadata.set_property(GlyphMetadata::MODIFIED, false);
         *ret.metadata.image.borrow_mut() = image;
         ret.metadata.advance.set(advance);
         ret.metadata.width.set(advance.map(|a| a.width));
 
         if let Some(outline) = outline {
+            let mut contour_index = 0;
             for contour in outline.contours {
                 let contour = match contour {
                     OutlineEntry::Contour(c) => c,
@@ -887,133 +1174,135 @@ impl From<glif::Glif> for Glyph {
                         y_scale,
                     }) => {
                         ret.components.push(crate::glyphs::Component {
-                            base_name: base,
-                            base: std::rc::Weak::new(),
                             x_offset,
                             y_offset,
                             x_scale,
                             xy_scale,
                             yx_scale,
                             y_scale,
+                            ..crate::glyphs::Component::new(base, std::rc::Weak::new())
                         });
                         continue;
                     }
                 };
 
-                let mut open = false;
-                let mut points = contour
-                    .point
-                    .iter()
-                    .collect::<std::collections::VecDeque<&_>>();
-                if points.is_empty() {
+                contour_index += 1;
+                if contour.point.is_empty() {
                     continue;
                 }
-                let mut c;
-                let mut prev_point;
-                let mut last_oncurve;
-                if points.front().unwrap().is_move() {
-                    open = true;
-                    // Open contour
-                    let p = points.pop_front().unwrap();
-                    prev_point = (p.x, p.y);
-                    last_oncurve = prev_point;
-                    c = vec![prev_point];
-                } else {
-                    c = vec![];
-                    let first_point = points.front().unwrap();
-                    last_oncurve = (first_point.x, first_point.y);
-                    // Closed contour
-                    while points.front().unwrap().is_curve() {
-                        points.rotate_left(1);
-                    }
-                    let last_point = points.back().unwrap();
-                    prev_point = (last_point.x, last_point.y);
-                }
-                if points.front().unwrap().is_line() {
-                    let p = points.back().unwrap();
-                    prev_point = (p.x, p.y);
-                }
-                let super_ = crate::glyphs::Contour::new();
-                loop {
-                    match points.pop_front() {
-                        Some(Point {
-                            type_: PointKind::Move,
-                            ..
-                        }) => {
-                            panic!() // [ref:FIXME] return Err
-                        }
-                        Some(Point {
-                            type_: PointKind::Offcurve,
-                            x,
-                            y,
-                            ..
-                        }) => {
-                            prev_point = (*x, *y);
-                            c.push(prev_point);
-                        }
-                        Some(Point {
-                            type_: PointKind::Curve,
-                            x,
-                            y,
-                            smooth,
-                            ..
-                        }) => {
-                            prev_point = (*x, *y);
-                            c.push(prev_point);
-                            c.insert(0, last_oncurve);
-                            let curv = Bezier::new(c.into_iter().map(Into::into).collect());
-                            if *smooth == Some(true) {
-                                curv.set_property(Bezier::SMOOTH, true);
-                            }
-                            super_.push_curve(curv);
-                            c = vec![];
-                            last_oncurve = prev_point;
-                        }
-                        Some(Point {
-                            type_: PointKind::Line,
-                            x,
-                            y,
-                            ..
-                        }) => {
-                            assert!(c.is_empty() || c.len() == 1);
-                            if c.is_empty() {
-                                c.push(prev_point);
-                            }
-                            c.push((*x, *y));
-                            super_.push_curve(Bezier::new(c.into_iter().map(Into::into).collect()));
-                            c = vec![];
-                            prev_point = (*x, *y);
-                            last_oncurve = prev_point;
-                        }
-                        Some(Point {
-                            type_: PointKind::Qcurve,
-                            ..
-                        }) => {
-                            todo!()
-                        }
-                        None => {
-                            if !c.is_empty() {
-                                if !c.contains(&prev_point) {
-                                    c.push(prev_point);
-                                }
-                                super_.push_curve(Bezier::new(
-                                    c.into_iter().map(Into::into).collect(),
-                                ));
-                            }
-                            break;
-                        }
+                ret.contours
+                    .push(contour_from_glif_points(&contour.point, contour_index - 1)?);
+            }
+        }
+
+        Ok((ret, warnings))
+    }
+}
+
+/// Convert the points of a `<contour>` element to a [`Contour`].
+///
+/// Closed contours start from their first on-curve point, and remember how many off-curve points
+/// came before it. Quadratic `qcurve` segments with more than one off-curve point are split into
+/// degree 2 curves at their implied on-curve points, which are marked with
+/// [`BezierInner::implied_start`](crate::utils::curves::BezierInner) so that they can be omitted
+/// again when saving. Contours with no on-curve points at all are treated as
+/// TrueType quadratic contours where every on-curve point is implied.
+///
+/// `index` is the index of the `<contour>` element, used for error reporting.
+fn contour_from_glif_points(
+    points: &[glif::Point],
+    index: usize,
+) -> Result<Contour, glif::GlifError> {
+    use glif::PointKind;
+
+    let contour = Contour::new();
+    let position = |p: &glif::Point| Point::from((p.x, p.y));
+    let open = points.first().map(glif::Point::is_move).unwrap_or(false);
+    let first_oncurve = points
+        .iter()
+        .position(|p| !matches!(p.type_, PointKind::Offcurve));
+    let Some(start_idx) = first_oncurve else {
+        /* Only off-curve points: every on-curve point is the midpoint of two off-curve points. */
+        let n = points.len();
+        for (i, p) in points.iter().enumerate() {
+            let prev = position(&points[(i + n - 1) % n]);
+            let next = position(&points[(i + 1) % n]);
+            let curr = position(p);
+            let curv = Bezier::new(vec![0.5 * (prev + curr), curr, 0.5 * (curr + next)]);
+            curv.imp().implied_start.set(true);
+            curv.set_property(Bezier::SMOOTH, true);
+            contour.push_curve(curv);
+        }
+        contour.close();
+        contour.is_contour_modified.set(true);
+        return Ok(contour);
+    };
+    /* Visit every point after the start point once; for closed contours the last segment ends at
+     * the start point. */
+    let n = points.len();
+    let rest = if open {
+        (start_idx + 1..n).collect::<Vec<_>>()
+    } else {
+        (start_idx + 1..n).chain(0..=start_idx).collect::<Vec<_>>()
+    };
+    let mut current = position(&points[start_idx]);
+    let mut current_smooth = points[start_idx].smooth == Some(true);
+    let mut offcurves: Vec<Point> = vec![];
+    for i in rest {
+        let p = &points[i];
+        match p.type_ {
+            PointKind::Offcurve => {
+                offcurves.push(position(p));
+                continue;
+            }
+            PointKind::Move => {
+                return Err(glif::GlifError::new(
+                    glif::GlifElement::Point {
+                        contour: index,
+                        point: i,
+                    },
+                    glif::GlifErrorKind::StrayMove,
+                ));
+            }
+            PointKind::Qcurve if offcurves.len() > 1 => {
+                let end = position(p);
+                let mut segment_start = current;
+                for (i, &off) in offcurves.iter().enumerate() {
+                    let segment_end = offcurves
+                        .get(i + 1)
+                        .map(|&next| 0.5 * (off + next))
+                        .unwrap_or(end);
+                    let curv = Bezier::new(vec![segment_start, off, segment_end]);
+                    if i == 0 {
+                        curv.set_property(Bezier::SMOOTH, current_smooth);
+                    } else {
+                        curv.imp().implied_start.set(true);
+                        curv.set_property(Bezier::SMOOTH, true);
                     }
+                    contour.push_curve(curv);
+                    segment_start = segment_end;
                 }
-                if !open {
-                    super_.close();
-                }
-                super_.is_contour_modified.set(true);
-                ret.contours.push(super_);
+            }
+            PointKind::Line | PointKind::Curve | PointKind::Qcurve => {
+                let mut curve_points = Vec::with_capacity(offcurves.len() + 2);
+                curve_points.push(current);
+                curve_points.extend(offcurves.iter().copied());
+                curve_points.push(position(p));
+                let curv = Bezier::new(curve_points);
+                curv.set_property(Bezier::SMOOTH, current_smooth);
+                contour.push_curve(curv);
             }
         }
-
-        ret
+        current = position(p);
+        current_smooth = p.smooth == Some(true);
+        offcurves.clear();
+    }
+    if !open {
+        contour.leading_offcurves.set(start_idx);
+        contour.close();
     }
+    contour.is_contour_modified.set(true);
+    Ok(contour)
 }
 
 #[derive(Clone, Hash, Eq, PartialEq, Debug, Default, Copy)]
@@ -1029,6 +1318,30 @@ impl GlyphPointIndex {
     const USZ: usize = std::mem::size_of::<usize>();
     const N: usize = Self::USZ * 2 + Self::U;
 
+    /// Sentinel `contour_index` for anchors; their `curve_index` is the position in
+    /// [`GlyphMetadata::anchors`].
+    pub const ANCHOR: usize = usize::MAX;
+
+    /// Sentinel `contour_index` for components; their `curve_index` is the position in
+    /// [`Glyph::components`].
+    pub const COMPONENT: usize = usize::MAX - 1;
+
+    #[inline(always)]
+    pub fn is_anchor(&self) -> bool {
+        self.contour_index == Self::ANCHOR
+    }
+
+    #[inline(always)]
+    pub fn is_component(&self) -> bool {
+        self.contour_index == Self::COMPONENT
+    }
+
+    /// Whether this indexes a point of [`Glyph::contours`].
+    #[inline(always)]
+    pub fn is_contour_point(&self) -> bool {
+        !self.is_anchor() && !self.is_component()
+    }
+
     pub fn as_bytes(&self) -> [u8; Self::N] {
         let mut ret: [u8; Self::N] = [0; Self::N];
         ret[..Self::USZ].copy_from_slice(&self.contour_index.to_le_bytes());
@@ -0,0 +1,342 @@
+/*
+ * gerb
+ *
+ * Copyright 2022 - Manos Pitsidianakis
+ *
+ * This file is part of gerb.
+ *
+ * gerb is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * gerb is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with gerb. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::*;
+use glib::{ParamFlags, ParamSpec, ParamSpecBoxed, ParamSpecDouble, ParamSpecString, Value};
+
+#[derive(Debug)]
+pub struct AnchorInner {
+    name: RefCell<String>,
+    identifier: RefCell<Option<String>>,
+    x: Cell<f64>,
+    y: Cell<f64>,
+    color: Cell<Option<Color>>,
+    /// Identifies the anchor in the editor's selection and [`KdTree`](crate::utils::range_query::KdTree).
+    pub uuid: Uuid,
+    pub modified: Cell<bool>,
+}
+
+impl Default for AnchorInner {
+    fn default() -> Self {
+        Self {
+            name: RefCell::new(String::new()),
+            identifier: RefCell::new(None),
+            x: Cell::new(0.0),
+            y: Cell::new(0.0),
+            color: Cell::new(None),
+            uuid: Uuid::new_v4(),
+            modified: Cell::new(false),
+        }
+    }
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for AnchorInner {
+    const NAME: &'static str = "Anchor";
+    type Type = Anchor;
+    type ParentType = glib::Object;
+    type Interfaces = ();
+}
+
+impl ObjectImpl for AnchorInner {
+    fn constructed(&self, obj: &Self::Type) {
+        self.parent_constructed(obj);
+    }
+
+    fn properties() -> &'static [ParamSpec] {
+        static PROPERTIES: once_cell::sync::Lazy<Vec<ParamSpec>> =
+            once_cell::sync::Lazy::new(|| {
+                vec![
+                    glib::ParamSpecBoolean::new(
+                        Anchor::MODIFIED,
+                        Anchor::MODIFIED,
+                        Anchor::MODIFIED,
+                        false,
+                        ParamFlags::READWRITE,
+                    ),
+                    ParamSpecString::new(
+                        Anchor::NAME,
+                        Anchor::NAME,
+                        "Anchor name, e.g. top or _top for mark glyphs.",
+                        None,
+                        ParamFlags::READWRITE | UI_EDITABLE,
+                    ),
+                    ParamSpecString::new(
+                        Anchor::IDENTIFIER,
+                        Anchor::IDENTIFIER,
+                        Anchor::IDENTIFIER,
+                        None,
+                        ParamFlags::READWRITE | UI_EDITABLE,
+                    ),
+                    ParamSpecDouble::new(
+                        Anchor::X,
+                        Anchor::X,
+                        Anchor::X,
+                        f64::MIN,
+                        f64::MAX,
+                        0.0,
+                        ParamFlags::READWRITE | UI_EDITABLE,
+                    ),
+                    ParamSpecDouble::new(
+                        Anchor::Y,
+                        Anchor::Y,
+                        Anchor::Y,
+                        f64::MIN,
+                        f64::MAX,
+                        0.0,
+                        ParamFlags::READWRITE | UI_EDITABLE,
+                    ),
+                    ParamSpecBoxed::new(
+                        Anchor::COLOR,
+                        Anchor::COLOR,
+                        Anchor::COLOR,
+                        Color::static_type(),
+                        ParamFlags::READWRITE | UI_EDITABLE,
+                    ),
+                ]
+            });
+        PROPERTIES.as_ref()
+    }
+
+    fn property(&self, _obj: &Self::Type, _id: usize, pspec: &ParamSpec) -> glib::Value {
+        match pspec.name() {
+            Anchor::NAME => Some(self.name.borrow().clone()).to_value(),
+            Anchor::IDENTIFIER => self.identifier.borrow().to_value(),
+            Anchor::X => self.x.get().to_value(),
+            Anchor::Y => self.y.get().to_value(),
+            Anchor::COLOR => self.color().to_value(),
+            Anchor::MODIFIED => self.modified.get().to_value(),
+            _ => unimplemented!("{}", pspec.name()),
+        }
+    }
+
+    fn set_property(&self, _obj: &Self::Type, _id: usize, value: &Value, pspec: &ParamSpec) {
+        macro_rules! set_cell {
+            ($field:tt) => {{
+                let val = value.get().unwrap();
+                if val != self.$field.get() {
+                    self.instance().set_property(Anchor::MODIFIED, true);
+                    self.$field.set(val);
+                }
+            }};
+        }
+        match pspec.name() {
+            Anchor::NAME => {
+                let val: String = value.get::<Option<String>>().unwrap().unwrap_or_default();
+                if val != *self.name.borrow() {
+                    self.instance().set_property(Anchor::MODIFIED, true);
+                    *self.name.borrow_mut() = val;
+                }
+            }
+            Anchor::IDENTIFIER => {
+                let val: Option<String> = value.get().unwrap();
+                let val = val.filter(|v| !v.is_empty());
+                if val != *self.identifier.borrow() {
+                    self.instance().set_property(Anchor::MODIFIED, true);
+                    *self.identifier.borrow_mut() = val;
+                }
+            }
+            Anchor::X => set_cell!(x),
+            Anchor::Y => set_cell!(y),
+            Anchor::COLOR => {
+                let val: Color = value.get().unwrap();
+                if Some(val) != self.color.get() {
+                    self.instance().set_property(Anchor::MODIFIED, true);
+                    self.color.set(Some(val));
+                }
+            }
+            Anchor::MODIFIED => {
+                self.modified.set(value.get().unwrap());
+            }
+            _ => unimplemented!("{}", pspec.name()),
+        }
+    }
+}
+
+impl AnchorInner {
+    const COLOR: Color = Color::from_hex("#8f2ea3");
+
+    #[inline(always)]
+    pub fn name(&self) -> String {
+        self.name.borrow().clone()
+    }
+
+    #[inline(always)]
+    pub fn identifier(&self) -> Option<String> {
+        self.identifier.borrow().clone()
+    }
+
+    #[inline(always)]
+    pub fn color(&self) -> Color {
+        self.color.get().unwrap_or(Self::COLOR)
+    }
+
+    #[inline(always)]
+    pub fn color_inner(&self) -> Option<Color> {
+        self.color.get()
+    }
+
+    #[inline(always)]
+    pub fn position(&self) -> Point {
+        (self.x.get(), self.y.get()).into()
+    }
+
+    /// Draw the anchor as a cross inside a diamond, with its name beside it.
+    ///
+    /// `size` is the length of the diamond's half diagonal in units.
+    pub fn draw(&self, mut cr: ContextRef, size: f64, selected: bool) {
+        let Point { x, y } = self.position();
+        let color = self.color();
+        cr.set_source_color_alpha(color);
+        cr.move_to(x, y + size);
+        cr.line_to(x + size, y);
+        cr.line_to(x, y - size);
+        cr.line_to(x - size, y);
+        cr.close_path();
+        if selected {
+            cr.fill_preserve().unwrap();
+        }
+        cr.stroke().unwrap();
+        cr.move_to(x - size / 2.0, y);
+        cr.line_to(x + size / 2.0, y);
+        cr.move_to(x, y - size / 2.0);
+        cr.line_to(x, y + size / 2.0);
+        if selected {
+            cr.set_source_color(Color::WHITE);
+        }
+        cr.stroke().unwrap();
+        let name = self.name.borrow();
+        if !name.is_empty() {
+            /* Text is drawn in a y-up coordinate space, so flip it back. */
+            cr.set_source_color_alpha(color);
+            cr.set_font_size(2.0 * size);
+            cr.move_to(1.5f64.mul_add(size, x), y - size / 2.0);
+            let cr = cr.push();
+            cr.scale(1.0, -1.0);
+            cr.show_text(&name).unwrap();
+        }
+    }
+}
+
+glib::wrapper! {
+    pub struct Anchor(ObjectSubclass<AnchorInner>);
+}
+
+impl std::ops::Deref for Anchor {
+    type Target = AnchorInner;
+    fn deref(&self) -> &Self::Target {
+        self.imp()
+    }
+}
+
+impl Default for Anchor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<glif::Anchor> for Anchor {
+    fn from(v: glif::Anchor) -> Self {
+        let ret = Self::new();
+        let glif::Anchor {
+            name,
+            x,
+            y,
+            color,
+            identifier,
+        } = v;
+        *ret.name.borrow_mut() = name;
+        *ret.identifier.borrow_mut() = identifier;
+        ret.x.set(x);
+        ret.y.set(y);
+        ret.color.set(color);
+        ret
+    }
+}
+
+impl From<&Anchor> for glif::Anchor {
+    fn from(v: &Anchor) -> Self {
+        Self {
+            name: v.name(),
+            x: v.x.get(),
+            y: v.y.get(),
+            color: v.color_inner(),
+            identifier: v.identifier(),
+        }
+    }
+}
+
+impl Anchor {
+    pub const NAME: &str = "name";
+    pub const COLOR: &str = "color";
+    pub const IDENTIFIER: &str = "identifier";
+    pub const X: &str = "x";
+    pub const Y: &str = "y";
+    pub const MODIFIED: &str = "modified";
+
+    pub fn new() -> Self {
+        let ret: Self = glib::Object::new::<Self>(&[]).unwrap();
+        ret
+    }
+
+    pub fn new_at(name: String, position: Point) -> Self {
+        let ret = Self::new();
+        *ret.name.borrow_mut() = name;
+        ret.x.set(position.x);
+        ret.y.set(position.y);
+        ret
+    }
+
+    #[inline(always)]
+    pub fn modified(&self) -> bool {
+        self.imp().modified.get()
+    }
+
+    /// Index of the anchor in the editor's selection and [`KdTree`](crate::utils::range_query::KdTree).
+    ///
+    /// `index` is the position of the anchor in [`GlyphMetadata::anchors`].
+    pub fn glyph_index(&self, index: usize) -> GlyphPointIndex {
+        GlyphPointIndex {
+            contour_index: GlyphPointIndex::ANCHOR,
+            curve_index: index,
+            uuid: self.uuid,
+        }
+    }
+}
+
+impl_modified!(Anchor);
+
+impl CreatePropertyWindow for Anchor {
+    fn new_property_window(&self, app: &Application, create: bool) -> PropertyWindow {
+        PropertyWindow::builder(self.clone().upcast(), app)
+            .title(if create {
+                "Add anchor".into()
+            } else {
+                "Edit anchor".into()
+            })
+            .type_(if create {
+                PropertyWindowType::Create
+            } else {
+                PropertyWindowType::Modify
+            })
+            .build()
+    }
+}
@@ -0,0 +1,1000 @@
+/*
+ * gerb
+ *
+ * Copyright 2022 - Manos Pitsidianakis
+ *
+ * This file is part of gerb.
+ *
+ * gerb is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * gerb is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with gerb. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Boolean operations on closed contours: remove overlap, union, difference, intersection and
+//! exclusion.
+//!
+//! The segments of both operands are split at every intersection. Each piece is then kept if the
+//! result is filled on exactly one of its sides, where each operand is filled according to the
+//! non-zero winding rule, and oriented so that the filled side is on its left. Kept pieces are
+//! chained back into contours. Coincident pieces of the two operands end up with the same
+//! endpoints and orientation, so only one of them is kept.
+
+use super::*;
+
+/// Points closer than this, in units, are the same vertex.
+const VERTEX_TOLERANCE: f64 = 1e-3;
+/// Curve pieces whose control points are this close to their chord are treated as lines when
+/// looking for intersections.
+const FLATNESS: f64 = 1e-2;
+/// Distance of the points on either side of a piece used to classify it.
+const SIDE_OFFSET: f64 = 1e-2;
+const MAX_DEPTH: usize = 40;
+
+/// A line, quadratic or cubic Bézier segment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Segment {
+    Line(Point, Point),
+    Quadratic(Point, Point, Point),
+    Cubic(Point, Point, Point, Point),
+}
+
+pub(crate) fn cross(a: Point, b: Point) -> f64 {
+    a.x.mul_add(b.y, -(a.y * b.x))
+}
+
+fn lerp(a: Point, b: Point, t: f64) -> Point {
+    a + t * (b - a)
+}
+
+impl Segment {
+    pub fn new(points: &[Point]) -> Option<Self> {
+        match *points {
+            [p0, p1] => Some(Self::Line(p0, p1)),
+            [p0, p1, p2] => Some(Self::Quadratic(p0, p1, p2)),
+            [p0, p1, p2, p3] => Some(Self::Cubic(p0, p1, p2, p3)),
+            _ => None,
+        }
+    }
+
+    pub fn points(&self) -> Vec<Point> {
+        match *self {
+            Self::Line(p0, p1) => vec![p0, p1],
+            Self::Quadratic(p0, p1, p2) => vec![p0, p1, p2],
+            Self::Cubic(p0, p1, p2, p3) => vec![p0, p1, p2, p3],
+        }
+    }
+
+    pub fn start(&self) -> Point {
+        match *self {
+            Self::Line(p, _) | Self::Quadratic(p, _, _) | Self::Cubic(p, _, _, _) => p,
+        }
+    }
+
+    pub fn end(&self) -> Point {
+        match *self {
+            Self::Line(_, p) | Self::Quadratic(_, _, p) | Self::Cubic(_, _, _, p) => p,
+        }
+    }
+
+    fn with_endpoints(self, start: Point, end: Point) -> Self {
+        match self {
+            Self::Line(_, _) => Self::Line(start, end),
+            Self::Quadratic(_, p1, _) => Self::Quadratic(start, p1, end),
+            Self::Cubic(_, p1, p2, _) => Self::Cubic(start, p1, p2, end),
+        }
+    }
+
+    pub fn reversed(&self) -> Self {
+        match *self {
+            Self::Line(p0, p1) => Self::Line(p1, p0),
+            Self::Quadratic(p0, p1, p2) => Self::Quadratic(p2, p1, p0),
+            Self::Cubic(p0, p1, p2, p3) => Self::Cubic(p3, p2, p1, p0),
+        }
+    }
+
+    pub fn eval(&self, t: f64) -> Point {
+        match *self {
+            Self::Line(p0, p1) => lerp(p0, p1, t),
+            Self::Quadratic(p0, p1, p2) => lerp(lerp(p0, p1, t), lerp(p1, p2, t), t),
+            Self::Cubic(p0, p1, p2, p3) => {
+                let (a, b, c) = (lerp(p0, p1, t), lerp(p1, p2, t), lerp(p2, p3, t));
+                lerp(lerp(a, b, t), lerp(b, c, t), t)
+            }
+        }
+    }
+
+    pub fn derivative(&self, t: f64) -> Point {
+        let mt = 1.0 - t;
+        match *self {
+            Self::Line(p0, p1) => p1 - p0,
+            Self::Quadratic(p0, p1, p2) => 2.0 * (mt * (p1 - p0) + t * (p2 - p1)),
+            Self::Cubic(p0, p1, p2, p3) => {
+                let (a, b, c) = (mt * mt, 2.0 * mt * t, t * t);
+                3.0 * (a * (p1 - p0) + b * (p2 - p1) + c * (p3 - p2))
+            }
+        }
+    }
+
+    /// Split at `t` with de Casteljau's algorithm.
+    pub fn split(&self, t: f64) -> (Self, Self) {
+        match *self {
+            Self::Line(p0, p1) => {
+                let m = lerp(p0, p1, t);
+                (Self::Line(p0, m), Self::Line(m, p1))
+            }
+            Self::Quadratic(p0, p1, p2) => {
+                let (a, b) = (lerp(p0, p1, t), lerp(p1, p2, t));
+                let m = lerp(a, b, t);
+                (Self::Quadratic(p0, a, m), Self::Quadratic(m, b, p2))
+            }
+            Self::Cubic(p0, p1, p2, p3) => {
+                let (a, b, c) = (lerp(p0, p1, t), lerp(p1, p2, t), lerp(p2, p3, t));
+                let (d, e) = (lerp(a, b, t), lerp(b, c, t));
+                let m = lerp(d, e, t);
+                (Self::Cubic(p0, a, d, m), Self::Cubic(m, e, c, p3))
+            }
+        }
+    }
+
+    /// The part of the segment between `t0` and `t1`.
+    pub fn subsegment(&self, t0: f64, t1: f64) -> Self {
+        if t0 <= 0.0 {
+            return if t1 >= 1.0 { *self } else { self.split(t1).0 };
+        }
+        let right = self.split(t0).1;
+        if t1 >= 1.0 {
+            right
+        } else {
+            right.split((t1 - t0) / (1.0 - t0)).0
+        }
+    }
+
+    /// Bounding box of the control points: `(min, max)`.
+    pub fn bbox(&self) -> (Point, Point) {
+        let points = self.points();
+        let mut min = points[0];
+        let mut max = points[0];
+        for p in &points[1..] {
+            min.x = min.x.min(p.x);
+            min.y = min.y.min(p.y);
+            max.x = max.x.max(p.x);
+            max.y = max.y.max(p.y);
+        }
+        (min, max)
+    }
+
+    /// Largest distance of a control point to the chord.
+    fn flatness(&self) -> f64 {
+        let (start, end) = (self.start(), self.end());
+        let chord = end - start;
+        let length = chord.norm();
+        self.points()
+            .iter()
+            .map(|&p| {
+                if length == 0.0 {
+                    p.distance(start)
+                } else {
+                    (cross(chord, p - start) / length).abs()
+                }
+            })
+            .fold(0.0, f64::max)
+    }
+
+    /// Direction at the start of the segment, ignoring control points that coincide with it.
+    pub fn start_tangent(&self) -> Point {
+        let start = self.start();
+        self.points()
+            .into_iter()
+            .skip(1)
+            .map(|p| p - start)
+            .find(|d| d.norm() > 0.0)
+            .unwrap_or_default()
+    }
+
+    /// Direction at the end of the segment, ignoring control points that coincide with it.
+    pub fn end_tangent(&self) -> Point {
+        let end = self.end();
+        self.points()
+            .into_iter()
+            .rev()
+            .skip(1)
+            .map(|p| end - p)
+            .find(|d| d.norm() > 0.0)
+            .unwrap_or_default()
+    }
+
+    /// Parameters in `(0, 1)` where the segment's y coordinate has a local extremum.
+    fn y_extrema(&self) -> Vec<f64> {
+        let mut ret = match *self {
+            Self::Line(_, _) => vec![],
+            Self::Quadratic(p0, p1, p2) => {
+                let denominator = 2.0f64.mul_add(-p1.y, p0.y) + p2.y;
+                if denominator == 0.0 {
+                    vec![]
+                } else {
+                    vec![(p0.y - p1.y) / denominator]
+                }
+            }
+            Self::Cubic(p0, p1, p2, p3) => {
+                let a = 3.0 * (3.0f64.mul_add(-p2.y, 3.0f64.mul_add(p1.y, -p0.y)) + p3.y);
+                let b = 6.0 * (2.0f64.mul_add(-p1.y, p0.y) + p2.y);
+                let c = 3.0 * (p1.y - p0.y);
+                if a.abs() < 1e-12 {
+                    if b == 0.0 {
+                        vec![]
+                    } else {
+                        vec![-c / b]
+                    }
+                } else {
+                    let discriminant = b.mul_add(b, -4.0 * a * c);
+                    if discriminant < 0.0 {
+                        vec![]
+                    } else {
+                        let root = discriminant.sqrt();
+                        vec![(-b + root) / (2.0 * a), (-b - root) / (2.0 * a)]
+                    }
+                }
+            }
+        };
+        ret.retain(|t| *t > 0.0 && *t < 1.0);
+        ret.sort_by(f64::total_cmp);
+        ret
+    }
+
+    /// Signed winding contribution of a ray from `p` towards positive x.
+    fn winding(&self, p: Point) -> i32 {
+        let (min, max) = self.bbox();
+        if p.y < min.y || p.y >= max.y || p.x > max.x {
+            return 0;
+        }
+        let mut ts = vec![0.0];
+        ts.extend(self.y_extrema());
+        ts.push(1.0);
+        let mut ret = 0;
+        for pair in ts.windows(2) {
+            let (mut t0, mut t1) = (pair[0], pair[1]);
+            let (y0, y1) = (self.eval(t0).y, self.eval(t1).y);
+            if y0 == y1 {
+                continue;
+            }
+            let direction = if y0 < y1 { 1 } else { -1 };
+            if !(y0.min(y1) <= p.y && p.y < y0.max(y1)) {
+                continue;
+            }
+            /* Bisect the monotonic piece for the crossing. */
+            if y0 > y1 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+            for _ in 0..60 {
+                let t = 0.5 * (t0 + t1);
+                if self.eval(t).y < p.y {
+                    t0 = t;
+                } else {
+                    t1 = t;
+                }
+            }
+            if self.eval(0.5 * (t0 + t1)).x > p.x {
+                ret += direction;
+            }
+        }
+        ret
+    }
+
+    /// Whether both segments have the same control points, in the same or opposite direction.
+    fn coincides(&self, other: &Self) -> bool {
+        let close = |a: &[Point], b: &[Point]| {
+            a.len() == b.len()
+                && a.iter()
+                    .zip(b.iter())
+                    .all(|(p, q)| p.distance(*q) < VERTEX_TOLERANCE)
+        };
+        close(&self.points(), &other.points()) || close(&self.points(), &other.reversed().points())
+    }
+}
+
+/// Signed area of a closed loop of segments; positive for counter-clockwise loops.
+pub fn area(segments: &[Segment]) -> f64 {
+    /* Three point Gauss-Legendre quadrature is exact for the degree 5 integrand of cubics. */
+    const NODES: [(f64, f64); 3] = [
+        (0.112_701_665_379_258_3, 5.0 / 18.0),
+        (0.5, 8.0 / 18.0),
+        (0.887_298_334_620_741_7, 5.0 / 18.0),
+    ];
+    0.5 * segments
+        .iter()
+        .map(|s| {
+            NODES
+                .iter()
+                .map(|&(t, w)| w * cross(s.eval(t), s.derivative(t)))
+                .sum::<f64>()
+        })
+        .sum::<f64>()
+}
+
+/// Non-zero winding number of `p` with respect to `loops`.
+pub fn winding(loops: &[Vec<Segment>], p: Point) -> i32 {
+    loops.iter().flatten().map(|s| s.winding(p)).sum()
+}
+
+fn bboxes_overlap((min_a, max_a): (Point, Point), (min_b, max_b): (Point, Point)) -> bool {
+    min_a.x <= max_b.x + VERTEX_TOLERANCE
+        && min_b.x <= max_a.x + VERTEX_TOLERANCE
+        && min_a.y <= max_b.y + VERTEX_TOLERANCE
+        && min_b.y <= max_a.y + VERTEX_TOLERANCE
+}
+
+/// Intersection parameters of line segments `a` and `b`. Collinear overlapping segments
+/// intersect at the endpoints of their overlap.
+fn line_intersections((p0, p1): (Point, Point), (q0, q1): (Point, Point)) -> Vec<(f64, f64)> {
+    const EPSILON: f64 = 1e-9;
+
+    let (r, s) = (p1 - p0, q1 - q0);
+    let denominator = cross(r, s);
+    let qp = q0 - p0;
+    if denominator.abs() <= EPSILON * r.norm() * s.norm() {
+        let (rr, ss) = (r.dot(r), s.dot(s));
+        if rr == 0.0 || ss == 0.0 || (cross(qp, r) / rr.sqrt()).abs() > VERTEX_TOLERANCE {
+            return vec![];
+        }
+        let on_a = |p: Point| (p - p0).dot(r) / rr;
+        let on_b = |p: Point| (p - q0).dot(s) / ss;
+        let inside = |t: f64| (-EPSILON..=1.0 + EPSILON).contains(&t);
+        let mut ret = vec![];
+        for (ta, tb) in [
+            (0.0, on_b(p0)),
+            (1.0, on_b(p1)),
+            (on_a(q0), 0.0),
+            (on_a(q1), 1.0),
+        ] {
+            if inside(ta) && inside(tb) {
+                ret.push((ta.clamp(0.0, 1.0), tb.clamp(0.0, 1.0)));
+            }
+        }
+        return ret;
+    }
+    let t = cross(qp, s) / denominator;
+    let u = cross(qp, r) / denominator;
+    let range = -EPSILON..=1.0 + EPSILON;
+    if range.contains(&t) && range.contains(&u) {
+        vec![(t.clamp(0.0, 1.0), u.clamp(0.0, 1.0))]
+    } else {
+        vec![]
+    }
+}
+
+/// Improve intersection parameters `(s, t)` of `a` and `b` with Newton's method.
+fn refine(a: &Segment, b: &Segment, (mut s, mut t): (f64, f64)) -> (f64, f64) {
+    let mut error = a.eval(s).distance(b.eval(t));
+    for _ in 0..8 {
+        if error < 1e-10 {
+            break;
+        }
+        let f = a.eval(s) - b.eval(t);
+        let (da, db) = (a.derivative(s), b.derivative(t));
+        let determinant = cross(db, da);
+        if determinant.abs() < 1e-12 {
+            break;
+        }
+        let new_s = (s + cross(f, db) / determinant).clamp(0.0, 1.0);
+        let new_t = (t + cross(f, da) / determinant).clamp(0.0, 1.0);
+        let new_error = a.eval(new_s).distance(b.eval(new_t));
+        if new_error >= error {
+            break;
+        }
+        (s, t, error) = (new_s, new_t, new_error);
+    }
+    (s, t)
+}
+
+/// Recursively subdivide `a` and `b` until they are flat, and intersect them as lines.
+fn curve_intersections(
+    (a, a_range): (Segment, (f64, f64)),
+    (b, b_range): (Segment, (f64, f64)),
+    depth: usize,
+    ret: &mut Vec<(f64, f64)>,
+) {
+    if !bboxes_overlap(a.bbox(), b.bbox()) {
+        return;
+    }
+    let (a_flat, b_flat) = (a.flatness() < FLATNESS, b.flatness() < FLATNESS);
+    if (a_flat && b_flat) || depth > MAX_DEPTH {
+        for (s, t) in line_intersections((a.start(), a.end()), (b.start(), b.end())) {
+            ret.push((
+                (a_range.1 - a_range.0).mul_add(s, a_range.0),
+                (b_range.1 - b_range.0).mul_add(t, b_range.0),
+            ));
+        }
+        return;
+    }
+    let a_size = {
+        let (min, max) = a.bbox();
+        max.distance(min)
+    };
+    let b_size = {
+        let (min, max) = b.bbox();
+        max.distance(min)
+    };
+    if !a_flat && (b_flat || a_size >= b_size) {
+        let (left, right) = a.split(0.5);
+        let mid = 0.5 * (a_range.0 + a_range.1);
+        curve_intersections((left, (a_range.0, mid)), (b, b_range), depth + 1, ret);
+        curve_intersections((right, (mid, a_range.1)), (b, b_range), depth + 1, ret);
+    } else {
+        let (left, right) = b.split(0.5);
+        let mid = 0.5 * (b_range.0 + b_range.1);
+        curve_intersections((a, a_range), (left, (b_range.0, mid)), depth + 1, ret);
+        curve_intersections((a, a_range), (right, (mid, b_range.1)), depth + 1, ret);
+    }
+}
+
+/// Intersection parameters of `a` and `b`.
+pub fn intersections(a: &Segment, b: &Segment) -> Vec<(f64, f64)> {
+    if a.coincides(b) || !bboxes_overlap(a.bbox(), b.bbox()) {
+        return vec![];
+    }
+    let mut ret = if let (Segment::Line(p0, p1), Segment::Line(q0, q1)) = (a, b) {
+        line_intersections((*p0, *p1), (*q0, *q1))
+    } else {
+        let mut ret = vec![];
+        curve_intersections((*a, (0.0, 1.0)), (*b, (0.0, 1.0)), 0, &mut ret);
+        ret.into_iter().map(|st| refine(a, b, st)).collect()
+    };
+    ret.retain(|&(s, t)| a.eval(s).distance(b.eval(t)) < 10.0 * FLATNESS);
+    ret
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BooleanOperation {
+    /// Merge the overlapping parts of contours, treating all of them as one operand.
+    RemoveOverlap,
+    Union,
+    /// The first operand minus the second.
+    Difference,
+    Intersection,
+    /// Exclusive or: the parts covered by exactly one operand.
+    Xor,
+}
+
+impl BooleanOperation {
+    fn filled(self, a: bool, b: bool) -> bool {
+        match self {
+            Self::RemoveOverlap | Self::Union => a || b,
+            Self::Difference => a && !b,
+            Self::Intersection => a && b,
+            Self::Xor => a != b,
+        }
+    }
+}
+
+impl std::fmt::Display for BooleanOperation {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            fmt,
+            "{}",
+            match self {
+                Self::RemoveOverlap => "remove overlap",
+                Self::Union => "union",
+                Self::Difference => "difference",
+                Self::Intersection => "intersection",
+                Self::Xor => "exclusion",
+            }
+        )
+    }
+}
+
+struct Vertices(Vec<Point>);
+
+impl Vertices {
+    fn id(&mut self, p: Point) -> usize {
+        if let Some(i) = self.0.iter().position(|v| v.distance(p) < VERTEX_TOLERANCE) {
+            return i;
+        }
+        self.0.push(p);
+        self.0.len() - 1
+    }
+}
+
+struct Edge {
+    segment: Segment,
+    start: usize,
+    end: usize,
+}
+
+/// Apply `operation` to closed loops of segments `a` and `b`. The resulting loops are oriented
+/// counter-clockwise around filled areas and clockwise around holes.
+pub fn boolean(
+    a: &[Vec<Segment>],
+    b: &[Vec<Segment>],
+    operation: BooleanOperation,
+) -> Result<Vec<Vec<Segment>>, Box<dyn std::error::Error>> {
+    let segments = a
+        .iter()
+        .chain(b.iter())
+        .flatten()
+        .copied()
+        .collect::<Vec<Segment>>();
+    select(segments, |p| {
+        operation.filled(
+            winding(a, p) != 0,
+            operation != BooleanOperation::RemoveOverlap && winding(b, p) != 0,
+        )
+    })
+    .map_err(|err| format!("Could not compute {operation}: {err}").into())
+}
+
+/// Remove the overlaps of closed `loops`, keeping the areas whose winding number satisfies
+/// `filled` instead of using the non-zero rule.
+pub fn resolve(
+    loops: &[Vec<Segment>],
+    filled: impl Fn(i32) -> bool,
+) -> Result<Vec<Vec<Segment>>, Box<dyn std::error::Error>> {
+    select(loops.iter().flatten().copied().collect(), |p| {
+        filled(winding(loops, p))
+    })
+}
+
+/// Split `segments` at their intersections and chain the pieces that border `filled` areas into
+/// loops.
+fn select(
+    segments: Vec<Segment>,
+    filled: impl Fn(Point) -> bool,
+) -> Result<Vec<Vec<Segment>>, Box<dyn std::error::Error>> {
+    let boxes = segments.iter().map(Segment::bbox).collect::<Vec<_>>();
+    let mut vertices = Vertices(vec![]);
+    for s in segments.iter() {
+        vertices.id(s.start());
+        vertices.id(s.end());
+    }
+
+    /* Split parameters of every segment. */
+    let mut splits: Vec<Vec<f64>> = vec![vec![]; segments.len()];
+    for i in 0..segments.len() {
+        for j in i + 1..segments.len() {
+            if !bboxes_overlap(boxes[i], boxes[j]) {
+                continue;
+            }
+            for (s, t) in intersections(&segments[i], &segments[j]) {
+                let p = 0.5 * (segments[i].eval(s) + segments[j].eval(t));
+                vertices.id(p);
+                splits[i].push(s);
+                splits[j].push(t);
+            }
+        }
+    }
+
+    let mut edges: Vec<Edge> = vec![];
+    for (segment, mut ts) in segments.iter().zip(splits) {
+        let (start, end) = (segment.start(), segment.end());
+        ts.retain(|&t| {
+            let p = segment.eval(t);
+            p.distance(start) >= VERTEX_TOLERANCE && p.distance(end) >= VERTEX_TOLERANCE
+        });
+        ts.sort_by(f64::total_cmp);
+        ts.dedup_by(|t, prev| segment.eval(*t).distance(segment.eval(*prev)) < VERTEX_TOLERANCE);
+        ts.insert(0, 0.0);
+        ts.push(1.0);
+        for pair in ts.windows(2) {
+            let piece = segment.subsegment(pair[0], pair[1]);
+            let (start, end) = (vertices.id(piece.start()), vertices.id(piece.end()));
+            let piece = piece.with_endpoints(vertices.0[start], vertices.0[end]);
+            if start == end && piece.bbox().1.distance(piece.bbox().0) < VERTEX_TOLERANCE {
+                continue;
+            }
+            edges.push(Edge {
+                segment: piece,
+                start,
+                end,
+            });
+        }
+    }
+
+    /* Keep pieces with the result filled on exactly one side, filled side on the left. */
+    let mut kept: Vec<Edge> = vec![];
+    for edge in edges {
+        let middle = edge.segment.eval(0.5);
+        let mut direction = edge.segment.derivative(0.5);
+        if direction.norm() == 0.0 {
+            direction = edge.segment.end() - edge.segment.start();
+        }
+        if direction.norm() == 0.0 {
+            continue;
+        }
+        let normal = (SIDE_OFFSET / direction.norm()) * Point::from((-direction.y, direction.x));
+        let (left, right) = (filled(middle + normal), filled(middle - normal));
+        if left == right {
+            continue;
+        }
+        let edge = if left {
+            edge
+        } else {
+            Edge {
+                segment: edge.segment.reversed(),
+                start: edge.end,
+                end: edge.start,
+            }
+        };
+        let duplicate = kept.iter().any(|k| {
+            k.start == edge.start
+                && k.end == edge.end
+                && k.segment.eval(0.5).distance(middle) < VERTEX_TOLERANCE
+        });
+        if !duplicate {
+            kept.push(edge);
+        }
+    }
+
+    /* Chain pieces into loops, turning as far left as possible at junctions. */
+    let mut used = vec![false; kept.len()];
+    let mut ret = vec![];
+    for first in 0..kept.len() {
+        if used[first] {
+            continue;
+        }
+        used[first] = true;
+        let mut current = first;
+        let mut chain = vec![kept[first].segment];
+        while kept[current].end != kept[first].start {
+            let incoming = kept[current].segment.end_tangent();
+            let next = (0..kept.len())
+                .filter(|&i| !used[i] && kept[i].start == kept[current].end)
+                .max_by(|&i, &j| {
+                    let turn = |k: usize| {
+                        let outgoing = kept[k].segment.start_tangent();
+                        cross(incoming, outgoing).atan2(incoming.dot(outgoing))
+                    };
+                    turn(i).total_cmp(&turn(j))
+                });
+            let Some(next) = next else {
+                return Err("the result has an open contour.".into());
+            };
+            used[next] = true;
+            chain.push(kept[next].segment);
+            current = next;
+        }
+        ret.push(merge_lines(chain));
+    }
+    Ok(ret)
+}
+
+/// Join consecutive collinear lines left over from splitting at intersections that are not
+/// part of the result.
+fn merge_lines(segments: Vec<Segment>) -> Vec<Segment> {
+    let collinear = |a: &Segment, b: &Segment| match (a, b) {
+        (Segment::Line(p0, p1), Segment::Line(_, q1)) => {
+            let (r, s) = (*p1 - *p0, *q1 - *p1);
+            r.dot(s) > 0.0 && (cross(r, s) / r.norm()).abs() < VERTEX_TOLERANCE
+        }
+        _ => false,
+    };
+    let mut ret: Vec<Segment> = vec![];
+    for s in segments {
+        match ret.last_mut() {
+            Some(last) if collinear(last, &s) => *last = Segment::Line(last.start(), s.end()),
+            _ => ret.push(s),
+        }
+    }
+    if ret.len() > 2 && collinear(&ret[ret.len() - 1], &ret[0]) {
+        let last = ret.pop().unwrap();
+        ret[0] = Segment::Line(last.start(), ret[0].end());
+    }
+    ret
+}
+
+/// Whether `a` and `b` are the same loops, regardless of their order, direction and starting
+/// segments.
+pub fn same_loops(a: &[Vec<Segment>], b: &[Vec<Segment>]) -> bool {
+    let mut unmatched = b.iter().collect::<Vec<_>>();
+    a.len() == b.len()
+        && a.iter().all(|l| {
+            let Some(pos) = unmatched.iter().position(|m| {
+                m.len() == l.len() && l.iter().all(|s| m.iter().any(|t| s.coincides(t)))
+            }) else {
+                return false;
+            };
+            unmatched.swap_remove(pos);
+            true
+        })
+}
+
+/// The segments of the curves of `contour`, open or closed.
+pub fn curve_segments(contour: &Contour) -> Result<Vec<Segment>, Box<dyn std::error::Error>> {
+    Ok(contour
+        .curves()
+        .iter()
+        .filter_map(|c| {
+            let points = c.points().iter().map(|cp| cp.position).collect::<Vec<_>>();
+            match points.len() {
+                0 | 1 => None,
+                _ => Some(Segment::new(&points).ok_or_else(|| {
+                    format!("Curves of degree {} are not supported.", points.len() - 1)
+                })),
+            }
+        })
+        .collect::<Result<Vec<Segment>, String>>()?)
+}
+
+/// The segments of closed `contour`.
+pub fn contour_segments(contour: &Contour) -> Result<Vec<Segment>, Box<dyn std::error::Error>> {
+    if contour.property::<bool>(Contour::OPEN) {
+        return Err("Boolean operations are only possible on closed contours.".into());
+    }
+    let mut ret = curve_segments(contour)?;
+    if let (Some(first), Some(last)) = (ret.first(), ret.last()) {
+        if first.start().distance(last.end()) > 0.0 {
+            ret.push(Segment::Line(last.end(), first.start()));
+        }
+    }
+    Ok(ret)
+}
+
+/// A closed contour made of `segments`. Joins between tangent segments are marked as smooth.
+pub fn segments_contour(segments: &[Segment]) -> Contour {
+    let contour = Contour::new();
+    for (i, s) in segments.iter().enumerate() {
+        let prev = &segments[(i + segments.len() - 1) % segments.len()];
+        let (incoming, outgoing) = (prev.end_tangent(), s.start_tangent());
+        let smooth = !matches!((prev, s), (Segment::Line(..), Segment::Line(..)))
+            && incoming.dot(outgoing) > 0.0
+            && (cross(incoming, outgoing) / (incoming.norm() * outgoing.norm())).abs() < 1e-3;
+        let curve = Bezier::new(s.points());
+        curve.set_property(Bezier::SMOOTH, smooth);
+        contour.push_curve(curve);
+    }
+    contour.close();
+    contour
+}
+
+/// Apply `operation` to closed contours `a` and `b`.
+pub fn apply(
+    a: &[Contour],
+    b: &[Contour],
+    operation: BooleanOperation,
+) -> Result<Vec<Contour>, Box<dyn std::error::Error>> {
+    let a = a
+        .iter()
+        .map(contour_segments)
+        .collect::<Result<Vec<_>, _>>()?;
+    let b = b
+        .iter()
+        .map(contour_segments)
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(boolean(&a, &b, operation)?
+        .iter()
+        .map(|s| segments_contour(s))
+        .collect())
+}
+
+/// Remove the overlaps of the closed contours in `contours`. Open contours are kept as they are,
+/// after the closed ones. Returns `None` if nothing overlaps.
+pub fn remove_overlap(
+    contours: &[Contour],
+) -> Result<Option<Vec<Contour>>, Box<dyn std::error::Error>> {
+    let (open, closed): (Vec<&Contour>, Vec<&Contour>) = contours
+        .iter()
+        .partition(|c| c.property::<bool>(Contour::OPEN));
+    let loops = closed
+        .into_iter()
+        .map(contour_segments)
+        .collect::<Result<Vec<_>, _>>()?;
+    let result = boolean(&loops, &[], BooleanOperation::RemoveOverlap)?;
+    if same_loops(&loops, &result) {
+        return Ok(None);
+    }
+    Ok(Some(
+        result
+            .iter()
+            .map(|s| segments_contour(s))
+            .chain(open.into_iter().cloned())
+            .collect(),
+    ))
+}
+
+/// Glyphs whose overlaps were removed by a batch operation and glyphs where it failed.
+#[derive(Debug, Default, Clone)]
+pub struct RemoveOverlapsReport {
+    /// Glyphs with overlaps and their contours without them.
+    pub changed: ContourChanges,
+    /// Glyph names and errors.
+    pub failed: Vec<(String, String)>,
+}
+
+#[cfg(test)]
+fn rectangle(x0: f64, y0: f64, x1: f64, y1: f64) -> Vec<Segment> {
+    let p = |x, y| Point::from((x, y));
+    vec![
+        Segment::Line(p(x0, y0), p(x1, y0)),
+        Segment::Line(p(x1, y0), p(x1, y1)),
+        Segment::Line(p(x1, y1), p(x0, y1)),
+        Segment::Line(p(x0, y1), p(x0, y0)),
+    ]
+}
+
+#[cfg(test)]
+fn circle(cx: f64, cy: f64, r: f64) -> Vec<Segment> {
+    const K: f64 = 0.552_284_749_830_793_4;
+    let p = |x: f64, y: f64| Point::from((x.mul_add(r, cx), y.mul_add(r, cy)));
+    vec![
+        Segment::Cubic(p(1.0, 0.0), p(1.0, K), p(K, 1.0), p(0.0, 1.0)),
+        Segment::Cubic(p(0.0, 1.0), p(-K, 1.0), p(-1.0, K), p(-1.0, 0.0)),
+        Segment::Cubic(p(-1.0, 0.0), p(-1.0, -K), p(-K, -1.0), p(0.0, -1.0)),
+        Segment::Cubic(p(0.0, -1.0), p(K, -1.0), p(1.0, -K), p(1.0, 0.0)),
+    ]
+}
+
+#[cfg(test)]
+fn total_area(loops: &[Vec<Segment>]) -> f64 {
+    loops.iter().map(|l| area(l)).sum()
+}
+
+#[test]
+fn test_boolean_overlapping_rectangles() {
+    use BooleanOperation::*;
+
+    let a = vec![rectangle(0.0, 0.0, 100.0, 100.0)];
+    let b = vec![rectangle(50.0, 50.0, 150.0, 150.0)];
+    let union = boolean(&a, &b, Union).unwrap();
+    assert_eq!(union.len(), 1);
+    assert_eq!(union[0].len(), 8);
+    assert!((total_area(&union) - 17500.0).abs() < 1e-6);
+    let difference = boolean(&a, &b, Difference).unwrap();
+    assert_eq!(difference.len(), 1);
+    assert_eq!(difference[0].len(), 6);
+    assert!((total_area(&difference) - 7500.0).abs() < 1e-6);
+    let intersection = boolean(&a, &b, Intersection).unwrap();
+    assert_eq!(intersection.len(), 1);
+    assert_eq!(intersection[0].len(), 4);
+    assert!((total_area(&intersection) - 2500.0).abs() < 1e-6);
+    let xor = boolean(&a, &b, Xor).unwrap();
+    assert_eq!(xor.len(), 2);
+    assert!((total_area(&xor) - 15000.0).abs() < 1e-6);
+    /* A clockwise operand covers the same area. */
+    let reversed = vec![b[0].iter().rev().map(Segment::reversed).collect()];
+    let union = boolean(&a, &reversed, Union).unwrap();
+    assert!((total_area(&union) - 17500.0).abs() < 1e-6);
+}
+
+#[test]
+fn test_boolean_coincident_edges() {
+    use BooleanOperation::*;
+
+    /* Rectangles sharing an edge. */
+    let a = vec![rectangle(0.0, 0.0, 100.0, 100.0)];
+    let b = vec![rectangle(100.0, 0.0, 200.0, 100.0)];
+    let union = boolean(&a, &b, Union).unwrap();
+    assert_eq!(union.len(), 1);
+    assert_eq!(union[0].len(), 4);
+    assert!((total_area(&union) - 20000.0).abs() < 1e-6);
+    assert!(boolean(&a, &b, Intersection).unwrap().is_empty());
+    let difference = boolean(&a, &b, Difference).unwrap();
+    assert_eq!(difference.len(), 1);
+    assert!((total_area(&difference) - 10000.0).abs() < 1e-6);
+
+    /* Rectangles sharing part of an edge. */
+    let b = vec![rectangle(100.0, 50.0, 200.0, 150.0)];
+    let union = boolean(&a, &b, Union).unwrap();
+    assert_eq!(union.len(), 1);
+    assert_eq!(union[0].len(), 8);
+    assert!((total_area(&union) - 20000.0).abs() < 1e-6);
+
+    /* Identical shapes. */
+    let c = vec![circle(0.0, 0.0, 100.0)];
+    let union = boolean(&c, &c, Union).unwrap();
+    assert_eq!(union.len(), 1);
+    assert_eq!(union[0].len(), 4);
+    assert!(boolean(&c, &c, Xor).unwrap().is_empty());
+    assert!(boolean(&c, &c, Difference).unwrap().is_empty());
+
+    /* Rectangles touching at a corner stay separate contours. */
+    let b = vec![rectangle(100.0, 100.0, 200.0, 200.0)];
+    let union = boolean(&a, &b, Union).unwrap();
+    assert_eq!(union.len(), 2);
+    assert!((total_area(&union) - 20000.0).abs() < 1e-6);
+}
+
+#[test]
+fn test_boolean_curves() {
+    use BooleanOperation::*;
+
+    let c = vec![circle(0.0, 0.0, 100.0)];
+    let circle_area = total_area(&c);
+    /* Cut the circle in half. */
+    let half = vec![rectangle(0.0, -200.0, 200.0, 200.0)];
+    let difference = boolean(&c, &half, Difference).unwrap();
+    assert_eq!(difference.len(), 1);
+    assert_eq!(difference[0].len(), 3);
+    assert!((total_area(&difference) - circle_area / 2.0).abs() < 1e-3);
+    let intersection = boolean(&c, &half, Intersection).unwrap();
+    assert!((total_area(&intersection) - circle_area / 2.0).abs() < 1e-3);
+
+    /* Two overlapping circles: the lens is covered twice. */
+    let d = vec![circle(100.0, 0.0, 100.0)];
+    let union = boolean(&c, &d, Union).unwrap();
+    let intersection = boolean(&c, &d, Intersection).unwrap();
+    let xor = boolean(&c, &d, Xor).unwrap();
+    assert_eq!(union.len(), 1);
+    assert_eq!(intersection.len(), 1);
+    assert!((total_area(&union) + total_area(&intersection) - 2.0 * circle_area).abs() < 1e-3);
+    assert!((total_area(&xor) - (total_area(&union) - total_area(&intersection))).abs() < 1e-3);
+
+    /* A quadratic diamond overlapping a rectangle. */
+    let p = |x, y| Point::from((x, y));
+    let diamond = vec![vec![
+        Segment::Quadratic(p(0.0, -100.0), p(100.0, -100.0), p(100.0, 0.0)),
+        Segment::Quadratic(p(100.0, 0.0), p(100.0, 100.0), p(0.0, 100.0)),
+        Segment::Quadratic(p(0.0, 100.0), p(-100.0, 100.0), p(-100.0, 0.0)),
+        Segment::Quadratic(p(-100.0, 0.0), p(-100.0, -100.0), p(0.0, -100.0)),
+    ]];
+    let diamond_area = total_area(&diamond);
+    let upper = vec![rectangle(-200.0, 0.0, 200.0, 200.0)];
+    let intersection = boolean(&diamond, &upper, Intersection).unwrap();
+    assert_eq!(intersection.len(), 1);
+    assert!(intersection[0]
+        .iter()
+        .any(|s| matches!(s, Segment::Quadratic(..))));
+    assert!((total_area(&intersection) - diamond_area / 2.0).abs() < 1e-3);
+}
+
+#[test]
+fn test_boolean_remove_overlap() {
+    use BooleanOperation::*;
+
+    let p = |x, y| Point::from((x, y));
+    /* A self-intersecting bow tie: both lobes are filled with the non-zero rule. */
+    let bow_tie = vec![vec![
+        Segment::Line(p(0.0, 0.0), p(100.0, 100.0)),
+        Segment::Line(p(100.0, 100.0), p(100.0, 0.0)),
+        Segment::Line(p(100.0, 0.0), p(0.0, 100.0)),
+        Segment::Line(p(0.0, 100.0), p(0.0, 0.0)),
+    ]];
+    let result = boolean(&bow_tie, &[], RemoveOverlap).unwrap();
+    assert!(!same_loops(&bow_tie, &result));
+    assert_eq!(result.len(), 2);
+    for l in result.iter() {
+        assert!((area(l) - 2500.0).abs() < 1e-6);
+    }
+
+    /* An "o" overlapping a bar keeps its counter. */
+    let mut counter = circle(0.0, 0.0, 50.0);
+    counter = counter.iter().rev().map(Segment::reversed).collect();
+    let o = vec![
+        circle(0.0, 0.0, 100.0),
+        counter,
+        rectangle(-150.0, -10.0, 0.0, 10.0),
+    ];
+    let result = boolean(&o, &[], RemoveOverlap).unwrap();
+    assert_eq!(result.len(), 2);
+    assert_eq!(result.iter().filter(|l| area(l) < 0.0).count(), 1);
+    assert!(!same_loops(&o, &result));
+    let covered = boolean(&o[..2], &o[2..], Intersection).unwrap();
+    let expected = total_area(&o[..2]) + 3000.0 - total_area(&covered);
+    assert!((total_area(&result) - expected).abs() < 1e-3);
+}
+
+#[test]
+fn test_boolean_no_overlap() {
+    /* Separate contours, a counter and a clockwise contour come out unchanged. */
+    let mut counter = rectangle(25.0, 25.0, 75.0, 75.0);
+    counter = counter.iter().rev().map(Segment::reversed).collect();
+    let mut clockwise = rectangle(200.0, 0.0, 300.0, 100.0);
+    clockwise = clockwise.iter().rev().map(Segment::reversed).collect();
+    let loops = vec![
+        rectangle(0.0, 0.0, 100.0, 100.0),
+        counter,
+        clockwise,
+        circle(500.0, 50.0, 50.0),
+    ];
+    let result = boolean(&loops, &[], BooleanOperation::RemoveOverlap).unwrap();
+    assert!(same_loops(&loops, &result));
+    assert!(
+        (total_area(&result) - (10000.0 - 2500.0 + 10000.0 + total_area(&loops[3..]))).abs() < 1e-6
+    );
+}
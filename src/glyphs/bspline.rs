@@ -0,0 +1,161 @@
+/*
+ * gerb
+ *
+ * Copyright 2022 - Manos Pitsidianakis
+ *
+ * This file is part of gerb.
+ *
+ * gerb is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * gerb is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with gerb. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Uniform cubic B-splines and their conversion to cubic Bézier curves.
+//!
+//! Every span of a uniform cubic B-spline depends on four consecutive control points and is
+//! exactly a cubic Bézier curve, so a spline converts to a contour without approximation. Open
+//! splines repeat their first and last control points three times so that the curve starts and
+//! ends on them, and closed splines wrap around their control points.
+
+use super::*;
+
+/// The control points of the spans of the spline with `control` points, four for each span.
+fn spans(control: &[Point], closed: bool) -> Vec<[Point; 4]> {
+    let points = match (control.first(), control.last()) {
+        _ if closed && control.len() < 3 => return vec![],
+        _ if closed => control
+            .iter()
+            .chain(control.iter().take(3))
+            .copied()
+            .collect::<Vec<Point>>(),
+        (Some(&first), Some(&last)) if control.len() >= 2 => [first, first]
+            .into_iter()
+            .chain(control.iter().copied())
+            .chain([last, last])
+            .collect::<Vec<Point>>(),
+        _ => return vec![],
+    };
+    points
+        .windows(4)
+        .map(|w| [w[0], w[1], w[2], w[3]])
+        .collect()
+}
+
+/// The cubic Bézier curves of the uniform cubic B-spline with `control` points, one for each
+/// span.
+///
+/// Open splines need at least two control points and closed ones at least three, otherwise
+/// there are no curves.
+pub fn bezier_segments(control: &[Point], closed: bool) -> Vec<[Point; 4]> {
+    spans(control, closed)
+        .into_iter()
+        .map(|[a, b, c, d]| {
+            [
+                (1.0 / 6.0) * (a + 4.0 * b + c),
+                (1.0 / 3.0) * (2.0 * b + c),
+                (1.0 / 3.0) * (b + 2.0 * c),
+                (1.0 / 6.0) * (b + 4.0 * c + d),
+            ]
+        })
+        .collect()
+}
+
+/// Evaluate the spline with `control` points at `u`, which goes from zero to the number of
+/// spans.
+pub fn evaluate(control: &[Point], closed: bool, u: f64) -> Option<Point> {
+    let spans = spans(control, closed);
+    let (mut start, mut span) = (0.0, *spans.first()?);
+    for &next in &spans[1..] {
+        if u < start + 1.0 {
+            break;
+        }
+        (start, span) = (start + 1.0, next);
+    }
+    let t = u - start;
+    let mt = 1.0 - t;
+    let [a, b, c, d] = span;
+    let weights = [
+        mt * mt * mt / 6.0,
+        (3.0 * t * t).mul_add(t - 2.0, 4.0) / 6.0,
+        (3.0 * t).mul_add(t.mul_add(-t, t + 1.0), 1.0) / 6.0,
+        t * t * t / 6.0,
+    ];
+    Some(weights[0] * a + weights[1] * b + weights[2] * c + weights[3] * d)
+}
+
+/// A contour of the cubic Bézier curves of the spline with `control` points.
+///
+/// The spline is twice continuously differentiable, so all the joins of the contour are smooth
+/// except the ends of open splines.
+pub fn to_contour(control: &[Point], closed: bool) -> Option<Contour> {
+    let segments = bezier_segments(control, closed);
+    if segments.is_empty() {
+        return None;
+    }
+    let ret = Contour::new();
+    for (i, segment) in segments.into_iter().enumerate() {
+        let curve = Bezier::new(segment.to_vec());
+        curve.set_property(Bezier::SMOOTH, closed || i > 0);
+        ret.push_curve(curve);
+    }
+    if closed {
+        ret.close();
+    }
+    Some(ret)
+}
+
+#[test]
+fn test_bspline_bezier_segments() {
+    fn eval([p0, p1, p2, p3]: [Point; 4], t: f64) -> Point {
+        let mt = 1.0 - t;
+        (mt * mt * mt) * p0 + (3.0 * mt * mt * t) * p1 + (3.0 * mt * t * t) * p2 + (t * t * t) * p3
+    }
+
+    let control = [
+        (0.0, 0.0),
+        (100.0, 250.0),
+        (300.0, 280.0),
+        (420.0, 40.0),
+        (600.0, 90.0),
+    ]
+    .map(Point::from);
+    for closed in [false, true] {
+        let segments = bezier_segments(&control, closed);
+        assert_eq!(segments.len(), if closed { 5 } else { 6 });
+        let mut start = 0.0;
+        for &segment in segments.iter() {
+            for j in 0..=10 {
+                let t = f64::from(j) / 10.0;
+                let expected = evaluate(&control, closed, start + t).unwrap();
+                assert!(
+                    eval(segment, t).distance(expected) < 1e-9,
+                    "{closed} {start} {t}"
+                );
+            }
+            start += 1.0;
+        }
+        for w in segments.windows(2) {
+            assert!(w[0][3].distance(w[1][0]) < 1e-9);
+        }
+        let (first, last) = (segments[0], segments[segments.len() - 1]);
+        if closed {
+            assert!(last[3].distance(first[0]) < 1e-9);
+        } else {
+            /* Open splines start and end on their end control points. */
+            assert!(first[0].distance(control[0]) < 1e-9);
+            assert!(last[3].distance(control[4]) < 1e-9);
+        }
+    }
+    assert!(bezier_segments(&control[..2], true).is_empty());
+    assert!(bezier_segments(&control[..1], false).is_empty());
+    assert_eq!(bezier_segments(&control[..2], false).len(), 3);
+}
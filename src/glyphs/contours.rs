@@ -62,6 +62,19 @@ impl Contour {
         self.imp().curves.borrow().into()
     }
 
+    /// Changes whenever curves are added to, removed from or reordered in the contour, so that
+    /// results computed from them can be cached. Curves whose points are edited in place change
+    /// their own [`Bezier::revision`] instead.
+    pub fn revision(&self) -> u64 {
+        self.imp().revision.get()
+    }
+
+    fn set_modified(&self) {
+        self.imp()
+            .revision
+            .set(self.imp().revision.get().wrapping_add(1));
+    }
+
     // [ref:needs_unit_test]
     pub fn recalc_continuities(&self) {
         let closed: bool = !self.imp().open.get();
@@ -135,6 +148,7 @@ impl Contour {
         if curve.points().is_empty() {
             return;
         }
+        self.set_modified();
         let new_len = curve.approx_length();
         if let Some(b) = self
             .imp()
@@ -182,6 +196,7 @@ impl Contour {
             return;
         }
         self.imp().open.set(false);
+        self.set_modified();
 
         let curves = self.imp().curves.borrow();
         if curves.is_empty() {
@@ -236,6 +251,7 @@ impl Contour {
     }
 
     pub fn reverse_direction(&self) {
+        self.set_modified();
         let mut curves = self.imp().curves.borrow_mut();
         curves.reverse();
         for c in curves.iter() {
@@ -251,6 +267,7 @@ impl Contour {
         m: Matrix,
     ) -> Vec<(GlyphPointIndex, Point)> {
         self.is_contour_modified.set(true);
+        self.set_modified();
 
         let uuids = idxs_slice
             .iter()
@@ -568,11 +585,156 @@ impl Contour {
         cl().unwrap_or((None, None))
     }
 
+    /// A contour made of `curves`, closed if `closed` is set.
+    fn from_curves(curves: Vec<Bezier>, closed: bool) -> Self {
+        let ret = Self::new();
+        for curve in curves {
+            ret.push_curve(curve);
+        }
+        if closed {
+            ret.close();
+        }
+        ret
+    }
+
+    fn copy_curve(curve: &Bezier) -> Bezier {
+        let ret = Bezier::new(curve.points().iter().map(|cp| cp.position).collect());
+        ret.set_property(Bezier::SMOOTH, curve.property::<bool>(Bezier::SMOOTH));
+        ret
+    }
+
+    /// A copy with on-curve points added at the horizontal and vertical extrema of the curves,
+    /// or `None` if there are no extrema between on-curve points.
+    pub fn with_extrema(&self) -> Option<Self> {
+        let mut changed = false;
+        let mut curves = vec![];
+        for curve in self.curves().iter() {
+            let mut rest = Self::copy_curve(curve);
+            let mut prev = 0.0;
+            for t in curve.extrema() {
+                let (left, right) = rest.split((t - prev) / (1.0 - prev));
+                curves.push(left);
+                rest = right;
+                prev = t;
+                changed = true;
+            }
+            curves.push(rest);
+        }
+        changed.then(|| Self::from_curves(curves, !self.imp().open.get()))
+    }
+
+    /// A copy without on-curve points that are collinear with the neighbouring points of the
+    /// lines they join, or `None` if there are none.
+    pub fn without_collinear_points(&self) -> Option<Self> {
+        let closed = !self.imp().open.get();
+        let line = |c: &Bezier| -> Option<(Point, Point)> {
+            match c.points().as_slice() {
+                [a, b] => Some((a.position, b.position)),
+                _ => None,
+            }
+        };
+        let redundant = |prev: &Bezier, next: &Bezier| {
+            let (Some((a, b)), Some((c, d))) = (line(prev), line(next)) else {
+                return false;
+            };
+            b == c && a.collinear(&b, &d) && (b - a).dot(d - b) >= 0.0
+        };
+        let mut changed = false;
+        let mut curves: Vec<Bezier> = vec![];
+        for curve in self.curves().iter() {
+            match curves.last_mut() {
+                Some(prev) if redundant(prev, curve) => {
+                    let merged =
+                        Bezier::new(vec![prev.points()[0].position, curve.points()[1].position]);
+                    merged.set_property(Bezier::SMOOTH, prev.property::<bool>(Bezier::SMOOTH));
+                    *prev = merged;
+                    changed = true;
+                }
+                _ => curves.push(Self::copy_curve(curve)),
+            }
+        }
+        if closed && curves.len() > 2 && redundant(&curves[curves.len() - 1], &curves[0]) {
+            let last = curves.pop().unwrap();
+            let merged = Bezier::new(vec![
+                last.points()[0].position,
+                curves[0].points()[1].position,
+            ]);
+            merged.set_property(Bezier::SMOOTH, last.property::<bool>(Bezier::SMOOTH));
+            curves[0] = merged;
+            changed = true;
+        }
+        changed.then(|| Self::from_curves(curves, closed))
+    }
+
+    /// A copy that runs the opposite way, starting from the same point.
+    pub fn reversed(&self) -> Self {
+        let curves = self.curves();
+        let closed = !self.imp().open.get();
+        let reversed = (0..curves.len())
+            .rev()
+            .map(|i| {
+                let curve = Bezier::new(
+                    curves[i]
+                        .points()
+                        .iter()
+                        .rev()
+                        .map(|cp| cp.position)
+                        .collect(),
+                );
+                /* The join at the start of the reversed curve is the one at the end of the
+                 * original. */
+                let next = curves
+                    .get(i + 1)
+                    .or_else(|| curves.first().filter(|_| closed));
+                curve.set_property(
+                    Bezier::SMOOTH,
+                    next.is_some_and(|c| c.property::<bool>(Bezier::SMOOTH)),
+                );
+                curve
+            })
+            .collect();
+        Self::from_curves(reversed, closed)
+    }
+
+    /// The contour as a loop of segments, open contours closed with a line.
+    fn loop_segments(&self) -> Vec<boolean::Segment> {
+        let mut ret = boolean::curve_segments(self).unwrap_or_default();
+        if let (Some(first), Some(last)) = (ret.first(), ret.last()) {
+            if first.start() != last.end() {
+                ret.push(boolean::Segment::Line(last.end(), first.start()));
+            }
+        }
+        ret
+    }
+
+    /// Signed area enclosed by the contour, positive if it runs counter-clockwise. Open contours
+    /// are closed with a line.
+    pub fn area(&self) -> f64 {
+        boolean::area(&self.loop_segments())
+    }
+
+    /// Non-zero winding number of `p` with respect to the contour.
+    pub fn winding(&self, p: Point) -> i32 {
+        boolean::winding(&[self.loop_segments()], p)
+    }
+
+    /// Whether all on-curve points of `other` are inside the contour.
+    pub fn contains(&self, other: &Self) -> bool {
+        let segments = [self.loop_segments()];
+        let curves = other.curves();
+        !curves.is_empty()
+            && curves
+                .iter()
+                .filter_map(|c| c.points().first().map(|cp| cp.position))
+                .all(|p| boolean::winding(&segments, p) != 0)
+    }
+
     pub fn pop_curve(&self) -> Option<Bezier> {
         let mut curves = self.curves.borrow_mut();
         if curves.is_empty() {
             return None;
         }
+        self.set_modified();
         curves.pop()
     }
 
@@ -650,6 +812,11 @@ pub struct ContourInner {
     curves: RefCell<Vec<Bezier>>,
     biggest_curve: Cell<Option<BiggestCurve>>,
     pub is_contour_modified: Cell<bool>,
+    /// Off-curve points before the first on-curve point of the `<contour>` element this was
+    /// loaded from. Saving moves them back to the front, so that the start point is kept.
+    pub leading_offcurves: Cell<usize>,
+    /// See [`Contour::revision`].
+    revision: Cell<u64>,
 }
 
 impl std::fmt::Debug for ContourInner {
@@ -779,3 +946,142 @@ impl Continuity {
         matches!(self, Self::Positional)
     }
 }
+
+#[test]
+fn test_contour_extrema_and_collinear_points() {
+    let curve = |points: &[(f64, f64)]| Bezier::new(points.iter().map(|&p| p.into()).collect());
+
+    /* A circle with on-curve points at 45 degrees has an extremum in every curve. */
+    const K: f64 = 0.552_284_749_830_793_4;
+    let (s, r) = (std::f64::consts::FRAC_1_SQRT_2, 100.0);
+    let rotate = |(x, y): (f64, f64)| (r * s * (x - y), r * s * (x + y));
+    let quarter = |i: u8| {
+        let points = [(1.0, 0.0), (1.0, K), (K, 1.0), (0.0, 1.0)].map(|(x, y)| {
+            let (x, y) = (0..i).fold((x, y), |(x, y), _| (-y, x));
+            rotate((x, y))
+        });
+        curve(&points)
+    };
+    let circle = Contour::from_curves((0..4).map(quarter).collect(), true);
+    assert!(circle.without_collinear_points().is_none());
+    let extrema = circle.with_extrema().unwrap();
+    assert_eq!(extrema.curves().len(), 8);
+    /* The curves that start at the new points are smooth. */
+    assert!(extrema
+        .curves()
+        .iter()
+        .skip(1)
+        .step_by(2)
+        .all(|c| c.property::<bool>(Bezier::SMOOTH)));
+    assert!(extrema.with_extrema().is_none());
+    for (new, old) in extrema
+        .curves()
+        .iter()
+        .zip(circle.curves().iter().flat_map(|c| [c.clone(), c.clone()]))
+    {
+        for i in 0..=10 {
+            let p = new.compute(f64::from(i) / 10.0);
+            let old_p = (0..=1000)
+                .map(|j| old.compute(f64::from(j) / 1000.0).distance(p))
+                .fold(f64::INFINITY, f64::min);
+            assert!(old_p < 0.1, "{old_p}");
+        }
+        let start = new.points()[0].position;
+        assert!(
+            (start.x.abs() < 1e-6 || start.y.abs() < 1e-6)
+                || (start.x.abs() - start.y.abs()).abs() < 1e-6,
+            "{start:?}"
+        );
+    }
+
+    /* A square with an extra point on its bottom edge and at its start. */
+    let square = Contour::from_curves(
+        vec![
+            curve(&[(50.0, 0.0), (100.0, 0.0)]),
+            curve(&[(100.0, 0.0), (100.0, 100.0)]),
+            curve(&[(100.0, 100.0), (0.0, 100.0)]),
+            curve(&[(0.0, 100.0), (0.0, 0.0)]),
+            curve(&[(0.0, 0.0), (20.0, 0.0)]),
+            curve(&[(20.0, 0.0), (50.0, 0.0)]),
+        ],
+        true,
+    );
+    assert!(square.with_extrema().is_none());
+    let clean = square.without_collinear_points().unwrap();
+    let curves = clean.curves();
+    assert_eq!(curves.len(), 4);
+    assert_eq!(
+        curves[0]
+            .points()
+            .iter()
+            .map(|cp| (cp.position.x, cp.position.y))
+            .collect::<Vec<_>>(),
+        vec![(0.0, 0.0), (100.0, 0.0)]
+    );
+    assert!(clean.without_collinear_points().is_none());
+    /* A spike going back on itself is not redundant. */
+    let spike = Contour::from_curves(
+        vec![
+            curve(&[(0.0, 0.0), (100.0, 0.0)]),
+            curve(&[(100.0, 0.0), (50.0, 0.0)]),
+            curve(&[(50.0, 0.0), (0.0, 100.0)]),
+            curve(&[(0.0, 100.0), (0.0, 0.0)]),
+        ],
+        true,
+    );
+    assert!(spike.without_collinear_points().is_none());
+}
+
+#[test]
+fn test_contour_direction() {
+    let square = |(x0, y0): (f64, f64), (x1, y1): (f64, f64)| {
+        Contour::from_curves(
+            [
+                ((x0, y0), (x1, y0)),
+                ((x1, y0), (x1, y1)),
+                ((x1, y1), (x0, y1)),
+                ((x0, y1), (x0, y0)),
+            ]
+            .into_iter()
+            .map(|(a, b)| Bezier::new(vec![a.into(), b.into()]))
+            .collect(),
+            true,
+        )
+    };
+    let outer = square((0.0, 0.0), (100.0, 100.0));
+    let inner = square((25.0, 25.0), (75.0, 75.0));
+    assert!((outer.area() - 10000.0).abs() < 1e-6);
+    assert!((outer.reversed().area() + 10000.0).abs() < 1e-6);
+    assert_eq!(
+        outer.reversed().curves()[0].points()[0].position,
+        outer.curves()[0].points()[0].position
+    );
+    assert_eq!(outer.winding((50.0, 50.0).into()), 1);
+    assert_eq!(outer.reversed().winding((50.0, 50.0).into()), -1);
+    assert_eq!(outer.winding((150.0, 50.0).into()), 0);
+    assert!(outer.contains(&inner));
+    assert!(!inner.contains(&outer));
+
+    let mut glyph = Glyph::new_empty("o".into(), 'o');
+    glyph.contours = vec![outer.clone(), inner.clone()];
+    assert_eq!(
+        glyph.wrong_directions(ContourDirection::PostScript),
+        vec![1]
+    );
+    assert_eq!(glyph.wrong_directions(ContourDirection::TrueType), vec![0]);
+    glyph.contours = vec![outer.reversed(), inner.reversed()];
+    assert_eq!(
+        glyph.wrong_directions(ContourDirection::PostScript),
+        vec![0]
+    );
+    glyph.contours = vec![outer, inner.reversed()];
+    assert!(glyph
+        .wrong_directions(ContourDirection::PostScript)
+        .is_empty());
+    /* The cached result follows changes made to the contours in place. */
+    glyph.contours[0].reverse_direction();
+    assert_eq!(
+        glyph.wrong_directions(ContourDirection::PostScript),
+        vec![0]
+    );
+}
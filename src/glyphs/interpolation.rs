@@ -0,0 +1,633 @@
+/*
+ * gerb
+ *
+ * Copyright 2022 - Manos Pitsidianakis
+ *
+ * This file is part of gerb.
+ *
+ * gerb is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * gerb is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with gerb. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! # Glyph interpolation
+//!
+//! Glyphs of different masters can be interpolated if they have the same point structure. The
+//! weight of each master at a location is computed by a [`VariationModel`], and the interpolated
+//! glyph is the weighted sum of the masters' glyphs.
+
+use super::*;
+
+/// How the point structure of a glyph differs from that of the reference glyph.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Incompatibility {
+    ContourCount {
+        expected: usize,
+        found: usize,
+    },
+    ContourOpen {
+        contour: usize,
+        open: bool,
+    },
+    SegmentCount {
+        contour: usize,
+        expected: usize,
+        found: usize,
+    },
+    SegmentDegree {
+        contour: usize,
+        segment: usize,
+        expected: Option<usize>,
+        found: Option<usize>,
+    },
+    Components {
+        expected: Vec<String>,
+        found: Vec<String>,
+    },
+    Anchors {
+        expected: Vec<String>,
+        found: Vec<String>,
+    },
+}
+
+impl std::fmt::Display for Incompatibility {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        fn segment_kind(degree: Option<usize>) -> &'static str {
+            match degree {
+                Some(1) => "line",
+                Some(2) => "quadratic curve",
+                Some(3) => "cubic curve",
+                _ => "curve",
+            }
+        }
+
+        match self {
+            Self::ContourCount { expected, found } => {
+                write!(fmt, "has {found} contours instead of {expected}")
+            }
+            Self::ContourOpen { contour, open } => write!(
+                fmt,
+                "contour {contour} is {}",
+                if *open { "open" } else { "closed" }
+            ),
+            Self::SegmentCount {
+                contour,
+                expected,
+                found,
+            } => write!(
+                fmt,
+                "contour {contour} has {found} segments instead of {expected}"
+            ),
+            Self::SegmentDegree {
+                contour,
+                segment,
+                expected,
+                found,
+            } => write!(
+                fmt,
+                "contour {contour} segment {segment} is a {} instead of a {}",
+                segment_kind(*found),
+                segment_kind(*expected)
+            ),
+            Self::Components { expected, found } => write!(
+                fmt,
+                "has components [{}] instead of [{}]",
+                found.join(", "),
+                expected.join(", ")
+            ),
+            Self::Anchors { expected, found } => write!(
+                fmt,
+                "has anchors [{}] instead of [{}]",
+                found.join(", "),
+                expected.join(", ")
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Incompatibility {}
+
+fn anchor_names(glyph: &Glyph) -> Vec<String> {
+    let mut ret = glyph
+        .metadata
+        .anchors()
+        .iter()
+        .map(|a| a.name())
+        .collect::<Vec<String>>();
+    ret.sort();
+    ret
+}
+
+/// Check that `other` can be interpolated with `reference`.
+///
+/// Glyphs are compatible if they have the same number of contours with the same kind of
+/// segments, the same components in the same order and the same anchors.
+pub fn check_compatibility(reference: &Glyph, other: &Glyph) -> Result<(), Incompatibility> {
+    if reference.contours.len() != other.contours.len() {
+        return Err(Incompatibility::ContourCount {
+            expected: reference.contours.len(),
+            found: other.contours.len(),
+        });
+    }
+    for (contour, (a, b)) in reference
+        .contours
+        .iter()
+        .zip(other.contours.iter())
+        .enumerate()
+    {
+        let open = b.imp().open.get();
+        if a.imp().open.get() != open {
+            return Err(Incompatibility::ContourOpen { contour, open });
+        }
+        let (a, b) = (a.curves(), b.curves());
+        if a.len() != b.len() {
+            return Err(Incompatibility::SegmentCount {
+                contour,
+                expected: a.len(),
+                found: b.len(),
+            });
+        }
+        for (segment, (a, b)) in a.iter().zip(b.iter()).enumerate() {
+            if a.degree() != b.degree() {
+                return Err(Incompatibility::SegmentDegree {
+                    contour,
+                    segment,
+                    expected: a.degree(),
+                    found: b.degree(),
+                });
+            }
+        }
+    }
+    let base_names = |g: &Glyph| {
+        g.components
+            .iter()
+            .map(|c| c.base_name.clone())
+            .collect::<Vec<String>>()
+    };
+    let (expected, found) = (base_names(reference), base_names(other));
+    if expected != found {
+        return Err(Incompatibility::Components { expected, found });
+    }
+    let (expected, found) = (anchor_names(reference), anchor_names(other));
+    if expected != found {
+        return Err(Incompatibility::Anchors { expected, found });
+    }
+    Ok(())
+}
+
+/// Create the weighted sum of `masters`, which must all be compatible with the first one.
+///
+/// Everything that cannot be interpolated, like the name, unicode values, smooth flags and
+/// component bases, is copied from the first master.
+pub fn interpolate(masters: &[(&Glyph, f64)]) -> Result<Glyph, Incompatibility> {
+    let Some(&(reference, _)) = masters.first() else {
+        return Ok(Glyph::default());
+    };
+    for (other, _) in &masters[1..] {
+        check_compatibility(reference, other)?;
+    }
+    let sum = |f: &dyn Fn(&Glyph) -> f64| masters.iter().map(|(g, w)| w * f(g)).sum::<f64>();
+    let sum_points = |f: &dyn Fn(&Glyph) -> Point| {
+        masters
+            .iter()
+            .fold(Point::from((0.0, 0.0)), |acc, (g, w)| acc + *w * f(g))
+    };
+
+    let mut ret = Glyph::new(reference.name().to_string(), ' ', vec![]);
+    *ret.metadata.kinds.borrow_mut() = reference.kinds().clone();
+    *ret.metadata.unicode.borrow_mut() = reference.metadata.unicode.borrow().clone();
+    *ret.metadata.filename.borrow_mut() = reference.filename().to_string();
+    if let Some(advance) = reference.metadata.advance.get() {
+        let width = sum(&|g| g.metadata.advance.get().map_or(advance.width, |a| a.width));
+        let height = sum(&|g| {
+            g.metadata
+                .advance
+                .get()
+                .map_or(advance.height, |a| a.height)
+        });
+        ret.metadata.advance.set(Some(Advance { width, height }));
+    }
+    if reference.width().is_some() {
+        ret.metadata
+            .width
+            .set(Some(sum(&|g| g.width().unwrap_or_default())));
+    }
+
+    for (i, contour) in reference.contours.iter().enumerate() {
+        let new = Contour::new();
+        for (j, curve) in contour.curves().iter().enumerate() {
+            let points = (0..curve.points().len())
+                .map(|k| sum_points(&|g| g.contours[i].curves()[j].points()[k].position))
+                .collect::<Vec<Point>>();
+            let new_curve = Bezier::new(points);
+            new_curve.set_property(Bezier::SMOOTH, curve.property::<bool>(Bezier::SMOOTH));
+            new_curve
+                .imp()
+                .implied_start
+                .set(curve.imp().implied_start.get());
+            new.push_curve(new_curve);
+        }
+        new.imp()
+            .leading_offcurves
+            .set(contour.imp().leading_offcurves.get());
+        if !contour.imp().open.get() {
+            new.close();
+        }
+        ret.contours.push(new);
+    }
+
+    for (i, component) in reference.components.iter().enumerate() {
+        let mut new = Component::new(component.base_name.clone(), component.base.clone());
+        let m = |f: &dyn Fn(&Matrix) -> f64| sum(&|g| f(&g.components[i].matrix()));
+        new.set_matrix(Matrix::new(
+            m(&|m| m.xx),
+            m(&|m| m.yx),
+            m(&|m| m.xy),
+            m(&|m| m.yy),
+            m(&|m| m.x0),
+            m(&|m| m.y0),
+        ));
+        ret.components.push(new);
+    }
+
+    /* Anchors are matched by name, and anchors with the same name by their order. */
+    let anchor_position = |g: &Glyph, name: &str, nth: usize| -> Point {
+        g.metadata
+            .anchors()
+            .iter()
+            .filter(|a| a.name() == name)
+            .nth(nth)
+            .map(|a| a.position())
+            .unwrap_or_default()
+    };
+    for (i, anchor) in reference.metadata.anchors().iter().enumerate() {
+        let name = anchor.name();
+        let nth = reference.metadata.anchors()[..i]
+            .iter()
+            .filter(|a| a.name() == name)
+            .count();
+        let Point { x, y } = sum_points(&|g| anchor_position(g, &name, nth));
+        ret.metadata.add_anchor(Anchor::from(glif::Anchor {
+            name,
+            x,
+            y,
+            color: anchor.color_inner(),
+            identifier: None,
+        }));
+    }
+    ret.metadata.set_property(GlyphMetadata::MODIFIED, false);
+    Ok(ret)
+}
+
+/// The region of influence of a master in an axis: `(lower, peak, upper)` normalized values.
+type Triple = (f64, f64, f64);
+
+/// Computes the weight of each master at any location of a design space, the same way OpenType
+/// variable fonts do.
+///
+/// Master locations are normalized with
+/// [`DesignspaceDocument::normalized_coordinates`](crate::ufo::designspace::DesignspaceDocument::normalized_coordinates),
+/// and one of them must be the default location, where all coordinates are zero. Each master gets
+/// a region of support, and the value at a location is the default master's value plus the
+/// deltas of all the regions that contain it. See the
+/// [OpenType specification](https://learn.microsoft.com/en-us/typography/opentype/spec/otvaroverview#algorithm-for-interpolation-of-instance-values)
+/// and `fontTools.varLib.models` for the algorithm.
+#[derive(Debug, Clone)]
+pub struct VariationModel {
+    axis_count: usize,
+    /// Master indices in the order their deltas are computed.
+    order: Vec<usize>,
+    /// Regions of support of the deltas, in `order`.
+    supports: Vec<Vec<Option<Triple>>>,
+    /// `deltas[i][k]` is the coefficient of master `k` in the `i`-th delta.
+    deltas: Vec<Vec<f64>>,
+}
+
+impl VariationModel {
+    pub fn new(locations: &[Vec<f64>]) -> Result<Self, Box<dyn std::error::Error>> {
+        let axis_count = locations.first().map(Vec::len).unwrap_or_default();
+        if locations.iter().any(|l| l.len() != axis_count) {
+            return Err("Master locations must specify every axis.".into());
+        }
+        if !locations.iter().any(|l| l.iter().all(|v| *v == 0.0)) {
+            return Err("No master is at the default location.".into());
+        }
+        for (i, l) in locations.iter().enumerate() {
+            if locations[..i].contains(l) {
+                return Err(format!("More than one master is at location {l:?}.").into());
+            }
+        }
+
+        let order = Self::sort(locations, axis_count);
+        let regions = Self::regions(locations, axis_count);
+        let mut supports: Vec<Vec<Option<Triple>>> = vec![];
+        for &i in &order {
+            let mut region = regions[i].clone();
+            let axes = region.iter().map(Option::is_some).collect::<Vec<bool>>();
+            for prev in &supports {
+                /* Masters with different axes do not participate. */
+                if prev.iter().map(Option::is_some).collect::<Vec<bool>>() != axes {
+                    continue;
+                }
+                /* Neither do masters outside of the region. */
+                let relevant = region.iter().zip(prev.iter()).all(|(r, p)| match (r, p) {
+                    (Some((lower, peak, upper)), Some((_, prev_peak, _))) => {
+                        prev_peak == peak || (lower < prev_peak && prev_peak < upper)
+                    }
+                    _ => true,
+                });
+                if !relevant {
+                    continue;
+                }
+                /* Split the region in the direction with the largest range ratio. */
+                let mut best_axes: Vec<(usize, Triple)> = vec![];
+                let mut best_ratio = -1.0;
+                for (axis, (r, p)) in region.iter().zip(prev.iter()).enumerate() {
+                    let (Some((lower, peak, upper)), Some((_, val, _))) = (*r, *p) else {
+                        continue;
+                    };
+                    let (new, ratio) = if val < peak {
+                        ((val, peak, upper), (val - peak) / (lower - peak))
+                    } else if peak < val {
+                        ((lower, peak, val), (val - peak) / (upper - peak))
+                    } else {
+                        continue;
+                    };
+                    if ratio > best_ratio {
+                        best_axes.clear();
+                        best_ratio = ratio;
+                    }
+                    if ratio == best_ratio {
+                        best_axes.push((axis, new));
+                    }
+                }
+                for (axis, triple) in best_axes {
+                    region[axis] = Some(triple);
+                }
+            }
+            supports.push(region);
+        }
+
+        let mut deltas: Vec<Vec<f64>> = vec![];
+        for (i, &master) in order.iter().enumerate() {
+            let mut delta = vec![0.0; locations.len()];
+            delta[master] = 1.0;
+            for (j, support) in supports[..i].iter().enumerate() {
+                let scalar = support_scalar(&locations[master], support);
+                if scalar != 0.0 {
+                    for (d, prev) in delta.iter_mut().zip(deltas[j].iter()) {
+                        *d -= scalar * prev;
+                    }
+                }
+            }
+            deltas.push(delta);
+        }
+
+        Ok(Self {
+            axis_count,
+            order,
+            supports,
+            deltas,
+        })
+    }
+
+    /// Sort masters so that the default comes first, then masters on a single axis, and so on.
+    fn sort(locations: &[Vec<f64>], axis_count: usize) -> Vec<usize> {
+        /* Values of masters that lie on a single axis, per axis. */
+        let mut axis_points: Vec<Vec<f64>> = vec![vec![0.0]; axis_count];
+        for l in locations {
+            let mut non_zero = l.iter().enumerate().filter(|(_, v)| **v != 0.0);
+            if let (Some((axis, v)), None) = (non_zero.next(), non_zero.next()) {
+                axis_points[axis].push(*v);
+            }
+        }
+        let key = |l: &Vec<f64>| {
+            let axes = (0..axis_count)
+                .filter(|a| l[*a] != 0.0)
+                .collect::<Vec<usize>>();
+            let on_point = axes
+                .iter()
+                .filter(|a| axis_points[**a].contains(&l[**a]))
+                .count();
+            let signs = axes
+                .iter()
+                .map(|a| if l[*a] < 0.0 { -1 } else { 1 })
+                .collect::<Vec<i8>>();
+            let magnitudes = axes.iter().map(|a| l[*a].abs()).collect::<Vec<f64>>();
+            (
+                axes.len(),
+                std::cmp::Reverse(on_point),
+                axes,
+                signs,
+                magnitudes,
+            )
+        };
+        let mut order = (0..locations.len()).collect::<Vec<usize>>();
+        order.sort_by(|a, b| {
+            key(&locations[*a])
+                .partial_cmp(&key(&locations[*b]))
+                .unwrap_or(Ordering::Equal)
+        });
+        order
+    }
+
+    /// Initial region of each master: from the default to the master and on to the extreme
+    /// master of each of its axes.
+    fn regions(locations: &[Vec<f64>], axis_count: usize) -> Vec<Vec<Option<Triple>>> {
+        let minimum = (0..axis_count)
+            .map(|a| locations.iter().map(|l| l[a]).fold(0.0, f64::min))
+            .collect::<Vec<f64>>();
+        let maximum = (0..axis_count)
+            .map(|a| locations.iter().map(|l| l[a]).fold(0.0, f64::max))
+            .collect::<Vec<f64>>();
+        locations
+            .iter()
+            .map(|l| {
+                l.iter()
+                    .enumerate()
+                    .map(|(a, &v)| {
+                        if v > 0.0 {
+                            Some((0.0, v, maximum[a]))
+                        } else if v < 0.0 {
+                            Some((minimum[a], v, 0.0))
+                        } else {
+                            None
+                        }
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Weight of each master at normalized `location`. The weights sum to `1.0`.
+    pub fn weights(&self, location: &[f64]) -> Vec<f64> {
+        debug_assert_eq!(location.len(), self.axis_count);
+        let mut ret = vec![0.0; self.order.len()];
+        for (support, delta) in self.supports.iter().zip(self.deltas.iter()) {
+            let scalar = support_scalar(location, support);
+            if scalar == 0.0 {
+                continue;
+            }
+            for (w, d) in ret.iter_mut().zip(delta.iter()) {
+                *w += scalar * d;
+            }
+        }
+        ret
+    }
+}
+
+/// How much a region of `support` influences `location`, from `0.0` to `1.0`.
+fn support_scalar(location: &[f64], support: &[Option<Triple>]) -> f64 {
+    let mut scalar = 1.0;
+    for (&v, triple) in location.iter().zip(support.iter()) {
+        let Some((lower, peak, upper)) = *triple else {
+            continue;
+        };
+        if peak == 0.0 || lower > peak || peak > upper || (lower < 0.0 && upper > 0.0) {
+            continue;
+        }
+        if v == peak {
+            continue;
+        }
+        if v <= lower || upper <= v {
+            return 0.0;
+        }
+        if v < peak {
+            scalar *= (v - lower) / (peak - lower);
+        } else {
+            scalar *= (v - upper) / (peak - upper);
+        }
+    }
+    scalar
+}
+
+#[test]
+fn test_compatibility() {
+    use crate::ufo::glif::{self, Glif};
+
+    let glyph = |src: &str| -> Glyph { Glif::from_str(src).unwrap().try_into().unwrap() };
+    let a = glyph(glif::_UPPERCASE_A_GLIF);
+    let b = glyph(glif::_LOWERCASE_B_GLIF);
+    assert_eq!(
+        check_compatibility(&a, &glyph(glif::_UPPERCASE_A_GLIF)),
+        Ok(())
+    );
+    assert_eq!(check_compatibility(&b, &b), Ok(()));
+    assert!(matches!(
+        check_compatibility(&a, &b),
+        Err(Incompatibility::SegmentCount { contour: 0, .. })
+    ));
+    assert!(check_compatibility(&a, &b)
+        .unwrap_err()
+        .to_string()
+        .starts_with("contour 0 has "));
+    assert_eq!(
+        check_compatibility(&a, &glyph(glif::_QUADRATIC_GLIF))
+            .unwrap_err()
+            .to_string(),
+        "has 3 contours instead of 2"
+    );
+}
+
+#[test]
+fn test_interpolate() {
+    use crate::ufo::glif::{self, Glif};
+
+    let glyph = |src: &str| -> Glyph { Glif::from_str(src).unwrap().try_into().unwrap() };
+    let a = glyph(glif::_UPPERCASE_A_GLIF);
+    let wide = glyph(glif::_UPPERCASE_A_GLIF);
+    for contour in wide.contours.iter() {
+        for curve in contour.curves().iter() {
+            for i in 0..curve.points().len() {
+                curve.transform_point(i, Matrix::new(2.0, 0.0, 0.0, 1.0, 0.0, 0.0));
+            }
+        }
+    }
+    wide.metadata.width.set(a.width().map(|w| 2.0 * w));
+
+    let middle = interpolate(&[(&a, 0.5), (&wide, 0.5)]).unwrap();
+    assert_eq!(middle.name().as_str(), a.name().as_str());
+    assert_eq!(middle.width(), a.width().map(|w| 1.5 * w));
+    for (c, (ca, cw)) in middle
+        .contours
+        .iter()
+        .zip(a.contours.iter().zip(wide.contours.iter()))
+    {
+        for (b, (ba, bw)) in c
+            .curves()
+            .iter()
+            .zip(ca.curves().iter().zip(cw.curves().iter()))
+        {
+            for (p, (pa, pw)) in b
+                .points()
+                .iter()
+                .zip(ba.points().iter().zip(bw.points().iter()))
+            {
+                assert_eq!(p.x, (pa.x + pw.x) / 2.0);
+                assert_eq!(p.y, pa.y);
+                assert_eq!(pw.x, 2.0 * pa.x);
+            }
+        }
+    }
+    assert_eq!(
+        interpolate(&[(&a, 0.5), (&glyph(glif::_QUADRATIC_GLIF), 0.5)])
+            .unwrap_err()
+            .to_string(),
+        "has 3 contours instead of 2"
+    );
+}
+
+#[test]
+fn test_variation_model() {
+    let close = |a: Vec<f64>, b: &[f64]| {
+        assert_eq!(a.len(), b.len());
+        assert!(
+            a.iter().zip(b.iter()).all(|(a, b)| (a - b).abs() < 1e-9),
+            "{a:?} != {b:?}"
+        );
+    };
+
+    /* Two masters on one axis. */
+    let model = VariationModel::new(&[vec![0.0], vec![1.0]]).unwrap();
+    close(model.weights(&[0.25]), &[0.75, 0.25]);
+    close(model.weights(&[0.0]), &[1.0, 0.0]);
+    close(model.weights(&[-0.5]), &[1.0, 0.0]);
+
+    /* The default master doesn't have to come first. */
+    let model = VariationModel::new(&[vec![1.0], vec![-1.0], vec![0.0]]).unwrap();
+    close(model.weights(&[-0.5]), &[0.0, 0.5, 0.5]);
+    close(model.weights(&[0.75]), &[0.75, 0.0, 0.25]);
+
+    /* An intermediate master. */
+    let model = VariationModel::new(&[vec![0.0], vec![1.0], vec![0.5]]).unwrap();
+    close(model.weights(&[0.5]), &[0.0, 0.0, 1.0]);
+    close(model.weights(&[0.25]), &[0.5, 0.0, 0.5]);
+    close(model.weights(&[0.75]), &[0.0, 0.5, 0.5]);
+
+    /* Two axes with a corner master is bilinear interpolation. */
+    let model = VariationModel::new(&[
+        vec![0.0, 0.0],
+        vec![1.0, 0.0],
+        vec![0.0, 1.0],
+        vec![1.0, 1.0],
+    ])
+    .unwrap();
+    close(model.weights(&[0.5, 0.5]), &[0.25, 0.25, 0.25, 0.25]);
+    close(model.weights(&[1.0, 0.5]), &[0.0, 0.5, 0.0, 0.5]);
+
+    /* Without a corner master, deltas are added up. */
+    let model = VariationModel::new(&[vec![0.0, 0.0], vec![1.0, 0.0], vec![0.0, 1.0]]).unwrap();
+    close(model.weights(&[1.0, 1.0]), &[-1.0, 1.0, 1.0]);
+
+    assert!(VariationModel::new(&[vec![1.0]]).is_err());
+    assert!(VariationModel::new(&[vec![0.0], vec![0.0]]).is_err());
+}
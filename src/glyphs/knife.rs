@@ -0,0 +1,325 @@
+/*
+ * gerb
+ *
+ * Copyright 2022 - Manos Pitsidianakis
+ *
+ * This file is part of gerb.
+ *
+ * gerb is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * gerb is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with gerb. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Cutting contours along a line.
+//!
+//! The crossings of the line with the closed contours are sorted along the line, and every
+//! consecutive pair whose midpoint is filled becomes a chord of the cut. The contours are split
+//! at the ends of the chords and the pieces are joined again through the chords, so a cut
+//! through a hole separates the outer contour and the hole into new contours as well. Open
+//! contours are split at their crossings.
+
+use std::collections::BTreeMap;
+
+use super::boolean::{self, Segment};
+use super::*;
+use crate::utils::curves::curve_line_intersections;
+
+/// Parameters closer than this to the ends of a segment are at its ends.
+const EPSILON: f64 = 1e-9;
+
+/// A crossing of the cutting line with a contour.
+#[derive(Debug, Clone, Copy)]
+struct Crossing {
+    segment: usize,
+    t: f64,
+    /// Parameter along the cutting line.
+    s: f64,
+    point: Point,
+}
+
+/// Crossings of `line` with `segments`, in the order of the contour. Crossings at the ends of
+/// segments are counted once, at the start of the next segment.
+fn crossings(segments: &[Segment], closed: bool, line: (Point, Point)) -> Vec<Crossing> {
+    let n = segments.len();
+    let mut ret: Vec<Crossing> = vec![];
+    for (i, segment) in segments.iter().enumerate() {
+        for (t, s) in curve_line_intersections(&segment.points(), line) {
+            let (segment, t) = match (t >= 1.0 - EPSILON, closed) {
+                (true, true) => ((i + 1) % n, 0.0),
+                (true, false) if i + 1 < n => (i + 1, 0.0),
+                (true, false) => continue,
+                (false, false) if i == 0 && t <= EPSILON => continue,
+                (false, _) => (i, if t <= EPSILON { 0.0 } else { t }),
+            };
+            let point = segments[segment].eval(t);
+            if !ret
+                .iter()
+                .any(|c| c.segment == segment && (c.t - t).abs() <= EPSILON)
+            {
+                ret.push(Crossing {
+                    segment,
+                    t,
+                    s,
+                    point,
+                });
+            }
+        }
+    }
+    ret.sort_by(|a, b| a.segment.cmp(&b.segment).then(a.t.total_cmp(&b.t)));
+    ret
+}
+
+/// Split `segments` at `cuts`, which are in the order of the contour, into pieces that start
+/// at each cut. The values of `cuts` are returned along with the piece that starts at them.
+fn split<T: Copy>(segments: &[Segment], cuts: &[(Crossing, T)]) -> Vec<(Option<T>, Segment)> {
+    let mut ret = vec![];
+    for (i, &segment) in segments.iter().enumerate() {
+        let (mut rest, mut offset, mut starts) = (segment, 0.0, None);
+        for &(crossing, value) in cuts.iter().filter(|(c, _)| c.segment == i) {
+            if crossing.t > 0.0 {
+                let (left, right) = rest.split((crossing.t - offset) / (1.0 - offset));
+                ret.push((starts, left));
+                (rest, offset) = (right, crossing.t);
+            }
+            starts = Some(value);
+        }
+        ret.push((starts, rest));
+    }
+    ret
+}
+
+/// Cut closed `loops` along `line`. Returns the indices of the loops that were cut, in
+/// ascending order, and the loops that replace them, or `None` if nothing was cut.
+pub fn cut_loops(
+    loops: &[Vec<Segment>],
+    line: (Point, Point),
+) -> Option<(Vec<usize>, Vec<Vec<Segment>>)> {
+    let mut all = loops
+        .iter()
+        .enumerate()
+        .flat_map(|(l, segments)| {
+            crossings(segments, true, line)
+                .into_iter()
+                .map(move |c| (l, c))
+        })
+        .collect::<Vec<(usize, Crossing)>>();
+    all.sort_by(|a, b| a.1.s.total_cmp(&b.1.s));
+    /* The crossing at the other end of the chord of each crossing. */
+    let mut partner = BTreeMap::new();
+    let mut i = 0;
+    while i + 1 < all.len() {
+        let (a, b) = (all[i].1.point, all[i + 1].1.point);
+        if a.distance(b) > EPSILON && boolean::winding(loops, 0.5 * (a + b)) != 0 {
+            partner.insert(i, i + 1);
+            partner.insert(i + 1, i);
+            i += 2;
+        } else {
+            i += 1;
+        }
+    }
+    if partner.is_empty() {
+        return None;
+    }
+    let mut cut = partner.keys().map(|&i| all[i].0).collect::<Vec<usize>>();
+    cut.sort_unstable();
+    cut.dedup();
+    /* The pieces of the cut loops between consecutive chord ends, by the crossing they start
+     * at, along with the crossing they end at. */
+    let mut arcs: BTreeMap<usize, (Vec<Segment>, usize)> = BTreeMap::new();
+    for &l in &cut {
+        let mut cuts = partner
+            .keys()
+            .filter(|&&i| all[i].0 == l)
+            .map(|&i| (all[i].1, i))
+            .collect::<Vec<(Crossing, usize)>>();
+        cuts.sort_by(|a, b| a.0.segment.cmp(&b.0.segment).then(a.0.t.total_cmp(&b.0.t)));
+        let mut pieces = split(&loops[l], &cuts);
+        let first = pieces.iter().position(|(c, _)| c.is_some())?;
+        pieces.rotate_left(first);
+        let mut current: Option<(usize, Vec<Segment>)> = None;
+        for (starts, segment) in pieces {
+            if let Some(crossing) = starts {
+                if let Some((start, arc)) = current.take() {
+                    arcs.insert(start, (arc, crossing));
+                }
+                current = Some((crossing, vec![]));
+            }
+            current.as_mut()?.1.push(segment);
+        }
+        let (start, arc) = current?;
+        arcs.insert(start, (arc, cuts[0].1));
+    }
+    let mut ret = vec![];
+    let mut used = vec![];
+    for &first in arcs.keys() {
+        let mut new_loop = vec![];
+        let mut current = first;
+        while !used.contains(&current) {
+            used.push(current);
+            let (arc, end) = &arcs[&current];
+            new_loop.extend(arc.iter().copied());
+            current = partner[end];
+            let next_start = arcs[&current].0.first()?.start();
+            new_loop.push(Segment::Line(new_loop.last()?.end(), next_start));
+        }
+        if !new_loop.is_empty() {
+            ret.push(new_loop);
+        }
+    }
+    Some((cut, ret))
+}
+
+/// Split open contour `segments` where `line` crosses them, or `None` if it does not.
+pub fn cut_open(segments: &[Segment], line: (Point, Point)) -> Option<Vec<Vec<Segment>>> {
+    let cuts = crossings(segments, false, line)
+        .into_iter()
+        .map(|c| (c, ()))
+        .collect::<Vec<(Crossing, ())>>();
+    if cuts.is_empty() {
+        return None;
+    }
+    let mut ret: Vec<Vec<Segment>> = vec![vec![]];
+    for (starts, segment) in split(segments, &cuts) {
+        if starts.is_some() {
+            ret.push(vec![]);
+        }
+        ret.last_mut()?.push(segment);
+    }
+    Some(ret)
+}
+
+/// An open contour made of `segments`. Joins between tangent segments are marked as smooth.
+pub(crate) fn open_contour(segments: &[Segment]) -> Contour {
+    let contour = Contour::new();
+    for (i, s) in segments.iter().enumerate() {
+        let smooth = i.checked_sub(1).is_some_and(|prev| {
+            let (incoming, outgoing) = (segments[prev].end_tangent(), s.start_tangent());
+            !matches!((segments[prev], s), (Segment::Line(..), Segment::Line(..)))
+                && incoming.dot(outgoing) > 0.0
+                && (boolean::cross(incoming, outgoing) / (incoming.norm() * outgoing.norm())).abs()
+                    < 1e-3
+        });
+        let curve = Bezier::new(s.points());
+        curve.set_property(Bezier::SMOOTH, smooth);
+        contour.push_curve(curve);
+    }
+    contour
+}
+
+/// Cut `contours` along the line from `start` to `end`. Returns the new list of contours, in
+/// which contours that were not cut keep their place, or `None` if nothing was cut.
+pub fn cut(
+    contours: &[Contour],
+    line: (Point, Point),
+) -> Result<Option<Vec<Contour>>, Box<dyn std::error::Error>> {
+    let mut closed = vec![];
+    let mut loops = vec![];
+    for (i, contour) in contours.iter().enumerate() {
+        if !contour.property::<bool>(Contour::OPEN) {
+            closed.push(i);
+            loops.push(boolean::contour_segments(contour)?);
+        }
+    }
+    let mut replaced: Vec<Option<Vec<Contour>>> = vec![None; contours.len()];
+    if let Some((cut, new_loops)) = cut_loops(&loops, line) {
+        /* The new contours take the place of the first contour that was cut. */
+        for &l in &cut {
+            replaced[closed[l]] = Some(vec![]);
+        }
+        replaced[closed[cut[0]]] = Some(
+            new_loops
+                .iter()
+                .map(|l| boolean::segments_contour(l))
+                .collect(),
+        );
+    }
+    for (i, contour) in contours.iter().enumerate() {
+        if contour.property::<bool>(Contour::OPEN) {
+            if let Some(pieces) = cut_open(&boolean::curve_segments(contour)?, line) {
+                replaced[i] = Some(pieces.iter().map(|p| open_contour(p)).collect());
+            }
+        }
+    }
+    if replaced.iter().all(Option::is_none) {
+        return Ok(None);
+    }
+    Ok(Some(
+        contours
+            .iter()
+            .zip(replaced)
+            .flat_map(|(contour, replacement)| replacement.unwrap_or_else(|| vec![contour.clone()]))
+            .collect(),
+    ))
+}
+
+#[test]
+fn test_knife_cut() {
+    fn rectangle(x0: f64, y0: f64, x1: f64, y1: f64) -> Vec<Segment> {
+        let p = |x: f64, y: f64| Point::from((x, y));
+        vec![
+            Segment::Line(p(x0, y0), p(x1, y0)),
+            Segment::Line(p(x1, y0), p(x1, y1)),
+            Segment::Line(p(x1, y1), p(x0, y1)),
+            Segment::Line(p(x0, y1), p(x0, y0)),
+        ]
+    }
+    fn is_closed(l: &[Segment]) -> bool {
+        l.windows(2).all(|w| w[0].end() == w[1].start()) && l[l.len() - 1].end() == l[0].start()
+    }
+    let area = |l: &Vec<Segment>| boolean::area(l);
+    let vertical = |x: f64| (Point::from((x, -50.0)), Point::from((x, 150.0)));
+
+    /* A square cut in two halves. */
+    let square = vec![rectangle(0.0, 0.0, 100.0, 100.0)];
+    let (cut, pieces) = cut_loops(&square, vertical(40.0)).unwrap();
+    assert_eq!(cut, vec![0]);
+    assert_eq!(pieces.len(), 2);
+    let mut areas = pieces.iter().map(area).collect::<Vec<f64>>();
+    areas.sort_by(f64::total_cmp);
+    assert!((areas[0] - 4000.0).abs() < 1e-6 && (areas[1] - 6000.0).abs() < 1e-6);
+    assert!(pieces.iter().all(|l| is_closed(l)));
+
+    /* A line that misses or ends inside the square does not cut it. */
+    assert!(cut_loops(&square, vertical(140.0)).is_none());
+    let inside = (Point::from((40.0, -50.0)), Point::from((40.0, 50.0)));
+    assert!(cut_loops(&square, inside).is_none());
+
+    /* A cut through a ring separates it in two C shapes. */
+    let ring = vec![
+        rectangle(0.0, 0.0, 100.0, 100.0),
+        rectangle(25.0, 25.0, 75.0, 75.0)
+            .iter()
+            .rev()
+            .map(Segment::reversed)
+            .collect(),
+    ];
+    let (cut, pieces) = cut_loops(&ring, vertical(50.0)).unwrap();
+    assert_eq!(cut, vec![0, 1]);
+    assert_eq!(pieces.len(), 2);
+    for l in &pieces {
+        assert!(is_closed(l));
+        assert!((area(l) - 3750.0).abs() < 1e-6, "{}", area(l));
+    }
+
+    /* A curve is split where it is crossed. */
+    let arch = [Segment::Cubic(
+        Point::from((0.0, 0.0)),
+        Point::from((0.0, 100.0)),
+        Point::from((100.0, 100.0)),
+        Point::from((100.0, 0.0)),
+    )];
+    let pieces = cut_open(&arch, vertical(50.0)).unwrap();
+    assert_eq!(pieces.len(), 2);
+    assert_eq!(pieces[0][0].end(), pieces[1][0].start());
+    assert!((pieces[0][0].end().x - 50.0).abs() < 1e-9);
+    assert!(cut_open(&arch, vertical(0.0)).is_none());
+}
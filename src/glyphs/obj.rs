@@ -215,6 +215,25 @@ impl GlyphMetadata {
     pub fn layer(&self) -> FieldRef<'_, Option<Layer>> {
         self.layer.borrow().into()
     }
+
+    pub fn anchors(&self) -> FieldRef<'_, Vec<Anchor>> {
+        self.anchors.borrow().into()
+    }
+
+    pub fn add_anchor(&self, anchor: Anchor) {
+        self.insert_anchor(self.anchors.borrow().len(), anchor);
+    }
+
+    pub fn insert_anchor(&self, index: usize, anchor: Anchor) {
+        self.link(&anchor);
+        self.set_property(Self::MODIFIED, true);
+        self.anchors.borrow_mut().insert(index, anchor);
+    }
+
+    pub fn remove_anchor(&self, index: usize) -> Anchor {
+        self.set_property(Self::MODIFIED, true);
+        self.anchors.borrow_mut().remove(index)
+    }
 }
 
 impl Default for GlyphMetadata {
@@ -0,0 +1,536 @@
+/*
+ * gerb
+ *
+ * Copyright 2022 - Manos Pitsidianakis
+ *
+ * This file is part of gerb.
+ *
+ * gerb is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * gerb is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with gerb. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Offsetting closed contours and expanding open contours to strokes.
+//!
+//! Every segment is offset separately and approximated with a cubic curve, which is subdivided
+//! until it is within the tolerance of the exact offset. The pieces are connected with joins,
+//! and the loops that appear at inner corners or where the offset distance exceeds the radius
+//! of curvature are removed by keeping only the areas wound in the same direction as the
+//! original contour.
+
+use super::boolean::{self, cross, Segment};
+use super::simplify::fit_cubic;
+use super::*;
+
+/// How many times a segment can be halved to approximate its offset.
+const MAX_DEPTH: usize = 10;
+/// Points where the offset error is measured, in addition to the endpoints.
+const SAMPLES: u32 = 8;
+
+/// The shape of the outer corners of an offset contour.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Join {
+    /// Extend the offset edges until they meet, unless the corner would exceed the miter limit.
+    Miter,
+    #[default]
+    Round,
+    Bevel,
+}
+
+/// The shape of the ends of a stroke of an open contour.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Cap {
+    #[default]
+    Butt,
+    Round,
+    /// Extend the stroke by the offset distance.
+    Square,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct OffsetOptions {
+    /// Offset distance. Positive values make closed contours bolder. Open contours are expanded
+    /// by this distance on both sides.
+    pub distance: f64,
+    pub join: Join,
+    pub cap: Cap,
+    /// Maximum distance of a miter's tip from the corner, relative to `distance`.
+    pub miter_limit: f64,
+    /// Maximum distance of the approximation from the exact offset, in units.
+    pub tolerance: f64,
+}
+
+impl Default for OffsetOptions {
+    fn default() -> Self {
+        Self {
+            distance: 10.0,
+            join: Join::default(),
+            cap: Cap::default(),
+            miter_limit: 4.0,
+            tolerance: 0.25,
+        }
+    }
+}
+
+/// The normal on the right side of `direction`, which is outside counter-clockwise contours.
+fn right_normal(direction: Point) -> Point {
+    Point::from((direction.y, -direction.x)).unit()
+}
+
+fn tangent_at(segment: &Segment, t: f64) -> Point {
+    let d = segment.derivative(t);
+    if d.norm() > 1e-9 {
+        d
+    } else if t < 0.5 {
+        segment.start_tangent()
+    } else {
+        segment.end_tangent()
+    }
+}
+
+fn offset_point(segment: &Segment, t: f64, distance: f64) -> Point {
+    segment.eval(t) + distance * right_normal(tangent_at(segment, t))
+}
+
+/// Approximate the offset of `segment` with cubics, appending them to `ret`.
+fn offset_segment(
+    segment: &Segment,
+    distance: f64,
+    tolerance: f64,
+    depth: usize,
+    ret: &mut Vec<Segment>,
+) {
+    if let Segment::Line(p0, p1) = *segment {
+        let n = distance * right_normal(p1 - p0);
+        ret.push(Segment::Line(p0 + n, p1 + n));
+        return;
+    }
+    let start = offset_point(segment, 0.0, distance);
+    let end = offset_point(segment, 1.0, distance);
+    let t0 = tangent_at(segment, 0.0).unit();
+    let t1 = tangent_at(segment, 1.0).unit();
+    let samples = (1..=SAMPLES)
+        .map(|i| {
+            let t = f64::from(i) / f64::from(SAMPLES + 1);
+            (t, offset_point(segment, t, distance))
+        })
+        .collect::<Vec<(f64, Point)>>();
+    let fit = fit_cubic((start, t0), (end, t1), &samples);
+    let error = samples
+        .iter()
+        .map(|&(t, p)| fit.eval(t).distance(p))
+        .fold(0.0, f64::max);
+    if error <= tolerance || depth >= MAX_DEPTH {
+        ret.push(fit);
+    } else {
+        let (left, right) = segment.split(0.5);
+        offset_segment(&left, distance, tolerance, depth + 1, ret);
+        offset_segment(&right, distance, tolerance, depth + 1, ret);
+    }
+}
+
+/// Cubic approximation of the arc around `center` from `from` to `to`, sweeping `angle`
+/// radians.
+fn arc(center: Point, from: Point, to: Point, angle: f64) -> Vec<Segment> {
+    let radius = from.distance(center);
+    let start_angle = (from - center).atan2();
+    let pieces = (angle.abs() / std::f64::consts::FRAC_PI_2).ceil().max(1.0);
+    let step = angle / pieces;
+    let k = 4.0 / 3.0 * (step / 4.0).tan();
+    let on_circle = |a: f64| center + radius * Point::from((a.cos(), a.sin()));
+    let tangent = |a: f64| (k * radius) * Point::from((-a.sin(), a.cos()));
+    let mut ret = vec![];
+    let mut prev = from;
+    /* Joins and caps never sweep more than half a turn. */
+    for i in (0..4).map(f64::from).take_while(|i| *i < pieces) {
+        let (a0, a1) = (
+            step.mul_add(i, start_angle),
+            step.mul_add(i + 1.0, start_angle),
+        );
+        let next = if i + 1.0 >= pieces { to } else { on_circle(a1) };
+        ret.push(Segment::Cubic(
+            prev,
+            prev + tangent(a0),
+            next - tangent(a1),
+            next,
+        ));
+        prev = next;
+    }
+    ret
+}
+
+/// Connect the offset of the segments meeting at `corner` from the end of `ret` to `next`.
+fn join(
+    ret: &mut Vec<Segment>,
+    corner: Point,
+    (incoming, outgoing): (Point, Point),
+    next: Point,
+    distance: f64,
+    options: &OffsetOptions,
+) {
+    let Some(end) = ret.last().map(Segment::end) else {
+        return;
+    };
+    if end.distance(next) < 1e-9 {
+        return;
+    }
+    let (incoming, outgoing) = (incoming.unit(), outgoing.unit());
+    let turn = cross(incoming, outgoing);
+    let reverses = turn.abs() < 1e-9 && incoming.dot(outgoing) < 0.0;
+    if turn * distance <= 0.0 && !reverses {
+        /* Inner corner: the loop this creates is removed afterwards. */
+        ret.push(Segment::Line(end, corner));
+        ret.push(Segment::Line(corner, next));
+        return;
+    }
+    match options.join {
+        Join::Miter if !reverses => {
+            let s = cross(next - end, outgoing) / turn;
+            let tip = end + s * incoming;
+            if s >= 0.0 && tip.distance(corner) <= options.miter_limit * distance.abs() {
+                ret.push(Segment::Line(end, tip));
+                ret.push(Segment::Line(tip, next));
+            } else {
+                ret.push(Segment::Line(end, next));
+            }
+        }
+        Join::Miter | Join::Bevel => ret.push(Segment::Line(end, next)),
+        Join::Round => {
+            let (u, v) = (end - corner, next - corner);
+            let angle = if reverses {
+                std::f64::consts::PI.copysign(distance)
+            } else {
+                cross(u, v).atan2(u.dot(v))
+            };
+            ret.extend(arc(corner, end, next, angle));
+        }
+    }
+}
+
+/// Close the stroke at the end of `path` from the end of `ret` to `next`.
+fn cap(
+    ret: &mut Vec<Segment>,
+    path_end: Point,
+    tangent: Point,
+    next: Point,
+    options: &OffsetOptions,
+) {
+    let Some(end) = ret.last().map(Segment::end) else {
+        return;
+    };
+    match options.cap {
+        Cap::Butt => ret.push(Segment::Line(end, next)),
+        Cap::Square => {
+            let extension = options.distance.abs() * tangent.unit();
+            ret.push(Segment::Line(end, end + extension));
+            ret.push(Segment::Line(end + extension, next + extension));
+            ret.push(Segment::Line(next + extension, next));
+        }
+        Cap::Round => ret.extend(arc(path_end, end, next, std::f64::consts::PI)),
+    }
+}
+
+/// The offset of `path`, on its right side, with joins between its segments.
+fn offset_path(
+    path: &[Segment],
+    closed: bool,
+    distance: f64,
+    options: &OffsetOptions,
+) -> Vec<Segment> {
+    let mut ret = vec![];
+    let pieces = path
+        .iter()
+        .map(|s| {
+            let mut v = vec![];
+            offset_segment(s, distance, options.tolerance, 0, &mut v);
+            v
+        })
+        .collect::<Vec<Vec<Segment>>>();
+    for (i, piece) in pieces.iter().enumerate() {
+        ret.extend(piece.iter().copied());
+        let next = if i + 1 < pieces.len() {
+            i + 1
+        } else if closed {
+            0
+        } else {
+            break;
+        };
+        join(
+            &mut ret,
+            path[i].end(),
+            (path[i].end_tangent(), path[next].start_tangent()),
+            pieces[next][0].start(),
+            distance,
+            options,
+        );
+    }
+    ret
+}
+
+/// Offset a loop or expand an open path of `segments`. The result is a set of loops oriented like
+/// the original contour, or counter-clockwise for strokes.
+pub fn offset(
+    segments: &[Segment],
+    closed: bool,
+    options: &OffsetOptions,
+) -> Result<Vec<Vec<Segment>>, Box<dyn std::error::Error>> {
+    let path = segments
+        .iter()
+        .filter(|s| {
+            let (min, max) = s.bbox();
+            min.distance(max) > 1e-9
+        })
+        .copied()
+        .collect::<Vec<Segment>>();
+    if path.is_empty() || options.distance == 0.0 {
+        return Ok(vec![]);
+    }
+    let (raw, orientation) = if closed {
+        let area = boolean::area(&path);
+        if area == 0.0 {
+            return Ok(vec![]);
+        }
+        (
+            offset_path(&path, true, options.distance, options),
+            area.signum(),
+        )
+    } else {
+        let distance = options.distance.abs();
+        let reversed = path.iter().rev().map(Segment::reversed).collect::<Vec<_>>();
+        let mut ret = offset_path(&path, false, distance, options);
+        let back = offset_path(&reversed, false, distance, options);
+        let last = path[path.len() - 1];
+        cap(
+            &mut ret,
+            last.end(),
+            last.end_tangent(),
+            back[0].start(),
+            options,
+        );
+        ret.extend(back);
+        let first = ret[0].start();
+        cap(
+            &mut ret,
+            path[0].start(),
+            -1.0 * path[0].start_tangent(),
+            first,
+            options,
+        );
+        (ret, 1.0)
+    };
+    let mut ret = boolean::resolve(&[raw], |w| f64::from(w) * orientation > 0.0)?;
+    if orientation < 0.0 {
+        for l in ret.iter_mut() {
+            *l = l.iter().rev().map(Segment::reversed).collect();
+        }
+    }
+    Ok(ret)
+}
+
+/// Offset closed contours and expand open contours to strokes, then merge the results.
+pub fn offset_contours(
+    contours: &[Contour],
+    options: &OffsetOptions,
+) -> Result<Vec<Contour>, Box<dyn std::error::Error>> {
+    let mut loops = vec![];
+    for contour in contours {
+        let closed = !contour.property::<bool>(Contour::OPEN);
+        let segments = if closed {
+            boolean::contour_segments(contour)?
+        } else {
+            boolean::curve_segments(contour)?
+        };
+        loops.extend(offset(&segments, closed, options)?);
+    }
+    Ok(
+        boolean::boolean(&loops, &[], boolean::BooleanOperation::RemoveOverlap)?
+            .iter()
+            .map(|s| boolean::segments_contour(s))
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+fn square(x0: f64, y0: f64, x1: f64, y1: f64) -> Vec<Segment> {
+    let p = |x, y| Point::from((x, y));
+    vec![
+        Segment::Line(p(x0, y0), p(x1, y0)),
+        Segment::Line(p(x1, y0), p(x1, y1)),
+        Segment::Line(p(x1, y1), p(x0, y1)),
+        Segment::Line(p(x0, y1), p(x0, y0)),
+    ]
+}
+
+#[cfg(test)]
+fn total_area(loops: &[Vec<Segment>]) -> f64 {
+    loops.iter().map(|l| boolean::area(l)).sum()
+}
+
+#[test]
+fn test_offset_joins() {
+    use std::f64::consts::PI;
+
+    let rect = square(0.0, 0.0, 100.0, 100.0);
+    let options = |join| OffsetOptions {
+        distance: 10.0,
+        join,
+        ..OffsetOptions::default()
+    };
+    let miter = offset(&rect, true, &options(Join::Miter)).unwrap();
+    assert_eq!(miter.len(), 1);
+    assert_eq!(miter[0].len(), 4);
+    assert!((total_area(&miter) - 120.0 * 120.0).abs() < 1e-6);
+    let bevel = offset(&rect, true, &options(Join::Bevel)).unwrap();
+    assert_eq!(bevel[0].len(), 8);
+    assert!((total_area(&bevel) - (120.0 * 120.0 - 4.0 * 50.0)).abs() < 1e-6);
+    let round = offset(&rect, true, &options(Join::Round)).unwrap();
+    assert!((total_area(&round) - (100.0 * 100.0 + 4.0 * 1000.0 + PI * 100.0)).abs() < 0.1);
+    /* A miter limit below the corner's miter length falls back to a bevel. */
+    let limited = offset(
+        &rect,
+        true,
+        &OffsetOptions {
+            miter_limit: 1.2,
+            ..options(Join::Miter)
+        },
+    )
+    .unwrap();
+    assert_eq!(limited[0].len(), 8);
+
+    /* Shrinking removes the loops at the inner corners. */
+    let inset = offset(
+        &rect,
+        true,
+        &OffsetOptions {
+            distance: -10.0,
+            ..options(Join::Round)
+        },
+    )
+    .unwrap();
+    assert_eq!(inset.len(), 1);
+    assert_eq!(inset[0].len(), 4);
+    assert!((total_area(&inset) - 80.0 * 80.0).abs() < 1e-6);
+    /* Shrinking more than the shape's size leaves nothing. */
+    let empty = offset(
+        &rect,
+        true,
+        &OffsetOptions {
+            distance: -60.0,
+            ..options(Join::Round)
+        },
+    )
+    .unwrap();
+    assert!(empty.is_empty());
+
+    /* A clockwise counter grows into its hole. */
+    let counter = rect.iter().rev().map(Segment::reversed).collect::<Vec<_>>();
+    let result = offset(&counter, true, &options(Join::Miter)).unwrap();
+    assert!((total_area(&result) + 80.0 * 80.0).abs() < 1e-6);
+}
+
+#[test]
+fn test_offset_curves() {
+    const K: f64 = 0.552_284_749_830_793_4;
+    const R: f64 = 100.0;
+
+    let p = |x: f64, y: f64| Point::from((x * R, y * R));
+    let circle = vec![
+        Segment::Cubic(p(1.0, 0.0), p(1.0, K), p(K, 1.0), p(0.0, 1.0)),
+        Segment::Cubic(p(0.0, 1.0), p(-K, 1.0), p(-1.0, K), p(-1.0, 0.0)),
+        Segment::Cubic(p(-1.0, 0.0), p(-1.0, -K), p(-K, -1.0), p(0.0, -1.0)),
+        Segment::Cubic(p(0.0, -1.0), p(K, -1.0), p(1.0, -K), p(1.0, 0.0)),
+    ];
+    let options = OffsetOptions {
+        distance: 20.0,
+        ..OffsetOptions::default()
+    };
+    for distance in [20.0, -20.0] {
+        let result = offset(
+            &circle,
+            true,
+            &OffsetOptions {
+                distance,
+                ..options
+            },
+        )
+        .unwrap();
+        assert_eq!(result.len(), 1);
+        for s in result[0].iter() {
+            for i in 0..=10 {
+                let d = s.eval(f64::from(i) / 10.0).norm();
+                /* The cubic circle itself deviates from a true circle by 0.03%. */
+                assert!((d - (R + distance)).abs() < options.tolerance + 0.03, "{d}");
+            }
+        }
+    }
+
+    /* A stroke of an S curve has twice the distance times its length as area. */
+    let s_curve = [Segment::Cubic(
+        p(0.0, 0.0),
+        p(1.0, 0.0),
+        p(0.0, 1.0),
+        p(1.0, 1.0),
+    )];
+    let length = (0..1000)
+        .map(|i| {
+            let t = f64::from(i) / 1000.0;
+            s_curve[0].eval(t).distance(s_curve[0].eval(t + 0.001))
+        })
+        .sum::<f64>();
+    let stroke = offset(
+        &s_curve,
+        false,
+        &OffsetOptions {
+            distance: 5.0,
+            ..options
+        },
+    )
+    .unwrap();
+    assert_eq!(stroke.len(), 1);
+    assert!((total_area(&stroke) - 10.0 * length).abs() < 0.01 * 10.0 * length);
+}
+
+#[test]
+fn test_offset_caps() {
+    use std::f64::consts::PI;
+
+    let line = [Segment::Line((0.0, 0.0).into(), (100.0, 0.0).into())];
+    let options = |cap| OffsetOptions {
+        distance: 10.0,
+        cap,
+        ..OffsetOptions::default()
+    };
+    let butt = offset(&line, false, &options(Cap::Butt)).unwrap();
+    assert_eq!(butt.len(), 1);
+    assert!((total_area(&butt) - 2000.0).abs() < 1e-6);
+    let square = offset(&line, false, &options(Cap::Square)).unwrap();
+    assert!((total_area(&square) - 2400.0).abs() < 1e-6);
+    let round = offset(&line, false, &options(Cap::Round)).unwrap();
+    assert!((total_area(&round) - (2000.0 + PI * 100.0)).abs() < 0.1);
+
+    /* A right angle stroke with a miter join. */
+    let corner = [
+        Segment::Line((0.0, 0.0).into(), (100.0, 0.0).into()),
+        Segment::Line((100.0, 0.0).into(), (100.0, 100.0).into()),
+    ];
+    let stroke = offset(
+        &corner,
+        false,
+        &OffsetOptions {
+            join: Join::Miter,
+            ..options(Cap::Butt)
+        },
+    )
+    .unwrap();
+    assert_eq!(stroke.len(), 1);
+    assert!((total_area(&stroke) - (110.0 * 20.0 + 90.0 * 20.0)).abs() < 1e-6);
+}
@@ -0,0 +1,405 @@
+/*
+ * gerb
+ *
+ * Copyright 2022 - Manos Pitsidianakis
+ *
+ * This file is part of gerb.
+ *
+ * gerb is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * gerb is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with gerb. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Simplification of contours by refitting runs of smoothly joined curves with fewer cubics.
+//!
+//! A run of curves is fitted with a single cubic that keeps the run's endpoints and end tangents,
+//! so the result stays tangent continuous wherever the original was. Runs are extended greedily
+//! from the start while the fit stays within the tolerance. Corners, that is joins with
+//! positional continuity, always end a run.
+
+use super::boolean::Segment;
+use super::*;
+use std::ops::Range;
+
+/// Points sampled on every curve of a run to measure the error of a fit.
+const SAMPLES: u32 = 16;
+/// Newton iterations that improve the parameters of the samples on a fit.
+const REPARAMETERIZATIONS: usize = 4;
+
+/// Fit a cubic from `start` to `end` with handles along unit tangents.
+///
+/// The handle lengths fit `samples` of `(parameter, point)` with least squares. Handles a third
+/// of the chord long are used if there is no fit.
+pub fn fit_cubic(
+    (start, start_tangent): (Point, Point),
+    (end, end_tangent): (Point, Point),
+    samples: &[(f64, Point)],
+) -> Segment {
+    let (mut aa, mut ab, mut bb, mut ar, mut br) = (0.0, 0.0, 0.0, 0.0, 0.0);
+    for &(t, p) in samples {
+        let mt = 1.0 - t;
+        let a = (3.0 * mt * mt * t) * start_tangent;
+        let b = (-3.0 * mt * t * t) * end_tangent;
+        let base =
+            (mt * mt * 3.0f64.mul_add(t, mt)) * start + (t * t * (3.0f64.mul_add(mt, t))) * end;
+        let r = p - base;
+        aa += a.dot(a);
+        ab += a.dot(b);
+        bb += b.dot(b);
+        ar += a.dot(r);
+        br += b.dot(r);
+    }
+    let determinant = aa.mul_add(bb, -(ab * ab));
+    let chord = start.distance(end) / 3.0;
+    let (mut a, mut b) = (chord, chord);
+    if determinant.abs() > 1e-12 {
+        let (fit_a, fit_b) = (
+            bb.mul_add(ar, -(ab * br)) / determinant,
+            aa.mul_add(br, -(ab * ar)) / determinant,
+        );
+        if fit_a > 0.0 && fit_b > 0.0 {
+            (a, b) = (fit_a, fit_b);
+        }
+    }
+    Segment::Cubic(start, start + a * start_tangent, end - b * end_tangent, end)
+}
+
+fn second_derivative(segment: &Segment, t: f64) -> Point {
+    match *segment {
+        Segment::Line(..) => Point::default(),
+        Segment::Quadratic(p0, p1, p2) => 2.0 * (p2 - 2.0 * p1 + p0),
+        Segment::Cubic(p0, p1, p2, p3) => {
+            (6.0 * (1.0 - t)) * (p2 - 2.0 * p1 + p0) + (6.0 * t) * (p3 - 2.0 * p2 + p1)
+        }
+    }
+}
+
+/// Fit all of `run` with one cubic, if that is possible within `tolerance`.
+fn fit_run(run: &[Segment], tolerance: f64) -> Option<Segment> {
+    let (first, last) = (run.first()?, run.last()?);
+    let (start_tangent, end_tangent) = (first.start_tangent().unit(), last.end_tangent().unit());
+    let mut points = vec![first.start()];
+    for segment in run {
+        points.extend((1..=SAMPLES).map(|i| segment.eval(f64::from(i) / f64::from(SAMPLES))));
+    }
+    /* Initial parameters proportional to the length of the polyline through the samples. */
+    let mut lengths = vec![0.0];
+    for w in points.windows(2) {
+        lengths.push(lengths[lengths.len() - 1] + w[0].distance(w[1]));
+    }
+    let total = lengths[lengths.len() - 1];
+    if total <= 0.0 {
+        return None;
+    }
+    let mut samples = points
+        .iter()
+        .zip(lengths.iter())
+        .map(|(&p, &l)| (l / total, p))
+        .collect::<Vec<(f64, Point)>>();
+    for _ in 0..=REPARAMETERIZATIONS {
+        let fit = fit_cubic(
+            (first.start(), start_tangent),
+            (last.end(), end_tangent),
+            &samples,
+        );
+        let error = samples
+            .iter()
+            .map(|&(t, p)| fit.eval(t).distance(p))
+            .fold(0.0, f64::max);
+        if error <= tolerance {
+            return Some(fit);
+        }
+        /* Move each parameter to the closest point of the fit with a Newton step. */
+        for (t, p) in samples.iter_mut() {
+            let diff = fit.eval(*t) - *p;
+            let d1 = fit.derivative(*t);
+            let denominator = d1.dot(d1) + diff.dot(second_derivative(&fit, *t));
+            if denominator.abs() > 1e-12 {
+                *t = (*t - diff.dot(d1) / denominator).clamp(0.0, 1.0);
+            }
+        }
+    }
+    None
+}
+
+/// Refit `run`, whose joins are all smooth, with as few cubics as possible within `tolerance`.
+/// Returns each new segment along with the range of indices of `run` it replaces.
+pub fn simplify_run(run: &[Segment], tolerance: f64) -> Vec<(Range<usize>, Segment)> {
+    let mut ret = vec![];
+    let mut i = 0;
+    while i < run.len() {
+        let (mut end, mut segment) = (i + 1, run[i]);
+        for j in (i + 2)..=run.len() {
+            let Some(fit) = fit_run(&run[i..j], tolerance) else {
+                break;
+            };
+            (end, segment) = (j, fit);
+        }
+        ret.push((i..end, segment));
+        i = end;
+    }
+    ret
+}
+
+/// Simplify the curves of `contour` for which `selected` is true, within `tolerance` units.
+///
+/// Joins with positional continuity and joins with unselected curves are kept. Returns `None` if
+/// no curve could be removed.
+pub fn simplify_contour(
+    contour: &Contour,
+    selected: impl Fn(usize) -> bool,
+    tolerance: f64,
+) -> Option<Contour> {
+    let closed = !contour.property::<bool>(Contour::OPEN);
+    let curves = contour.curves();
+    let n = curves.len();
+    let segments = curves
+        .iter()
+        .map(|c| {
+            let points = c.points().iter().map(|cp| cp.position).collect::<Vec<_>>();
+            Segment::new(&points)
+        })
+        .collect::<Option<Vec<Segment>>>()?;
+    /* Whether the join at the start of each curve must be kept. */
+    let corners = (0..n)
+        .map(|i| {
+            let prev = (i + n - 1) % n;
+            (i == 0 && (!closed || segments[prev].end() != segments[i].start()))
+                || !selected(i)
+                || !selected(prev)
+                || curves[i]
+                    .property::<Option<Continuity>>(Bezier::CONTINUITY_IN)
+                    .is_none_or(Continuity::is_positional)
+        })
+        .collect::<Vec<bool>>();
+    let first = corners.iter().position(|&c| c).unwrap_or(0);
+    let mut runs: Vec<Vec<usize>> = vec![];
+    for i in (first..n).chain(0..first) {
+        match runs.last_mut() {
+            Some(run) if !corners[i] => run.push(i),
+            _ => runs.push(vec![i]),
+        }
+    }
+    /* Each new segment along with the index of the curve it starts at. */
+    let mut result: Vec<(usize, Segment)> = vec![];
+    for run in runs {
+        let run_segments = run.iter().map(|&i| segments[i]).collect::<Vec<_>>();
+        result.extend(
+            simplify_run(&run_segments, tolerance)
+                .into_iter()
+                .map(|(range, segment)| (run[range.start], segment)),
+        );
+    }
+    if result.len() >= n {
+        return None;
+    }
+    /* Keep the starting point of the contour if it was not removed. */
+    if let Some(pos) = result.iter().position(|(i, _)| *i == 0) {
+        result.rotate_left(pos);
+    }
+    let ret = Contour::new();
+    for (i, segment) in result {
+        let curve = Bezier::new(segment.points());
+        curve.set_property(
+            Bezier::SMOOTH,
+            !corners[i] || curves[i].property::<bool>(Bezier::SMOOTH),
+        );
+        ret.push_curve(curve);
+    }
+    if closed {
+        ret.close();
+    }
+    Some(ret)
+}
+
+#[test]
+fn test_simplify_run() {
+    const K: f64 = 0.552_284_749_830_793_4;
+    const R: f64 = 100.0;
+
+    /* A circle of four quarters, each split into five curves. */
+    let p = |x: f64, y: f64| Point::from((x * R, y * R));
+    let quarters = [
+        Segment::Cubic(p(1.0, 0.0), p(1.0, K), p(K, 1.0), p(0.0, 1.0)),
+        Segment::Cubic(p(0.0, 1.0), p(-K, 1.0), p(-1.0, K), p(-1.0, 0.0)),
+        Segment::Cubic(p(-1.0, 0.0), p(-1.0, -K), p(-K, -1.0), p(0.0, -1.0)),
+        Segment::Cubic(p(0.0, -1.0), p(K, -1.0), p(1.0, -K), p(1.0, 0.0)),
+    ];
+    let mut run = vec![];
+    for q in quarters {
+        let mut rest = q;
+        for i in (1..5).rev() {
+            let (left, right) = rest.split(1.0 / f64::from(i + 1));
+            run.push(left);
+            rest = right;
+        }
+        run.push(rest);
+    }
+    assert_eq!(run.len(), 20);
+    let tolerance = 0.5;
+    let (ranges, result): (Vec<_>, Vec<_>) = simplify_run(&run, tolerance).into_iter().unzip();
+    assert_eq!(ranges[0].start, 0);
+    assert_eq!(ranges[ranges.len() - 1].end, run.len());
+    for w in ranges.windows(2) {
+        assert_eq!(w[0].end, w[1].start);
+    }
+    assert!(result.len() <= 5, "{}", result.len());
+    assert_eq!(result[0].start(), run[0].start());
+    assert_eq!(result[result.len() - 1].end(), run[19].end());
+    for w in result.windows(2) {
+        assert_eq!(w[0].end(), w[1].start());
+    }
+    for s in result.iter() {
+        for i in 0..=20 {
+            let d = s.eval(f64::from(i) / 20.0).norm();
+            assert!((d - R).abs() < tolerance + 0.03, "{d}");
+        }
+    }
+
+    /* A curve split in pieces is refitted to one curve close to the original. */
+    let curve = Segment::Cubic(p(0.0, 0.0), p(0.2, 1.0), p(1.0, 1.2), p(1.5, 0.3));
+    let (a, rest) = curve.split(0.3);
+    let (b, c) = rest.split(0.5);
+    let result = simplify_run(&[a, b, c], tolerance);
+    assert_eq!(result.len(), 1);
+    assert_eq!(result[0].0, 0..3);
+    for i in 0..=20 {
+        let q = result[0].1.eval(f64::from(i) / 20.0);
+        let distance = (0..=1000)
+            .map(|j| curve.eval(f64::from(j) / 1000.0).distance(q))
+            .fold(f64::INFINITY, f64::min);
+        assert!(distance < tolerance + 0.1, "{distance}");
+    }
+
+    /* A tolerance smaller than the error keeps the curves. */
+    let s_curve = [
+        Segment::Cubic(p(0.0, 0.0), p(0.5, 0.0), p(0.5, 1.0), p(1.0, 1.0)),
+        Segment::Cubic(p(1.0, 1.0), p(1.5, 1.0), p(1.5, 0.0), p(2.0, 0.0)),
+    ];
+    assert_eq!(
+        simplify_run(&s_curve, 0.1),
+        vec![(0..1, s_curve[0]), (1..2, s_curve[1])]
+    );
+}
+
+#[test]
+fn test_simplify_contour() {
+    let contour = |curves: &[(&[(f64, f64)], bool)]| {
+        let ret = Contour::new();
+        for &(points, smooth) in curves {
+            let curve = Bezier::new(points.iter().map(|&p| Point::from(p)).collect());
+            curve.set_property(Bezier::SMOOTH, smooth);
+            ret.push_curve(curve);
+        }
+        ret.close();
+        ret
+    };
+    let positions = |contour: &Contour| {
+        contour
+            .curves()
+            .iter()
+            .map(|c| c.points().iter().map(|cp| cp.position).collect::<Vec<_>>())
+            .collect::<Vec<_>>()
+    };
+    let p = Point::from;
+
+    /* The right half of an arch split in three, closed by a line with corners at both ends and
+     * a curve that is too round to be merged with the rest. */
+    let left: &[(f64, f64)] = &[(0.0, 0.0), (0.0, 50.0), (25.0, 75.0), (50.0, 75.0)];
+    let a: &[(f64, f64)] = &[(50.0, 75.0), (62.5, 75.0), (75.0, 68.75), (84.375, 56.25)];
+    let b: &[(f64, f64)] = &[
+        (84.375, 56.25),
+        (89.0625, 50.0),
+        (92.968_75, 42.1875),
+        (95.703_125, 32.8125),
+    ];
+    let c: &[(f64, f64)] = &[
+        (95.703_125, 32.8125),
+        (98.4375, 23.4375),
+        (100.0, 12.5),
+        (100.0, 0.0),
+    ];
+    let line: &[(f64, f64)] = &[(100.0, 0.0), (0.0, 0.0)];
+    let arch = contour(&[
+        (left, false),
+        (a, true),
+        (b, true),
+        (c, true),
+        (line, false),
+    ]);
+    let original = positions(&arch);
+
+    /* The smooth run is refitted with one curve and the corners are kept. */
+    let result = simplify_contour(&arch, |_| true, 0.5).unwrap();
+    let points = positions(&result);
+    assert_eq!(points.len(), 3);
+    assert_eq!(points[0], original[0]);
+    assert_eq!(points[1].len(), 4);
+    assert_eq!(
+        (points[1][0], points[1][3]),
+        (p((50.0, 75.0)), p((100.0, 0.0)))
+    );
+    assert!(points[1][1].y == 75.0 && points[1][2].x == 100.0);
+    assert_eq!(points[2], original[4]);
+    for curve in [&result.curves()[0], &result.curves()[2]] {
+        assert!(curve
+            .property::<Option<Continuity>>(Bezier::CONTINUITY_IN)
+            .is_some_and(Continuity::is_positional));
+    }
+
+    /* Unselected curves are left untouched. */
+    assert!(simplify_contour(&arch, |_| false, 0.5).is_none());
+    assert!(simplify_contour(&arch, |i| i == 2, 0.5).is_none());
+    let result = simplify_contour(&arch, |i| i == 1 || i == 2, 0.5).unwrap();
+    let points = positions(&result);
+    assert_eq!(points.len(), 4);
+    assert_eq!(points[0], original[0]);
+    assert_eq!(
+        (points[1][0], points[1][3]),
+        (p((50.0, 75.0)), p((95.703_125, 32.8125)))
+    );
+    assert_eq!(points[2..], original[3..]);
+
+    /* A join with positional continuity is a corner even if its handles are collinear. */
+    let corner = contour(&[
+        (left, false),
+        (a, true),
+        (b, false),
+        (c, true),
+        (line, false),
+    ]);
+    let result = simplify_contour(&corner, |_| true, 0.5).unwrap();
+    let points = positions(&result);
+    assert_eq!(points.len(), 4);
+    assert_eq!(points[..2], original[..2]);
+    assert_eq!(
+        (points[2][0], points[2][3]),
+        (p((84.375, 56.25)), p((100.0, 0.0)))
+    );
+    assert_eq!(points[3], original[4]);
+
+    /* So is a join whose handles are not collinear, even if the curve is smooth. */
+    let b: &[(f64, f64)] = &[
+        (84.375, 56.25),
+        (89.0625, 51.0),
+        (92.968_75, 42.1875),
+        (95.703_125, 32.8125),
+    ];
+    let corners = contour(&[
+        (left, false),
+        (a, true),
+        (b, true),
+        (c, false),
+        (line, false),
+    ]);
+    assert!(simplify_contour(&corners, |_| true, 0.5).is_none());
+}
@@ -0,0 +1,906 @@
+/*
+ * gerb
+ *
+ * Copyright 2022 - Manos Pitsidianakis
+ *
+ * This file is part of gerb.
+ *
+ * gerb is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * gerb is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with gerb. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Reading and writing outlines as SVG path data, and importing SVG documents.
+//!
+//! SVG coordinates grow downwards while font units grow upwards, so written path data has its y
+//! coordinates negated and read path data is converted back by the caller with a transformation
+//! such as [`flip_y`], or scaled to the em square by [`import`].
+//!
+//! Imported paths become contours of a glyph. An SVG document can also be shown behind a glyph
+//! as a reference for drawing with [`render_image`], which rasterizes it for a glyph image.
+
+use super::boolean::{self, Segment};
+use super::*;
+
+/// A subpath of SVG path data, which becomes one contour.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Subpath {
+    pub segments: Vec<Segment>,
+    pub closed: bool,
+}
+
+impl Subpath {
+    /// Apply `m` to all the points of the subpath.
+    pub fn transform(&mut self, m: Matrix) {
+        for s in self.segments.iter_mut() {
+            let points = s
+                .points()
+                .into_iter()
+                .map(|p| {
+                    let (x, y) = m.transform_point(p.x, p.y);
+                    Point::from((x, y))
+                })
+                .collect::<Vec<Point>>();
+            if let Some(new) = Segment::new(&points) {
+                *s = new;
+            }
+        }
+    }
+
+    pub fn to_contour(&self) -> Contour {
+        if self.closed {
+            boolean::segments_contour(&self.segments)
+        } else {
+            knife::open_contour(&self.segments)
+        }
+    }
+}
+
+/// The transformation between SVG and font coordinates, which is its own inverse.
+pub fn flip_y() -> Matrix {
+    Matrix::new(1.0, 0.0, 0.0, -1.0, 0.0, 0.0)
+}
+
+/// The bounding box of the control points of `subpaths`.
+pub fn bbox(subpaths: &[Subpath]) -> Option<(Point, Point)> {
+    subpaths
+        .iter()
+        .flat_map(|s| s.segments.iter().flat_map(Segment::points))
+        .fold(None, |acc, p| match acc {
+            None => Some((p, p)),
+            Some((min, max)) => Some((
+                (min.x.min(p.x), min.y.min(p.y)).into(),
+                (max.x.max(p.x), max.y.max(p.y)).into(),
+            )),
+        })
+}
+
+/// Reader of the commands and numbers of path data.
+struct Tokens<'a> {
+    s: &'a str,
+    pos: usize,
+}
+
+impl Tokens<'_> {
+    fn skip_separators(&mut self) {
+        while let Some(c) = self.s[self.pos..].chars().next() {
+            if !(c.is_whitespace() || c == ',') {
+                break;
+            }
+            self.pos += c.len_utf8();
+        }
+    }
+
+    fn is_done(&mut self) -> bool {
+        self.skip_separators();
+        self.pos == self.s.len()
+    }
+
+    /// Consume the next command letter, if the next token is one.
+    fn command(&mut self) -> Option<char> {
+        self.skip_separators();
+        let c = self.s[self.pos..].chars().next()?;
+        if c.is_ascii_alphabetic() && !matches!(c, 'e' | 'E') {
+            self.pos += 1;
+            Some(c)
+        } else {
+            None
+        }
+    }
+
+    fn number(&mut self) -> Result<f64, Box<dyn std::error::Error>> {
+        self.skip_separators();
+        let bytes = self.s.as_bytes();
+        let start = self.pos;
+        let mut end = start;
+        if matches!(bytes.get(end), Some(b'-' | b'+')) {
+            end += 1;
+        }
+        let mut seen_dot = false;
+        while let Some(&b) = bytes.get(end) {
+            match b {
+                b'0'..=b'9' => {}
+                /* A second dot starts the next number, as in "0.5.5". */
+                b'.' if !seen_dot => seen_dot = true,
+                _ => break,
+            }
+            end += 1;
+        }
+        if matches!(bytes.get(end), Some(b'e' | b'E')) {
+            let mut exponent = end + 1;
+            if matches!(bytes.get(exponent), Some(b'-' | b'+')) {
+                exponent += 1;
+            }
+            if bytes.get(exponent).is_some_and(u8::is_ascii_digit) {
+                end = exponent;
+                while bytes.get(end).is_some_and(u8::is_ascii_digit) {
+                    end += 1;
+                }
+            }
+        }
+        let number = self.s[start..end].parse::<f64>().map_err(|_| {
+            format!(
+                "Expected a number at position {} of path data, found {:?}.",
+                start,
+                &self.s[start..self.s.len().min(start + 10)]
+            )
+        })?;
+        self.pos = end;
+        Ok(number)
+    }
+
+    /// Consume an arc flag, which may be followed by the next number without a separator.
+    fn flag(&mut self) -> Result<bool, Box<dyn std::error::Error>> {
+        self.skip_separators();
+        let ret = match self.s.as_bytes().get(self.pos) {
+            Some(b'0') => false,
+            Some(b'1') => true,
+            _ => {
+                return Err(format!(
+                    "Expected an arc flag at position {} of path data.",
+                    self.pos
+                )
+                .into())
+            }
+        };
+        self.pos += 1;
+        Ok(ret)
+    }
+
+    fn point(&mut self) -> Result<Point, Box<dyn std::error::Error>> {
+        let x = self.number()?;
+        let y = self.number()?;
+        Ok((x, y).into())
+    }
+}
+
+/// Cubic approximations of an elliptical arc from `start` to `end`, with the radii enlarged if
+/// they are too small to reach `end`, as in the implementation notes of the SVG specification.
+/// `angle` is the rotation of the ellipse's x axis in degrees.
+fn arc_segments(
+    start: Point,
+    (rx, ry): (f64, f64),
+    angle: f64,
+    large_arc: bool,
+    sweep: bool,
+    end: Point,
+) -> Vec<Segment> {
+    if start == end {
+        return vec![];
+    }
+    let (mut rx, mut ry) = (rx.abs(), ry.abs());
+    if rx < f64::EPSILON || ry < f64::EPSILON {
+        return vec![Segment::Line(start, end)];
+    }
+    let (sin, cos) = angle.to_radians().sin_cos();
+    /* Half the chord, in the coordinates of the ellipse's axes. */
+    let half = 0.5 * (start - end);
+    let (x1, y1) = Matrix::new(cos, -sin, sin, cos, 0.0, 0.0).transform_point(half.x, half.y);
+    let (x, y) = (x1 / rx, y1 / ry);
+    let lambda = x.mul_add(x, y * y);
+    let mut coefficient = if lambda > 1.0 {
+        rx *= lambda.sqrt();
+        ry *= lambda.sqrt();
+        0.0
+    } else {
+        ((1.0 - lambda) / lambda).sqrt()
+    };
+    if large_arc == sweep {
+        coefficient = -coefficient;
+    }
+    let (cx1, cy1) = (coefficient * rx * y1 / ry, -coefficient * ry * x1 / rx);
+    let mid = 0.5 * (start + end);
+    let (cx, cy) = Matrix::new(cos, sin, -sin, cos, mid.x, mid.y).transform_point(cx1, cy1);
+
+    let u: Point = ((x1 - cx1) / rx, (y1 - cy1) / ry).into();
+    let v: Point = ((-x1 - cx1) / rx, (-y1 - cy1) / ry).into();
+    let mut theta = u.y.atan2(u.x);
+    let mut delta =
+        u.x.mul_add(v.y, -u.y * v.x)
+            .atan2(u.x.mul_add(v.x, u.y * v.y));
+    if !sweep && delta > 0.0 {
+        delta -= std::f64::consts::TAU;
+    } else if sweep && delta < 0.0 {
+        delta += std::f64::consts::TAU;
+    }
+
+    /* Pieces of at most a quarter turn, whose control points lie on the tangents of their ends. */
+    let pieces = (delta.abs() / std::f64::consts::FRAC_PI_2)
+        .ceil()
+        .clamp(1.0, 4.0);
+    let step = delta / pieces;
+    let k = 4.0 / 3.0 * (step / 4.0).tan();
+    let ellipse = Matrix::new(rx * cos, rx * sin, -ry * sin, ry * cos, cx, cy);
+    let point = |t: f64, d: f64| {
+        let (sin, cos) = t.sin_cos();
+        let (x, y) = ellipse.transform_point((-d).mul_add(sin, cos), d.mul_add(cos, sin));
+        Point::from((x, y))
+    };
+    let mut ret = vec![];
+    let mut previous = start;
+    for i in [1.0, 2.0, 3.0, 4.0].into_iter().filter(|&i| i <= pieces) {
+        let next = if i < pieces {
+            point(theta + step, 0.0)
+        } else {
+            end
+        };
+        ret.push(Segment::Cubic(
+            previous,
+            point(theta, k),
+            point(theta + step, -k),
+            next,
+        ));
+        previous = next;
+        theta += step;
+    }
+    ret
+}
+
+/// Parse the path data of an SVG `<path>` element's `d` attribute, in SVG coordinates.
+pub fn parse_path_data(d: &str) -> Result<Vec<Subpath>, Box<dyn std::error::Error>> {
+    let mut tokens = Tokens { s: d, pos: 0 };
+    let mut ret = vec![];
+    let mut segments: Vec<Segment> = vec![];
+    let (mut pen, mut start) = (Point::default(), Point::default());
+    let mut command: Option<char> = None;
+    while !tokens.is_done() {
+        let c = match (tokens.command(), command) {
+            (Some(c), _) => c,
+            /* Coordinates after a move are implicit lines. */
+            (None, Some('M')) => 'L',
+            (None, Some('m')) => 'l',
+            (None, Some(c)) if !matches!(c, 'Z' | 'z') => c,
+            _ => return Err("Path data must start with a move command.".into()),
+        };
+        if command.is_none() && !matches!(c, 'M' | 'm') {
+            return Err("Path data must start with a move command.".into());
+        }
+        let relative = c.is_ascii_lowercase();
+        let offset = if relative { pen } else { Point::default() };
+        let previous = segments.last().copied();
+        let segment = match c.to_ascii_uppercase() {
+            'M' => {
+                if !segments.is_empty() {
+                    ret.push(Subpath {
+                        segments: std::mem::take(&mut segments),
+                        closed: false,
+                    });
+                }
+                pen = offset + tokens.point()?;
+                start = pen;
+                None
+            }
+            'Z' => {
+                if !segments.is_empty() {
+                    if pen != start {
+                        segments.push(Segment::Line(pen, start));
+                    }
+                    ret.push(Subpath {
+                        segments: std::mem::take(&mut segments),
+                        closed: true,
+                    });
+                }
+                pen = start;
+                None
+            }
+            'L' => Some(Segment::Line(pen, offset + tokens.point()?)),
+            'H' => {
+                let x = tokens.number()? + offset.x;
+                Some(Segment::Line(pen, (x, pen.y).into()))
+            }
+            'V' => {
+                let y = tokens.number()? + offset.y;
+                Some(Segment::Line(pen, (pen.x, y).into()))
+            }
+            'C' => {
+                let (c1, c2, end) = (tokens.point()?, tokens.point()?, tokens.point()?);
+                Some(Segment::Cubic(pen, offset + c1, offset + c2, offset + end))
+            }
+            'S' => {
+                let c1 = match previous {
+                    Some(Segment::Cubic(_, _, c2, end))
+                        if matches!(command, Some('C' | 'c' | 'S' | 's')) =>
+                    {
+                        2.0 * end - c2
+                    }
+                    _ => pen,
+                };
+                let (c2, end) = (tokens.point()?, tokens.point()?);
+                Some(Segment::Cubic(pen, c1, offset + c2, offset + end))
+            }
+            'Q' => {
+                let (c1, end) = (tokens.point()?, tokens.point()?);
+                Some(Segment::Quadratic(pen, offset + c1, offset + end))
+            }
+            'T' => {
+                let c1 = match previous {
+                    Some(Segment::Quadratic(_, c1, end))
+                        if matches!(command, Some('Q' | 'q' | 'T' | 't')) =>
+                    {
+                        2.0 * end - c1
+                    }
+                    _ => pen,
+                };
+                Some(Segment::Quadratic(pen, c1, offset + tokens.point()?))
+            }
+            'A' => {
+                let (rx, ry, angle) = (tokens.number()?, tokens.number()?, tokens.number()?);
+                let (large_arc, sweep) = (tokens.flag()?, tokens.flag()?);
+                let end = offset + tokens.point()?;
+                segments.extend(arc_segments(pen, (rx, ry), angle, large_arc, sweep, end));
+                pen = end;
+                None
+            }
+            _ => return Err(format!("Unknown path data command {c:?}.").into()),
+        };
+        if let Some(segment) = segment {
+            pen = segment.end();
+            /* Zero length lines add nothing to the outline. */
+            if !matches!(segment, Segment::Line(a, b) if a == b) {
+                segments.push(segment);
+            }
+        }
+        command = Some(c);
+    }
+    if !segments.is_empty() {
+        ret.push(Subpath {
+            segments,
+            closed: false,
+        });
+    }
+    Ok(ret)
+}
+
+/// Parse the value of a `transform` attribute.
+pub fn parse_transform(s: &str) -> Result<Matrix, Box<dyn std::error::Error>> {
+    let invalid = || format!("Invalid transform {s:?}.");
+    let mut ret = Matrix::identity();
+    let mut rest = s;
+    loop {
+        rest = rest.trim_start_matches(|c: char| c.is_whitespace() || c == ',');
+        if rest.is_empty() {
+            break;
+        }
+        let (name, tail) = rest.split_once('(').ok_or_else(invalid)?;
+        let (arguments, tail) = tail.split_once(')').ok_or_else(invalid)?;
+        let mut tokens = Tokens {
+            s: arguments,
+            pos: 0,
+        };
+        let mut values = vec![];
+        while !tokens.is_done() {
+            values.push(tokens.number()?);
+        }
+        let mut m = Matrix::identity();
+        match (name.trim(), values.as_slice()) {
+            ("matrix", &[xx, yx, xy, yy, x0, y0]) => m = Matrix::new(xx, yx, xy, yy, x0, y0),
+            ("translate", &[x]) => m.translate(x, 0.0),
+            ("translate", &[x, y]) => m.translate(x, y),
+            ("scale", &[factor]) => m.scale(factor, factor),
+            ("scale", &[x, y]) => m.scale(x, y),
+            ("rotate", &[angle]) => m.rotate(angle.to_radians()),
+            ("rotate", &[angle, x, y]) => {
+                m.translate(x, y);
+                m.rotate(angle.to_radians());
+                m.translate(-x, -y);
+            }
+            ("skewX", &[angle]) => {
+                m = Matrix::new(1.0, 0.0, angle.to_radians().tan(), 1.0, 0.0, 0.0)
+            }
+            ("skewY", &[angle]) => {
+                m = Matrix::new(1.0, angle.to_radians().tan(), 0.0, 1.0, 0.0, 0.0)
+            }
+            _ => return Err(invalid().into()),
+        }
+        /* The rightmost transformation of a list is applied first. */
+        ret = Matrix::multiply(&m, &ret);
+        rest = tail;
+    }
+    Ok(ret)
+}
+
+/// The paths of an SVG document.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Document {
+    /// Minimum and maximum corners of the root element's `viewBox`, or of its `width` and
+    /// `height` if it has no `viewBox`.
+    pub canvas: Option<(Point, Point)>,
+    /// Subpaths of every `<path>` element outside of definitions, with their `transform`
+    /// attributes and those of their ancestors applied.
+    pub subpaths: Vec<Subpath>,
+}
+
+/// Elements whose descendants are not drawn where they are defined.
+const DEFINITIONS: [&[u8]; 6] = [
+    b"defs",
+    b"clipPath",
+    b"mask",
+    b"marker",
+    b"pattern",
+    b"symbol",
+];
+
+/// Parse the paths of an SVG document.
+pub fn parse_document(svg: &str) -> Result<Document, Box<dyn std::error::Error>> {
+    use quick_xml::events::Event;
+
+    let mut reader = quick_xml::Reader::from_str(svg);
+    let mut ret = Document::default();
+    /* The transformation and whether the content is a definition, for each open element. */
+    let mut stack: Vec<(Matrix, bool)> = vec![];
+    loop {
+        let (e, empty) = match reader.read_event()? {
+            Event::Start(e) => (e, false),
+            Event::Empty(e) => (e, true),
+            Event::End(_) => {
+                stack.pop();
+                continue;
+            }
+            Event::Eof => break,
+            _ => continue,
+        };
+        let mut attributes: IndexMap<Vec<u8>, String> = IndexMap::default();
+        for attribute in e.attributes() {
+            let attribute = attribute?;
+            attributes.insert(
+                attribute.key.as_ref().to_vec(),
+                attribute.unescape_value()?.into_owned(),
+            );
+        }
+        let (parent, in_definition) = stack
+            .last()
+            .copied()
+            .unwrap_or_else(|| (Matrix::identity(), false));
+        let m = match attributes.get(b"transform".as_slice()) {
+            Some(transform) => Matrix::multiply(&parse_transform(transform)?, &parent),
+            None => parent,
+        };
+        let name = e.local_name();
+        let in_definition = in_definition || DEFINITIONS.contains(&name.as_ref());
+        if name.as_ref() == b"svg" && stack.is_empty() {
+            ret.canvas = canvas(&attributes);
+        } else if name.as_ref() == b"path" && !in_definition {
+            if let Some(d) = attributes.get(b"d".as_slice()) {
+                let mut subpaths = parse_path_data(d)?;
+                for s in &mut subpaths {
+                    s.transform(m);
+                }
+                ret.subpaths.extend(subpaths);
+            }
+        }
+        if !empty {
+            stack.push((m, in_definition));
+        }
+    }
+    Ok(ret)
+}
+
+/// The canvas of the root `<svg>` element with `attributes`. Percentages are ignored and other
+/// units are taken as user units.
+fn canvas(attributes: &IndexMap<Vec<u8>, String>) -> Option<(Point, Point)> {
+    let numbers = |value: &str| {
+        let mut tokens = Tokens { s: value, pos: 0 };
+        let mut ret = vec![];
+        while let Ok(n) = tokens.number() {
+            ret.push(n);
+        }
+        ret
+    };
+    if let Some(&[x, y, width, height]) = attributes
+        .get(b"viewBox".as_slice())
+        .map(|v| numbers(v))
+        .as_deref()
+    {
+        return Some(((x, y).into(), (x + width, y + height).into()));
+    }
+    let length = |name: &[u8]| {
+        attributes
+            .get(name)
+            .filter(|v| !v.contains('%'))
+            .and_then(|v| numbers(v).first().copied())
+    };
+    Some((
+        Point::default(),
+        (length(b"width")?, length(b"height")?).into(),
+    ))
+}
+
+/// Options of [`import`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ImportOptions {
+    /// The height that the canvas is scaled to, in font units.
+    pub units_per_em: f64,
+    /// The height of the baseline above the bottom edge of the canvas, in font units.
+    pub baseline: f64,
+}
+
+/// Convert the paths of an SVG document to contours in font units.
+///
+/// The canvas is scaled to `units_per_em` and its bottom left corner is placed `baseline` units
+/// below the origin. Documents without a canvas size use the bounding box of their paths
+/// instead.
+pub fn import(
+    svg: &str,
+    options: ImportOptions,
+) -> Result<Vec<Contour>, Box<dyn std::error::Error>> {
+    let Document {
+        canvas,
+        mut subpaths,
+    } = parse_document(svg)?;
+    let Some((min, max)) = canvas.or_else(|| bbox(&subpaths)) else {
+        return Ok(vec![]);
+    };
+    let height = max.y - min.y;
+    if height <= 0.0 {
+        return Err("The SVG canvas has no height.".into());
+    }
+    let scale = options.units_per_em / height;
+    let m = Matrix::new(
+        scale,
+        0.0,
+        0.0,
+        -scale,
+        -min.x * scale,
+        max.y.mul_add(scale, -options.baseline),
+    );
+    for s in &mut subpaths {
+        s.transform(m);
+    }
+    Ok(subpaths.iter().map(Subpath::to_contour).collect())
+}
+
+/// New contours of the glyphs matched by [`import_folder`], and the files that could not be
+/// imported with their errors.
+pub type FolderImport = (ContourChanges, Vec<(std::path::PathBuf, String)>);
+
+/// Import the SVG files of `dir` for the glyphs of `glyphs` they are named after.
+///
+/// E.g. `a.svg` is imported for glyph `a`, as contours that replace its own. The glyphs are not
+/// changed.
+pub fn import_folder(
+    dir: &Path,
+    glyphs: &IndexMap<String, Rc<RefCell<Glyph>>>,
+    options: ImportOptions,
+) -> std::io::Result<FolderImport> {
+    let mut changes = vec![];
+    let mut failed = vec![];
+    let mut paths = std::fs::read_dir(dir)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<std::io::Result<Vec<_>>>()?;
+    paths.sort();
+    for path in paths {
+        if !path
+            .extension()
+            .is_some_and(|e| e.eq_ignore_ascii_case("svg"))
+        {
+            continue;
+        }
+        let Some(glyph) = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .and_then(|name| glyphs.get(name))
+        else {
+            continue;
+        };
+        match std::fs::read_to_string(&path)
+            .map_err(Into::into)
+            .and_then(|svg| import(&svg, options))
+        {
+            Ok(contours) => changes.push((glyph.clone(), contours)),
+            Err(err) => failed.push((path, err.to_string())),
+        }
+    }
+    Ok((changes, failed))
+}
+
+/// Render the SVG document at `path` to a glyph image of `width` by `height` pixels.
+///
+/// One pixel stands for one font unit. The document keeps its aspect ratio and is placed at the
+/// top left of the image, which is otherwise transparent.
+pub fn render_image(
+    path: &std::path::Path,
+    width: f64,
+    height: f64,
+) -> Result<gtk::gdk_pixbuf::Pixbuf, Box<dyn std::error::Error>> {
+    use gtk::gdk_pixbuf::{Colorspace, Pixbuf};
+
+    let (width, height) = (
+        width.round().max(1.0) as i32,
+        height.round().max(1.0) as i32,
+    );
+    let document = Pixbuf::from_file_at_scale(path, width, height, true)?;
+    let ret = Pixbuf::new(Colorspace::Rgb, true, 8, width, height)
+        .ok_or_else(|| format!("Could not allocate a {width}x{height} image."))?;
+    ret.fill(0);
+    document.copy_area(0, 0, document.width(), document.height(), &ret, 0, 0);
+    Ok(ret)
+}
+
+/// Ask for an SVG file, or a folder of SVG files if `folder` is true, and for the placement of
+/// the baseline, which defaults to the descender of `project`.
+pub fn import_dialog(
+    window: &gtk::Window,
+    project: &Project,
+    folder: bool,
+) -> Option<(std::path::PathBuf, ImportOptions)> {
+    let dialog = gtk::FileChooserDialog::builder()
+        .title(if folder {
+            "Import folder of SVG files by glyph name"
+        } else {
+            "Import SVG file"
+        })
+        .action(if folder {
+            gtk::FileChooserAction::SelectFolder
+        } else {
+            gtk::FileChooserAction::Open
+        })
+        .transient_for(window)
+        .modal(true)
+        .build();
+    dialog.add_button("Import", gtk::ResponseType::Ok);
+    dialog.add_button("Cancel", gtk::ResponseType::Cancel);
+    if !folder {
+        let filter = gtk::FileFilter::new();
+        filter.set_name(Some("SVG files"));
+        filter.add_pattern("*.svg");
+        dialog.add_filter(&filter);
+    }
+    let units_per_em = project.property::<f64>(Project::UNITS_PER_EM);
+    let baseline = gtk::SpinButton::with_range(-units_per_em, units_per_em, 1.0);
+    baseline.set_value(-project.property::<f64>(Project::DESCENDER));
+    let extra = gtk::Box::new(gtk::Orientation::Horizontal, 5);
+    extra.pack_start(
+        &gtk::Label::new(Some("Baseline above the bottom of the canvas:")),
+        false,
+        false,
+        0,
+    );
+    extra.pack_start(&baseline, false, false, 0);
+    extra.show_all();
+    dialog.set_extra_widget(&extra);
+    let ret = if dialog.run() == gtk::ResponseType::Ok {
+        dialog.filename().map(|path| {
+            (
+                path,
+                ImportOptions {
+                    units_per_em,
+                    baseline: baseline.value(),
+                },
+            )
+        })
+    } else {
+        None
+    };
+    dialog.emit_close();
+    ret
+}
+
+/// Format a coordinate without trailing zeros.
+fn number(v: f64) -> String {
+    let s = format!("{v:.3}");
+    let s = s.trim_end_matches('0').trim_end_matches('.');
+    if s == "-0" {
+        "0".to_string()
+    } else {
+        s.to_string()
+    }
+}
+
+/// The path data of `contours`, with y coordinates negated. Contours with curves of degree
+/// higher than three are left out.
+pub fn path_data(contours: &[Contour]) -> String {
+    let mut ret = String::new();
+    let point = |p: Point| format!("{} {}", number(p.x), number(-p.y));
+    for contour in contours {
+        let Ok(segments) = boolean::curve_segments(contour) else {
+            continue;
+        };
+        let Some(first) = segments.first() else {
+            continue;
+        };
+        if !ret.is_empty() {
+            ret.push(' ');
+        }
+        ret.push_str(&format!("M{}", point(first.start())));
+        for s in &segments {
+            ret.push_str(&match *s {
+                Segment::Line(_, p) => format!(" L{}", point(p)),
+                Segment::Quadratic(_, c, p) => format!(" Q{} {}", point(c), point(p)),
+                Segment::Cubic(_, c1, c2, p) => {
+                    format!(" C{} {} {}", point(c1), point(c2), point(p))
+                }
+            });
+        }
+        if !contour.property::<bool>(Contour::OPEN) {
+            ret.push_str(" Z");
+        }
+    }
+    ret
+}
+
+/// An SVG document with a single path of `contours`, sized to fit them.
+pub fn document(contours: &[Contour]) -> String {
+    let d = path_data(contours);
+    let subpaths = parse_path_data(&d).unwrap_or_default();
+    let (min, max) = bbox(&subpaths).unwrap_or_default();
+    let (width, height) = (number(max.x - min.x), number(max.y - min.y));
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" viewBox=\"{} {} {width} {height}\">\n<path d=\"{d}\"/>\n</svg>\n",
+        number(min.x),
+        number(min.y),
+    )
+}
+
+#[test]
+fn test_svg_path_data() {
+    let subpaths = parse_path_data(
+        "M10,20 h30v-10 L10 10z m5-5 c0 -10 10-10 10 0 s10 10 10 0 Q45 -15 50 -5 T60 -5",
+    )
+    .unwrap();
+    assert_eq!(subpaths.len(), 2);
+    assert!(subpaths[0].closed);
+    assert_eq!(
+        subpaths[0].segments,
+        vec![
+            Segment::Line((10.0, 20.0).into(), (40.0, 20.0).into()),
+            Segment::Line((40.0, 20.0).into(), (40.0, 10.0).into()),
+            Segment::Line((40.0, 10.0).into(), (10.0, 10.0).into()),
+            Segment::Line((10.0, 10.0).into(), (10.0, 20.0).into()),
+        ]
+    );
+    /* The relative move after closing starts from the start of the closed subpath. */
+    assert!(!subpaths[1].closed);
+    assert_eq!(
+        subpaths[1].segments,
+        vec![
+            Segment::Cubic(
+                (15.0, 15.0).into(),
+                (15.0, 5.0).into(),
+                (25.0, 5.0).into(),
+                (25.0, 15.0).into()
+            ),
+            Segment::Cubic(
+                (25.0, 15.0).into(),
+                (25.0, 25.0).into(),
+                (35.0, 25.0).into(),
+                (35.0, 15.0).into()
+            ),
+            Segment::Quadratic(
+                (35.0, 15.0).into(),
+                (45.0, -15.0).into(),
+                (50.0, -5.0).into()
+            ),
+            Segment::Quadratic((50.0, -5.0).into(), (55.0, 5.0).into(), (60.0, -5.0).into()),
+        ]
+    );
+    /* Numbers without separators. */
+    let subpaths = parse_path_data("M.5.5-1e1-2.5E+1").unwrap();
+    assert_eq!(
+        subpaths[0].segments,
+        vec![Segment::Line((0.5, 0.5).into(), (-10.0, -25.0).into())]
+    );
+    assert!(parse_path_data("L10 10").is_err());
+    assert!(parse_path_data("M0 0 L10").is_err());
+
+    /* Written path data reads back to the same outline. */
+    let contours = subpaths_contours("M0 0 L100 0 Q150 50 100 100 C50 150 0 50 0 0 Z");
+    let d = path_data(&contours);
+    assert_eq!(d, "M0 0 L100 0 Q150 -50 100 -100 C50 -150 0 -50 0 0 Z");
+    let mut again = parse_path_data(&d).unwrap();
+    again[0].transform(flip_y());
+    assert_eq!(
+        again,
+        parse_path_data("M0 0 L100 0 Q150 50 100 100 C50 150 0 50 0 0 Z").unwrap()
+    );
+
+    let svg = document(&contours);
+    assert_eq!(
+        parse_document(&svg).unwrap().subpaths,
+        parse_path_data(&d).unwrap()
+    );
+}
+
+#[test]
+fn test_svg_import() {
+    let close = |a: Point, b: Point| a.distance(b) < 1e-9;
+    /* A half circle through the point above its center, since SVG coordinates grow downwards. */
+    let subpaths = parse_path_data("M0 0 A10 10 0 0 1 20 0").unwrap();
+    let segments = &subpaths[0].segments;
+    assert_eq!(segments.len(), 2);
+    assert!(close(segments[0].end(), (10.0, -10.0).into()));
+    assert_eq!(segments[1].end(), (20.0, 0.0).into());
+    /* Radii too small to reach the end are enlarged, and flags need no separators. */
+    let subpaths = parse_path_data("M0 0a1 1 0 1020 0").unwrap();
+    let segments = &subpaths[0].segments;
+    assert_eq!(segments.len(), 2);
+    assert!(close(segments[0].end(), (10.0, 10.0).into()));
+    assert!(parse_path_data("M0 0 A10 10 0 2 1 20 0").is_err());
+
+    let m = parse_transform("translate(10, 20) scale(2)").unwrap();
+    assert_eq!(m.transform_point(1.0, 1.0), (12.0, 22.0));
+    let (x, y) = parse_transform("rotate(90 10 0)")
+        .unwrap()
+        .transform_point(20.0, 0.0);
+    assert!(close((x, y).into(), (10.0, 10.0).into()));
+    assert!(parse_transform("scale(1, 2, 3)").is_err());
+
+    let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 100 200">
+<defs><path d="M0 0 L1 1"/></defs>
+<g transform="translate(10 0)"><path transform="scale(2)" d="M0 0 H10 V10 Z"/></g>
+</svg>"#;
+    let document = parse_document(svg).unwrap();
+    assert_eq!(
+        document.canvas,
+        Some(((0.0, 0.0).into(), (100.0, 200.0).into()))
+    );
+    assert_eq!(document.subpaths.len(), 1);
+    assert_eq!(
+        document.subpaths[0].segments[0],
+        Segment::Line((10.0, 0.0).into(), (30.0, 0.0).into())
+    );
+    assert_eq!(
+        parse_document(r#"<svg width="50px" height="100"/>"#)
+            .unwrap()
+            .canvas,
+        Some((Point::default(), (50.0, 100.0).into()))
+    );
+    assert_eq!(
+        parse_document(r#"<svg width="100%" height="100%"/>"#)
+            .unwrap()
+            .canvas,
+        None
+    );
+
+    /* The canvas is scaled by 5 and its bottom edge is 200 units below the baseline. */
+    let contours = import(
+        svg,
+        ImportOptions {
+            units_per_em: 1000.0,
+            baseline: 200.0,
+        },
+    )
+    .unwrap();
+    assert_eq!(contours.len(), 1);
+    assert_eq!(
+        boolean::curve_segments(&contours[0]).unwrap()[0],
+        Segment::Line((50.0, 800.0).into(), (150.0, 800.0).into())
+    );
+}
+
+#[cfg(test)]
+fn subpaths_contours(d: &str) -> Vec<Contour> {
+    parse_path_data(d)
+        .unwrap()
+        .iter()
+        .map(Subpath::to_contour)
+        .collect()
+}
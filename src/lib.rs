@@ -60,10 +60,12 @@ extern crate glib;
 #[cfg(feature = "python")]
 pub mod api;
 pub mod app;
+pub mod designspace;
 pub mod editor;
 #[cfg(feature = "git")]
 pub mod git;
 pub mod glyphs;
+pub mod lint;
 pub mod project;
 pub mod resources;
 pub mod ufo;
@@ -96,6 +98,7 @@ pub mod prelude {
     pub use app::Application;
     pub use app::*;
     pub use app::{types::*, Settings};
+    pub use designspace::Designspace;
     pub use editor::*;
     pub use glyphs::obj::GlyphMetadata;
     pub use glyphs::{Continuity, Glyph, GlyphPointIndex, Guideline};
@@ -0,0 +1,430 @@
+/*
+ * gerb
+ *
+ * Copyright 2022 - Manos Pitsidianakis
+ *
+ * This file is part of gerb.
+ *
+ * gerb is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * gerb is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with gerb. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Font validation: checks of glyph outlines and of the glyph set for common problems.
+//!
+//! Outlines follow the PostScript convention of UFO, where outer contours run counter-clockwise
+//! and counters clockwise.
+
+use crate::glyphs::boolean;
+use crate::glyphs::{Contour, Glyph, GlyphPointIndex};
+use crate::prelude::*;
+use crate::utils::curves::Bezier;
+
+/// On-curve points closer than this, in units, overlap.
+const OVERLAP_DISTANCE: f64 = 1e-3;
+/// Segments whose control polygon is shorter than this, in units, are too small.
+const TINY_SEGMENT: f64 = 2.0;
+/// Extrema closer than this to an on-curve point, in units, are not missing.
+const EXTREMUM_DISTANCE: f64 = 1.0;
+/// Smooth points whose tangents differ by more than this, in degrees, have a kink.
+const KINK_ANGLE: f64 = 1.0;
+/// Points closer than this to a metric line, in units, were probably meant to be on it.
+const METRIC_DISTANCE: f64 = 3.0;
+
+/// The kind of problem a check looks for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Check {
+    OpenContour,
+    Direction,
+    MissingExtrema,
+    OverlappingPoints,
+    TinySegment,
+    Kink,
+    MissingComponentBase,
+    DuplicateUnicode,
+    OffMetric,
+}
+
+impl Check {
+    pub const ALL: [Self; 9] = [
+        Self::OpenContour,
+        Self::Direction,
+        Self::MissingExtrema,
+        Self::OverlappingPoints,
+        Self::TinySegment,
+        Self::Kink,
+        Self::MissingComponentBase,
+        Self::DuplicateUnicode,
+        Self::OffMetric,
+    ];
+}
+
+impl std::fmt::Display for Check {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            fmt,
+            "{}",
+            match self {
+                Self::OpenContour => "open contour",
+                Self::Direction => "wrong direction",
+                Self::MissingExtrema => "missing extrema",
+                Self::OverlappingPoints => "overlapping points",
+                Self::TinySegment => "tiny segment",
+                Self::Kink => "kink",
+                Self::MissingComponentBase => "missing component base",
+                Self::DuplicateUnicode => "duplicate unicode",
+                Self::OffMetric => "off metric",
+            }
+        )
+    }
+}
+
+/// A problem found in a glyph.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Problem {
+    pub check: Check,
+    pub glyph: String,
+    /// The point the problem is at, if it is about a single point.
+    pub point: Option<GlyphPointIndex>,
+    pub message: String,
+}
+
+impl std::fmt::Display for Problem {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(fmt, "{}: {}", self.glyph, self.message)
+    }
+}
+
+/// Vertical metrics points are expected to lie on, along with their names.
+#[derive(Debug, Clone, Default)]
+pub struct Metrics(pub Vec<(&'static str, f64)>);
+
+impl From<&Project> for Metrics {
+    fn from(project: &Project) -> Self {
+        Self(vec![
+            ("baseline", 0.0),
+            ("x-height", project.property::<f64>(Project::X_HEIGHT)),
+            ("cap height", project.property::<f64>(Project::CAP_HEIGHT)),
+            ("ascender", project.property::<f64>(Project::ASCENDER)),
+            ("descender", project.property::<f64>(Project::DESCENDER)),
+        ])
+    }
+}
+
+/// Index of the on-curve point at the start of curve `curve_index` of `contour`.
+fn point_index(contour: &Contour, contour_index: usize, curve_index: usize) -> GlyphPointIndex {
+    let uuid = contour.curves()[curve_index]
+        .points()
+        .first()
+        .map(|cp| cp.uuid)
+        .unwrap_or_default();
+    GlyphPointIndex {
+        contour_index,
+        curve_index,
+        uuid,
+    }
+}
+
+/// Check the outline of `glyph` on its own.
+pub fn check_glyph(glyph: &Glyph, metrics: &Metrics) -> Vec<Problem> {
+    let name = glyph.metadata.name().to_string();
+    let mut ret = vec![];
+    let mut problem = |check: Check, point: Option<GlyphPointIndex>, message: String| {
+        ret.push(Problem {
+            check,
+            glyph: name.clone(),
+            point,
+            message,
+        });
+    };
+    for (contour_index, contour) in glyph.contours.iter().enumerate() {
+        let Ok(segments) = boolean::curve_segments(contour) else {
+            continue;
+        };
+        if segments.is_empty() {
+            continue;
+        }
+        let open = contour.property::<bool>(Contour::OPEN);
+        let at = |curve_index: usize| Some(point_index(contour, contour_index, curve_index));
+        if open {
+            problem(
+                Check::OpenContour,
+                at(0),
+                format!("contour {contour_index} is open"),
+            );
+        }
+        let curves = contour.curves();
+        for (curve_index, (curve, segment)) in curves.iter().zip(segments.iter()).enumerate() {
+            let (start, end) = (segment.start(), segment.end());
+            let length = segment
+                .points()
+                .windows(2)
+                .map(|w| w[0].distance(w[1]))
+                .sum::<f64>();
+            if start.distance(end) < OVERLAP_DISTANCE {
+                problem(
+                    Check::OverlappingPoints,
+                    at(curve_index),
+                    format!("overlapping points at ({}, {})", start.x, start.y),
+                );
+            } else if length < TINY_SEGMENT {
+                problem(
+                    Check::TinySegment,
+                    at(curve_index),
+                    format!(
+                        "segment at ({}, {}) is {length:.2} units long",
+                        start.x, start.y
+                    ),
+                );
+            }
+            for t in curve.extrema() {
+                let p = curve.compute(t);
+                if p.distance(start) > EXTREMUM_DISTANCE && p.distance(end) > EXTREMUM_DISTANCE {
+                    problem(
+                        Check::MissingExtrema,
+                        at(curve_index),
+                        format!("missing point at extremum ({:.0}, {:.0})", p.x, p.y),
+                    );
+                }
+            }
+            /* The join at the start of the curve. */
+            let prev = if curve_index > 0 {
+                Some(&segments[curve_index - 1])
+            } else if open {
+                None
+            } else {
+                segments
+                    .last()
+                    .filter(|s| s.end().distance(start) < OVERLAP_DISTANCE)
+            };
+            if let Some(prev) = prev.filter(|_| curve.property::<bool>(Bezier::SMOOTH)) {
+                let (incoming, outgoing) = (prev.end_tangent(), segment.start_tangent());
+                if incoming.norm() > 0.0 && outgoing.norm() > 0.0 {
+                    let angle = boolean::cross(incoming, outgoing)
+                        .atan2(incoming.dot(outgoing))
+                        .abs()
+                        .to_degrees();
+                    if angle > KINK_ANGLE {
+                        problem(
+                            Check::Kink,
+                            at(curve_index),
+                            format!(
+                                "kink of {angle:.1}° at smooth point ({}, {})",
+                                start.x, start.y
+                            ),
+                        );
+                    }
+                }
+            }
+        }
+        /* On-curve points, with the end of open contours. */
+        let mut on_curve = segments
+            .iter()
+            .enumerate()
+            .map(|(i, s)| (i, s.start()))
+            .collect::<Vec<(usize, Point)>>();
+        if open {
+            on_curve.push((segments.len() - 1, segments[segments.len() - 1].end()));
+        }
+        for (curve_index, p) in on_curve {
+            let closest = metrics
+                .0
+                .iter()
+                .map(|&(name, y)| (name, p.y - y))
+                .filter(|(_, d)| *d != 0.0 && d.abs() <= METRIC_DISTANCE)
+                .min_by(|a, b| a.1.abs().total_cmp(&b.1.abs()));
+            if let Some((metric, d)) = closest {
+                problem(
+                    Check::OffMetric,
+                    at(curve_index),
+                    format!(
+                        "point ({}, {}) is {} units {} the {metric}",
+                        p.x,
+                        p.y,
+                        d.abs(),
+                        if d > 0.0 { "above" } else { "below" }
+                    ),
+                );
+            }
+        }
+    }
+    for contour_index in glyph.wrong_directions(ContourDirection::PostScript) {
+        problem(
+            Check::Direction,
+            Some(point_index(
+                &glyph.contours[contour_index],
+                contour_index,
+                0,
+            )),
+            format!("contour {contour_index} runs the wrong way"),
+        );
+    }
+    ret
+}
+
+/// Check every glyph of `glyphs` along with problems across glyphs, such as components of
+/// missing glyphs and unicode values assigned to more than one glyph.
+pub fn check_glyphs(
+    glyphs: &IndexMap<String, Rc<RefCell<Glyph>>>,
+    metrics: &Metrics,
+) -> Vec<Problem> {
+    let mut ret = vec![];
+    let mut unicodes: IndexMap<String, Vec<String>> = IndexMap::default();
+    for (name, glyph) in glyphs.iter() {
+        let glyph = glyph.borrow();
+        ret.extend(check_glyph(&glyph, metrics));
+        for (i, component) in glyph.components.iter().enumerate() {
+            if !glyphs.contains_key(&component.base_name) {
+                ret.push(Problem {
+                    check: Check::MissingComponentBase,
+                    glyph: name.clone(),
+                    point: Some(component.glyph_index(i)),
+                    message: format!("component of missing glyph {}", component.base_name),
+                });
+            }
+        }
+        for unicode in glyph.metadata.unicode.borrow().iter() {
+            unicodes
+                .entry(unicode.hex().to_uppercase())
+                .or_default()
+                .push(name.clone());
+        }
+    }
+    for (hex, names) in unicodes.iter().filter(|(_, names)| names.len() > 1) {
+        for name in names {
+            ret.push(Problem {
+                check: Check::DuplicateUnicode,
+                glyph: name.clone(),
+                point: None,
+                message: format!(
+                    "U+{hex} is also assigned to {}",
+                    names
+                        .iter()
+                        .filter(|n| *n != name)
+                        .map(String::as_str)
+                        .collect::<Vec<&str>>()
+                        .join(", ")
+                ),
+            });
+        }
+    }
+    ret
+}
+
+/// Check the glyphs of the default layer of `project`.
+pub fn check_project(project: &Project) -> Vec<Problem> {
+    check_glyphs(&project.default_layer.glyphs(), &Metrics::from(project))
+}
+
+#[test]
+fn test_lint_checks() {
+    use crate::ufo::glif::Unicode;
+
+    let rectangle = |(x0, y0): (f64, f64), (x1, y1): (f64, f64)| {
+        let contour = Contour::new();
+        for (a, b) in [
+            ((x0, y0), (x1, y0)),
+            ((x1, y0), (x1, y1)),
+            ((x1, y1), (x0, y1)),
+            ((x0, y1), (x0, y0)),
+        ] {
+            contour.push_curve(Bezier::new(vec![a.into(), b.into()]));
+        }
+        contour.close();
+        contour
+    };
+    let metrics = Metrics(vec![("baseline", 0.0), ("x-height", 450.0)]);
+    let checks = |glyph: &Glyph| {
+        check_glyph(glyph, &metrics)
+            .into_iter()
+            .map(|p| p.check)
+            .collect::<Vec<Check>>()
+    };
+
+    /* A counter-clockwise outer contour with a clockwise counter is correct. */
+    let mut glyph = Glyph::new_empty("o".into(), 'o');
+    glyph.contours.push(rectangle((0.0, 0.0), (400.0, 450.0)));
+    let counter = rectangle((100.0, 100.0), (300.0, 350.0));
+    counter.reverse_direction();
+    glyph.contours.push(counter);
+    assert_eq!(checks(&glyph), vec![]);
+    glyph.contours[1].reverse_direction();
+    let problems = check_glyph(&glyph, &metrics);
+    assert_eq!(problems.len(), 1);
+    assert_eq!(problems[0].check, Check::Direction);
+    assert_eq!(problems[0].point.map(|p| p.contour_index), Some(1));
+
+    /* Points just off the metrics, and a tiny segment. */
+    let mut glyph = Glyph::new_empty("x".into(), 'x');
+    glyph.contours.push(rectangle((0.0, -1.0), (400.0, 451.0)));
+    assert_eq!(checks(&glyph), vec![Check::OffMetric; 4]);
+    let mut glyph = Glyph::new_empty("period".into(), '.');
+    glyph
+        .contours
+        .push(rectangle((100.0, 100.0), (101.0, 200.0)));
+    assert_eq!(checks(&glyph), vec![Check::TinySegment; 2]);
+
+    /* An open curve without points at its extremum, with a kink at a smooth point. */
+    let contour = Contour::new();
+    contour.push_curve(Bezier::new(vec![
+        (0.0, 100.0).into(),
+        (0.0, 300.0).into(),
+        (200.0, 300.0).into(),
+        (200.0, 100.0).into(),
+    ]));
+    let curve = Bezier::new(vec![(200.0, 100.0).into(), (300.0, 100.0).into()]);
+    curve.set_property(Bezier::SMOOTH, true);
+    contour.push_curve(curve);
+    let mut glyph = Glyph::new_empty("n".into(), 'n');
+    glyph.contours.push(contour);
+    assert_eq!(
+        checks(&glyph),
+        vec![Check::OpenContour, Check::MissingExtrema, Check::Kink]
+    );
+
+    /* Problems across glyphs. */
+    let a = Glyph::new_empty("a".into(), 'a');
+    a.metadata
+        .unicode
+        .borrow_mut()
+        .push(Unicode::new("0061".into()));
+    let mut aacute = Glyph::new_empty("aacute".into(), 'á');
+    aacute
+        .metadata
+        .unicode
+        .borrow_mut()
+        .push(Unicode::new("0061".into()));
+    aacute.components.push(crate::glyphs::Component::new(
+        "acutecomb".into(),
+        std::rc::Weak::new(),
+    ));
+    let glyphs = [a, aacute]
+        .into_iter()
+        .map(|g| {
+            let name = g.metadata.name().to_string();
+            (name, Rc::new(RefCell::new(g)))
+        })
+        .collect::<IndexMap<String, Rc<RefCell<Glyph>>>>();
+    let mut problems = check_glyphs(&glyphs, &metrics)
+        .into_iter()
+        .map(|p| (p.check, p.glyph))
+        .collect::<Vec<(Check, String)>>();
+    problems.sort();
+    assert_eq!(
+        problems,
+        vec![
+            (Check::MissingComponentBase, "aacute".to_string()),
+            (Check::DuplicateUnicode, "a".to_string()),
+            (Check::DuplicateUnicode, "aacute".to_string()),
+        ]
+    );
+}
@@ -32,6 +32,21 @@ use crate::prelude::*;
 // An idea is to keep a counter of single modifications, and decrease it when the user performs an
 // undo action.
 
+/// Problems found while loading a project, shown once its tabs are open.
+#[derive(Debug, Default)]
+pub struct LoadReport {
+    /// Errors of glyphs that could not be loaded, in all layers.
+    pub errors: Vec<ufo::glif::GlifError>,
+    /// Data that was loaded, but not exactly as it is on disk.
+    pub warnings: Vec<String>,
+}
+
+impl LoadReport {
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty() && self.warnings.is_empty()
+    }
+}
+
 #[derive(Debug)]
 pub struct ProjectInner {
     name: RefCell<String>,
@@ -69,9 +84,13 @@ pub struct ProjectInner {
     pub fontinfo: RefCell<ufo::objects::FontInfo>,
     pub metainfo: RefCell<ufo::MetaInfo>,
     pub layercontents: RefCell<ufo::LayerContents>,
+    pub kerning: RefCell<ufo::Kerning>,
+    pub groups: RefCell<ufo::Groups>,
+    pub features: RefCell<ufo::features::Features>,
     pub default_layer: ufo::objects::Layer,
     pub background_layer: RefCell<Option<ufo::objects::Layer>>,
     pub all_layers: RefCell<Vec<ufo::objects::Layer>>,
+    pub load_report: RefCell<LoadReport>,
     #[cfg(feature = "git")]
     pub repository: RefCell<Result<Option<git::Repository>, Box<dyn std::error::Error>>>,
 }
@@ -104,9 +123,13 @@ impl Default for ProjectInner {
             fontinfo: RefCell::new(ufo::objects::FontInfo::new()),
             metainfo: RefCell::new(ufo::MetaInfo::default()),
             layercontents: RefCell::new(ufo::LayerContents::default()),
+            kerning: RefCell::new(ufo::Kerning::default()),
+            groups: RefCell::new(ufo::Groups::default()),
+            features: RefCell::new(ufo::features::Features::default()),
             default_layer: ufo::objects::Layer::new(),
             background_layer: RefCell::new(None),
             all_layers: RefCell::new(vec![]),
+            load_report: RefCell::new(LoadReport::default()),
             #[cfg(feature = "git")]
             repository: RefCell::new(Ok(None)),
         }
@@ -346,6 +369,25 @@ impl Project {
             )
         })?;
 
+        path.pop();
+        path.push("groups.plist");
+        let groups = ufo::Groups::from_path(&path)
+            .map_err(|err| format!("couldn't read groups.plist {}:\n\n{}", path.display(), err))?;
+        path.pop();
+        path.push("kerning.plist");
+        let kerning = ufo::Kerning::from_path(&path)
+            .map_err(|err| format!("couldn't read kerning.plist {}:\n\n{}", path.display(), err))?;
+        path.pop();
+        path.push("features.fea");
+        let features = ufo::features::Features::from_path(&path)
+            .map_err(|err| format!("couldn't read features.fea {}:\n\n{}", path.display(), err))?;
+        let features_warning = features.lossy().then(|| {
+            format!(
+                "{}: not valid UTF-8, invalid bytes were replaced with U+FFFD.",
+                path.display()
+            )
+        });
+
         path.pop();
         path.push("layercontents.plist");
         let layercontents = ufo::LayerContents::from_path(&path, ret.default_layer.clone(), false)
@@ -358,6 +400,19 @@ impl Project {
         for obj in all_layers.iter() {
             ret.link(obj);
         }
+        {
+            let mut load_report = ret.load_report.borrow_mut();
+            load_report.errors = all_layers
+                .iter()
+                .flat_map(|l| l.load_errors.borrow().clone())
+                .collect();
+            load_report.warnings = all_layers
+                .iter()
+                .flat_map(|l| l.load_warnings.borrow().clone())
+                .map(|w| format!("{w}, skipped"))
+                .chain(features_warning)
+                .collect();
+        }
         *ret.all_layers.borrow_mut() = all_layers;
         *ret.layercontents.borrow_mut() = layercontents;
         path.pop();
@@ -423,6 +478,9 @@ impl Project {
         ret.link(&fontinfo);
         *ret.fontinfo.borrow_mut() = fontinfo;
         *ret.metainfo.borrow_mut() = metainfo;
+        *ret.groups.borrow_mut() = groups;
+        *ret.kerning.borrow_mut() = kerning;
+        *ret.features.borrow_mut() = features;
         {
             let mut metric_guidelines = ret.metric_guidelines.borrow_mut();
             for (name, field) in [
@@ -458,6 +516,20 @@ impl Project {
         Ok(cairo::ImageSurface::create_from_png(&mut bytes.as_ref())?)
     }
 
+    /// Write `pixbuf` as a PNG file to the `images` directory, where glyph image references
+    /// point to.
+    pub fn save_image(
+        &self,
+        file_name: &str,
+        pixbuf: &gtk::gdk_pixbuf::Pixbuf,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let images = self.path.borrow().join("images");
+        std::fs::create_dir_all(&images)
+            .map_err(|err| format!("Could not create {}: {err}", images.display()))?;
+        pixbuf.savev(images.join(file_name), "png", &[])?;
+        Ok(())
+    }
+
     pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
         let fontinfo = self.fontinfo.borrow();
         {
@@ -482,6 +554,21 @@ impl Project {
         for obj in self.all_layers.borrow().iter().filter(|obj| obj.modified()) {
             obj.save(&mut self.layercontents.borrow_mut())?;
         }
+        {
+            let prefix = self.path.borrow();
+            self.groups
+                .borrow_mut()
+                .save(&prefix.join("groups.plist"))
+                .map_err(|err| format!("Could not save groups.plist:\n\n{err}"))?;
+            self.kerning
+                .borrow_mut()
+                .save(&prefix.join("kerning.plist"))
+                .map_err(|err| format!("Could not save kerning.plist:\n\n{err}"))?;
+            self.features
+                .borrow_mut()
+                .save(&prefix.join("features.fea"))
+                .map_err(|err| format!("Could not save features.fea:\n\n{err}"))?;
+        }
         /* Metric guidelines have their value properties bound with FontInfo via Project
          * [ref:project_bind_metrics] */
         for g in self
@@ -545,6 +632,66 @@ impl Project {
         let layer = layer.unwrap_or(&self.default_layer);
         layer.new_glyph(name, glyph)
     }
+
+    /// Rename glyph `old` to `new` in every layer that contains it, and update references to it
+    /// in groups and kerning pairs.
+    pub fn rename_glyph(&self, old: &str, new: &str) -> Result<(), Box<dyn std::error::Error>> {
+        if old == new {
+            return Ok(());
+        }
+        let layers = self.all_layers.borrow();
+        if let Some(layer) = layers.iter().find(|l| l.glyphs().contains_key(new)) {
+            return Err(format!(
+                "Glyph `{new}` already exists in layer {}.",
+                layer.name.borrow()
+            )
+            .into());
+        }
+        if !self.kerning.borrow().can_rename(old, new) {
+            return Err(format!(
+                "Kerning pairs of `{old}` would replace existing kerning pairs of `{new}`."
+            )
+            .into());
+        }
+        for layer in layers.iter().filter(|l| l.glyphs().contains_key(old)) {
+            layer.rename_glyph(old, new)?;
+        }
+        self.groups.borrow_mut().rename_glyph(old, new);
+        self.kerning.borrow_mut().rename(old, new);
+        self.set_property(Self::MODIFIED, true);
+        Ok(())
+    }
+
+    /// Delete glyph `name` from every layer, and remove it from groups and kerning pairs.
+    pub fn delete_glyph(&self, name: &str) {
+        for layer in self.all_layers.borrow().iter() {
+            layer.delete_glyph(name);
+        }
+        self.groups.borrow_mut().remove_glyph(name);
+        self.kerning.borrow_mut().remove_all(name);
+        self.set_property(Self::MODIFIED, true);
+    }
+
+    /// Rename group `old` to `new` and update references to it in kerning pairs. Returns `false`
+    /// without changing anything if group `new` exists or renamed kerning pairs would collide.
+    pub fn rename_group(&self, old: &str, new: &str) -> bool {
+        if !self.kerning.borrow().can_rename(old, new)
+            || !self.groups.borrow_mut().rename_group(old, new)
+        {
+            return false;
+        }
+        self.kerning.borrow_mut().rename(old, new);
+        self.set_property(Self::MODIFIED, true);
+        true
+    }
+
+    /// Delete group `name` and every kerning pair that refers to it.
+    pub fn delete_group(&self, name: &str) -> Option<Vec<String>> {
+        let retval = self.groups.borrow_mut().remove(name)?;
+        self.kerning.borrow_mut().remove_all(name);
+        self.set_property(Self::MODIFIED, true);
+        Some(retval)
+    }
 }
 
 impl Default for Project {
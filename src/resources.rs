@@ -111,6 +111,9 @@ pub mod icons {
     decl_icon! {BSPLINE_ICON, "./resources/b-spline-icon"}
     decl_icon! {RECTANGLE_ICON, "./resources/rectangle-icon"}
     decl_icon! {ELLIPSE_ICON, "./resources/ellipse-icon"}
+    decl_icon! {KNIFE_ICON, "./resources/knife-icon"}
+    decl_icon! {MEASURE_ICON, "./resources/measure-icon"}
+    decl_icon! {TRANSFORM_ICON, "./resources/transform-icon"}
     decl_icon! {CHECKBOX_ICON, "./resources/icons/checkbox"}
     decl_icon! {CHECKBOX_CHECKED_ICON, "./resources/icons/checkbox-checked"}
     decl_icon! {RIGHT_MOUSE_BUTTON, "./resources/icons/right_mouse_button"}
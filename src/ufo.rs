@@ -19,12 +19,16 @@
  * along with gerb. If not, see <http://www.gnu.org/licenses/>.
  */
 
-#[cfg(feature = "python")]
+#[cfg(any(feature = "python", feature = "native-export"))]
 pub mod export;
 #[cfg(feature = "python")]
 pub mod import;
+#[cfg(feature = "native-export")]
+pub mod sfnt;
 
 pub mod constants;
+pub mod designspace;
+pub mod features;
 pub mod glif;
 pub mod objects;
 
@@ -576,6 +580,19 @@ impl Contents {
         self.glyphs.insert(name, filename);
         self.modified = true;
     }
+
+    pub fn remove(&mut self, name: &str) -> Option<String> {
+        let retval = self.glyphs.shift_remove(name);
+        self.modified |= retval.is_some();
+        retval
+    }
+
+    /// Rename glyph entry `old` to `new` keeping its filename.
+    pub fn rename(&mut self, old: &str, new: &str) -> bool {
+        let retval = rename_key(&mut self.glyphs, old, new);
+        self.modified |= retval;
+        retval
+    }
 }
 
 /// metainfo.plist
@@ -895,6 +912,358 @@ impl Lib {
     }
 }
 
+/// Prefix of first-side kerning group names.
+pub const KERN1_PREFIX: &str = "public.kern1.";
+/// Prefix of second-side kerning group names.
+pub const KERN2_PREFIX: &str = "public.kern2.";
+
+/// groups.plist
+///
+/// > This file contains the group definitions for the font. This file is optional.
+///
+/// Groups are a mapping of group names to lists of glyph names. Groups with names starting with
+/// [`KERN1_PREFIX`] or [`KERN2_PREFIX`] are kerning groups and can be referenced in
+/// `kerning.plist` pairs.
+///
+/// # Specification
+///
+/// <https://unifiedfontobject.org/versions/ufo3/groups.plist/>
+#[derive(Default, PartialEq, Eq, Clone, Debug, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Groups {
+    pub groups: IndexMap<String, Vec<String>>,
+    #[serde(skip)]
+    modified: bool,
+}
+
+impl Groups {
+    pub fn from_path(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        if !path.exists() {
+            // This file is optional.
+            return Ok(Self::default());
+        }
+        let retval: Self = plist::from_file(path)?;
+        Ok(retval)
+    }
+
+    pub fn new_from_str(xml: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let retval: Self = plist::from_reader_xml(std::io::Cursor::new(xml))?;
+        Ok(retval)
+    }
+
+    /// Write to `destination` if modified. If there are no groups left, the file is removed
+    /// instead since it is optional.
+    pub fn save(&mut self, destination: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        if !self.modified {
+            return Ok(());
+        }
+        if self.groups.is_empty() {
+            if destination.exists() {
+                std::fs::remove_file(destination)?;
+            }
+            self.modified = false;
+            return Ok(());
+        }
+        #[allow(deprecated)]
+        let opts = plist::XmlWriteOptions::default()
+            .indent_string("    ")
+            .root_element(true);
+
+        let file = OpenOptions::new()
+            .read(false)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(destination)?;
+        plist::to_writer_xml_with_options(file, self, &opts)?;
+        self.modified = false;
+        Ok(())
+    }
+
+    pub fn modified(&self) -> bool {
+        self.modified
+    }
+
+    pub fn get(&self, name: &str) -> Option<&[String]> {
+        self.groups.get(name).map(Vec::as_slice)
+    }
+
+    pub fn insert(&mut self, name: String, members: Vec<String>) {
+        self.groups.insert(name, members);
+        self.modified = true;
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<Vec<String>> {
+        let retval = self.groups.shift_remove(name);
+        self.modified |= retval.is_some();
+        retval
+    }
+
+    /// Returns the name of the first-side (`first == true`) or second-side kerning group
+    /// `glyph_name` belongs to, if any.
+    pub fn kerning_group_of(&self, glyph_name: &str, first: bool) -> Option<&str> {
+        let prefix = if first { KERN1_PREFIX } else { KERN2_PREFIX };
+        self.groups
+            .iter()
+            .find(|(name, members)| {
+                name.starts_with(prefix) && members.iter().any(|m| m == glyph_name)
+            })
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// Replace every occurence of `old` glyph name with `new` in group members.
+    pub fn rename_glyph(&mut self, old: &str, new: &str) {
+        for members in self.groups.values_mut() {
+            for m in members.iter_mut().filter(|m| m.as_str() == old) {
+                *m = new.to_string();
+                self.modified = true;
+            }
+        }
+    }
+
+    /// Remove `glyph_name` from all group members.
+    pub fn remove_glyph(&mut self, glyph_name: &str) {
+        for members in self.groups.values_mut() {
+            let len = members.len();
+            members.retain(|m| m != glyph_name);
+            self.modified |= len != members.len();
+        }
+    }
+
+    /// Rename group `old` to `new`, preserving its position.
+    pub fn rename_group(&mut self, old: &str, new: &str) -> bool {
+        let retval = rename_key(&mut self.groups, old, new);
+        self.modified |= retval;
+        retval
+    }
+}
+
+/// Rename key `old` of `map` to `new` in place, preserving its position. Returns `false`
+/// without changing `map` if `old` does not exist or `new` already does, so that the data of
+/// `new` is not overwritten.
+fn rename_key<V>(map: &mut IndexMap<String, V>, old: &str, new: &str) -> bool {
+    if !map.contains_key(old) || map.contains_key(new) {
+        return false;
+    }
+    *map = std::mem::take(map)
+        .into_iter()
+        .map(|(k, v)| {
+            if k == old {
+                (new.to_string(), v)
+            } else {
+                (k, v)
+            }
+        })
+        .collect();
+    true
+}
+
+/// kerning.plist
+///
+/// > This file contains horizontal kerning pairs. This file is optional.
+///
+/// Pairs are stored as a mapping of first sides to a mapping of second sides to kerning values.
+/// Each side is either a glyph name or a kerning group name from [`Groups`].
+///
+/// # Specification
+///
+/// <https://unifiedfontobject.org/versions/ufo3/kerning.plist/>
+#[derive(Default, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Kerning {
+    #[serde(serialize_with = "ser_kerning")]
+    pub pairs: IndexMap<String, IndexMap<String, f64>>,
+    #[serde(skip)]
+    modified: bool,
+}
+
+/// Write integral values as `<integer>` like other UFO tools do, to keep diffs minimal.
+fn ser_kerning<S>(
+    s: &IndexMap<String, IndexMap<String, f64>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    use serde::ser::SerializeMap;
+
+    struct Values<'a>(&'a IndexMap<String, f64>);
+
+    impl Serialize for Values<'_> {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: serde::Serializer,
+        {
+            let mut map = serializer.serialize_map(Some(self.0.len()))?;
+            for (k, v) in self.0 {
+                if v.fract() == 0.0 && v.abs() < i64::MAX as f64 {
+                    map.serialize_entry(k, &(*v as i64))?;
+                } else {
+                    map.serialize_entry(k, v)?;
+                }
+            }
+            map.end()
+        }
+    }
+
+    let mut map = serializer.serialize_map(Some(s.len()))?;
+    for (k, v) in s {
+        map.serialize_entry(k, &Values(v))?;
+    }
+    map.end()
+}
+
+impl Kerning {
+    pub fn from_path(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        if !path.exists() {
+            // This file is optional.
+            return Ok(Self::default());
+        }
+        let retval: Self = plist::from_file(path)?;
+        Ok(retval)
+    }
+
+    pub fn new_from_str(xml: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let retval: Self = plist::from_reader_xml(std::io::Cursor::new(xml))?;
+        Ok(retval)
+    }
+
+    /// Write to `destination` if modified. If there are no pairs left, the file is removed
+    /// instead since it is optional.
+    pub fn save(&mut self, destination: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        if !self.modified {
+            return Ok(());
+        }
+        if self.pairs.is_empty() {
+            if destination.exists() {
+                std::fs::remove_file(destination)?;
+            }
+            self.modified = false;
+            return Ok(());
+        }
+        #[allow(deprecated)]
+        let opts = plist::XmlWriteOptions::default()
+            .indent_string("    ")
+            .root_element(true);
+
+        let file = OpenOptions::new()
+            .read(false)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(destination)?;
+        plist::to_writer_xml_with_options(file, self, &opts)?;
+        self.modified = false;
+        Ok(())
+    }
+
+    pub fn modified(&self) -> bool {
+        self.modified
+    }
+
+    pub fn get(&self, first: &str, second: &str) -> Option<f64> {
+        self.pairs.get(first)?.get(second).copied()
+    }
+
+    /// Kerning value applied between glyphs `first` and `second`, taking kerning groups into
+    /// account. Glyph pairs take precedence over glyph-group and group-glyph pairs, which take
+    /// precedence over group pairs.
+    pub fn lookup(&self, groups: &Groups, first: &str, second: &str) -> Option<f64> {
+        let first_group = groups.kerning_group_of(first, true);
+        let second_group = groups.kerning_group_of(second, false);
+        self.get(first, second)
+            .or_else(|| self.get(first, second_group?))
+            .or_else(|| self.get(first_group?, second))
+            .or_else(|| self.get(first_group?, second_group?))
+    }
+
+    /// Iterate over all `(first, second, value)` pairs.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str, f64)> + '_ {
+        self.pairs.iter().flat_map(|(first, seconds)| {
+            seconds
+                .iter()
+                .map(move |(second, v)| (first.as_str(), second.as_str(), *v))
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.pairs.values().map(IndexMap::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn set(&mut self, first: &str, second: &str, value: f64) {
+        self.pairs
+            .entry(first.to_string())
+            .or_default()
+            .insert(second.to_string(), value);
+        self.modified = true;
+    }
+
+    pub fn remove(&mut self, first: &str, second: &str) -> Option<f64> {
+        let seconds = self.pairs.get_mut(first)?;
+        let retval = seconds.shift_remove(second);
+        if seconds.is_empty() {
+            self.pairs.shift_remove(first);
+        }
+        self.modified |= retval.is_some();
+        retval
+    }
+
+    /// Whether [`Kerning::rename`] can rename `old` to `new`, i.e. no renamed pair would
+    /// collide with a pair that already exists.
+    pub fn can_rename(&self, old: &str, new: &str) -> bool {
+        let rename = |name: &str| if name == old { new } else { name }.to_string();
+        old == new
+            || !self
+                .iter()
+                .filter(|&(first, second, _)| first == old || second == old)
+                .any(|(first, second, _)| self.get(&rename(first), &rename(second)).is_some())
+    }
+
+    /// Rename either side of every pair that refers to `old`, which can be a glyph or a group
+    /// name. Returns `false` without changing any pair if a renamed pair would collide with an
+    /// existing one, since merging them would overwrite its value.
+    pub fn rename(&mut self, old: &str, new: &str) -> bool {
+        if !self.can_rename(old, new) {
+            return false;
+        }
+        if old == new
+            || !self
+                .iter()
+                .any(|(first, second, _)| first == old || second == old)
+        {
+            return true;
+        }
+        let rename = |name: String| if name == old { new.to_string() } else { name };
+        let mut pairs: IndexMap<String, IndexMap<String, f64>> = IndexMap::default();
+        for (first, seconds) in std::mem::take(&mut self.pairs) {
+            let entry = pairs.entry(rename(first)).or_default();
+            for (second, value) in seconds {
+                entry.insert(rename(second), value);
+            }
+        }
+        self.pairs = pairs;
+        self.modified = true;
+        true
+    }
+
+    /// Remove every pair that refers to `name`, which can be a glyph or a group name.
+    pub fn remove_all(&mut self, name: &str) {
+        if self.pairs.shift_remove(name).is_some() {
+            self.modified = true;
+        }
+        for seconds in self.pairs.values_mut() {
+            if seconds.shift_remove(name).is_some() {
+                self.modified = true;
+            }
+        }
+        self.pairs.retain(|_, seconds| !seconds.is_empty());
+    }
+}
+
 #[test]
 fn test_fontinfo_plist_parse() {
     let p: FontInfo = FontInfo::new_from_str(PLIST).unwrap();
@@ -1230,6 +1599,178 @@ fn test_lib_plist_parse() {
     );
 }
 
+#[test]
+fn test_groups_plist_parse() {
+    let mut g: Groups = Groups::new_from_str(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple Computer//DTD PLIST 1.0//EN"
+"http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+  <key>public.kern1.O</key>
+  <array>
+    <string>O</string>
+    <string>D</string>
+    <string>Q</string>
+  </array>
+  <key>public.kern2.O</key>
+  <array>
+    <string>O</string>
+    <string>C</string>
+  </array>
+  <key>vowels</key>
+  <array>
+    <string>A</string>
+    <string>O</string>
+  </array>
+</dict>
+</plist>
+"#,
+    )
+    .unwrap();
+    assert_eq!(
+        &g.groups.keys().cloned().collect::<Vec<String>>(),
+        &["public.kern1.O", "public.kern2.O", "vowels"]
+    );
+    assert_eq!(g.get("public.kern1.O").unwrap(), &["O", "D", "Q"]);
+    assert_eq!(g.kerning_group_of("D", true), Some("public.kern1.O"));
+    assert_eq!(g.kerning_group_of("D", false), None);
+    assert_eq!(g.kerning_group_of("C", false), Some("public.kern2.O"));
+    assert!(!g.modified());
+
+    #[allow(deprecated)]
+    let opts = plist::XmlWriteOptions::default()
+        .indent_string("  ")
+        .root_element(true);
+    let mut s = vec![];
+    plist::to_writer_xml_with_options(std::io::Cursor::new(&mut s), &g, &opts).unwrap();
+    let g2: Groups = Groups::new_from_str(&String::from_utf8(s).unwrap()).unwrap();
+    assert_eq!(g, g2);
+
+    g.rename_glyph("O", "O.alt");
+    assert!(g.modified());
+    assert_eq!(g.get("public.kern1.O").unwrap(), &["O.alt", "D", "Q"]);
+    assert_eq!(g.get("vowels").unwrap(), &["A", "O.alt"]);
+    g.remove_glyph("O.alt");
+    assert_eq!(g.get("public.kern2.O").unwrap(), &["C"]);
+    assert!(g.rename_group("public.kern1.O", "public.kern1.round"));
+    assert!(!g.rename_group("public.kern1.O", "public.kern1.round"));
+    /* Renaming onto an existing group keeps both groups as they are. */
+    assert!(!g.rename_group("public.kern1.round", "vowels"));
+    assert_eq!(g.get("public.kern1.round").unwrap(), &["D", "Q"]);
+    assert_eq!(g.get("vowels").unwrap(), &["A"]);
+    assert_eq!(
+        &g.groups.keys().cloned().collect::<Vec<String>>(),
+        &["public.kern1.round", "public.kern2.O", "vowels"]
+    );
+}
+
+#[test]
+fn test_kerning_plist_parse() {
+    let mut k: Kerning = Kerning::new_from_str(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple Computer//DTD PLIST 1.0//EN"
+"http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+  <key>A</key>
+  <dict>
+    <key>V</key>
+    <integer>-80</integer>
+    <key>public.kern2.O</key>
+    <real>-12.5</real>
+  </dict>
+  <key>public.kern1.O</key>
+  <dict>
+    <key>A</key>
+    <integer>-20</integer>
+  </dict>
+</dict>
+</plist>
+"#,
+    )
+    .unwrap();
+    assert_eq!(k.len(), 3);
+    assert_eq!(k.get("A", "V"), Some(-80.0));
+    assert_eq!(k.get("A", "public.kern2.O"), Some(-12.5));
+    assert_eq!(k.get("public.kern1.O", "A"), Some(-20.0));
+    assert_eq!(k.get("V", "A"), None);
+    assert_eq!(
+        k.iter().collect::<Vec<_>>(),
+        vec![
+            ("A", "V", -80.0),
+            ("A", "public.kern2.O", -12.5),
+            ("public.kern1.O", "A", -20.0),
+        ]
+    );
+    assert!(!k.modified());
+
+    #[allow(deprecated)]
+    let opts = plist::XmlWriteOptions::default()
+        .indent_string("  ")
+        .root_element(true);
+    let mut s = vec![];
+    plist::to_writer_xml_with_options(std::io::Cursor::new(&mut s), &k, &opts).unwrap();
+    let s = String::from_utf8(s).unwrap();
+    assert!(s.contains("<integer>-80</integer>"));
+    assert!(s.contains("<real>-12.5</real>"));
+    let k2: Kerning = Kerning::new_from_str(&s).unwrap();
+    assert_eq!(k, k2);
+
+    let groups = Groups::new_from_str(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple Computer//DTD PLIST 1.0//EN"
+"http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+  <key>public.kern1.O</key>
+  <array>
+    <string>O</string>
+    <string>D</string>
+  </array>
+  <key>public.kern2.O</key>
+  <array>
+    <string>O</string>
+    <string>C</string>
+  </array>
+</dict>
+</plist>
+"#,
+    )
+    .unwrap();
+    assert_eq!(k.lookup(&groups, "A", "V"), Some(-80.0));
+    assert_eq!(k.lookup(&groups, "A", "C"), Some(-12.5));
+    assert_eq!(k.lookup(&groups, "D", "A"), Some(-20.0));
+    assert_eq!(k.lookup(&groups, "D", "C"), None);
+    k.set("A", "C", 5.0);
+    assert_eq!(k.lookup(&groups, "A", "C"), Some(5.0));
+    k.remove("A", "C");
+
+    /* Renaming so that two pairs collide leaves every pair untouched. */
+    k.set("public.kern1.O", "V", -10.0);
+    assert!(!k.can_rename("A", "public.kern1.O"));
+    assert!(!k.rename("A", "public.kern1.O"));
+    assert_eq!(k.get("A", "V"), Some(-80.0));
+    assert_eq!(k.get("public.kern1.O", "V"), Some(-10.0));
+    assert_eq!(k.get("public.kern1.O", "A"), Some(-20.0));
+    k.remove("public.kern1.O", "V");
+    /* A name that is only used on the other side of different pairs is merged. */
+    k.set("T", "Aacute", -30.0);
+    assert!(k.rename("A", "Aacute"));
+    assert!(k.modified());
+    assert_eq!(k.get("Aacute", "V"), Some(-80.0));
+    assert_eq!(k.get("public.kern1.O", "Aacute"), Some(-20.0));
+    assert_eq!(k.get("T", "Aacute"), Some(-30.0));
+    assert_eq!(k.pairs.get_index_of("Aacute"), Some(0));
+    k.remove_all("Aacute");
+    assert!(k.is_empty());
+    assert!(k.pairs.is_empty());
+    k.set("T", "o", -40.0);
+    assert_eq!(k.remove("T", "o"), Some(-40.0));
+    assert_eq!(k.remove("T", "o"), None);
+    assert!(k.pairs.is_empty());
+}
+
 #[test]
 fn test_plist_write() {
     let p: FontInfo = FontInfo::new_from_str(PLIST).unwrap();
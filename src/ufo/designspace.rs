@@ -0,0 +1,737 @@
+/*
+ * gerb
+ *
+ * Copyright 2022 - Manos Pitsidianakis
+ *
+ * This file is part of gerb.
+ *
+ * gerb is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * gerb is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with gerb. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! `.designspace` documents
+//!
+//! A designspace document describes a variable design space: its axes, the UFO sources (masters)
+//! placed in it, the instances that are generated from them and substitution rules.
+//!
+//! # Specification
+//!
+//! <https://fonttools.readthedocs.io/en/latest/designspaceLib/xml.html>
+
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+
+use super::glif::{lib_from_xml, lib_to_xml};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename = "designspace")]
+pub struct DesignspaceDocument {
+    #[serde(rename = "@format")]
+    pub format: String,
+    #[serde(default)]
+    pub axes: Axes,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rules: Option<Rules>,
+    #[serde(default)]
+    pub sources: Sources,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instances: Option<Instances>,
+    /// Contents of the top level `<lib>` element, read and written like the `<lib>` of a glif
+    /// document.
+    #[serde(skip)]
+    pub lib: IndexMap<String, plist::Value>,
+}
+
+impl Default for DesignspaceDocument {
+    fn default() -> Self {
+        Self {
+            format: "5.0".to_string(),
+            axes: Axes::default(),
+            rules: None,
+            sources: Sources::default(),
+            instances: None,
+            lib: IndexMap::default(),
+        }
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Axes {
+    #[serde(rename = "@elidedfallbackname")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub elided_fallback_name: Option<String>,
+    #[serde(rename = "axis", default)]
+    pub axes: Vec<Axis>,
+}
+
+/// A design axis.
+///
+/// `minimum`, `maximum` and `default` are user space values. `map` maps user space values to
+/// design space values, which are the values used in source and instance locations.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Axis {
+    #[serde(rename = "@tag")]
+    pub tag: String,
+    #[serde(rename = "@name")]
+    pub name: String,
+    #[serde(rename = "@minimum")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub minimum: Option<f64>,
+    #[serde(rename = "@maximum")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub maximum: Option<f64>,
+    /// Space separated values of a discrete axis.
+    #[serde(rename = "@values")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub values: Option<String>,
+    #[serde(rename = "@default")]
+    pub default: f64,
+    #[serde(rename = "@hidden")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hidden: Option<u8>,
+    #[serde(rename = "labelname", default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub label_names: Vec<LabelName>,
+    #[serde(rename = "map", default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub map: Vec<AxisMap>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub labels: Option<AxisLabels>,
+}
+
+impl Axis {
+    /// Map a user space value to design space.
+    pub fn map_forward(&self, value: f64) -> f64 {
+        piecewise_linear(self.map.iter().map(|m| (m.input, m.output)), value)
+    }
+
+    /// Map a design space value to user space.
+    pub fn map_backward(&self, value: f64) -> f64 {
+        piecewise_linear(self.map.iter().map(|m| (m.output, m.input)), value)
+    }
+
+    /// Default value in design space.
+    pub fn design_default(&self) -> f64 {
+        self.map_forward(self.default)
+    }
+
+    /// Minimum, default and maximum values in design space. Discrete axes use the extremes of
+    /// their `values`.
+    pub fn design_range(&self) -> (f64, f64, f64) {
+        let (minimum, maximum) = if let (Some(min), Some(max)) = (self.minimum, self.maximum) {
+            (min, max)
+        } else {
+            self.values
+                .iter()
+                .flat_map(|v| v.split_whitespace())
+                .filter_map(|v| v.parse::<f64>().ok())
+                .fold((self.default, self.default), |(min, max), v| {
+                    (min.min(v), max.max(v))
+                })
+        };
+        (
+            self.map_forward(minimum),
+            self.design_default(),
+            self.map_forward(maximum),
+        )
+    }
+
+    /// Map a design space value to the `-1.0..=1.0` range, where the default is `0.0`.
+    pub fn normalize(&self, value: f64) -> f64 {
+        let (minimum, default, maximum) = self.design_range();
+        let value = value.clamp(minimum.min(default), maximum.max(default));
+        if value < default {
+            (value - default) / (default - minimum)
+        } else if value > default {
+            (value - default) / (maximum - default)
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Interpolate `value` in a list of `(from, to)` mappings, extrapolating the first and last
+/// segments. An empty list is the identity.
+fn piecewise_linear(mapping: impl Iterator<Item = (f64, f64)>, value: f64) -> f64 {
+    let mut mapping = mapping.collect::<Vec<(f64, f64)>>();
+    mapping.sort_by(|a, b| a.0.total_cmp(&b.0));
+    match mapping.as_slice() {
+        [] => value,
+        [(from, to)] => value - from + to,
+        _ => {
+            let i = mapping
+                .windows(2)
+                .position(|w| value <= w[1].0)
+                .unwrap_or(mapping.len() - 2);
+            let ((a, b), (c, d)) = (mapping[i], mapping[i + 1]);
+            if c == a {
+                return b;
+            }
+            (value - a).mul_add((d - b) / (c - a), b)
+        }
+    }
+}
+
+/// A localised name.
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LabelName {
+    /// The deserializer strips the `xml` namespace prefix of the attribute name.
+    #[serde(rename = "@xml:lang", alias = "@lang")]
+    pub lang: String,
+    #[serde(rename = "$text")]
+    pub name: String,
+}
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct AxisMap {
+    #[serde(rename = "@input")]
+    pub input: f64,
+    #[serde(rename = "@output")]
+    pub output: f64,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AxisLabels {
+    #[serde(rename = "label", default)]
+    pub labels: Vec<AxisLabel>,
+}
+
+/// A STAT table axis value.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AxisLabel {
+    #[serde(rename = "@name")]
+    pub name: String,
+    #[serde(rename = "@uservalue")]
+    pub user_value: f64,
+    #[serde(rename = "@userminimum")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_minimum: Option<f64>,
+    #[serde(rename = "@usermaximum")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_maximum: Option<f64>,
+    #[serde(rename = "@linkeduservalue")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub linked_user_value: Option<f64>,
+    #[serde(rename = "@elidable")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub elidable: Option<String>,
+    #[serde(rename = "@oldersibling")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub older_sibling: Option<String>,
+    #[serde(rename = "labelname", default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub label_names: Vec<LabelName>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Rules {
+    /// Either `"first"` or `"last"`: whether the substitutions are applied before or after
+    /// other glyph substitution features.
+    #[serde(rename = "@processing")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub processing: Option<String>,
+    #[serde(rename = "rule", default)]
+    pub rules: Vec<Rule>,
+}
+
+/// Glyph substitutions that apply when the location is within any of the condition sets.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Rule {
+    #[serde(rename = "@name")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(rename = "conditionset", default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub condition_sets: Vec<ConditionSet>,
+    /// Conditions outside a `<conditionset>` element, as written by format 3 documents.
+    #[serde(rename = "condition", default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub conditions: Vec<Condition>,
+    #[serde(rename = "sub", default)]
+    pub substitutions: Vec<Substitution>,
+}
+
+impl Rule {
+    /// Whether the rule applies at design space `location`.
+    pub fn matches(&self, location: &Location) -> bool {
+        let matches_all = |conditions: &[Condition]| {
+            conditions.iter().all(|c| {
+                let Some(v) = location.get(&c.name) else {
+                    return true;
+                };
+                !c.minimum.is_some_and(|min| v < min) && !c.maximum.is_some_and(|max| v > max)
+            })
+        };
+        (!self.conditions.is_empty() && matches_all(&self.conditions))
+            || self
+                .condition_sets
+                .iter()
+                .any(|set| matches_all(&set.conditions))
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConditionSet {
+    #[serde(rename = "condition", default)]
+    pub conditions: Vec<Condition>,
+}
+
+/// A design space range of an axis. At least one of `minimum` and `maximum` is set.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Condition {
+    #[serde(rename = "@name")]
+    pub name: String,
+    #[serde(rename = "@minimum")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub minimum: Option<f64>,
+    #[serde(rename = "@maximum")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub maximum: Option<f64>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Substitution {
+    #[serde(rename = "@name")]
+    pub name: String,
+    #[serde(rename = "@with")]
+    pub with: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Sources {
+    #[serde(rename = "source", default)]
+    pub sources: Vec<Source>,
+}
+
+/// A master UFO, or a layer of a UFO, at a location.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Source {
+    /// Path of the UFO relative to the designspace document.
+    #[serde(rename = "@filename")]
+    pub filename: String,
+    #[serde(rename = "@name")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(rename = "@familyname")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub family_name: Option<String>,
+    #[serde(rename = "@stylename")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub style_name: Option<String>,
+    /// Name of the UFO layer to use instead of the default layer. Layer sources are usually
+    /// sparse: they only contain some glyphs.
+    #[serde(rename = "@layer")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub layer: Option<String>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lib: Option<CopyFlag>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub groups: Option<CopyFlag>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub features: Option<CopyFlag>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub info: Option<CopyFlag>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kerning: Option<Mute>,
+    /// Glyphs that must not be used from this source when interpolating.
+    #[serde(rename = "glyph", default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub glyphs: Vec<MutedGlyph>,
+    #[serde(default)]
+    pub location: Location,
+}
+
+impl Source {
+    /// `name`, or the file name and layer if it is not set.
+    pub fn display_name(&self) -> String {
+        match (&self.name, &self.layer) {
+            (Some(name), _) => name.clone(),
+            (None, Some(layer)) => format!("{} ({layer})", self.filename),
+            (None, None) => self.filename.clone(),
+        }
+    }
+}
+
+/// Deprecated flag of format 4 documents that marks the source as the one to copy font data
+/// from.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CopyFlag {
+    #[serde(rename = "@copy")]
+    pub copy: u8,
+}
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Mute {
+    #[serde(rename = "@mute")]
+    pub mute: u8,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MutedGlyph {
+    #[serde(rename = "@name")]
+    pub name: String,
+    #[serde(rename = "@mute")]
+    pub mute: u8,
+}
+
+/// A design space location.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Location {
+    #[serde(rename = "dimension", default)]
+    pub dimensions: Vec<Dimension>,
+}
+
+impl Location {
+    pub fn get(&self, axis_name: &str) -> Option<f64> {
+        self.dimensions
+            .iter()
+            .find(|d| d.name == axis_name)
+            .map(|d| d.xvalue)
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Dimension {
+    #[serde(rename = "@name")]
+    pub name: String,
+    #[serde(rename = "@xvalue")]
+    pub xvalue: f64,
+    /// Value of anisotropic locations in the vertical direction.
+    #[serde(rename = "@yvalue")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub yvalue: Option<f64>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Instances {
+    #[serde(rename = "instance", default)]
+    pub instances: Vec<Instance>,
+}
+
+/// A font to be generated by interpolating the sources at a location.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Instance {
+    #[serde(rename = "@name")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(rename = "@familyname")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub family_name: Option<String>,
+    #[serde(rename = "@stylename")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub style_name: Option<String>,
+    /// Path of the UFO to generate, relative to the designspace document.
+    #[serde(rename = "@filename")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filename: Option<String>,
+    #[serde(rename = "@postscriptfontname")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub postscript_font_name: Option<String>,
+    #[serde(rename = "@stylemapfamilyname")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub style_map_family_name: Option<String>,
+    #[serde(rename = "@stylemapstylename")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub style_map_style_name: Option<String>,
+    #[serde(default)]
+    pub location: Location,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kerning: Option<Empty>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub info: Option<Empty>,
+}
+
+/// An element without attributes or children.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Empty {}
+
+impl DesignspaceDocument {
+    pub fn from_path(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let xml = std::fs::read_to_string(path)?;
+        Self::new_from_str(&xml)
+    }
+
+    pub fn new_from_str(xml: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut retval: Self = quick_xml::de::from_str(xml)?;
+        retval.lib = lib_from_xml(xml)?;
+        Ok(retval)
+    }
+
+    pub fn to_xml(&self) -> Result<String, Box<dyn std::error::Error>> {
+        let mut xml = quick_xml::se::to_string(&self)?;
+        if !self.lib.is_empty() {
+            let lib = lib_to_xml(&self.lib)?;
+            xml.truncate(xml.len() - "</designspace>".len());
+            xml.push_str(&lib);
+            xml.push_str("</designspace>");
+        }
+        Ok(format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n{}\n",
+            xml
+        ))
+    }
+
+    pub fn save(&self, destination: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        let xml = self.to_xml()?;
+        let mut file = OpenOptions::new()
+            .read(false)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(destination)?;
+        file.write_all(xml.as_bytes())?;
+        Ok(())
+    }
+
+    /// Design space location of the default source: the default value of every axis.
+    pub fn default_location(&self) -> Location {
+        Location {
+            dimensions: self
+                .axes
+                .axes
+                .iter()
+                .map(|a| Dimension {
+                    name: a.name.clone(),
+                    xvalue: a.design_default(),
+                    yvalue: None,
+                })
+                .collect(),
+        }
+    }
+
+    /// Complete `location` with the default value of the axes it doesn't specify, in axis order.
+    pub fn normalize_location(&self, location: &Location) -> Location {
+        Location {
+            dimensions: self
+                .axes
+                .axes
+                .iter()
+                .map(|a| Dimension {
+                    name: a.name.clone(),
+                    xvalue: location.get(&a.name).unwrap_or_else(|| a.design_default()),
+                    yvalue: None,
+                })
+                .collect(),
+        }
+    }
+
+    /// Normalized coordinates of `location` in axis order; see [`Axis::normalize`].
+    pub fn normalized_coordinates(&self, location: &Location) -> Vec<f64> {
+        self.axes
+            .axes
+            .iter()
+            .map(|a| a.normalize(location.get(&a.name).unwrap_or_else(|| a.design_default())))
+            .collect()
+    }
+
+    /// Index of the source at the default location that uses the default layer of its UFO.
+    pub fn default_source(&self) -> Option<usize> {
+        let default = self.default_location();
+        self.sources.sources.iter().position(|s| {
+            s.layer.is_none()
+                && self
+                    .normalize_location(&s.location)
+                    .dimensions
+                    .iter()
+                    .zip(default.dimensions.iter())
+                    .all(|(a, b)| (a.xvalue - b.xvalue).abs() < f64::EPSILON)
+        })
+    }
+
+    /// Substitutions of the rules that apply at design space `location`.
+    pub fn substitutions(&self, location: &Location) -> Vec<&Substitution> {
+        let location = self.normalize_location(location);
+        self.rules
+            .iter()
+            .flat_map(|r| r.rules.iter())
+            .filter(|r| r.matches(&location))
+            .flat_map(|r| r.substitutions.iter())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+const _DESIGNSPACE: &str = r##"<?xml version='1.0' encoding='UTF-8'?>
+<designspace format="5.0">
+  <axes elidedfallbackname="Regular">
+    <axis tag="wght" name="weight" minimum="300" maximum="900" default="400">
+      <labelname xml:lang="en">Weight</labelname>
+      <map input="300" output="0"/>
+      <map input="400" output="100"/>
+      <map input="900" output="1000"/>
+      <labels>
+        <label uservalue="400" name="Regular" elidable="true"/>
+      </labels>
+    </axis>
+    <axis tag="wdth" name="width" minimum="0" maximum="1000" default="0"/>
+    <axis tag="ital" name="italic" values="0 1" default="0" hidden="1"/>
+  </axes>
+  <rules processing="last">
+    <rule name="fold_S">
+      <conditionset>
+        <condition name="weight" minimum="600" maximum="1000"/>
+      </conditionset>
+      <sub name="S" with="S.closed"/>
+      <sub name="dollar" with="dollar.nostroke"/>
+    </rule>
+  </rules>
+  <sources>
+    <source filename="MutatorSansLightCondensed.ufo" name="master.light.condensed" familyname="MutatorMathTest" stylename="LightCondensed">
+      <lib copy="1"/>
+      <groups copy="1"/>
+      <features copy="1"/>
+      <info copy="1"/>
+      <glyph name="A" mute="1"/>
+      <location>
+        <dimension name="weight" xvalue="100"/>
+        <dimension name="width" xvalue="0"/>
+      </location>
+    </source>
+    <source filename="MutatorSansBoldCondensed.ufo" name="master.bold.condensed">
+      <kerning mute="1"/>
+      <location>
+        <dimension name="weight" xvalue="1000"/>
+      </location>
+    </source>
+    <source filename="MutatorSansLightCondensed.ufo" name="support.sparse" layer="support">
+      <location>
+        <dimension name="weight" xvalue="500"/>
+      </location>
+    </source>
+  </sources>
+  <instances>
+    <instance name="instance.medium" familyname="MutatorMathTest" stylename="Medium" filename="instances/MutatorSans-Medium.ufo" postscriptfontname="MutatorMathTest-Medium" stylemapfamilyname="MutatorMathTest Medium" stylemapstylename="regular">
+      <location>
+        <dimension name="weight" xvalue="500"/>
+        <dimension name="width" xvalue="327"/>
+      </location>
+      <kerning/>
+      <info/>
+    </instance>
+  </instances>
+  <lib>
+    <dict>
+      <key>com.github.fonttools.varLib.featureVarsFeatureTag</key>
+      <string>rclt</string>
+    </dict>
+  </lib>
+</designspace>
+"##;
+
+#[test]
+fn test_designspace_read() {
+    let doc = DesignspaceDocument::new_from_str(_DESIGNSPACE).unwrap();
+    assert_eq!(doc.format, "5.0");
+    assert_eq!(doc.axes.elided_fallback_name.as_deref(), Some("Regular"));
+    let [weight, width, italic] = &doc.axes.axes[..] else {
+        panic!("{:?}", doc.axes);
+    };
+    assert_eq!(
+        (weight.tag.as_str(), weight.name.as_str()),
+        ("wght", "weight")
+    );
+    assert_eq!((weight.minimum, weight.maximum), (Some(300.0), Some(900.0)));
+    assert_eq!(
+        weight.label_names,
+        [LabelName {
+            lang: "en".into(),
+            name: "Weight".into()
+        }]
+    );
+    assert_eq!(weight.map.len(), 3);
+    assert_eq!(weight.labels.as_ref().unwrap().labels[0].name, "Regular");
+    assert_eq!(weight.design_default(), 100.0);
+    assert_eq!(weight.map_forward(650.0), 550.0);
+    assert_eq!(weight.map_backward(550.0), 650.0);
+    assert_eq!(width.map_forward(327.0), 327.0);
+    assert_eq!(weight.design_range(), (0.0, 100.0, 1000.0));
+    assert_eq!(weight.normalize(0.0), -1.0);
+    assert_eq!(weight.normalize(550.0), 0.5);
+    assert_eq!(weight.normalize(2000.0), 1.0);
+    assert_eq!(width.normalize(327.0), 0.327);
+    assert_eq!(italic.design_range(), (0.0, 0.0, 1.0));
+    assert_eq!(italic.values.as_deref(), Some("0 1"));
+    assert_eq!(italic.hidden, Some(1));
+
+    let rules = doc.rules.as_ref().unwrap();
+    assert_eq!(rules.processing.as_deref(), Some("last"));
+    assert_eq!(
+        rules.rules[0].condition_sets[0].conditions[0].name,
+        "weight"
+    );
+    assert_eq!(rules.rules[0].substitutions.len(), 2);
+
+    let sources = &doc.sources.sources;
+    assert_eq!(sources.len(), 3);
+    assert_eq!(sources[0].filename, "MutatorSansLightCondensed.ufo");
+    assert_eq!(sources[0].style_name.as_deref(), Some("LightCondensed"));
+    assert_eq!(sources[0].info, Some(CopyFlag { copy: 1 }));
+    assert_eq!(sources[0].glyphs[0].name, "A");
+    assert_eq!(sources[0].location.get("weight"), Some(100.0));
+    assert_eq!(sources[1].kerning, Some(Mute { mute: 1 }));
+    assert_eq!(sources[1].location.get("width"), None);
+    assert_eq!(sources[2].layer.as_deref(), Some("support"));
+    assert_eq!(sources[2].display_name(), "support.sparse");
+    assert_eq!(doc.default_source(), Some(0));
+
+    let instance = &doc.instances.as_ref().unwrap().instances[0];
+    assert_eq!(instance.style_name.as_deref(), Some("Medium"));
+    assert_eq!(instance.location.get("width"), Some(327.0));
+    assert!(instance.kerning.is_some() && instance.info.is_some());
+
+    assert_eq!(
+        doc.lib["com.github.fonttools.varLib.featureVarsFeatureTag"],
+        plist::Value::String("rclt".into())
+    );
+
+    let substitutions = |weight: f64| {
+        doc.substitutions(&Location {
+            dimensions: vec![Dimension {
+                name: "weight".into(),
+                xvalue: weight,
+                yvalue: None,
+            }],
+        })
+        .len()
+    };
+    assert_eq!(
+        doc.normalized_coordinates(&sources[1].location),
+        vec![1.0, 0.0, 0.0]
+    );
+    assert_eq!(substitutions(500.0), 0);
+    assert_eq!(substitutions(700.0), 2);
+}
+
+#[test]
+fn test_designspace_write() {
+    let doc = DesignspaceDocument::new_from_str(_DESIGNSPACE).unwrap();
+    let xml = doc.to_xml().unwrap();
+    assert_eq!(DesignspaceDocument::new_from_str(&xml).unwrap(), doc);
+
+    let empty = DesignspaceDocument::default();
+    let xml = empty.to_xml().unwrap();
+    assert_eq!(DesignspaceDocument::new_from_str(&xml).unwrap(), empty);
+}
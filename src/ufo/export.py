@@ -30,10 +30,10 @@ def export(options) -> str:
         ufo = Font(tmpdirname)
         result = make_output_path(ufo, options)
         if options.format == OutputFormat.Otf:
-            otf = compileOTF(ufo)
+            otf = compileOTF(ufo, removeOverlaps=options.remove_overlaps)
             otf.save(result)
         elif options.format == OutputFormat.Ttf:
-            ttf = compileTTF(ufo)
+            ttf = compileTTF(ufo, removeOverlaps=options.remove_overlaps)
             ttf.save(result)
         else:
             raise ValueError(f"Got unrecognized output format option: {options.format}")
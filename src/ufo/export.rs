@@ -20,6 +20,7 @@
  */
 #![allow(non_snake_case)]
 
+#[cfg(feature = "python")]
 use pyo3::prelude::*;
 use std::path::PathBuf;
 
@@ -27,26 +28,24 @@ pub use ufo_compile::*;
 pub mod ufo_compile {
     use super::*;
 
-    #[pyclass(module = "export")]
-    #[derive(Debug, Clone, Copy)]
+    #[cfg_attr(feature = "python", pyclass(module = "export"))]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
     pub enum OutputFormat {
         Otf,
         Ttf,
     }
 
-    #[pyclass]
+    #[cfg_attr(feature = "python", pyclass(get_all, set_all))]
     #[derive(Debug, Clone)]
     pub struct UFOCompileOptions {
-        #[pyo3(get, set)]
-        input_dir: PathBuf,
-        #[pyo3(get, set)]
-        output_dir: PathBuf,
-        #[pyo3(get, set)]
-        format: OutputFormat,
-        #[pyo3(get, set)]
-        filename_stem: Option<String>,
-        #[pyo3(get, set)]
-        output_path: Option<PathBuf>,
+        pub input_dir: PathBuf,
+        pub output_dir: PathBuf,
+        pub format: OutputFormat,
+        pub filename_stem: Option<String>,
+        pub output_path: Option<PathBuf>,
+        /// Merge overlapping contours before compiling. Variable fonts can keep overlaps, but
+        /// hinting of static TrueType fonts needs them removed.
+        pub remove_overlaps: bool,
     }
 
     impl Default for UFOCompileOptions {
@@ -55,6 +54,7 @@ pub mod ufo_compile {
         }
     }
 
+    #[cfg(feature = "python")]
     #[pymethods]
     impl UFOCompileOptions {
         #[new]
@@ -65,6 +65,7 @@ pub mod ufo_compile {
                 format: OutputFormat::Otf,
                 filename_stem: None,
                 output_path: None,
+                remove_overlaps: false,
             }
         }
     }
@@ -86,6 +87,7 @@ pub mod ufo_compile {
                 format: OutputFormat::Otf,
                 filename_stem: None,
                 output_path: None,
+                remove_overlaps: false,
             }
         }
 
@@ -95,11 +97,15 @@ pub mod ufo_compile {
             format: OutputFormat,
             filename_stem: Option<String>,
             output_path: Option<PathBuf>,
+            remove_overlaps: bool,
         }
     }
 
+    #[cfg(feature = "python")]
     const FUNC: &str = include_str!("export.py");
 
+    /// Compile the UFO project in `options.input_dir` with the `ufo2ft` python library.
+    #[cfg(feature = "python")]
     pub fn export(options: UFOCompileOptions) -> Result<PathBuf, Box<dyn std::error::Error>> {
         let res: PyResult<PathBuf> = Python::with_gil(|py| {
             let module = PyModule::from_code(py, FUNC, "export.py", "export")?;
@@ -112,6 +118,35 @@ pub mod ufo_compile {
         Ok(res?)
     }
 
+    /// Font compilers this build can export with.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Compiler {
+        /// The `ufo2ft` python library, which also compiles kerning and OpenType features. It
+        /// reads the project files saved on disk.
+        #[cfg(feature = "python")]
+        Ufo2ft,
+        /// The built-in compiler of [`crate::ufo::sfnt`]. It compiles the glyphs as they are in
+        /// memory, including unsaved changes.
+        #[cfg(feature = "native-export")]
+        Native,
+    }
+
+    impl Compiler {
+        #[cfg_attr(not(feature = "native-export"), allow(unused_variables))]
+        pub fn compile(
+            self,
+            project: &crate::prelude::Project,
+            options: UFOCompileOptions,
+        ) -> Result<PathBuf, Box<dyn std::error::Error>> {
+            match self {
+                #[cfg(feature = "python")]
+                Self::Ufo2ft => export(options),
+                #[cfg(feature = "native-export")]
+                Self::Native => crate::ufo::sfnt::export(project, options),
+            }
+        }
+    }
+
     pub fn export_action_cb(window: gtk::Window, project: crate::prelude::Project) {
         use crate::prelude::*;
         const OPEN_FOLDER: gtk::ResponseType = gtk::ResponseType::Other(0);
@@ -164,14 +199,92 @@ pub mod ufo_compile {
             }
         };
         filechooser.hide();
-        match export(
-            UFOCompileOptions::new()
-                .input_dir(input_dir)
-                .output_dir(output_dir.into())
-                .format(format)
-                .filename_stem(filename_stem)
-                .output_path(output_path),
-        ) {
+        /* Prefer ufo2ft, since it also compiles kerning and OpenType features, but let the user
+         * pick the built-in compiler, e.g. when ufo2ft is not installed. */
+        #[cfg(all(feature = "python", feature = "native-export"))]
+        let compiler = {
+            let dialog = gtk::MessageDialog::builder()
+                .transient_for(&window)
+                .destroy_with_parent(true)
+                .modal(true)
+                .buttons(gtk::ButtonsType::None)
+                .message_type(gtk::MessageType::Question)
+                .text("Select compiler")
+                .secondary_text("The <tt>ufo2ft</tt> python3 library also compiles kerning and OpenType features, and compiles the project as saved on disk. The built-in compiler only compiles outlines and metrics, but compiles the glyphs as they are in the editor, including unsaved changes, and needs no python installation.")
+                .secondary_use_markup(true)
+                .build();
+            dialog.add_button("Built-in", gtk::ResponseType::No);
+            dialog.add_button("ufo2ft", gtk::ResponseType::Yes);
+            dialog.set_default_response(gtk::ResponseType::Yes);
+            let response = dialog.run();
+            dialog.emit_close();
+            match response {
+                gtk::ResponseType::Yes => Compiler::Ufo2ft,
+                gtk::ResponseType::No => Compiler::Native,
+                _ => return,
+            }
+        };
+        #[cfg(not(feature = "native-export"))]
+        let compiler = Compiler::Ufo2ft;
+        #[cfg(not(feature = "python"))]
+        let compiler = Compiler::Native;
+        /* ufo2ft reads the project from disk, so unsaved changes must be saved first or they
+         * would be silently left out of the exported font. */
+        #[cfg(feature = "python")]
+        if compiler == Compiler::Ufo2ft && project.property::<bool>(Project::MODIFIED) {
+            let dialog = gtk::MessageDialog::builder()
+                .transient_for(&window)
+                .destroy_with_parent(true)
+                .modal(true)
+                .buttons(gtk::ButtonsType::None)
+                .message_type(gtk::MessageType::Question)
+                .text("Save project?")
+                .secondary_text("The project has unsaved changes. <tt>ufo2ft</tt> compiles the project as saved on disk, so it must be saved before exporting.")
+                .secondary_use_markup(true)
+                .build();
+            dialog.add_button("Cancel", gtk::ResponseType::Cancel);
+            dialog.add_button("Save and export", gtk::ResponseType::Yes);
+            dialog.set_default_response(gtk::ResponseType::Yes);
+            let response = dialog.run();
+            dialog.emit_close();
+            if response != gtk::ResponseType::Yes {
+                return;
+            }
+            if let Err(err) = project.save() {
+                let dialog = crate::utils::widgets::new_simple_error_dialog(
+                    Some("Error: could not save project"),
+                    &err.to_string(),
+                    None,
+                    &window,
+                );
+                dialog.run();
+                dialog.emit_close();
+                return;
+            }
+        }
+        let remove_overlaps = format == OutputFormat::Ttf
+            && {
+                let dialog = gtk::MessageDialog::builder()
+                .transient_for(&window)
+                .destroy_with_parent(true)
+                .modal(true)
+                .buttons(gtk::ButtonsType::YesNo)
+                .message_type(gtk::MessageType::Question)
+                .text("Remove overlaps?")
+                .secondary_text("TrueType hinting doesn't work well with overlapping contours. The project's glyphs are not modified.")
+                .build();
+                let response = dialog.run();
+                dialog.emit_close();
+                response == gtk::ResponseType::Yes
+            };
+        let options = UFOCompileOptions::new()
+            .input_dir(input_dir)
+            .output_dir(output_dir.into())
+            .format(format)
+            .filename_stem(filename_stem)
+            .output_path(output_path)
+            .remove_overlaps(remove_overlaps);
+        match compiler.compile(&project, options) {
             Ok(result_path) => {
                 let dialog = crate::utils::widgets::new_simple_info_dialog(
                     match format {
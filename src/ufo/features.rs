@@ -0,0 +1,803 @@
+/*
+ * gerb
+ *
+ * Copyright 2022 - Manos Pitsidianakis
+ *
+ * This file is part of gerb.
+ *
+ * gerb is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * gerb is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with gerb. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! `features.fea`: OpenType feature code in the Adobe feature file syntax.
+//!
+//! The parser is deliberately tolerant: it is used to highlight and check the file while it is
+//! being edited, so it never fails but reports [`Diagnostic`]s instead.
+//!
+//! # Specification
+//!
+//! <https://unifiedfontobject.org/versions/ufo3/features.fea/>
+//! <https://adobe-type-tools.github.io/afdko/OpenTypeFeatureFileSpecification.html>
+
+use std::path::Path;
+
+/// features.fea
+///
+/// UFO3 Spec:
+///
+/// > This file contains OpenType feature descriptions that are expressed in the Adobe
+/// > Font Development Kit for OpenType (AFDKO) .fea syntax. This file is optional.
+#[derive(Default, PartialEq, Eq, Clone, Debug)]
+pub struct Features {
+    pub source: String,
+    modified: bool,
+    /// See [`Features::lossy`].
+    lossy: bool,
+}
+
+impl Features {
+    /// Read `path`. Invalid UTF-8 sequences are replaced with U+FFFD instead of failing, so that
+    /// a bad feature file doesn't prevent the project from loading.
+    pub fn from_path(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        if !path.exists() {
+            // This file is optional.
+            return Ok(Self::default());
+        }
+        let bytes = std::fs::read(path)?;
+        Ok(Self {
+            source: String::from_utf8_lossy(&bytes).into_owned(),
+            modified: false,
+            lossy: std::str::from_utf8(&bytes).is_err(),
+        })
+    }
+
+    /// Write to `destination` if modified. If the feature code is empty, the file is removed
+    /// instead since it is optional.
+    ///
+    /// Fails if the file was [decoded lossily](Self::lossy), unless [`Self::accept_lossy`] was
+    /// called, so that the replaced bytes aren't lost by accident.
+    pub fn save(&mut self, destination: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        if !self.modified {
+            return Ok(());
+        }
+        if self.lossy {
+            return Err(format!(
+                "{} was not valid UTF-8 when it was loaded. Saving it would replace the invalid \
+                 bytes with U+FFFD.",
+                destination.display()
+            )
+            .into());
+        }
+        if self.source.trim().is_empty() {
+            if destination.exists() {
+                std::fs::remove_file(destination)?;
+            }
+        } else {
+            std::fs::write(destination, &self.source)?;
+        }
+        self.modified = false;
+        Ok(())
+    }
+
+    pub fn modified(&self) -> bool {
+        self.modified
+    }
+
+    /// The file was not valid UTF-8 when it was read, and `source` has U+FFFD in place of the
+    /// invalid bytes.
+    pub fn lossy(&self) -> bool {
+        self.lossy
+    }
+
+    /// Allow saving lossily decoded feature code, replacing the invalid bytes on disk.
+    pub fn accept_lossy(&mut self) {
+        self.lossy = false;
+    }
+
+    pub fn set(&mut self, source: String) {
+        if self.source != source {
+            self.source = source;
+            self.modified = true;
+        }
+    }
+}
+
+/// Reserved words of the feature file syntax. They can't be used as glyph names unless escaped
+/// with a backslash.
+pub const KEYWORDS: &[&str] = &[
+    "anchor",
+    "anchorDef",
+    "anon",
+    "anonymous",
+    "Ascender",
+    "Attach",
+    "AxisValue",
+    "base",
+    "by",
+    "CapHeight",
+    "CaretOffset",
+    "Character",
+    "CodePageRange",
+    "contourpoint",
+    "cursive",
+    "cvParameters",
+    "Descender",
+    "DesignAxis",
+    "device",
+    "ElidedFallbackName",
+    "ElidedFallbackNameID",
+    "enum",
+    "enumerate",
+    "exclude_dflt",
+    "excludeDFLT",
+    "feature",
+    "featureNames",
+    "FeatUILabelNameID",
+    "FeatUITooltipTextNameID",
+    "FontRevision",
+    "from",
+    "FSType",
+    "GlyphClassDef",
+    "HorizAxis.BaseScriptList",
+    "HorizAxis.BaseTagList",
+    "HorizAxis.MinMax",
+    "ignore",
+    "IgnoreBaseGlyphs",
+    "IgnoreLigatures",
+    "IgnoreMarks",
+    "include",
+    "include_dflt",
+    "includeDFLT",
+    "language",
+    "languagesystem",
+    "ligature",
+    "LigatureCaretByDev",
+    "LigatureCaretByIndex",
+    "LigatureCaretByPos",
+    "ligComponent",
+    "LineGap",
+    "lookup",
+    "lookupflag",
+    "LowerOpSize",
+    "mark",
+    "MarkAttachmentType",
+    "markClass",
+    "nameid",
+    "NULL",
+    "Panose",
+    "parameters",
+    "ParamUILabelNameID",
+    "pos",
+    "position",
+    "required",
+    "reversesub",
+    "RightToLeft",
+    "rsub",
+    "SampleTextNameID",
+    "script",
+    "sizemenuname",
+    "sub",
+    "substitute",
+    "subtable",
+    "table",
+    "TypoAscender",
+    "TypoDescender",
+    "TypoLineGap",
+    "UnicodeRange",
+    "UpperOpSize",
+    "useExtension",
+    "UseMarkFilteringSet",
+    "valueRecordDef",
+    "Vendor",
+    "VertAdvanceY",
+    "VertAxis.BaseScriptList",
+    "VertAxis.BaseTagList",
+    "VertAxis.MinMax",
+    "VertOriginY",
+    "VertTypoAscender",
+    "VertTypoDescender",
+    "VertTypoLineGap",
+    "WeightClass",
+    "WidthClass",
+    "winAscent",
+    "winDescent",
+    "XHeight",
+];
+
+/// Keywords that can only appear at the start of a statement. Seeing one in the middle of a
+/// statement means the previous one is missing its `;`.
+const STATEMENT_KEYWORDS: &[&str] = &[
+    "feature",
+    "include",
+    "language",
+    "languagesystem",
+    "lookupflag",
+    "markClass",
+    "pos",
+    "position",
+    "reversesub",
+    "rsub",
+    "script",
+    "sub",
+    "substitute",
+    "subtable",
+    "table",
+];
+
+/// Statements whose glyph names are checked against the glyphs of the font.
+const GLYPH_STATEMENTS: &[&str] = &[
+    "Attach",
+    "enum",
+    "enumerate",
+    "GlyphClassDef",
+    "ignore",
+    "LigatureCaretByIndex",
+    "LigatureCaretByPos",
+    "markClass",
+    "pos",
+    "position",
+    "reversesub",
+    "rsub",
+    "sub",
+    "substitute",
+];
+
+/// Blocks that must be closed with their name, e.g. `feature liga { ... } liga;`.
+const NAMED_BLOCKS: &[&str] = &["anon", "anonymous", "feature", "lookup", "table"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    Keyword,
+    /// A glyph name, or any other identifier such as a tag or a lookup name.
+    Name,
+    /// A glyph class name, starting with `@`.
+    Class,
+    /// A CID, like `\101`.
+    Cid,
+    Number,
+    String,
+    Comment,
+    Punct(char),
+    /// Unexpected characters or an unterminated string.
+    Invalid,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Token<'a> {
+    pub kind: TokenKind,
+    pub text: &'a str,
+    /// Byte offset in the source.
+    pub start: usize,
+    /// Line number, starting from zero.
+    pub line: usize,
+    /// Byte offset in the line.
+    pub column: usize,
+}
+
+impl Token<'_> {
+    pub fn end(&self) -> usize {
+        self.start + self.text.len()
+    }
+
+    /// The name without its escaping backslash, if any.
+    pub fn unescaped(&self) -> &str {
+        self.text.strip_prefix('\\').unwrap_or(self.text)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagnosticKind {
+    Syntax,
+    UnknownGlyph,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub kind: DiagnosticKind,
+    pub message: String,
+    /// Byte span in the source.
+    pub start: usize,
+    pub end: usize,
+    /// Line number, starting from zero.
+    pub line: usize,
+    /// Byte offset in the line.
+    pub column: usize,
+}
+
+impl Diagnostic {
+    fn at(token: &Token, kind: DiagnosticKind, message: String) -> Self {
+        Self {
+            kind,
+            message,
+            start: token.start,
+            end: token.end(),
+            line: token.line,
+            column: token.column,
+        }
+    }
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            fmt,
+            "{}:{}: {}",
+            self.line + 1,
+            self.column + 1,
+            self.message
+        )
+    }
+}
+
+fn is_name_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_' || c == '.'
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-' | '/' | '*' | '+')
+}
+
+/// Split `source` into tokens. Whitespace is skipped; comments are kept so that they can be
+/// highlighted.
+pub fn tokenize(source: &str) -> Vec<Token<'_>> {
+    let mut ret = vec![];
+    let mut chars = source.char_indices().peekable();
+    let (mut line, mut line_start) = (0, 0);
+    while let Some((start, c)) = chars.next() {
+        let (token_line, column) = (line, start - line_start);
+        let kind = match c {
+            '\n' => {
+                line += 1;
+                line_start = start + 1;
+                continue;
+            }
+            c if c.is_whitespace() => continue,
+            '#' => {
+                while chars.next_if(|(_, c)| *c != '\n').is_some() {}
+                TokenKind::Comment
+            }
+            '"' => {
+                let mut terminated = false;
+                for (i, c) in chars.by_ref() {
+                    if c == '"' {
+                        terminated = true;
+                        break;
+                    }
+                    if c == '\n' {
+                        line += 1;
+                        line_start = i + 1;
+                    }
+                }
+                if terminated {
+                    TokenKind::String
+                } else {
+                    TokenKind::Invalid
+                }
+            }
+            '@' => {
+                while chars.next_if(|(_, c)| is_name_char(*c)).is_some() {}
+                TokenKind::Class
+            }
+            '\\' if chars.peek().is_some_and(|(_, c)| c.is_ascii_digit()) => {
+                while chars.next_if(|(_, c)| c.is_ascii_digit()).is_some() {}
+                TokenKind::Cid
+            }
+            '\\' if chars.peek().is_some_and(|(_, c)| is_name_start(*c)) => {
+                while chars.next_if(|(_, c)| is_name_char(*c)).is_some() {}
+                TokenKind::Name
+            }
+            c if c.is_ascii_digit()
+                || (c == '-' && chars.peek().is_some_and(|(_, c)| c.is_ascii_digit())) =>
+            {
+                while chars
+                    .next_if(|(_, c)| c.is_ascii_alphanumeric() || *c == '.')
+                    .is_some()
+                {}
+                TokenKind::Number
+            }
+            c if is_name_start(c) => {
+                while chars.next_if(|(_, c)| is_name_char(*c)).is_some() {}
+                TokenKind::Name
+            }
+            '{' | '}' | '[' | ']' | '(' | ')' | '<' | '>' | ';' | ',' | '=' | '\'' | '-' => {
+                TokenKind::Punct(c)
+            }
+            _ => TokenKind::Invalid,
+        };
+        let end = chars.peek().map_or(source.len(), |(i, _)| *i);
+        let text = &source[start..end];
+        let kind = if kind == TokenKind::Name && KEYWORDS.contains(&text) {
+            TokenKind::Keyword
+        } else {
+            kind
+        };
+        ret.push(Token {
+            kind,
+            text,
+            start,
+            line: token_line,
+            column,
+        });
+    }
+    ret
+}
+
+/// Names of the glyph classes that appear in `tokens`, without duplicates.
+pub fn class_names<'a>(tokens: &[Token<'a>]) -> Vec<&'a str> {
+    let mut ret: Vec<&'a str> = vec![];
+    for t in tokens.iter().filter(|t| t.kind == TokenKind::Class) {
+        if !ret.contains(&t.text) {
+            ret.push(t.text);
+        }
+    }
+    ret
+}
+
+struct Block<'a> {
+    keyword: &'a str,
+    name: Option<&'a str>,
+    token: Token<'a>,
+}
+
+/// Check the syntax of `source` and the glyph names it refers to. `is_glyph` returns whether a
+/// glyph name exists in the font.
+pub fn check(source: &str, is_glyph: impl Fn(&str) -> bool) -> Vec<Diagnostic> {
+    use DiagnosticKind::Syntax;
+
+    let tokens = tokenize(source);
+    let mut ret = vec![];
+    for t in tokens.iter().filter(|t| t.kind == TokenKind::Invalid) {
+        ret.push(Diagnostic::at(
+            t,
+            Syntax,
+            if t.text.starts_with('"') {
+                "unterminated string".to_string()
+            } else {
+                format!("unexpected `{}`", t.text)
+            },
+        ));
+    }
+    let tokens = tokens
+        .into_iter()
+        .filter(|t| !matches!(t.kind, TokenKind::Comment | TokenKind::Invalid))
+        .collect::<Vec<Token>>();
+    let mut stack: Vec<Block> = vec![];
+    let mut statement: Vec<Token> = vec![];
+    let mut i = 0;
+    while i < tokens.len() {
+        let t = tokens[i];
+        i += 1;
+        match t.kind {
+            TokenKind::Punct(';') => {
+                check_statement(&statement, &is_glyph, &mut ret);
+                statement.clear();
+            }
+            TokenKind::Punct('{') => {
+                check_statement(&statement, &is_glyph, &mut ret);
+                let Some(first) = statement.first() else {
+                    ret.push(Diagnostic::at(&t, Syntax, "unexpected `{`".to_string()));
+                    stack.push(Block {
+                        keyword: "",
+                        name: None,
+                        token: t,
+                    });
+                    continue;
+                };
+                let block = Block {
+                    keyword: first.text,
+                    name: statement.get(1).map(|t| t.text),
+                    token: *first,
+                };
+                statement.clear();
+                if matches!(block.keyword, "anon" | "anonymous") {
+                    /* The contents of anonymous blocks are not feature file syntax. */
+                    while i < tokens.len()
+                        && !(tokens[i].kind == TokenKind::Punct('}')
+                            && tokens.get(i + 1).map(|t| t.text) == block.name)
+                    {
+                        i += 1;
+                    }
+                }
+                stack.push(block);
+            }
+            TokenKind::Punct('}') => {
+                if let Some(last) = statement.last() {
+                    ret.push(Diagnostic::at(
+                        last,
+                        Syntax,
+                        format!("expected `;` after `{}`", last.text),
+                    ));
+                    check_statement(&statement, &is_glyph, &mut ret);
+                    statement.clear();
+                }
+                let Some(block) = stack.pop() else {
+                    ret.push(Diagnostic::at(&t, Syntax, "unmatched `}`".to_string()));
+                    continue;
+                };
+                let mut last = t;
+                if NAMED_BLOCKS.contains(&block.keyword) {
+                    let name = block.name.unwrap_or_default();
+                    match tokens.get(i) {
+                        Some(end) if end.text == name => {
+                            last = *end;
+                            i += 1;
+                        }
+                        Some(end) if matches!(end.kind, TokenKind::Name | TokenKind::Keyword) => {
+                            ret.push(Diagnostic::at(
+                                end,
+                                Syntax,
+                                format!(
+                                    "expected end of {} `{name}`, found `{}`",
+                                    block.keyword, end.text
+                                ),
+                            ));
+                            last = *end;
+                            i += 1;
+                        }
+                        _ => {
+                            ret.push(Diagnostic::at(
+                                &t,
+                                Syntax,
+                                format!("expected `}} {name};` to end {} `{name}`", block.keyword),
+                            ));
+                        }
+                    }
+                }
+                if tokens.get(i).map(|t| t.kind) == Some(TokenKind::Punct(';')) {
+                    i += 1;
+                } else {
+                    ret.push(Diagnostic::at(
+                        &last,
+                        Syntax,
+                        format!("expected `;` after `{}`", last.text),
+                    ));
+                }
+            }
+            _ => {
+                let follows_ignore = statement.len() == 1
+                    && matches!(statement[0].text, "ignore" | "enum" | "enumerate");
+                if let Some(last) = statement.last() {
+                    if t.kind == TokenKind::Keyword
+                        && STATEMENT_KEYWORDS.contains(&t.text)
+                        && !follows_ignore
+                    {
+                        ret.push(Diagnostic::at(
+                            last,
+                            Syntax,
+                            format!("expected `;` after `{}`", last.text),
+                        ));
+                        check_statement(&statement, &is_glyph, &mut ret);
+                        statement.clear();
+                    }
+                }
+                statement.push(t);
+            }
+        }
+    }
+    if let Some(last) = statement.last() {
+        ret.push(Diagnostic::at(
+            last,
+            Syntax,
+            format!("expected `;` after `{}`", last.text),
+        ));
+        check_statement(&statement, &is_glyph, &mut ret);
+    }
+    for block in stack {
+        ret.push(Diagnostic::at(
+            &block.token,
+            Syntax,
+            match block.name {
+                Some(name) => format!("unclosed {} `{name}`", block.keyword),
+                None => "unclosed `{`".to_string(),
+            },
+        ));
+    }
+    ret.sort_by_key(|d| d.start);
+    ret
+}
+
+/// Check the brackets of a statement, and its glyph names if it refers to glyphs.
+fn check_statement(
+    statement: &[Token],
+    is_glyph: &impl Fn(&str) -> bool,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    use DiagnosticKind::{Syntax, UnknownGlyph};
+
+    let Some(first) = statement.first() else {
+        return;
+    };
+    let class_definition = first.kind == TokenKind::Class
+        && statement.get(1).map(|t| t.kind) == Some(TokenKind::Punct('='));
+    let check_glyphs = class_definition || GLYPH_STATEMENTS.contains(&first.text);
+    let mut class: Option<&Token> = None;
+    let mut angle: Option<&Token> = None;
+    let mut previous: Option<&Token> = None;
+    for t in statement {
+        match t.kind {
+            TokenKind::Punct('[') => {
+                if class.is_some() {
+                    diagnostics.push(Diagnostic::at(
+                        t,
+                        Syntax,
+                        "glyph classes can't be nested".to_string(),
+                    ));
+                }
+                class = Some(t);
+            }
+            TokenKind::Punct(']') if class.take().is_none() => {
+                diagnostics.push(Diagnostic::at(t, Syntax, "unmatched `]`".to_string()));
+            }
+            TokenKind::Punct('<') => {
+                if angle.is_some() {
+                    diagnostics.push(Diagnostic::at(t, Syntax, "unexpected `<`".to_string()));
+                }
+                angle = Some(t);
+            }
+            TokenKind::Punct('>') if angle.take().is_none() => {
+                diagnostics.push(Diagnostic::at(t, Syntax, "unmatched `>`".to_string()));
+            }
+            TokenKind::Name
+                if check_glyphs
+                    && angle.is_none()
+                    && previous.is_none_or(|p| p.text != "lookup")
+                    && !is_glyph_or_range(t.unescaped(), is_glyph) =>
+            {
+                diagnostics.push(Diagnostic::at(
+                    t,
+                    UnknownGlyph,
+                    format!("unknown glyph `{}`", t.unescaped()),
+                ));
+            }
+            _ => {}
+        }
+        previous = Some(t);
+    }
+    if let Some(t) = class {
+        diagnostics.push(Diagnostic::at(t, Syntax, "unclosed `[`".to_string()));
+    }
+    if let Some(t) = angle {
+        diagnostics.push(Diagnostic::at(t, Syntax, "unclosed `<`".to_string()));
+    }
+}
+
+/// Glyph names can contain hyphens, so `a-z` is a range only if it isn't a glyph name itself.
+fn is_glyph_or_range(name: &str, is_glyph: &impl Fn(&str) -> bool) -> bool {
+    is_glyph(name)
+        || name
+            .match_indices('-')
+            .any(|(i, _)| is_glyph(&name[..i]) && is_glyph(&name[i + 1..]))
+}
+
+#[test]
+fn test_features_tokenize() {
+    let tokens = tokenize("@UC = [A-Z \\sub]; # caps\nsub a' by -1 \"x\"\\12 ~");
+    assert_eq!(
+        tokens
+            .iter()
+            .map(|t| (t.kind, t.text))
+            .collect::<Vec<(TokenKind, &str)>>(),
+        vec![
+            (TokenKind::Class, "@UC"),
+            (TokenKind::Punct('='), "="),
+            (TokenKind::Punct('['), "["),
+            (TokenKind::Name, "A-Z"),
+            (TokenKind::Name, "\\sub"),
+            (TokenKind::Punct(']'), "]"),
+            (TokenKind::Punct(';'), ";"),
+            (TokenKind::Comment, "# caps"),
+            (TokenKind::Keyword, "sub"),
+            (TokenKind::Name, "a"),
+            (TokenKind::Punct('\''), "'"),
+            (TokenKind::Keyword, "by"),
+            (TokenKind::Number, "-1"),
+            (TokenKind::String, "\"x\""),
+            (TokenKind::Cid, "\\12"),
+            (TokenKind::Invalid, "~"),
+        ]
+    );
+    assert_eq!((tokens[8].line, tokens[8].column), (1, 0));
+    assert_eq!((tokens[12].line, tokens[12].column), (1, 10));
+    assert_eq!(class_names(&tokens), vec!["@UC"]);
+}
+
+#[test]
+fn test_features_check() {
+    let glyphs = ["a", "b", "c", "f", "i", "f_i", "a.sc", "acute", "one-two"];
+    let is_glyph = |name: &str| glyphs.contains(&name);
+    let messages = |source: &str| {
+        check(source, is_glyph)
+            .into_iter()
+            .map(|d| d.to_string())
+            .collect::<Vec<String>>()
+    };
+
+    let valid = r#"languagesystem DFLT dflt;
+@lower = [a - c one-two];
+markClass acute <anchor 0 500> @TOP;
+table GDEF {
+    GlyphClassDef @lower, , [acute], ;
+} GDEF;
+lookup smcp_lookup {
+    sub [a-c] by a.sc;
+} smcp_lookup;
+feature liga {
+    # f_i
+    sub f i by f_i;
+    ignore sub f' i;
+    sub a' lookup smcp_lookup b;
+    pos base [a b] <anchor 250 450> mark @TOP;
+} liga;
+feature aalt { feature liga; } aalt;
+anon zzzz {
+    anything { goes
+} zzzz;
+"#;
+    assert_eq!(messages(valid), Vec::<String>::new());
+    assert_eq!(
+        messages("feature liga {\n  sub f x by f_i;\n} liga;"),
+        vec!["2:9: unknown glyph `x`"]
+    );
+    assert_eq!(
+        messages("feature liga {\n  sub f i by f_i\n  sub a by b;\n} liga;"),
+        vec!["2:14: expected `;` after `f_i`"]
+    );
+    assert_eq!(
+        messages("feature liga {\n  sub f i by f_i;\n} kern;"),
+        vec!["3:3: expected end of feature `liga`, found `kern`"]
+    );
+    assert_eq!(
+        messages("feature liga {\n  sub [f i by f_i;\n"),
+        vec!["1:1: unclosed feature `liga`", "2:7: unclosed `[`"]
+    );
+    assert_eq!(
+        messages("sub a by b; } \"abc"),
+        vec!["1:13: unmatched `}`", "1:15: unterminated string"]
+    );
+}
+
+#[test]
+fn test_features_save() {
+    let dir = std::env::temp_dir().join(format!("gerb-features-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("features.fea");
+    let mut features = Features::from_path(&path).unwrap();
+    assert_eq!(features, Features::default());
+    features.set("languagesystem DFLT dflt;\n".to_string());
+    assert!(features.modified());
+    features.save(&path).unwrap();
+    assert!(!features.modified());
+    assert_eq!(Features::from_path(&path).unwrap(), features);
+    features.set(String::new());
+    features.save(&path).unwrap();
+    assert!(!path.exists());
+
+    std::fs::write(&path, b"# caf\xe9\nlanguagesystem DFLT dflt;\n").unwrap();
+    let mut features = Features::from_path(&path).unwrap();
+    assert_eq!(
+        features.source,
+        "# caf\u{fffd}\nlanguagesystem DFLT dflt;\n"
+    );
+    assert!(!features.modified());
+    assert!(features.lossy());
+    features.set("languagesystem DFLT dflt;\n".to_string());
+    assert!(features.save(&path).is_err());
+    assert_eq!(
+        std::fs::read(&path).unwrap(),
+        b"# caf\xe9\nlanguagesystem DFLT dflt;\n"
+    );
+    features.accept_lossy();
+    features.save(&path).unwrap();
+    assert!(!Features::from_path(&path).unwrap().lossy());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
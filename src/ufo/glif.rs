@@ -222,6 +222,19 @@ pub struct Anchor {
     pub x: f64,
     #[serde(rename = "@y")]
     pub y: f64,
+    #[serde(
+        default,
+        rename = "@color",
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "color_serialize"
+    )]
+    pub color: Option<Color>,
+    #[serde(
+        default,
+        rename = "@identifier",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub identifier: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -396,48 +409,297 @@ pub struct Glif {
     #[serde(rename = "guideline", default)]
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub guidelines: Vec<Guideline>,
-    // [ref:FIXME]: impl ser de
-    // <https://github.com/ebarnard/rust-plist/issues/79>
-    #[serde(
-        default,
-        skip,
-        rename = "lib",
-        skip_serializing_if = "IndexMap::is_empty",
-        serialize_with = "_plist_ser",
-        deserialize_with = "_plist_de"
-    )]
+    /// Contents of the `<lib>` element.
+    ///
+    /// The element is a plist dictionary embedded in the glif XML, which `quick_xml`'s serde
+    /// support cannot map (<https://github.com/ebarnard/rust-plist/issues/79>), so it is skipped
+    /// here and read and written by the `plist` crate in `Glif::from_str` and [`Glif::to_xml`].
+    #[serde(skip)]
     pub lib: IndexMap<String, plist::Value>,
+    /// Contents of the `<lib>` element as they were read. They are written back instead of
+    /// serializing [`Glif::lib`] again as long as they still describe it, so that the formatting
+    /// of unchanged libs is preserved.
+    #[serde(skip)]
+    pub lib_source: Option<String>,
 }
 
-fn _plist_de<'de, D>(deserializer: D) -> Result<IndexMap<String, plist::Value>, D::Error>
-where
-    D: serde::Deserializer<'de>,
-{
-    use serde::de::Error;
+/// Byte range of the contents of the top level `<lib>` element of a glif document, if any.
+fn lib_range(xml: &str) -> Result<Option<std::ops::Range<usize>>, quick_xml::Error> {
+    use quick_xml::events::Event;
+
+    let mut reader = quick_xml::Reader::from_str(xml);
+    let mut depth = 0_usize;
+    let mut start = None;
+    loop {
+        let before = reader.buffer_position();
+        match reader.read_event()? {
+            Event::Start(e) => {
+                depth += 1;
+                if depth == 2 && e.name().as_ref() == b"lib" {
+                    start = Some(reader.buffer_position());
+                }
+            }
+            Event::Empty(e) if depth == 1 && e.name().as_ref() == b"lib" => {
+                return Ok(Some(before..before));
+            }
+            Event::End(_) => {
+                if let (2, Some(start)) = (depth, start) {
+                    return Ok(Some(start..before));
+                }
+                depth = depth.saturating_sub(1);
+            }
+            Event::Eof => return Ok(None),
+            _ => {}
+        }
+    }
+}
+
+/// Parse the `<lib>` element of a glif document.
+pub(super) fn lib_from_xml(
+    xml: &str,
+) -> Result<IndexMap<String, plist::Value>, Box<dyn std::error::Error>> {
+    match lib_range(xml)? {
+        Some(range) => parse_lib(&xml[range]),
+        None => Ok(IndexMap::default()),
+    }
+}
 
-    let dict: plist::Value = plist::Value::deserialize(deserializer)?;
-    let dict: plist::Dictionary = dict
+/// Parse the contents of a `<lib>` element.
+fn parse_lib(contents: &str) -> Result<IndexMap<String, plist::Value>, Box<dyn std::error::Error>> {
+    if contents.trim().is_empty() {
+        return Ok(IndexMap::default());
+    }
+    let value = plist::Value::from_reader_xml(std::io::Cursor::new(format!(
+        "<plist version=\"1.0\">{}</plist>",
+        contents
+    )))?;
+    let dict: plist::Dictionary = value
         .into_dictionary()
-        .ok_or_else(|| Error::custom(String::new()))?;
+        .ok_or("glif <lib> element does not contain a <dict>")?;
     Ok(dict.into_iter().collect())
 }
 
-fn _plist_ser<S>(p: &IndexMap<String, plist::Value>, serializer: S) -> Result<S::Ok, S::Error>
-where
-    S: Serializer,
-{
-    let dict: plist::Dictionary = p.clone().into_iter().collect();
-    let val = plist::Value::Dictionary(dict);
-    val.serialize(serializer)
+/// Serialize `lib` as a `<lib>` element.
+pub(super) fn lib_to_xml(lib: &IndexMap<String, plist::Value>) -> Result<String, plist::Error> {
+    let dict: plist::Dictionary = lib.clone().into_iter().collect();
+    let mut buf = vec![];
+    #[allow(deprecated)]
+    plist::Value::Dictionary(dict).to_writer_xml_with_options(
+        &mut buf,
+        &plist::XmlWriteOptions::default()
+            .indent_string("  ")
+            .root_element(false),
+    )?;
+    Ok(format!("<lib>{}</lib>", String::from_utf8_lossy(&buf)))
+}
+
+/// Part of a glif document that a [`GlifError`] refers to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GlifElement {
+    /// The `<glyph>` root element, or the document as a whole.
+    Glyph,
+    /// The nth `<unicode>` element.
+    Unicode(usize),
+    /// The nth `<contour>` element of the `<outline>`.
+    Contour(usize),
+    /// The nth `<point>` element of a `<contour>`.
+    Point { contour: usize, point: usize },
+    /// The `<lib>` element.
+    Lib,
+}
+
+impl std::fmt::Display for GlifElement {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Glyph => write!(fmt, "<glyph>"),
+            Self::Unicode(n) => write!(fmt, "<unicode> #{}", n + 1),
+            Self::Contour(n) => write!(fmt, "<contour> #{}", n + 1),
+            Self::Point { contour, point } => {
+                write!(fmt, "<point> #{} of <contour> #{}", point + 1, contour + 1)
+            }
+            Self::Lib => write!(fmt, "<lib>"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GlifErrorKind {
+    /// The file could not be read.
+    Io(String),
+    /// The document is not well-formed or does not follow the glif schema.
+    Syntax(String),
+    /// A `move` point that is not the first point of its contour.
+    StrayMove,
+    /// A `hex` value that is not a Unicode scalar value.
+    InvalidUnicode(String),
+    /// The `<lib>` element is not a plist dictionary.
+    Lib(String),
+}
+
+impl std::fmt::Display for GlifErrorKind {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Io(err) => write!(fmt, "could not read file: {}", err),
+            Self::Syntax(err) => write!(fmt, "could not parse: {}", err),
+            Self::StrayMove => write!(fmt, "move point in the middle of a contour"),
+            Self::InvalidUnicode(hex) => write!(fmt, "invalid unicode value {:?}", hex),
+            Self::Lib(err) => write!(fmt, "invalid lib: {}", err),
+        }
+    }
+}
+
+/// Error while reading a glif file or converting it to a [`Glyph`](glyphs::Glyph).
+///
+/// `file` and `line` are filled in with [`GlifError::locate`] once the source is known.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GlifError {
+    pub file: Option<std::path::PathBuf>,
+    pub line: Option<usize>,
+    pub element: GlifElement,
+    pub kind: GlifErrorKind,
+}
+
+impl GlifError {
+    pub fn new(element: GlifElement, kind: GlifErrorKind) -> Self {
+        Self {
+            file: None,
+            line: None,
+            element,
+            kind,
+        }
+    }
+
+    /// Set the file of the error and, unless already known, the line of its element in `source`.
+    pub fn locate(mut self, file: &std::path::Path, source: &str) -> Self {
+        self.file = Some(file.to_path_buf());
+        if self.line.is_none() {
+            self.line = element_position(source, self.element).map(|pos| line_of(source, pos));
+        }
+        self
+    }
+}
+
+impl std::fmt::Display for GlifError {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match (&self.file, self.line) {
+            (Some(file), Some(line)) => write!(fmt, "{}:{}: ", file.display(), line)?,
+            (Some(file), None) => write!(fmt, "{}: ", file.display())?,
+            (None, Some(line)) => write!(fmt, "line {}: ", line)?,
+            (None, None) => {}
+        }
+        write!(fmt, "{}: {}", self.element, self.kind)
+    }
+}
+
+impl std::error::Error for GlifError {}
+
+/// 1-based line number of byte offset `pos` in `source`.
+fn line_of(source: &str, pos: usize) -> usize {
+    source.as_bytes()[..pos.min(source.len())]
+        .split(|&b| b == b'\n')
+        .count()
+}
+
+/// Byte offset of the start tag of `element` in `source`.
+fn element_position(source: &str, element: GlifElement) -> Option<usize> {
+    use quick_xml::events::Event;
+
+    let mut reader = quick_xml::Reader::from_str(source);
+    let (mut unicodes, mut contours, mut points) = (0, 0, 0);
+    loop {
+        let before = reader.buffer_position();
+        let e = match reader.read_event().ok()? {
+            Event::Start(e) | Event::Empty(e) => e,
+            Event::Eof => return None,
+            _ => continue,
+        };
+        let found = match e.name().as_ref() {
+            b"glyph" => element == GlifElement::Glyph,
+            b"lib" => element == GlifElement::Lib,
+            b"unicode" => {
+                unicodes += 1;
+                element == GlifElement::Unicode(unicodes - 1)
+            }
+            b"contour" => {
+                contours += 1;
+                points = 0;
+                element == GlifElement::Contour(contours - 1)
+            }
+            b"point" if contours > 0 => {
+                points += 1;
+                element
+                    == GlifElement::Point {
+                        contour: contours - 1,
+                        point: points - 1,
+                    }
+            }
+            _ => false,
+        };
+        if found {
+            return Some(before);
+        }
+    }
+}
+
+/// Byte offset of the first XML syntax error in `source`, if any.
+fn syntax_error_position(source: &str) -> Option<usize> {
+    let mut reader = quick_xml::Reader::from_str(source);
+    loop {
+        match reader.read_event() {
+            Err(_) => return Some(reader.buffer_position()),
+            Ok(quick_xml::events::Event::Eof) => return None,
+            Ok(_) => {}
+        }
+    }
+}
+
+/// Insert `element` as the last child of the root element of `xml`.
+fn append_to_root(xml: &str, element: &str) -> Result<String, quick_xml::Error> {
+    use quick_xml::events::{BytesEnd, BytesText, Event};
+
+    let mut reader = quick_xml::Reader::from_str(xml);
+    let mut writer = quick_xml::Writer::new(vec![]);
+    let mut depth = 0_usize;
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) => {
+                depth += 1;
+                writer.write_event(Event::Start(e))?;
+            }
+            Event::Empty(e) if depth == 0 => {
+                let end = BytesEnd::new(String::from_utf8_lossy(e.name().as_ref()).into_owned());
+                writer.write_event(Event::Start(e))?;
+                writer.write_event(Event::Text(BytesText::from_escaped(element)))?;
+                writer.write_event(Event::End(end))?;
+            }
+            Event::End(e) => {
+                depth = depth.saturating_sub(1);
+                if depth == 0 {
+                    writer.write_event(Event::Text(BytesText::from_escaped(element)))?;
+                }
+                writer.write_event(Event::End(e))?;
+            }
+            Event::Eof => break,
+            e => writer.write_event(e)?,
+        }
+    }
+    Ok(String::from_utf8_lossy(&writer.into_inner()).into_owned())
 }
 
 impl Glif {
-    #[allow(dead_code)]
     pub fn to_xml(&self) -> String {
-        format!(
-            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n{}\n",
-            quick_xml::se::to_string(&self).unwrap(),
-        )
+        let mut xml = quick_xml::se::to_string(&self).unwrap();
+        if !self.lib.is_empty() {
+            let lib =
+                match self.lib_source.as_ref().filter(|source| {
+                    parse_lib(source).is_ok_and(|lib| lib.iter().eq(self.lib.iter()))
+                }) {
+                    Some(source) => format!("<lib>{source}</lib>"),
+                    None => lib_to_xml(&self.lib).unwrap(),
+                };
+            xml = append_to_root(&xml, &lib).unwrap();
+        }
+        format!("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n{}\n", xml)
     }
 }
 
@@ -445,81 +707,18 @@ impl From<&glyphs::Glyph> for Glif {
     fn from(glyph: &glyphs::Glyph) -> Self {
         let mut outline: Vec<OutlineEntry> =
             Vec::with_capacity(glyph.components.len() + glyph.contours.len());
-        outline.extend(glyph.components.iter().map(|c| {
-            OutlineEntry::Component(Component {
-                base: c.base_name.clone(),
-                x_offset: c.x_offset,
-                y_offset: c.y_offset,
-                x_scale: c.x_scale,
-                xy_scale: c.xy_scale,
-                yx_scale: c.yx_scale,
-                y_scale: c.y_scale,
-            })
-        }));
-        outline.extend(glyph.contours.iter().map(|c| {
-            let mut point = vec![];
-            if c.imp().open.get() {
-                let mut first = true;
-                for curv in c.curves().iter() {
-                    let degree = curv.degree();
-                    point.extend(curv.points().iter().enumerate().map(|(i, cp)| Point {
-                        x: cp.position.x,
-                        y: cp.position.y,
-                        name: None,
-                        identifier: None,
-                        type_: if first {
-                            first = false;
-                            PointKind::Move
-                        } else {
-                            match (i, degree) {
-                                (0 | 3, Some(3)) => PointKind::Curve,
-                                (1 | 2, Some(3)) => PointKind::Offcurve,
-                                (0 | 2, Some(2)) => PointKind::Curve,
-                                (1, Some(2)) => PointKind::Qcurve,
-                                (0 | 1, Some(1)) => PointKind::Line,
-                                _ => PointKind::Move,
-                            }
-                        },
-                        smooth: None,
-                    }));
-                }
-            } else {
-                let mut last = false;
-                for curv in c.curves().iter() {
-                    let degree = curv.degree();
-                    point.extend(curv.points().iter().enumerate().filter_map(|(i, cp)| {
-                        if last {
-                            last = false;
-                            None
-                        } else {
-                            if Some(i + 1) == degree {
-                                last = true;
-                            }
-
-                            Some(Point {
-                                x: cp.position.x,
-                                y: cp.position.y,
-                                name: None,
-                                identifier: None,
-                                type_: match (i, degree) {
-                                    (0 | 3, Some(3)) => PointKind::Curve,
-                                    (1 | 2, Some(3)) => PointKind::Offcurve,
-                                    (0 | 2, Some(2)) => PointKind::Curve,
-                                    (1, Some(2)) => PointKind::Qcurve,
-                                    (0 | 1, Some(1)) => PointKind::Line,
-                                    _ => PointKind::Move,
-                                },
-                                smooth: None,
-                            })
-                        }
-                    }));
-                }
-            }
-            OutlineEntry::Contour(Contour {
-                identifier: None,
-                point,
-            })
-        }));
+        outline.extend(
+            glyph
+                .components
+                .iter()
+                .map(|c| OutlineEntry::Component(c.into())),
+        );
+        outline.extend(
+            glyph
+                .contours
+                .iter()
+                .map(|c| OutlineEntry::Contour(c.into())),
+        );
 
         Self {
             name: glyph.name().to_string(),
@@ -528,17 +727,154 @@ impl From<&glyphs::Glyph> for Glif {
             image: glyph.metadata.image.borrow().clone(),
             advance: glyph.metadata.advance.get(),
             outline: Some(Outline { contours: outline }),
-            anchors: glyph.metadata.anchors.borrow().clone(),
+            anchors: glyph
+                .metadata
+                .anchors
+                .borrow()
+                .iter()
+                .map(Into::into)
+                .collect(),
             guidelines: glyph.guidelines().iter().map(Into::into).collect(),
             lib: glyph.lib.clone(),
+            lib_source: glyph.lib_source.clone(),
+        }
+    }
+}
+
+impl From<&glyphs::Contour> for Contour {
+    fn from(c: &glyphs::Contour) -> Self {
+        Self {
+            identifier: None,
+            point: contour_to_points(c),
+        }
+    }
+}
+
+impl From<&glyphs::Component> for Component {
+    fn from(c: &glyphs::Component) -> Self {
+        Self {
+            base: c.base_name.clone(),
+            x_offset: c.x_offset,
+            y_offset: c.y_offset,
+            x_scale: c.x_scale,
+            xy_scale: c.xy_scale,
+            yx_scale: c.yx_scale,
+            y_scale: c.y_scale,
         }
     }
 }
 
+/// Convert a [`Contour`](glyphs::Contour) to the points of a `<contour>` element.
+///
+/// This is the inverse of how contours are read: closed contours start with the start point of
+/// their first curve, and implied on-curve points of quadratic curves are omitted if they are
+/// still the midpoint of their neighbouring off-curve points.
+fn contour_to_points(contour: &glyphs::Contour) -> Vec<Point> {
+    use crate::utils::curves::Bezier;
+
+    let curves = contour.curves();
+    let open = contour.imp().open.get();
+    let new_point = |position: crate::utils::points::Point, type_, smooth: bool| Point {
+        x: position.x,
+        y: position.y,
+        name: None,
+        identifier: None,
+        type_,
+        smooth: if smooth { Some(true) } else { None },
+    };
+    let end_kind = |curv: &Bezier| match curv.degree() {
+        Some(0 | 1) => PointKind::Line,
+        Some(2) => PointKind::Qcurve,
+        _ => PointKind::Curve,
+    };
+    /* Whether the start point of `curv`, which is the end point of `prev`, can be left implied. */
+    let is_implied = |prev: &Bezier, curv: &Bezier| -> bool {
+        if !curv.imp().implied_start.get() || prev.degree() != Some(2) || curv.degree() != Some(2) {
+            return false;
+        }
+        let (prev, curr) = (prev.points(), curv.points());
+        let mid = 0.5 * (prev[1].position + curr[1].position);
+        (mid - curr[0].position).norm() < 1e-6
+    };
+    let mut ret = vec![];
+    let Some(first) = curves.first() else {
+        return ret;
+    };
+    let last = &curves[curves.len() - 1];
+    if open {
+        if let Some(start) = first.points().first() {
+            ret.push(new_point(start.position, PointKind::Move, false));
+        }
+    } else if !is_implied(last, first) {
+        if let Some(start) = first.points().first() {
+            ret.push(new_point(
+                start.position,
+                end_kind(last),
+                first.imp().smooth.get(),
+            ));
+        }
+    }
+    for (i, curv) in curves.iter().enumerate() {
+        let points = curv.points();
+        if points.len() < 2 {
+            continue;
+        }
+        ret.extend(
+            points[1..points.len() - 1]
+                .iter()
+                .map(|cp| new_point(cp.position, PointKind::Offcurve, false)),
+        );
+        let next = match curves.get(i + 1) {
+            Some(next) => next,
+            /* The end point of the last curve of a closed contour is its start point. */
+            None if !open => continue,
+            None => {
+                ret.push(new_point(
+                    points[points.len() - 1].position,
+                    end_kind(curv),
+                    false,
+                ));
+                continue;
+            }
+        };
+        if is_implied(curv, next) {
+            continue;
+        }
+        ret.push(new_point(
+            points[points.len() - 1].position,
+            end_kind(curv),
+            next.imp().smooth.get(),
+        ));
+    }
+    if !open {
+        /* Put the off-curve points that came before the first on-curve point when loading back at
+         * the front. Only trailing off-curve points can move, whatever happened to the contour
+         * since. */
+        let trailing = ret
+            .iter()
+            .rev()
+            .take_while(|p| p.type_ == PointKind::Offcurve)
+            .count();
+        if trailing < ret.len() {
+            ret.rotate_right(contour.imp().leading_offcurves.get().min(trailing));
+        }
+    }
+    ret
+}
+
 impl std::str::FromStr for Glif {
-    type Err = Box<dyn std::error::Error>;
-    fn from_str(s: &str) -> Result<Self, Box<dyn std::error::Error>> {
-        Ok(quick_xml::de::from_str(s)?)
+    type Err = GlifError;
+    fn from_str(s: &str) -> Result<Self, GlifError> {
+        let mut ret: Self = quick_xml::de::from_str(s).map_err(|err| GlifError {
+            line: syntax_error_position(s).map(|pos| line_of(s, pos)),
+            ..GlifError::new(GlifElement::Glyph, GlifErrorKind::Syntax(err.to_string()))
+        })?;
+        let lib_error = |err: String| GlifError::new(GlifElement::Lib, GlifErrorKind::Lib(err));
+        if let Some(range) = lib_range(s).map_err(|err| lib_error(err.to_string()))? {
+            ret.lib = parse_lib(&s[range.clone()]).map_err(|err| lib_error(err.to_string()))?;
+            ret.lib_source = Some(s[range].to_string());
+        }
+        Ok(ret)
     }
 }
 
@@ -551,24 +887,372 @@ impl Default for Glif {
 #[test]
 fn test_glif_parse() {
     let g: Glif = quick_xml::de::from_str(_UPPERCASE_A_GLIF).unwrap();
-    let _: glyphs::Glyph = g.into();
+    let _: glyphs::Glyph = g.try_into().unwrap();
     let glif: Glif = quick_xml::de::from_str(EXCLAM_GLYPH).unwrap();
-    let glyph: glyphs::Glyph = glif.into();
+    let glyph: glyphs::Glyph = glif.try_into().unwrap();
     let _glif2: Glif = Glif::from(&glyph);
     //print!("{}\n\n{}", glif.to_xml(), glif2.to_xml());
     //assert_eq!(glif.to_xml(), glif2.to_xml());
 }
 
+#[test]
+fn test_glif_qcurve() {
+    use crate::utils::points::Point as P;
+
+    let glif: Glif = quick_xml::de::from_str(_QUADRATIC_GLIF).unwrap();
+    let glyph: glyphs::Glyph = glif.clone().try_into().unwrap();
+    assert_eq!(glyph.contours.len(), 3);
+    let degrees = |c: &glyphs::Contour| c.curves().iter().map(|b| b.degree()).collect::<Vec<_>>();
+
+    /* Implied on-curve points between consecutive off-curve points. */
+    let closed = &glyph.contours[0];
+    assert!(!closed.imp().open.get());
+    assert_eq!(
+        degrees(closed),
+        vec![Some(2), Some(2), Some(2), Some(2), Some(1), Some(2)]
+    );
+    {
+        let curves = closed.curves();
+        assert_eq!(curves[0].points()[0].position, P::from((250.0, 0.0)));
+        assert_eq!(curves[0].points()[2].position, P::from((400.0, 50.0)));
+        assert_eq!(curves[1].points()[0].position, P::from((400.0, 50.0)));
+        assert_eq!(curves[1].points()[2].position, P::from((450.0, 250.0)));
+        assert!(!curves[0].imp().implied_start.get());
+        assert!(curves[1].imp().implied_start.get());
+        assert_eq!(curves[5].points()[2].position, P::from((250.0, 0.0)));
+    }
+
+    /* No on-curve points at all. */
+    let offcurves = &glyph.contours[1];
+    assert!(!offcurves.imp().open.get());
+    assert_eq!(degrees(offcurves), vec![Some(2); 4]);
+    {
+        let curves = offcurves.curves();
+        assert_eq!(curves[0].points()[0].position, P::from((100.0, 150.0)));
+        assert_eq!(curves[0].points()[1].position, P::from((100.0, 100.0)));
+        assert_eq!(curves[0].points()[2].position, P::from((150.0, 100.0)));
+        assert!(curves.iter().all(|c| c.imp().implied_start.get()));
+    }
+
+    let open = &glyph.contours[2];
+    assert!(open.imp().open.get());
+    assert_eq!(degrees(open), vec![Some(1), Some(2), Some(2)]);
+
+    /* Quadratic sources round-trip unchanged. */
+    let glif2: Glif = Glif::from(&glyph);
+    assert_eq!(glif.outline, glif2.outline);
+
+    /* Moving an implied on-curve point makes it explicit. */
+    closed.curves()[0].modify_point(2, |cp| cp.position = P::from((400.0, 60.0)));
+    closed.curves()[1].modify_point(0, |cp| cp.position = P::from((400.0, 60.0)));
+    let glif3: Glif = Glif::from(&glyph);
+    let Some(OutlineEntry::Contour(ref contour)) = glif3.outline.as_ref().unwrap().contours.first()
+    else {
+        panic!("expected contour");
+    };
+    assert_eq!(contour.point.len(), 10);
+    assert_eq!(contour.point[2].type_, PointKind::Qcurve);
+    assert_eq!((contour.point[2].x, contour.point[2].y), (400.0, 60.0));
+
+    /* Cubic sources round-trip unchanged as well. */
+    for src in [_LOWERCASE_B_GLIF, _UPPERCASE_A_GLIF] {
+        let glif: Glif = quick_xml::de::from_str(src).unwrap();
+        let glyph: glyphs::Glyph = glif.clone().try_into().unwrap();
+        assert_eq!(glif.outline, Glif::from(&glyph).outline);
+    }
+
+    /* Closed contours that start with off-curve points keep their start point. */
+    for src in [_QUADRATIC_GLIF, _LOWERCASE_B_GLIF, _UPPERCASE_A_GLIF] {
+        let mut glif: Glif = quick_xml::de::from_str(src).unwrap();
+        for entry in glif.outline.as_mut().unwrap().contours.iter_mut() {
+            let OutlineEntry::Contour(contour) = entry else {
+                continue;
+            };
+            if contour.point.first().map(Point::is_move).unwrap_or(true) {
+                continue;
+            }
+            if let Some(i) = contour
+                .point
+                .iter()
+                .position(|p| p.type_ == PointKind::Offcurve)
+            {
+                contour.point.rotate_left(i);
+            }
+        }
+        let glyph: glyphs::Glyph = glif.clone().try_into().unwrap();
+        assert_eq!(glif.outline, Glif::from(&glyph).outline);
+    }
+}
+
 #[test]
 fn test_glif_write() {
     let g: Glif = quick_xml::de::from_str(_UPPERCASE_A_GLIF).unwrap();
-    let _: glyphs::Glyph = g.into();
+    let _: glyphs::Glyph = g.try_into().unwrap();
     let g: Glif = quick_xml::de::from_str(_UPPERCASE_A_GLIF).unwrap();
     let g2: Glif = quick_xml::de::from_str(&g.to_xml()).unwrap();
     assert_eq!(g.to_xml(), g2.to_xml());
 }
 
-const _LOWERCASE_B_GLIF: &str = r##"<?xml version="1.0" encoding="UTF-8"?>
+#[test]
+fn test_glif_anchors() {
+    use glib::prelude::*;
+
+    let glif: Glif = quick_xml::de::from_str(_UPPERCASE_A_GLIF).unwrap();
+    let glyph: glyphs::Glyph = glif.clone().try_into().unwrap();
+    assert_eq!(
+        glyph
+            .metadata
+            .anchors()
+            .iter()
+            .map(|a| a.name())
+            .collect::<Vec<_>>(),
+        glif.anchors
+            .iter()
+            .map(|a| a.name.clone())
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(glif.anchors, Glif::from(&glyph).anchors);
+    assert!(!glyph.metadata.modified());
+
+    glyph.metadata.anchors()[0].set_property(glyphs::Anchor::Y, 700.0);
+    assert!(glyph.metadata.modified());
+    let glif2 = Glif::from(&glyph);
+    assert_eq!(glif2.anchors[0].y, 700.0);
+    assert_eq!(glif2.anchors[1..], glif.anchors[1..]);
+
+    let anchor: Anchor = quick_xml::de::from_str(
+        r##"<anchor name="top" x="10" y="20" color="1,0,0,1" identifier="a1"/>"##,
+    )
+    .unwrap();
+    assert_eq!(anchor.identifier.as_deref(), Some("a1"));
+    assert!(anchor.color.is_some());
+    let anchor2: Anchor = (&glyphs::Anchor::from(anchor.clone())).into();
+    assert_eq!(anchor, anchor2);
+}
+
+#[test]
+fn test_glif_components() {
+    use crate::utils::points::Point as P;
+    use std::{cell::RefCell, rc::Rc, str::FromStr};
+
+    let base: glyphs::Glyph = Glif::from_str(_UPPERCASE_A_GLIF)
+        .unwrap()
+        .try_into()
+        .unwrap();
+    let base = Rc::new(RefCell::new(base));
+    let glif = Glif::from_str(
+        r##"<?xml version="1.0" encoding="UTF-8"?>
+<glyph name="Aacute" format="2">
+  <outline>
+    <component base="A" xOffset="100" yOffset="-20" xScale="0.5" yScale="2"/>
+  </outline>
+</glyph>"##,
+    )
+    .unwrap();
+    let mut glyph: glyphs::Glyph = glif.clone().try_into().unwrap();
+    assert_eq!(glyph.components.len(), 1);
+    glyph.components[0].base = Rc::downgrade(&base);
+
+    let m = glyph.components[0].matrix();
+    assert_eq!(m.transform_point(3.0, 10.0), (101.5, 0.0));
+    glyph.components[0].set_matrix(m);
+    assert_eq!(glif.outline, Glif::from(&glyph).outline);
+
+    let contours = glyph.components[0].decompose();
+    assert_eq!(contours.len(), base.borrow().contours.len());
+    assert_eq!(
+        contours[0].curves()[0].points()[0].position,
+        P::from((101.5, -20.0))
+    );
+    let (min, max) = glyph.components[0].bbox().unwrap();
+    assert!(glyph.component_query((min + max) / 2.0).is_some());
+    assert!(glyph.component_query(max + (1.0, 1.0).into()).is_none());
+}
+
+#[test]
+fn test_glif_lib() {
+    use std::str::FromStr;
+
+    let glif = Glif::from_str(EXCLAM_GLYPH).unwrap();
+    assert_eq!(
+        glif.lib["com.schriftgestaltung.Glyphs.lastChange"],
+        plist::Value::String("2018-04-05 15:21:53 +0000".to_string())
+    );
+    assert_eq!(
+        glif.lib["com.typemytype.robofont.mark"],
+        plist::Value::Array(vec![
+            plist::Value::Real(0.6),
+            plist::Value::Real(0.609),
+            plist::Value::Integer(1.into()),
+            plist::Value::Integer(1.into()),
+        ])
+    );
+
+    let glif = Glif::from_str(_LIB_GLIF).unwrap();
+    assert_eq!(
+        glif.lib.keys().collect::<Vec<_>>(),
+        vec![
+            "org.example.nested",
+            "org.example.data",
+            "org.example.date",
+            "org.example.empty",
+            "public.markColor",
+        ]
+    );
+    let nested = glif.lib["org.example.nested"].as_dictionary().unwrap();
+    assert_eq!(
+        nested["name"],
+        plist::Value::String("  spaces & <escapes>  ".to_string())
+    );
+    let array = nested["values"].as_array().unwrap();
+    assert_eq!(array.len(), 6);
+    assert_eq!(array[0], plist::Value::Integer((-3).into()));
+    assert_eq!(array[2], plist::Value::Boolean(true));
+    assert_eq!(array[3], plist::Value::Boolean(false));
+    assert_eq!(array[4], plist::Value::String(String::new()));
+    assert_eq!(
+        array[5].as_array().unwrap()[0]
+            .as_dictionary()
+            .unwrap()
+            .get("depth"),
+        Some(&plist::Value::Integer(3.into()))
+    );
+    assert_eq!(
+        glif.lib["org.example.data"].as_data(),
+        Some(b"hello, world".as_slice())
+    );
+    assert_eq!(
+        glif.lib["org.example.date"]
+            .as_date()
+            .map(|d| d.to_xml_format()),
+        Some("2018-04-05T15:21:53Z".to_string())
+    );
+    assert_eq!(
+        glif.lib["org.example.empty"],
+        plist::Value::Dictionary(Default::default())
+    );
+
+    /* Rewriting keeps the lib intact, and the written form is stable. */
+    let xml = glif.to_xml();
+    let glif2 = Glif::from_str(&xml).unwrap();
+    assert_eq!(glif.lib, glif2.lib);
+    assert_eq!(glif.outline, glif2.outline);
+    assert_eq!(xml, glif2.to_xml());
+
+    /* Through a `Glyph` as well. */
+    let glyph: glyphs::Glyph = glif.clone().try_into().unwrap();
+    assert_eq!(glyph.lib, glif.lib);
+    let glif3 = Glif::from(&glyph);
+    assert_eq!(Glif::from_str(&glif3.to_xml()).unwrap().lib, glif.lib);
+
+    /* The unchanged `<lib>` of a source file is written back byte for byte, also through a
+     * `Glyph`, and a changed one is written anew. */
+    let lib_element = |xml: &str| {
+        let start = xml.find("<lib>").unwrap();
+        let end = xml.find("</lib>").unwrap() + "</lib>".len();
+        xml[start..end].to_string()
+    };
+    for src in [EXCLAM_GLYPH, _LIB_GLIF] {
+        let mut glif = Glif::from_str(src).unwrap();
+        assert_eq!(lib_element(&glif.to_xml()), lib_element(src));
+        let glyph: glyphs::Glyph = glif.clone().try_into().unwrap();
+        assert_eq!(lib_element(&Glif::from(&glyph).to_xml()), lib_element(src));
+        glif.lib.insert(
+            "public.markColor".to_string(),
+            plist::Value::String("0,1,0,1".to_string()),
+        );
+        let xml = glif.to_xml();
+        assert_ne!(lib_element(&xml), lib_element(src));
+        assert_eq!(Glif::from_str(&xml).unwrap().lib, glif.lib);
+    }
+
+    /* Empty and missing `<lib>` elements. */
+    for src in [
+        r#"<glyph name="a" format="2"><lib/></glyph>"#,
+        r#"<glyph name="a" format="2"><lib></lib></glyph>"#,
+        r#"<glyph name="a" format="2"><lib><dict/></lib></glyph>"#,
+        _LOWERCASE_B_GLIF,
+    ] {
+        let glif = Glif::from_str(src).unwrap();
+        assert!(glif.lib.is_empty());
+        assert!(!glif.to_xml().contains("<lib>"));
+    }
+    assert!(Glif::from_str(r#"<glyph name="a" format="2"><lib><array/></lib></glyph>"#).is_err());
+}
+
+#[test]
+fn test_glif_errors() {
+    use std::convert::TryFrom;
+    use std::path::Path;
+    use std::str::FromStr;
+
+    const STRAY_MOVE: &str = r##"<?xml version="1.0" encoding="UTF-8"?>
+<glyph name="a" format="2">
+  <unicode hex="0061"/>
+  <outline>
+    <contour>
+      <point x="0" y="0" type="line"/>
+      <point x="100" y="0" type="line"/>
+    </contour>
+    <component base="b"/>
+    <contour>
+      <point x="0" y="0" type="line"/>
+      <point x="100" y="0" type="line"/>
+      <point x="100" y="100" type="move"/>
+    </contour>
+  </outline>
+</glyph>
+"##;
+    let err = glyphs::Glyph::try_from(Glif::from_str(STRAY_MOVE).unwrap()).unwrap_err();
+    assert_eq!(err.kind, GlifErrorKind::StrayMove);
+    assert_eq!(
+        err.element,
+        GlifElement::Point {
+            contour: 1,
+            point: 2
+        }
+    );
+    assert_eq!(err.line, None);
+    let err = err.locate(Path::new("a.glif"), STRAY_MOVE);
+    assert_eq!(err.line, Some(13));
+    assert_eq!(
+        err.to_string(),
+        "a.glif:13: <point> #3 of <contour> #2: move point in the middle of a contour"
+    );
+
+    let invalid_unicode = STRAY_MOVE
+        .replace(r#"type="move""#, r#"type="line""#)
+        .replace(
+            r#"<unicode hex="0061"/>"#,
+            "<unicode hex=\"0061\"/>\n  <unicode hex=\"D800\"/>",
+        );
+    let (glyph, warnings) =
+        glyphs::Glyph::from_glif(Glif::from_str(&invalid_unicode).unwrap()).unwrap();
+    assert_eq!(glyph.kinds().0, glyphs::GlyphKind::Char('a'));
+    assert!(glyph.kinds().1.is_empty());
+    let [warning] = <[_; 1]>::try_from(warnings).unwrap();
+    assert_eq!(
+        warning.kind,
+        GlifErrorKind::InvalidUnicode("D800".to_string())
+    );
+    assert_eq!(warning.element, GlifElement::Unicode(1));
+    assert_eq!(
+        warning.locate(Path::new("a.glif"), &invalid_unicode).line,
+        Some(4)
+    );
+
+    let syntax = STRAY_MOVE.replace("</contour>\n  </outline>", "</outline>");
+    let err = Glif::from_str(&syntax).unwrap_err();
+    assert!(matches!(err.kind, GlifErrorKind::Syntax(_)));
+    assert_eq!(err.line, Some(14));
+
+    let lib = STRAY_MOVE.replace("</outline>", "</outline>\n  <lib><string/></lib>");
+    let err = Glif::from_str(&lib).unwrap_err();
+    assert!(matches!(err.kind, GlifErrorKind::Lib(_)));
+    assert_eq!(err.locate(Path::new("a.glif"), &lib).line, Some(16));
+}
+
+pub(crate) const _LOWERCASE_B_GLIF: &str = r##"<?xml version="1.0" encoding="UTF-8"?>
 <glyph name="b" format="2">
 	<unicode hex="0062"/>
 	<advance width="553"/>
@@ -616,7 +1300,39 @@ const _LOWERCASE_B_GLIF: &str = r##"<?xml version="1.0" encoding="UTF-8"?>
 </glyph>"##;
 
 #[cfg(test)]
-const _UPPERCASE_A_GLIF: &str = r##"<?xml version="1.0" encoding="UTF-8"?>
+pub(crate) const _QUADRATIC_GLIF: &str = r##"<?xml version="1.0" encoding="UTF-8"?>
+<glyph name="o.quadratic" format="2">
+	<advance width="500"/>
+	<outline>
+		<contour>
+			<point x="250" y="0" type="qcurve" smooth="yes"/>
+			<point x="350" y="0"/>
+			<point x="450" y="100"/>
+			<point x="450" y="250" type="qcurve" smooth="yes"/>
+			<point x="450" y="400"/>
+			<point x="350" y="500"/>
+			<point x="250" y="500" type="qcurve" smooth="yes"/>
+			<point x="50" y="500" type="line"/>
+			<point x="50" y="0"/>
+		</contour>
+		<contour>
+			<point x="100" y="100"/>
+			<point x="200" y="100"/>
+			<point x="200" y="200"/>
+			<point x="100" y="200"/>
+		</contour>
+		<contour>
+			<point x="0" y="0" type="move"/>
+			<point x="10" y="0" type="line"/>
+			<point x="20" y="0"/>
+			<point x="30" y="10"/>
+			<point x="30" y="20" type="qcurve"/>
+		</contour>
+	</outline>
+</glyph>"##;
+
+#[cfg(test)]
+pub(crate) const _UPPERCASE_A_GLIF: &str = r##"<?xml version="1.0" encoding="UTF-8"?>
 <glyph name="A" format="2">
 	<unicode hex="0041"/>
   <image fileName="Sketch 1.png" xOffset="100" yOffset="200"
@@ -652,7 +1368,7 @@ const _UPPERCASE_A_GLIF: &str = r##"<?xml version="1.0" encoding="UTF-8"?>
 </glyph>"##;
 
 #[cfg(test)]
-const _AE_GLIF: &str = r##"<?xml version="1.0" encoding="UTF-8"?>
+pub(crate) const _AE_GLIF: &str = r##"<?xml version="1.0" encoding="UTF-8"?>
 <glyph name="ae" format="2">
 	<unicode hex="00E6"/>
 	<advance width="778"/>
@@ -768,3 +1484,52 @@ const EXCLAM_GLYPH: &str = r##"<?xml version='1.0' encoding='UTF-8'?>
   </lib>
 </glyph>
 "##;
+
+#[cfg(test)]
+const _LIB_GLIF: &str = r##"<?xml version="1.0" encoding="UTF-8"?>
+<glyph name="period" format="2">
+  <advance width="250"/>
+  <unicode hex="002E"/>
+  <outline>
+    <contour>
+      <point x="60" y="0" type="line"/>
+      <point x="190" y="0" type="line"/>
+      <point x="190" y="130" type="line"/>
+      <point x="60" y="130" type="line"/>
+    </contour>
+  </outline>
+  <lib>
+    <dict>
+      <key>org.example.nested</key>
+      <dict>
+        <key>name</key>
+        <string>  spaces &amp; &lt;escapes&gt;  </string>
+        <key>values</key>
+        <array>
+          <integer>-3</integer>
+          <real>0.125</real>
+          <true/>
+          <false/>
+          <string/>
+          <array>
+            <dict>
+              <key>depth</key>
+              <integer>3</integer>
+            </dict>
+          </array>
+        </array>
+      </dict>
+      <key>org.example.data</key>
+      <data>
+      aGVsbG8sIHdvcmxk
+      </data>
+      <key>org.example.date</key>
+      <date>2018-04-05T15:21:53Z</date>
+      <key>org.example.empty</key>
+      <dict/>
+      <key>public.markColor</key>
+      <string>1,0,0,1</string>
+    </dict>
+  </lib>
+</glyph>
+"##;
@@ -373,6 +373,12 @@ mod layer {
         pub dir_name: RefCell<String>,
         pub contents_plist: RefCell<ufo::Contents>,
         pub glyphs: RefCell<IndexMap<String, Rc<RefCell<Glyph>>>>,
+        /// Filenames of deleted glyphs, to be removed from disk on save.
+        deleted_files: RefCell<Vec<String>>,
+        /// Glyphs that could not be loaded.
+        pub load_errors: RefCell<Vec<ufo::glif::GlifError>>,
+        /// Data of loaded glyphs that was left out, see [`Glyph::from_glif`].
+        pub load_warnings: RefCell<Vec<ufo::glif::GlifError>>,
     }
 
     impl Default for LayerInner {
@@ -385,6 +391,9 @@ mod layer {
                 dir_name: RefCell::new(String::new()),
                 contents_plist: RefCell::new(ufo::Contents::default()),
                 glyphs: RefCell::new(IndexMap::default()),
+                deleted_files: RefCell::new(vec![]),
+                load_errors: RefCell::new(vec![]),
+                load_warnings: RefCell::new(vec![]),
             }
         }
     }
@@ -507,11 +516,13 @@ mod layer {
                 format!("couldn't read contents.plist {}: {}", path.display(), err)
             })?;
             path.pop();
-            let glyphs = Glyph::from_ufo(path.clone(), &contents)?;
+            let (glyphs, load_errors, load_warnings) = Glyph::from_ufo(path.clone(), &contents);
             for g in glyphs.values() {
                 self.link(&g.borrow().metadata);
             }
             *self.glyphs.borrow_mut() = glyphs;
+            *self.load_errors.borrow_mut() = load_errors;
+            *self.load_warnings.borrow_mut() = load_warnings;
             *self.contents_plist.borrow_mut() = contents;
             *self.path.borrow_mut() = path;
 
@@ -545,6 +556,13 @@ mod layer {
                 g.save(&prefix)?;
                 g.metadata.set_property(GlyphMetadata::MODIFIED, false);
             }
+            for filename in self.deleted_files.borrow_mut().drain(..) {
+                let path = prefix.join(&filename);
+                if path.exists() {
+                    std::fs::remove_file(&path)
+                        .map_err(|err| format!("Could not remove {}: {err}", path.display()))?;
+                }
+            }
             self.modified.set(false);
             Ok(())
         }
@@ -567,11 +585,146 @@ mod layer {
                 .metadata
                 .set_property(GlyphMetadata::LAYER, Some(self.clone()));
             self.set_property(Self::MODIFIED, true);
-            contents.insert(name.clone(), glyph.borrow().metadata.filename().to_string());
+            let filename = glyph.borrow().metadata.filename().to_string();
+            self.deleted_files.borrow_mut().retain(|f| f != &filename);
+            contents.insert(name.clone(), filename);
             self.glyphs.borrow_mut().insert(name, glyph);
             Ok(())
         }
 
+        /// Rename glyph `old` to `new`, updating references from components of other glyphs in
+        /// this layer. The glyph keeps its filename.
+        pub fn rename_glyph(&self, old: &str, new: &str) -> Result<(), Box<dyn std::error::Error>> {
+            if old == new {
+                return Ok(());
+            }
+            let mut glyphs = self.glyphs.borrow_mut();
+            if glyphs.contains_key(new) {
+                return Err(format!(
+                    "Glyph `{new}` already exists in layer {layer}.",
+                    layer = self.name.borrow()
+                )
+                .into());
+            }
+            let Some(index) = glyphs.get_index_of(old) else {
+                return Err(format!(
+                    "Glyph `{old}` does not exist in layer {layer}.",
+                    layer = self.name.borrow()
+                )
+                .into());
+            };
+            let (_, glyph) = glyphs.shift_remove_index(index).unwrap();
+            glyphs.insert(new.to_string(), glyph.clone());
+            let last = glyphs.len() - 1;
+            glyphs.move_index(last, index);
+            {
+                let g = glyph.borrow();
+                g.metadata
+                    .set_property(GlyphMetadata::NAME, Some(new.to_string()));
+                g.metadata.set_property(GlyphMetadata::MODIFIED, true);
+            }
+            for g in glyphs.values().filter(|g| !Rc::ptr_eq(g, &glyph)) {
+                let mut g = g.borrow_mut();
+                let mut changed = false;
+                for c in g.components.iter_mut().filter(|c| c.base_name == old) {
+                    c.base_name = new.to_string();
+                    changed = true;
+                }
+                if changed {
+                    g.metadata.set_property(GlyphMetadata::MODIFIED, true);
+                }
+            }
+            self.contents_plist.borrow_mut().rename(old, new);
+            self.set_property(Self::MODIFIED, true);
+            Ok(())
+        }
+
+        /// Remove glyph `name` from this layer. Its .glif file is removed on the next save.
+        pub fn delete_glyph(&self, name: &str) -> Option<Rc<RefCell<Glyph>>> {
+            let glyph = self.glyphs.borrow_mut().shift_remove(name)?;
+            if let Some(filename) = self.contents_plist.borrow_mut().remove(name) {
+                self.deleted_files.borrow_mut().push(filename);
+            }
+            self.set_property(Self::MODIFIED, true);
+            Some(glyph)
+        }
+
+        /// The glyphs `names` in layer order, or every glyph if `names` is `None`.
+        pub fn selected_glyphs(
+            &self,
+            names: Option<&[String]>,
+        ) -> Vec<(String, Rc<RefCell<Glyph>>)> {
+            self.glyphs
+                .borrow()
+                .iter()
+                .filter(|(name, _)| names.is_none_or(|names| names.contains(name)))
+                .map(|(name, glyph)| (name.clone(), glyph.clone()))
+                .collect()
+        }
+
+        /// Compute the contours without overlaps of the [selected glyphs](Self::selected_glyphs),
+        /// without changing them.
+        pub fn remove_overlaps(
+            &self,
+            names: Option<&[String]>,
+        ) -> crate::glyphs::boolean::RemoveOverlapsReport {
+            let mut report = crate::glyphs::boolean::RemoveOverlapsReport::default();
+            for (name, glyph) in self.selected_glyphs(names) {
+                match crate::glyphs::boolean::remove_overlap(&glyph.borrow().contours) {
+                    Ok(None) => {}
+                    Ok(Some(contours)) => report.changed.push((glyph.clone(), contours)),
+                    Err(err) => report.failed.push((name, err.to_string())),
+                }
+            }
+            report
+        }
+
+        /// Replace every contour of the [selected glyphs](Self::selected_glyphs) for which `f`
+        /// returns a new contour, and return the glyphs that would change along with their new
+        /// contours. The glyphs themselves are left as they are.
+        pub fn map_contours(
+            &self,
+            names: Option<&[String]>,
+            f: impl Fn(&crate::glyphs::Contour) -> Option<crate::glyphs::Contour>,
+        ) -> crate::glyphs::ContourChanges {
+            let mut ret = vec![];
+            for (_, glyph) in self.selected_glyphs(names) {
+                let mut contours = glyph.borrow().contours.clone();
+                let mut changed = false;
+                for contour in contours.iter_mut() {
+                    if let Some(new) = f(contour) {
+                        *contour = new;
+                        changed = true;
+                    }
+                }
+                if changed {
+                    ret.push((glyph, contours));
+                }
+            }
+            ret
+        }
+
+        /// Like [`Self::map_contours`], but reverse the contours that run against `convention`,
+        /// which depends on how the contours of a glyph nest.
+        pub fn correct_directions(
+            &self,
+            names: Option<&[String]>,
+            convention: ContourDirection,
+        ) -> crate::glyphs::ContourChanges {
+            let mut ret = vec![];
+            for (_, glyph) in self.selected_glyphs(names) {
+                let mut contours = glyph.borrow().contours.clone();
+                let wrong = glyph.borrow().wrong_directions(convention);
+                for &i in wrong.iter() {
+                    contours[i] = contours[i].reversed();
+                }
+                if !wrong.is_empty() {
+                    ret.push((glyph, contours));
+                }
+            }
+            ret
+        }
+
         pub fn path(&'_ self) -> FieldRef<'_, PathBuf> {
             self.path.borrow().into()
         }
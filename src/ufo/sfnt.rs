@@ -0,0 +1,1811 @@
+/*
+ * gerb
+ *
+ * Copyright 2022 - Manos Pitsidianakis
+ *
+ * This file is part of gerb.
+ *
+ * gerb is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * gerb is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with gerb. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Native OpenType font compiler.
+//!
+//! Writes binary `sfnt` fonts with either TrueType (`glyf`/`loca`) or CFF outlines from a
+//! project's glyphs and [`FontInfo`], without requiring python. The `cmap`, `head`, `hhea`,
+//! `hmtx`, `maxp`, `name`, `OS/2` and `post` tables are always written. Kerning and OpenType
+//! layout features are not compiled.
+//!
+//! Missing metrics fall back to the same defaults `ufo2ft` uses where possible.
+
+use super::export::{OutputFormat, UFOCompileOptions};
+use super::FontInfo;
+use crate::glyphs::Glyph;
+use crate::prelude::Project;
+use crate::utils::points::Point;
+use gtk::cairo::Matrix;
+use indexmap::IndexMap;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+mod cff;
+mod glyf;
+
+type Tag = [u8; 4];
+type Glyphs = IndexMap<String, Rc<RefCell<Glyph>>>;
+
+/// Compile `project` and write it to the output path described by `options`.
+///
+/// Returns the path of the written file.
+pub fn export(
+    project: &Project,
+    options: UFOCompileOptions,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let info = project.fontinfo.borrow().source.borrow().clone();
+    let data = {
+        let glyphs = project.default_layer.glyphs();
+        if options.remove_overlaps {
+            compile(&info, &without_overlaps(&glyphs)?, options.format)?
+        } else {
+            compile(&info, &glyphs, options.format)?
+        }
+    };
+    let path = match options.output_path {
+        Some(path) if !path.as_os_str().is_empty() => path,
+        _ => {
+            let ext = match options.format {
+                OutputFormat::Otf => "otf",
+                OutputFormat::Ttf => "ttf",
+            };
+            let stem = options
+                .filename_stem
+                .unwrap_or_else(|| format!("{}-{}", family_name(&info), style_name(&info)));
+            options.output_dir.join(format!("{stem}.{ext}"))
+        }
+    };
+    std::fs::write(&path, data)
+        .map_err(|err| format!("Could not write to {}: {err}", path.display()))?;
+    Ok(path)
+}
+
+/// Copies of `glyphs` with their overlapping contours merged. Glyphs without overlaps are not
+/// copied.
+fn without_overlaps(glyphs: &Glyphs) -> Result<Glyphs, Box<dyn std::error::Error>> {
+    let mut ret = IndexMap::with_capacity(glyphs.len());
+    for (name, glyph) in glyphs {
+        let contours = crate::glyphs::boolean::remove_overlap(&glyph.borrow().contours)
+            .map_err(|err| format!("Could not remove overlaps of glyph {name}: {err}"))?;
+        let glyph = match contours {
+            None => glyph.clone(),
+            Some(contours) => {
+                let mut copy = glyph.borrow().clone();
+                copy.contours = contours;
+                Rc::new(RefCell::new(copy))
+            }
+        };
+        ret.insert(name.clone(), glyph);
+    }
+    Ok(ret)
+}
+
+/// Compile `glyphs` to a binary font file, in the order they appear in the map.
+///
+/// A `.notdef` glyph is always placed first; if `glyphs` doesn't contain one, a box glyph is
+/// generated.
+pub fn compile(
+    info: &FontInfo,
+    glyphs: &IndexMap<String, Rc<RefCell<Glyph>>>,
+    format: OutputFormat,
+) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let font = Font::new(info, glyphs)?;
+    let mut tables: Vec<(Tag, Vec<u8>)> = Vec::with_capacity(10);
+    let (sfnt_version, bounds, long_loca) = match format {
+        OutputFormat::Ttf => {
+            let glyf::Glyf {
+                glyf,
+                loca,
+                long_loca,
+                bounds,
+                maxp,
+            } = glyf::compile(&font)?;
+            tables.push((*b"glyf", glyf));
+            tables.push((*b"loca", loca));
+            tables.push((*b"maxp", maxp));
+            (0x0001_0000, bounds, long_loca)
+        }
+        OutputFormat::Otf => {
+            let cff::Cff { data, bounds } = cff::compile(&font)?;
+            tables.push((*b"CFF ", data));
+            let mut maxp = vec![];
+            maxp.put_u32(0x0000_5000);
+            maxp.put_u16(font.glyphs.len() as u16);
+            tables.push((*b"maxp", maxp));
+            (u32::from_be_bytes(*b"OTTO"), bounds, false)
+        }
+    };
+    let metrics = HorizontalMetrics::new(&font, &bounds);
+    tables.push((*b"head", font.head(&bounds, long_loca)));
+    tables.push((*b"hhea", font.hhea(&metrics)));
+    tables.push((*b"hmtx", metrics.hmtx()));
+    tables.push((*b"cmap", font.cmap()?));
+    tables.push((*b"name", font.name()));
+    tables.push((*b"OS/2", font.os2(&bounds)));
+    tables.push((*b"post", font.post(format)?));
+    Ok(assemble(sfnt_version, tables))
+}
+
+/// Big endian serialization helpers.
+trait WriteBe {
+    fn put_u8(&mut self, v: u8);
+    fn put_u16(&mut self, v: u16);
+    fn put_i16(&mut self, v: i16);
+    fn put_u32(&mut self, v: u32);
+    fn put_i32(&mut self, v: i32);
+}
+
+impl WriteBe for Vec<u8> {
+    fn put_u8(&mut self, v: u8) {
+        self.push(v);
+    }
+
+    fn put_u16(&mut self, v: u16) {
+        self.extend_from_slice(&v.to_be_bytes());
+    }
+
+    fn put_i16(&mut self, v: i16) {
+        self.extend_from_slice(&v.to_be_bytes());
+    }
+
+    fn put_u32(&mut self, v: u32) {
+        self.extend_from_slice(&v.to_be_bytes());
+    }
+
+    fn put_i32(&mut self, v: i32) {
+        self.extend_from_slice(&v.to_be_bytes());
+    }
+}
+
+/// Round and saturate a font unit value to `i16`.
+fn fword(v: f64) -> i16 {
+    v.round().clamp(f64::from(i16::MIN), f64::from(i16::MAX)) as i16
+}
+
+/// Round and saturate a font unit value to `u16`.
+fn ufword(v: f64) -> u16 {
+    v.round().clamp(0.0, f64::from(u16::MAX)) as u16
+}
+
+/// 16.16 fixed point number.
+fn fixed(v: f64) -> i32 {
+    (v * 65536.0).round() as i32
+}
+
+/// Sum of the big endian `u32` words of `data`, zero padded.
+fn checksum(data: &[u8]) -> u32 {
+    data.chunks(4).fold(0u32, |sum, chunk| {
+        let mut word = [0; 4];
+        word[..chunk.len()].copy_from_slice(chunk);
+        sum.wrapping_add(u32::from_be_bytes(word))
+    })
+}
+
+/// `searchRange`, `entrySelector` and `rangeShift` of binary search headers.
+fn search_params(count: u16, size: u16) -> (u16, u16, u16) {
+    let mut entry_selector = 0;
+    while count >> (entry_selector + 1) != 0 {
+        entry_selector += 1;
+    }
+    let search_range = (1 << entry_selector) * size;
+    (
+        search_range,
+        entry_selector,
+        (count * size).saturating_sub(search_range),
+    )
+}
+
+/// Write the table directory and table data, and set `head.checkSumAdjustment`.
+fn assemble(sfnt_version: u32, mut tables: Vec<(Tag, Vec<u8>)>) -> Vec<u8> {
+    tables.sort_by_key(|t| t.0);
+    let num_tables = tables.len() as u16;
+    let (search_range, entry_selector, range_shift) = search_params(num_tables, 16);
+    let mut ret = vec![];
+    ret.put_u32(sfnt_version);
+    ret.put_u16(num_tables);
+    ret.put_u16(search_range);
+    ret.put_u16(entry_selector);
+    ret.put_u16(range_shift);
+    let mut offset = 12 + 16 * tables.len();
+    let mut head_offset = None;
+    for (tag, data) in tables.iter() {
+        if tag == b"head" {
+            head_offset = Some(offset);
+        }
+        ret.extend_from_slice(tag);
+        ret.put_u32(checksum(data));
+        ret.put_u32(offset as u32);
+        ret.put_u32(data.len() as u32);
+        offset += (data.len() + 3) & !3;
+    }
+    for (_, data) in tables.iter() {
+        ret.extend_from_slice(data);
+        ret.resize((ret.len() + 3) & !3, 0);
+    }
+    if let Some(head_offset) = head_offset {
+        let adjustment = 0xB1B0_AFBA_u32.wrapping_sub(checksum(&ret));
+        ret[head_offset + 8..head_offset + 12].copy_from_slice(&adjustment.to_be_bytes());
+    }
+    ret
+}
+
+/// A contour segment, without its start point.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Segment {
+    Line(Point),
+    Quad(Point, Point),
+    Cubic(Point, Point, Point),
+}
+
+impl Segment {
+    fn end(&self) -> Point {
+        match *self {
+            Self::Line(p) | Self::Quad(_, p) | Self::Cubic(_, _, p) => p,
+        }
+    }
+}
+
+fn cubic_point(p: [Point; 4], t: f64) -> Point {
+    let mt = 1.0 - t;
+    (mt * mt * mt) * p[0]
+        + (3.0 * mt * mt * t) * p[1]
+        + (3.0 * mt * t * t) * p[2]
+        + (t * t * t) * p[3]
+}
+
+/// A closed outline in font units.
+#[derive(Clone, Debug)]
+struct Path {
+    start: Point,
+    segments: Vec<Segment>,
+}
+
+/// Glyph data gathered from the project before compilation.
+struct FontGlyph {
+    name: String,
+    advance: f64,
+    /// The glyph's own contours.
+    contours: Vec<Path>,
+    /// Base glyph names and transformations of the glyph's components.
+    components: Vec<(String, Matrix)>,
+    /// Own contours and decomposed components.
+    decomposed: Vec<Path>,
+}
+
+struct Font<'a> {
+    info: &'a FontInfo,
+    units_per_em: f64,
+    glyphs: Vec<FontGlyph>,
+    glyph_ids: IndexMap<String, u16>,
+    cmap: BTreeMap<u32, u16>,
+}
+
+impl<'a> Font<'a> {
+    fn new(
+        info: &'a FontInfo,
+        glyphs: &IndexMap<String, Rc<RefCell<Glyph>>>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let units_per_em = info
+            .units_per_em
+            .filter(|upm| (16.0..=16384.0).contains(upm))
+            .unwrap_or(1000.0);
+        let mut names = glyphs.keys().collect::<Vec<_>>();
+        if let Some(pos) = names.iter().position(|n| *n == ".notdef") {
+            let notdef = names.remove(pos);
+            names.insert(0, notdef);
+        }
+        let needs_notdef = names.first().map(|n| *n != ".notdef").unwrap_or(true);
+        /* A generated `.notdef` counts towards the `u16` glyph count too. */
+        if names.len() + usize::from(needs_notdef) > usize::from(u16::MAX) {
+            return Err(format!(
+                "Font has {} glyphs, the maximum is {}.",
+                names.len() + usize::from(needs_notdef),
+                u16::MAX
+            )
+            .into());
+        }
+        let mut ret = Self {
+            info,
+            units_per_em,
+            glyphs: Vec::with_capacity(names.len() + 1),
+            glyph_ids: IndexMap::default(),
+            cmap: BTreeMap::default(),
+        };
+        if needs_notdef {
+            ret.glyphs.push(ret.notdef());
+        }
+        for name in names {
+            let glyph = glyphs[name].borrow();
+            let mut contours = vec![];
+            glyph_paths(glyphs, &glyph, Matrix::identity(), 0, false, &mut contours)?;
+            let mut decomposed = vec![];
+            glyph_paths(glyphs, &glyph, Matrix::identity(), 0, true, &mut decomposed)?;
+            let gid = ret.glyphs.len() as u16;
+            for u in glyph.metadata.unicode.borrow().iter() {
+                let codepoint = u32::from_str_radix(u.hex(), 16).map_err(|err| {
+                    format!("Glyph {name} has invalid unicode {}: {err}", u.hex())
+                })?;
+                ret.cmap.entry(codepoint).or_insert(gid);
+            }
+            ret.glyphs.push(FontGlyph {
+                name: name.clone(),
+                advance: glyph.width().unwrap_or(0.0),
+                contours,
+                components: glyph
+                    .components
+                    .iter()
+                    .map(|c| (c.base_name.clone(), c.matrix()))
+                    .collect(),
+                decomposed,
+            });
+        }
+        for (gid, glyph) in ret.glyphs.iter().enumerate() {
+            ret.glyph_ids.insert(glyph.name.clone(), gid as u16);
+        }
+        Ok(ret)
+    }
+
+    /// A box with a counter, drawn when a character is missing from the font.
+    fn notdef(&self) -> FontGlyph {
+        let upm = self.units_per_em;
+        let rect = |x0: f64, y0: f64, x1: f64, y1: f64, clockwise: bool| {
+            let mut corners = vec![(x1, y0), (x1, y1), (x0, y1), (x0, y0)];
+            if clockwise {
+                corners.reverse();
+                corners.rotate_left(1);
+            }
+            Path {
+                start: (x0, y0).into(),
+                segments: corners
+                    .into_iter()
+                    .map(|p| Segment::Line(p.into()))
+                    .collect(),
+            }
+        };
+        let (width, height, stroke) = ((upm * 0.5).round(), (upm * 0.7).round(), upm * 0.05);
+        let contours = vec![
+            rect(stroke, 0.0, width - stroke, height, false),
+            rect(
+                2.0 * stroke,
+                stroke,
+                2.0f64.mul_add(-stroke, width),
+                height - stroke,
+                true,
+            ),
+        ];
+        FontGlyph {
+            name: ".notdef".to_string(),
+            advance: width,
+            contours: contours.clone(),
+            components: vec![],
+            decomposed: contours,
+        }
+    }
+
+    fn ascender(&self) -> f64 {
+        self.info
+            .ascender
+            .unwrap_or_else(|| (self.units_per_em * 0.8).round())
+    }
+
+    fn descender(&self) -> f64 {
+        self.info
+            .descender
+            .unwrap_or_else(|| (-self.units_per_em * 0.2).round())
+    }
+
+    fn typo_line_gap(&self) -> f64 {
+        self.info
+            .open_type_os2_typo_line_gap
+            .map(|v| v as f64)
+            .unwrap_or_else(|| {
+                (self.units_per_em.mul_add(1.2, -self.ascender()) + self.descender())
+                    .round()
+                    .max(0.0)
+            })
+    }
+
+    fn italic_angle(&self) -> f64 {
+        self.info.italic_angle.unwrap_or(0.0)
+    }
+
+    fn underline(&self) -> (f64, f64) {
+        (
+            self.info
+                .postscript_underline_position
+                .unwrap_or_else(|| (-self.units_per_em * 0.075).round()),
+            self.info
+                .postscript_underline_thickness
+                .unwrap_or_else(|| (self.units_per_em * 0.05).round()),
+        )
+    }
+
+    fn version(&self) -> (i64, u64) {
+        (
+            self.info.version_major.unwrap_or(0),
+            self.info.version_minor.unwrap_or(0),
+        )
+    }
+
+    /// (bold, italic) flags from `styleMapStyleName`.
+    fn style_flags(&self) -> (bool, bool) {
+        match self.info.style_map_style_name.to_lowercase().as_str() {
+            "bold" => (true, false),
+            "italic" => (false, true),
+            "bold italic" => (true, true),
+            _ => (false, false),
+        }
+    }
+
+    fn head(&self, bounds: &[Option<Bounds>], long_loca: bool) -> Vec<u8> {
+        let (major, minor) = self.version();
+        let flags = if self.info.open_type_head_flags.is_empty() {
+            0b11
+        } else {
+            bits(&self.info.open_type_head_flags) as u16
+        };
+        let created = self
+            .info
+            .open_type_head_created
+            .as_deref()
+            .and_then(parse_datetime)
+            .unwrap_or_else(now);
+        let total = Bounds::union(bounds).unwrap_or_default();
+        let (bold, italic) = self.style_flags();
+
+        let mut ret = Vec::with_capacity(54);
+        ret.put_u32(0x0001_0000);
+        ret.put_i32(fixed(major as f64 + minor as f64 / 1000.0));
+        /* checkSumAdjustment is set after all tables are assembled. */
+        ret.put_u32(0);
+        ret.put_u32(0x5F0F_3CF5);
+        ret.put_u16(flags);
+        ret.put_u16(self.units_per_em as u16);
+        ret.extend_from_slice(&created.to_be_bytes());
+        ret.extend_from_slice(&created.to_be_bytes());
+        ret.put_i16(total.x_min);
+        ret.put_i16(total.y_min);
+        ret.put_i16(total.x_max);
+        ret.put_i16(total.y_max);
+        ret.put_u16(u16::from(bold) | u16::from(italic) << 1);
+        ret.put_u16(
+            self.info
+                .open_type_head_lowest_rec_ppem
+                .map(|v| v.clamp(0, i64::from(u16::MAX)) as u16)
+                .unwrap_or(6),
+        );
+        /* fontDirectionHint: deprecated, always 2. */
+        ret.put_i16(2);
+        ret.put_i16(i16::from(long_loca));
+        ret.put_i16(0);
+        ret
+    }
+
+    fn hhea(&self, metrics: &HorizontalMetrics) -> Vec<u8> {
+        let info = self.info;
+        let italic_angle = self.italic_angle();
+        let (rise, run) = if italic_angle == 0.0 {
+            (1.0, 0.0)
+        } else {
+            (
+                self.units_per_em,
+                self.units_per_em * (-italic_angle).to_radians().tan(),
+            )
+        };
+        let mut ret = Vec::with_capacity(36);
+        ret.put_u32(0x0001_0000);
+        ret.put_i16(fword(
+            info.open_type_hhea_ascender
+                .unwrap_or_else(|| self.ascender()),
+        ));
+        ret.put_i16(fword(
+            info.open_type_hhea_descender
+                .unwrap_or_else(|| self.descender()),
+        ));
+        ret.put_i16(fword(
+            info.open_type_hhea_line_gap
+                .unwrap_or_else(|| self.typo_line_gap()),
+        ));
+        ret.put_u16(metrics.advance_width_max);
+        ret.put_i16(metrics.min_left_side_bearing);
+        ret.put_i16(metrics.min_right_side_bearing);
+        ret.put_i16(metrics.x_max_extent);
+        ret.put_i16(fword(info.open_type_hhea_caret_slope_rise.unwrap_or(rise)));
+        ret.put_i16(fword(info.open_type_hhea_caret_slope_run.unwrap_or(run)));
+        ret.put_i16(fword(info.open_type_hhea_caret_offset.unwrap_or(0.0)));
+        for _ in 0..4 {
+            ret.put_i16(0);
+        }
+        /* metricDataFormat */
+        ret.put_i16(0);
+        ret.put_u16(metrics.number_of_h_metrics);
+        ret
+    }
+
+    fn cmap(&self) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        let bmp = self
+            .cmap
+            .iter()
+            .filter(|(c, _)| **c < 0xFFFF)
+            .map(|(c, g)| (*c, *g))
+            .collect::<Vec<_>>();
+        let format_4 = cmap_format_4(&bmp)?;
+        let format_12 = if self.cmap.keys().any(|c| *c > 0xFFFF) {
+            Some(cmap_format_12(&self.cmap))
+        } else {
+            None
+        };
+
+        let num_tables: u16 = if format_12.is_some() { 4 } else { 2 };
+        let format_4_offset = 4 + 8 * u32::from(num_tables);
+        let format_12_offset = format_4_offset + format_4.len() as u32;
+        let mut ret = vec![];
+        ret.put_u16(0);
+        ret.put_u16(num_tables);
+        /* Unicode BMP and Windows Unicode BMP */
+        let mut records = vec![(0, 3, format_4_offset), (3, 1, format_4_offset)];
+        if format_12.is_some() {
+            /* Unicode full repertoire and Windows Unicode full repertoire */
+            records.extend([(0, 4, format_12_offset), (3, 10, format_12_offset)]);
+        }
+        records.sort();
+        for (platform_id, encoding_id, offset) in records {
+            ret.put_u16(platform_id);
+            ret.put_u16(encoding_id);
+            ret.put_u32(offset);
+        }
+        ret.extend(format_4);
+        ret.extend(format_12.unwrap_or_default());
+        Ok(ret)
+    }
+
+    fn name(&self) -> Vec<u8> {
+        let info = self.info;
+        let (major, minor) = self.version();
+        let family = family_name(info);
+        let style = style_name(info);
+        let ps_name = postscript_name(info);
+        let style_map_family = Some(info.style_map_family_name.clone())
+            .filter(|s| !s.is_empty())
+            .unwrap_or_else(|| {
+                if ["Regular", "Bold", "Italic", "Bold Italic"].contains(&style.as_str()) {
+                    family.clone()
+                } else {
+                    format!("{family} {style}")
+                }
+            });
+        let style_map_style = match self.style_flags() {
+            (true, true) => "Bold Italic",
+            (true, false) => "Bold",
+            (false, true) => "Italic",
+            (false, false) => "Regular",
+        };
+        let version = info
+            .open_type_name_version
+            .clone()
+            .unwrap_or_else(|| format!("Version {major}.{minor:03}"));
+        let vendor = info
+            .open_type_os2_vendor_id
+            .clone()
+            .unwrap_or_else(|| "NONE".to_string());
+
+        let mut names: BTreeMap<(u16, u16, u16, u16), String> = BTreeMap::default();
+        let mut add = |name_id: u16, value: Option<String>| {
+            if let Some(value) = value.filter(|v| !v.is_empty()) {
+                names.insert((3, 1, 0x409, name_id), value);
+            }
+        };
+        add(0, Some(info.copyright.clone()));
+        add(1, Some(style_map_family.clone()));
+        add(2, Some(style_map_style.to_string()));
+        add(
+            3,
+            Some(
+                info.open_type_name_unique_id
+                    .clone()
+                    .unwrap_or_else(|| format!("{major}.{minor:03};{};{ps_name}", vendor.trim())),
+            ),
+        );
+        add(
+            4,
+            Some(
+                info.postscript_full_name
+                    .clone()
+                    .unwrap_or_else(|| format!("{family} {style}")),
+            ),
+        );
+        add(5, Some(version));
+        add(6, Some(ps_name));
+        add(7, Some(info.trademark.clone()));
+        add(8, info.open_type_name_manufacturer.clone());
+        add(9, info.open_type_name_designer.clone());
+        add(10, info.open_type_name_description.clone());
+        add(11, info.open_type_name_manufacturer_url.clone());
+        add(12, info.open_type_name_designer_url.clone());
+        add(13, info.open_type_name_license.clone());
+        add(14, info.open_type_name_license_url.clone());
+        add(
+            16,
+            info.open_type_name_preferred_family_name
+                .clone()
+                .or_else(|| Some(family.clone()).filter(|f| *f != style_map_family)),
+        );
+        add(
+            17,
+            info.open_type_name_preferred_subfamily_name
+                .clone()
+                .or_else(|| Some(style.clone()).filter(|s| s != style_map_style)),
+        );
+        add(19, info.open_type_name_sample_text.clone());
+        add(21, info.open_type_name_wws_family_name.clone());
+        add(22, info.open_type_name_wws_sub_family_name.clone());
+        for record in info.open_type_name_records.iter().flatten() {
+            if let (Some(name_id), Some(platform_id), Some(encoding_id), Some(language_id)) = (
+                record.name_id,
+                record.platform_id,
+                record.encoding_id,
+                record.language_id,
+            ) {
+                names.insert(
+                    (
+                        platform_id as u16,
+                        encoding_id as u16,
+                        language_id as u16,
+                        name_id as u16,
+                    ),
+                    record.string.clone().unwrap_or_default(),
+                );
+            }
+        }
+
+        let mut storage = vec![];
+        let mut ret = vec![];
+        ret.put_u16(0);
+        ret.put_u16(names.len() as u16);
+        ret.put_u16(6 + 12 * names.len() as u16);
+        for ((platform_id, encoding_id, language_id, name_id), value) in names {
+            let encoded = if platform_id == 1 {
+                /* Macintosh Roman; only its ASCII subset is written. */
+                value
+                    .chars()
+                    .map(|c| if c.is_ascii() { c as u8 } else { b'?' })
+                    .collect::<Vec<u8>>()
+            } else {
+                value
+                    .encode_utf16()
+                    .flat_map(|u| u.to_be_bytes())
+                    .collect::<Vec<u8>>()
+            };
+            ret.put_u16(platform_id);
+            ret.put_u16(encoding_id);
+            ret.put_u16(language_id);
+            ret.put_u16(name_id);
+            ret.put_u16(encoded.len() as u16);
+            ret.put_u16(storage.len() as u16);
+            storage.extend(encoded);
+        }
+        ret.extend(storage);
+        ret
+    }
+
+    fn os2(&self, bounds: &[Option<Bounds>]) -> Vec<u8> {
+        let info = self.info;
+        let upm = self.units_per_em;
+        let total = Bounds::union(bounds).unwrap_or_default();
+        let (_, underline_thickness) = self.underline();
+        let x_height = info.x_height.unwrap_or_else(|| (upm * 0.5).round());
+        let cap_height = info.cap_height.unwrap_or_else(|| (upm * 0.7).round());
+        let or = |v: Option<i64>, default: f64| v.map(|v| v as f64).unwrap_or(default);
+        let advances = self
+            .glyphs
+            .iter()
+            .map(|g| g.advance.round())
+            .filter(|a| *a > 0.0)
+            .collect::<Vec<f64>>();
+        let avg_width = if advances.is_empty() {
+            0.0
+        } else {
+            advances.iter().sum::<f64>() / advances.len() as f64
+        };
+        let (bold, italic) = self.style_flags();
+        let mut fs_selection = info
+            .open_type_os2_selection
+            .as_deref()
+            .map(bits)
+            .unwrap_or(0) as u16;
+        /* Bits 0, 5 and 6 are set from the style map style name. */
+        fs_selection &= !0b110_0001;
+        if italic {
+            fs_selection |= 1;
+        }
+        if bold {
+            fs_selection |= 1 << 5;
+        }
+        if !bold && !italic {
+            fs_selection |= 1 << 6;
+        }
+        let unicode_ranges = match info.open_type_os2_unicode_ranges.as_deref() {
+            Some(ranges) => {
+                let mut ret = [0u32; 4];
+                for bit in ranges.iter().filter(|b| **b < 128) {
+                    ret[*bit as usize / 32] |= 1 << (bit % 32);
+                }
+                ret
+            }
+            None => unicode_ranges(self.cmap.keys().copied()),
+        };
+        let code_page_ranges = match info.open_type_os2_code_page_ranges.as_deref() {
+            Some(ranges) => {
+                let mut ret = [0u32; 2];
+                for bit in ranges.iter().filter(|b| **b < 64) {
+                    ret[*bit as usize / 32] |= 1 << (bit % 32);
+                }
+                ret
+            }
+            None => code_page_ranges(&unicode_ranges),
+        };
+        let mut vendor = *b"NONE";
+        if let Some(vendor_id) = info.open_type_os2_vendor_id.as_deref() {
+            vendor = *b"    ";
+            for (i, b) in vendor_id.bytes().take(4).enumerate() {
+                vendor[i] = b;
+            }
+        }
+        let family_class = info
+            .open_type_os2_family_class
+            .as_deref()
+            .map(|c| {
+                (c.first().copied().unwrap_or(0) as i16) << 8
+                    | c.get(1).copied().unwrap_or(0) as i16
+            })
+            .unwrap_or(0);
+
+        let mut ret = Vec::with_capacity(96);
+        ret.put_u16(4);
+        ret.put_i16(fword(avg_width));
+        ret.put_u16(info.open_type_os2_weight_class.unwrap_or(400).min(1000) as u16);
+        ret.put_u16(info.open_type_os2_width_class.unwrap_or(5).clamp(1, 9) as u16);
+        ret.put_u16(
+            info.open_type_os2_type
+                .as_deref()
+                .map(bits)
+                .unwrap_or(1 << 2) as u16,
+        );
+        for (v, default) in [
+            (info.open_type_os2_subscript_xsize, upm * 0.65),
+            (info.open_type_os2_subscript_ysize, upm * 0.6),
+            (info.open_type_os2_subscript_xoffset, 0.0),
+            (info.open_type_os2_subscript_yoffset, upm * 0.075),
+            (info.open_type_os2_superscript_xsize, upm * 0.65),
+            (info.open_type_os2_superscript_ysize, upm * 0.6),
+            (info.open_type_os2_superscript_xoffset, 0.0),
+            (info.open_type_os2_superscript_yoffset, upm * 0.35),
+            (info.open_type_os2_strikeout_size, underline_thickness),
+            (info.open_type_os2_strikeout_position, x_height * 0.6),
+        ] {
+            ret.put_i16(fword(or(v, default)));
+        }
+        ret.put_i16(family_class);
+        let mut panose = [0u8; 10];
+        for (i, v) in info
+            .open_type_os2_panose
+            .iter()
+            .flatten()
+            .take(10)
+            .enumerate()
+        {
+            panose[i] = (*v).min(u64::from(u8::MAX)) as u8;
+        }
+        ret.extend_from_slice(&panose);
+        for range in unicode_ranges {
+            ret.put_u32(range);
+        }
+        ret.extend_from_slice(&vendor);
+        ret.put_u16(fs_selection);
+        let first = self.cmap.keys().next().copied().unwrap_or(0);
+        let last = self.cmap.keys().next_back().copied().unwrap_or(0);
+        ret.put_u16(first.min(0xFFFF) as u16);
+        ret.put_u16(last.min(0xFFFF) as u16);
+        ret.put_i16(fword(or(info.open_type_os2_typo_ascender, self.ascender())));
+        ret.put_i16(fword(or(
+            info.open_type_os2_typo_descender,
+            self.descender(),
+        )));
+        ret.put_i16(fword(self.typo_line_gap()));
+        ret.put_u16(ufword(
+            info.open_type_os2_win_ascent
+                .map(|v| v as f64)
+                .unwrap_or_else(|| self.ascender().max(total.y_max.into())),
+        ));
+        ret.put_u16(ufword(
+            info.open_type_os2_win_descent
+                .map(|v| v as f64)
+                .unwrap_or_else(|| (-self.descender()).max(-f64::from(total.y_min))),
+        ));
+        for range in code_page_ranges {
+            ret.put_u32(range);
+        }
+        ret.put_i16(fword(x_height));
+        ret.put_i16(fword(cap_height));
+        /* usDefaultChar, usBreakChar, usMaxContext */
+        ret.put_u16(0);
+        ret.put_u16(0x20);
+        ret.put_u16(0);
+        ret
+    }
+
+    fn post(&self, format: OutputFormat) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        let (underline_position, underline_thickness) = self.underline();
+        let mut ret = vec![];
+        ret.put_u32(match format {
+            OutputFormat::Ttf => 0x0002_0000,
+            /* CFF fonts store glyph names in the CFF table. */
+            OutputFormat::Otf => 0x0003_0000,
+        });
+        ret.put_i32(fixed(self.italic_angle()));
+        ret.put_i16(fword(underline_position));
+        ret.put_i16(fword(underline_thickness));
+        ret.put_u32(u32::from(
+            self.info.postscript_is_fixed_pitch.unwrap_or(false),
+        ));
+        for _ in 0..4 {
+            ret.put_u32(0);
+        }
+        if format == OutputFormat::Ttf {
+            ret.put_u16(self.glyphs.len() as u16);
+            let mut names = vec![];
+            let mut index = 258;
+            for glyph in self.glyphs.iter() {
+                if glyph.name == ".notdef" {
+                    /* Index of .notdef in the standard Macintosh glyph order. */
+                    ret.put_u16(0);
+                    continue;
+                }
+                if !glyph.name.is_ascii() || glyph.name.len() > 63 {
+                    return Err(format!(
+                        "Glyph name {} must be ASCII and at most 63 characters long.",
+                        glyph.name
+                    )
+                    .into());
+                }
+                ret.put_u16(index);
+                index += 1;
+                names.put_u8(glyph.name.len() as u8);
+                names.extend_from_slice(glyph.name.as_bytes());
+            }
+            ret.extend(names);
+        }
+        Ok(ret)
+    }
+}
+
+/// Collect the outlines of `glyph` transformed by `m`. If `decompose` is true, the outlines of
+/// its components are included.
+fn glyph_paths(
+    glyphs: &IndexMap<String, Rc<RefCell<Glyph>>>,
+    glyph: &Glyph,
+    m: Matrix,
+    depth: usize,
+    decompose: bool,
+    ret: &mut Vec<Path>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    /* Guard against components that reference themselves. */
+    const MAX_DEPTH: usize = 32;
+
+    let name = glyph.name().as_str().to_string();
+    if depth > MAX_DEPTH {
+        return Err(format!("Glyph {name} has a component that references itself.").into());
+    }
+    for contour in glyph.contours.iter() {
+        let curves = contour.curves();
+        let Some(first) = curves.first() else {
+            continue;
+        };
+        let Some(start) = first.points().first().map(|p| m * p.position) else {
+            continue;
+        };
+        let mut path = Path {
+            start,
+            segments: Vec::with_capacity(curves.len()),
+        };
+        for curve in curves.iter() {
+            let points = curve
+                .points()
+                .iter()
+                .map(|p| m * p.position)
+                .collect::<Vec<Point>>();
+            path.segments.push(match points.as_slice() {
+                [] | [_] => continue,
+                [_, p] => Segment::Line(*p),
+                [_, c, p] => Segment::Quad(*c, *p),
+                [_, c1, c2, p] => Segment::Cubic(*c1, *c2, *p),
+                _ => {
+                    return Err(format!(
+                        "Glyph {name} has a curve of degree {} which is not supported.",
+                        points.len() - 1
+                    )
+                    .into())
+                }
+            });
+        }
+        ret.push(path);
+    }
+    if decompose {
+        for component in glyph.components.iter() {
+            let Some(base) = glyphs.get(&component.base_name) else {
+                return Err(format!(
+                    "Glyph {name} has a component with base glyph {} which doesn't exist.",
+                    component.base_name
+                )
+                .into());
+            };
+            glyph_paths(
+                glyphs,
+                &base.borrow(),
+                Matrix::multiply(&component.matrix(), &m),
+                depth + 1,
+                true,
+                ret,
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// A glyph's bounding box in font units.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct Bounds {
+    x_min: i16,
+    y_min: i16,
+    x_max: i16,
+    y_max: i16,
+}
+
+impl Bounds {
+    fn from_points(points: impl IntoIterator<Item = Point>) -> Option<Self> {
+        let mut points = points.into_iter();
+        let first = points.next()?;
+        let (min, max) = points.fold((first, first), |(min, max), p| {
+            (
+                (min.x.min(p.x), min.y.min(p.y)).into(),
+                (max.x.max(p.x), max.y.max(p.y)).into(),
+            )
+        });
+        Some(Self {
+            x_min: fword(min.x),
+            y_min: fword(min.y),
+            x_max: fword(max.x),
+            y_max: fword(max.y),
+        })
+    }
+
+    fn union(bounds: &[Option<Self>]) -> Option<Self> {
+        bounds.iter().flatten().copied().reduce(|a, b| Self {
+            x_min: a.x_min.min(b.x_min),
+            y_min: a.y_min.min(b.y_min),
+            x_max: a.x_max.max(b.x_max),
+            y_max: a.y_max.max(b.y_max),
+        })
+    }
+}
+
+/// `hmtx` entries and the `hhea` fields derived from them.
+struct HorizontalMetrics {
+    /// Advance width and left side bearing of each glyph.
+    metrics: Vec<(u16, i16)>,
+    number_of_h_metrics: u16,
+    advance_width_max: u16,
+    min_left_side_bearing: i16,
+    min_right_side_bearing: i16,
+    x_max_extent: i16,
+}
+
+impl HorizontalMetrics {
+    fn new(font: &Font<'_>, bounds: &[Option<Bounds>]) -> Self {
+        let metrics = font
+            .glyphs
+            .iter()
+            .zip(bounds.iter())
+            .map(|(g, b)| (ufword(g.advance), b.map(|b| b.x_min).unwrap_or(0)))
+            .collect::<Vec<_>>();
+        /* Trailing glyphs with the same advance width only store their left side bearing. */
+        let mut number_of_h_metrics = metrics.len();
+        while number_of_h_metrics > 1
+            && metrics[number_of_h_metrics - 1].0 == metrics[number_of_h_metrics - 2].0
+        {
+            number_of_h_metrics -= 1;
+        }
+        let inked = || {
+            metrics
+                .iter()
+                .zip(bounds.iter())
+                .filter_map(|(m, b)| Some((i32::from(m.0), (*b)?)))
+        };
+        Self {
+            number_of_h_metrics: number_of_h_metrics as u16,
+            advance_width_max: metrics.iter().map(|m| m.0).max().unwrap_or(0),
+            min_left_side_bearing: inked().map(|(_, b)| b.x_min).min().unwrap_or(0),
+            min_right_side_bearing: inked()
+                .map(|(advance, b)| (advance - i32::from(b.x_max)) as i16)
+                .min()
+                .unwrap_or(0),
+            x_max_extent: inked().map(|(_, b)| b.x_max).max().unwrap_or(0),
+            metrics,
+        }
+    }
+
+    fn hmtx(&self) -> Vec<u8> {
+        let mut ret = Vec::with_capacity(self.metrics.len() * 4);
+        for (i, (advance, lsb)) in self.metrics.iter().enumerate() {
+            if i < self.number_of_h_metrics as usize {
+                ret.put_u16(*advance);
+            }
+            ret.put_i16(*lsb);
+        }
+        ret
+    }
+}
+
+/// `cmap` subtable format 4, segment mapping to delta values, for the Basic Multilingual Plane.
+fn cmap_format_4(map: &[(u32, u16)]) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    /* (start, end, delta) segments of consecutive codepoints mapped to consecutive glyphs. */
+    let mut segments: Vec<(u16, u16, u16)> = vec![];
+    for &(c, gid) in map {
+        let c = c as u16;
+        match segments.last_mut() {
+            Some((_, end, delta)) if *end + 1 == c && gid.wrapping_sub(c) == *delta => {
+                *end = c;
+            }
+            _ => segments.push((c, c, gid.wrapping_sub(c))),
+        }
+    }
+    segments.push((0xFFFF, 0xFFFF, 1));
+    let seg_count = segments.len() as u16;
+    let length = 16 + 8 * segments.len();
+    if length > u16::MAX as usize {
+        return Err("Character map has too many ranges for a format 4 cmap subtable.".into());
+    }
+    let (search_range, entry_selector, range_shift) = search_params(seg_count, 2);
+    let mut ret = Vec::with_capacity(length);
+    ret.put_u16(4);
+    ret.put_u16(length as u16);
+    /* language */
+    ret.put_u16(0);
+    ret.put_u16(seg_count * 2);
+    ret.put_u16(search_range);
+    ret.put_u16(entry_selector);
+    ret.put_u16(range_shift);
+    for (_, end, _) in segments.iter() {
+        ret.put_u16(*end);
+    }
+    /* reservedPad */
+    ret.put_u16(0);
+    for (start, _, _) in segments.iter() {
+        ret.put_u16(*start);
+    }
+    for (_, _, delta) in segments.iter() {
+        ret.put_u16(*delta);
+    }
+    /* idRangeOffset: glyph ids are always computed with idDelta. */
+    for _ in segments.iter() {
+        ret.put_u16(0);
+    }
+    Ok(ret)
+}
+
+/// `cmap` subtable format 12, segmented coverage, for the full Unicode repertoire.
+fn cmap_format_12(map: &BTreeMap<u32, u16>) -> Vec<u8> {
+    /* (start, end, start glyph id) groups */
+    let mut groups: Vec<(u32, u32, u16)> = vec![];
+    for (&c, &gid) in map {
+        match groups.last_mut() {
+            Some((start, end, start_gid))
+                if *end + 1 == c && u32::from(*start_gid) + (c - *start) == u32::from(gid) =>
+            {
+                *end = c;
+            }
+            _ => groups.push((c, c, gid)),
+        }
+    }
+    let mut ret = vec![];
+    ret.put_u16(12);
+    ret.put_u16(0);
+    ret.put_u32(16 + 12 * groups.len() as u32);
+    /* language */
+    ret.put_u32(0);
+    ret.put_u32(groups.len() as u32);
+    for (start, end, gid) in groups {
+        ret.put_u32(start);
+        ret.put_u32(end);
+        ret.put_u32(gid.into());
+    }
+    ret
+}
+
+/// `OS/2` unicode range bits of the most common blocks present in `codepoints`.
+fn unicode_ranges(codepoints: impl Iterator<Item = u32>) -> [u32; 4] {
+    const RANGES: &[(u32, u32, u32)] = &[
+        (0, 0x0000, 0x007F),     // Basic Latin
+        (1, 0x0080, 0x00FF),     // Latin-1 Supplement
+        (2, 0x0100, 0x017F),     // Latin Extended-A
+        (3, 0x0180, 0x024F),     // Latin Extended-B
+        (4, 0x0250, 0x02AF),     // IPA Extensions
+        (5, 0x02B0, 0x02FF),     // Spacing Modifier Letters
+        (6, 0x0300, 0x036F),     // Combining Diacritical Marks
+        (7, 0x0370, 0x03FF),     // Greek and Coptic
+        (9, 0x0400, 0x052F),     // Cyrillic
+        (11, 0x0590, 0x05FF),    // Hebrew
+        (13, 0x0600, 0x06FF),    // Arabic
+        (29, 0x1E00, 0x1EFF),    // Latin Extended Additional
+        (30, 0x1F00, 0x1FFF),    // Greek Extended
+        (31, 0x2000, 0x206F),    // General Punctuation
+        (33, 0x20A0, 0x20CF),    // Currency Symbols
+        (35, 0x2100, 0x214F),    // Letterlike Symbols
+        (38, 0x2200, 0x22FF),    // Mathematical Operators
+        (57, 0x10000, 0x10FFFF), // Non-Plane 0
+        (60, 0xE000, 0xF8FF),    // Private Use Area
+    ];
+    let mut ret = [0u32; 4];
+    for c in codepoints {
+        if let Some((bit, _, _)) = RANGES
+            .iter()
+            .find(|(_, start, end)| (*start..=*end).contains(&c))
+        {
+            ret[*bit as usize / 32] |= 1 << (bit % 32);
+        }
+    }
+    ret
+}
+
+/// `OS/2` code page range bits implied by `unicode_ranges`.
+fn code_page_ranges(unicode_ranges: &[u32; 4]) -> [u32; 2] {
+    let has = |bit: u32| unicode_ranges[bit as usize / 32] & (1 << (bit % 32)) != 0;
+    let mut ret = [0u32; 2];
+    if has(0) || has(1) {
+        /* Latin 1 */
+        ret[0] |= 1;
+    }
+    if has(2) {
+        /* Latin 2: Eastern Europe */
+        ret[0] |= 1 << 1;
+    }
+    if has(9) {
+        /* Cyrillic */
+        ret[0] |= 1 << 2;
+    }
+    if has(7) {
+        /* Greek */
+        ret[0] |= 1 << 3;
+    }
+    ret
+}
+
+/// Fold a list of bit numbers into a mask.
+fn bits(list: &[u32]) -> u32 {
+    list.iter()
+        .filter(|b| **b < 32)
+        .fold(0, |acc, bit| acc | (1 << bit))
+}
+
+fn family_name(info: &FontInfo) -> String {
+    Some(info.family_name.clone())
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| "New Font".to_string())
+}
+
+fn style_name(info: &FontInfo) -> String {
+    Some(info.style_name.clone())
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| "Regular".to_string())
+}
+
+/// The PostScript font name, restricted to the printable ASCII characters it allows.
+fn postscript_name(info: &FontInfo) -> String {
+    let name = info
+        .postscript_font_name
+        .clone()
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| format!("{}-{}", family_name(info), style_name(info)));
+    name.chars()
+        .filter(|c| c.is_ascii_graphic() && !"[](){}<>/%".contains(*c))
+        .take(63)
+        .collect()
+}
+
+/// Seconds between 1904-01-01 and 1970-01-01.
+const MAC_EPOCH_OFFSET: i64 = 2_082_844_800;
+
+/// Current time as an OpenType `LONGDATETIME`.
+fn now() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| i64::try_from(d.as_secs()).unwrap_or(0))
+        .unwrap_or(0)
+        + MAC_EPOCH_OFFSET
+}
+
+/// Parse a UFO `YYYY/MM/DD HH:MM:SS` date as an OpenType `LONGDATETIME`.
+fn parse_datetime(s: &str) -> Option<i64> {
+    let (date, time) = s.trim().split_once(' ')?;
+    let mut date = date.split('/').map(|n| n.parse::<i64>().ok());
+    let (y, m, d) = (date.next()??, date.next()??, date.next()??);
+    let mut time = time.split(':').map(|n| n.parse::<i64>().ok());
+    let (hh, mm, ss) = (time.next()??, time.next()??, time.next()??);
+    if !(1..=12).contains(&m) || !(1..=31).contains(&d) {
+        return None;
+    }
+    /* Days since 1970-01-01 in the proleptic Gregorian calendar. */
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * ((m + 9) % 12) + 2) / 5 + d - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146_097 + doe - 719_468;
+    Some(days * 86400 + hh * 3600 + mm * 60 + ss + MAC_EPOCH_OFFSET)
+}
+
+#[cfg(test)]
+const _AGRAVE_GLIF: &str = r##"<?xml version="1.0" encoding="UTF-8"?>
+<glyph name="Agrave" format="2">
+  <unicode hex="00C0"/>
+  <advance width="544"/>
+  <outline>
+    <component base="A" xOffset="10" yOffset="20"/>
+  </outline>
+</glyph>"##;
+
+#[cfg(test)]
+fn read_u16(data: &[u8], offset: usize) -> u16 {
+    u16::from_be_bytes([data[offset], data[offset + 1]])
+}
+
+#[cfg(test)]
+fn read_i16(data: &[u8], offset: usize) -> i16 {
+    read_u16(data, offset) as i16
+}
+
+#[cfg(test)]
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_be_bytes([
+        data[offset],
+        data[offset + 1],
+        data[offset + 2],
+        data[offset + 3],
+    ])
+}
+
+/// Read the table directory of a font file, and verify the checksums of its tables.
+#[cfg(test)]
+fn read_tables(data: &[u8]) -> BTreeMap<Tag, &[u8]> {
+    let mut ret = BTreeMap::default();
+    assert_eq!(checksum(data), 0xB1B0_AFBA);
+    for i in 0..read_u16(data, 4) as usize {
+        let record = 12 + 16 * i;
+        let tag: Tag = data[record..record + 4].try_into().unwrap();
+        let offset = read_u32(data, record + 8) as usize;
+        let table = &data[offset..offset + read_u32(data, record + 12) as usize];
+        let mut expected = checksum(table);
+        if &tag == b"head" {
+            expected = expected.wrapping_sub(read_u32(table, 8));
+        }
+        assert_eq!(read_u32(data, record + 4), expected, "{:?}", tag);
+        ret.insert(tag, table);
+    }
+    ret
+}
+
+/// Look up `c` in the Windows Unicode BMP subtable of `cmap`.
+#[cfg(test)]
+fn read_cmap(cmap: &[u8], c: char) -> u16 {
+    let offset = (0..read_u16(cmap, 2) as usize)
+        .map(|i| 4 + 8 * i)
+        .find(|&r| read_u16(cmap, r) == 3 && read_u16(cmap, r + 2) == 1)
+        .map(|r| read_u32(cmap, r + 4) as usize)
+        .unwrap();
+    let subtable = &cmap[offset..];
+    assert_eq!(read_u16(subtable, 0), 4);
+    let seg_count_x2 = read_u16(subtable, 6) as usize;
+    let c = c as u16;
+    for seg in (0..seg_count_x2).step_by(2) {
+        let end = read_u16(subtable, 14 + seg);
+        let start = read_u16(subtable, 16 + seg_count_x2 + seg);
+        if (start..=end).contains(&c) {
+            assert_eq!(read_u16(subtable, 16 + 3 * seg_count_x2 + seg), 0);
+            return c.wrapping_add(read_u16(subtable, 16 + 2 * seg_count_x2 + seg));
+        }
+    }
+    0
+}
+
+/// Read a Windows Unicode English string of the `name` table.
+#[cfg(test)]
+fn read_name(name: &[u8], name_id: u16) -> Option<String> {
+    let storage = read_u16(name, 4) as usize;
+    (0..read_u16(name, 2) as usize)
+        .map(|i| 6 + 12 * i)
+        .find(|&r| {
+            [3, 1, 0x409, name_id]
+                .into_iter()
+                .enumerate()
+                .all(|(i, v)| read_u16(name, r + 2 * i) == v)
+        })
+        .map(|r| {
+            let (len, offset) = (
+                read_u16(name, r + 8) as usize,
+                read_u16(name, r + 10) as usize,
+            );
+            let units = name[storage + offset..storage + offset + len]
+                .chunks(2)
+                .map(|c| u16::from_be_bytes([c[0], c[1]]))
+                .collect::<Vec<u16>>();
+            String::from_utf16(&units).unwrap()
+        })
+}
+
+/// Decode the contours of a simple glyph description.
+#[cfg(test)]
+fn read_simple_glyph(glyph: &[u8]) -> Vec<Vec<(i16, i16, bool)>> {
+    let contours = read_i16(glyph, 0) as usize;
+    let end_points = (0..contours)
+        .map(|i| read_u16(glyph, 10 + 2 * i) as usize)
+        .collect::<Vec<_>>();
+    let n = end_points.last().map(|e| e + 1).unwrap_or(0);
+    let mut pos = 12 + 2 * contours;
+    pos += 2 + read_u16(glyph, pos) as usize;
+    let mut flags = vec![];
+    while flags.len() < n {
+        let flag = glyph[pos];
+        pos += 1;
+        flags.push(flag);
+        if flag & 0x08 != 0 {
+            for _ in 0..glyph[pos] {
+                flags.push(flag);
+            }
+            pos += 1;
+        }
+    }
+    let mut read_coords = |short: u8, same_or_positive: u8| {
+        let mut v = 0i16;
+        flags
+            .iter()
+            .map(|f| {
+                if f & short != 0 {
+                    let d = glyph[pos] as i16;
+                    pos += 1;
+                    v += if f & same_or_positive != 0 { d } else { -d };
+                } else if f & same_or_positive == 0 {
+                    v += read_i16(glyph, pos);
+                    pos += 2;
+                }
+                v
+            })
+            .collect::<Vec<i16>>()
+    };
+    let xs = read_coords(0x02, 0x10);
+    let ys = read_coords(0x04, 0x20);
+    let mut start = 0;
+    end_points
+        .into_iter()
+        .map(|end| {
+            let contour = (start..=end)
+                .map(|i| (xs[i], ys[i], flags[i] & 0x01 != 0))
+                .collect();
+            start = end + 1;
+            contour
+        })
+        .collect()
+}
+
+/// Read a CFF INDEX at `pos`, and return its objects and the position after it.
+#[cfg(test)]
+fn read_cff_index(data: &[u8], pos: usize) -> (Vec<&[u8]>, usize) {
+    let count = read_u16(data, pos) as usize;
+    if count == 0 {
+        return (vec![], pos + 2);
+    }
+    let off_size = data[pos + 2] as usize;
+    let offset = |i: usize| {
+        let start = pos + 3 + i * off_size;
+        data[start..start + off_size]
+            .iter()
+            .fold(0usize, |acc, b| acc << 8 | *b as usize)
+    };
+    let base = pos + 2 + (count + 1) * off_size;
+    let objects = (0..count)
+        .map(|i| &data[base + offset(i)..base + offset(i + 1)])
+        .collect();
+    (objects, base + offset(count))
+}
+
+/// Decode the integer operands of a CFF DICT or charstring, calling `op` for each operator.
+#[cfg(test)]
+fn read_cff_ops(data: &[u8], mut op: impl FnMut(u16, &mut Vec<f64>)) {
+    let mut operands = vec![];
+    let mut pos = 0;
+    while pos < data.len() {
+        let b0 = data[pos] as i32;
+        pos += 1;
+        match b0 {
+            32..=246 => operands.push((b0 - 139) as f64),
+            247..=250 => {
+                operands.push(((b0 - 247) * 256 + data[pos] as i32 + 108) as f64);
+                pos += 1;
+            }
+            251..=254 => {
+                operands.push((-(b0 - 251) * 256 - data[pos] as i32 - 108) as f64);
+                pos += 1;
+            }
+            28 => {
+                operands.push(read_i16(data, pos).into());
+                pos += 2;
+            }
+            29 => {
+                operands.push(read_u32(data, pos) as i32 as f64);
+                pos += 4;
+            }
+            30 => {
+                let mut s = String::new();
+                'real: loop {
+                    for nibble in [data[pos] >> 4, data[pos] & 0xf] {
+                        match nibble {
+                            0..=9 => s.push((b'0' + nibble) as char),
+                            0xa => s.push('.'),
+                            0xe => s.push('-'),
+                            _ => {
+                                pos += 1;
+                                break 'real;
+                            }
+                        }
+                    }
+                    pos += 1;
+                }
+                operands.push(s.parse().unwrap());
+            }
+            12 => {
+                op(1200 + data[pos] as u16, &mut operands);
+                pos += 1;
+                operands.clear();
+            }
+            _ => {
+                op(b0 as u16, &mut operands);
+                operands.clear();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+fn test_font() -> (FontInfo, IndexMap<String, Rc<RefCell<Glyph>>>) {
+    use super::glif::{self, Glif};
+    use std::str::FromStr;
+
+    let info = FontInfo {
+        family_name: "Test Family".to_string(),
+        style_name: "Regular".to_string(),
+        units_per_em: Some(1000.0),
+        ascender: Some(750.0),
+        descender: Some(-250.0),
+        version_major: Some(1),
+        version_minor: Some(2),
+        open_type_os2_weight_class: Some(500),
+        ..FontInfo::default()
+    };
+    let mut glyphs = IndexMap::default();
+    for src in [
+        glif::_UPPERCASE_A_GLIF,
+        glif::_LOWERCASE_B_GLIF,
+        glif::_QUADRATIC_GLIF,
+        glif::_AE_GLIF,
+        _AGRAVE_GLIF,
+    ] {
+        let glyph: Glyph = Glif::from_str(src).unwrap().try_into().unwrap();
+        let name = glyph.name().as_str().to_string();
+        glyphs.insert(name, Rc::new(RefCell::new(glyph)));
+    }
+    (info, glyphs)
+}
+
+#[test]
+fn test_sfnt_truetype() {
+    let (info, glyphs) = test_font();
+    let data = compile(&info, &glyphs, OutputFormat::Ttf).unwrap();
+    assert_eq!(read_u32(&data, 0), 0x0001_0000);
+    let tables = read_tables(&data);
+    assert_eq!(
+        tables.keys().copied().collect::<Vec<Tag>>(),
+        [
+            b"OS/2", b"cmap", b"glyf", b"head", b"hhea", b"hmtx", b"loca", b"maxp", b"name",
+            b"post"
+        ]
+        .map(|t| *t)
+    );
+
+    let head = tables[b"head"];
+    assert_eq!(head.len(), 54);
+    assert_eq!(read_u32(head, 4), 0x0001_0083);
+    assert_eq!(read_u32(head, 12), 0x5F0F_3CF5);
+    assert_eq!(read_u16(head, 18), 1000);
+    let long_loca = read_i16(head, 50) == 1;
+
+    let maxp = tables[b"maxp"];
+    assert_eq!(maxp.len(), 32);
+    assert_eq!(read_u16(maxp, 4) as usize, glyphs.len() + 1);
+    /* maxComponentElements, maxComponentDepth */
+    assert_eq!((read_u16(maxp, 28), read_u16(maxp, 30)), (1, 1));
+
+    let post = tables[b"post"];
+    assert_eq!(read_u32(post, 0), 0x0002_0000);
+    let num_glyphs = read_u16(post, 32) as usize;
+    let mut pos = 34 + 2 * num_glyphs;
+    let mut names = vec![];
+    for i in 0..num_glyphs {
+        let index = read_u16(post, 34 + 2 * i);
+        if index == 0 {
+            names.push(".notdef".to_string());
+            continue;
+        }
+        assert_eq!(index as usize, 258 + names.len() - 1);
+        let len = post[pos] as usize;
+        names.push(String::from_utf8(post[pos + 1..pos + 1 + len].to_vec()).unwrap());
+        pos += 1 + len;
+    }
+    assert_eq!(names, [".notdef", "A", "b", "o.quadratic", "ae", "Agrave"]);
+
+    let cmap = tables[b"cmap"];
+    for (c, gid) in [('A', 1), ('b', 2), ('æ', 4), ('À', 5), ('z', 0)] {
+        assert_eq!(read_cmap(cmap, c), gid, "{c}");
+    }
+
+    let hhea = tables[b"hhea"];
+    assert_eq!(read_i16(hhea, 4), 750);
+    assert_eq!(read_i16(hhea, 6), -250);
+    assert_eq!(read_u16(hhea, 10), 778);
+    let number_of_h_metrics = read_u16(hhea, 34) as usize;
+    let hmtx = tables[b"hmtx"];
+    assert_eq!(
+        hmtx.len(),
+        4 * number_of_h_metrics + 2 * (num_glyphs - number_of_h_metrics)
+    );
+    let advance = |gid: usize| read_u16(hmtx, 4 * gid.min(number_of_h_metrics - 1));
+    for (name, glyph) in glyphs.iter() {
+        let gid = names.iter().position(|n| n == name).unwrap();
+        assert_eq!(
+            advance(gid) as f64,
+            glyph.borrow().width().unwrap(),
+            "{name}"
+        );
+    }
+
+    let name = tables[b"name"];
+    assert_eq!(read_name(name, 1).as_deref(), Some("Test Family"));
+    assert_eq!(read_name(name, 2).as_deref(), Some("Regular"));
+    assert_eq!(read_name(name, 5).as_deref(), Some("Version 1.002"));
+    assert_eq!(read_name(name, 6).as_deref(), Some("TestFamily-Regular"));
+
+    let os2 = tables[b"OS/2"];
+    assert_eq!(os2.len(), 96);
+    assert_eq!(read_u16(os2, 4), 500);
+    /* fsSelection REGULAR */
+    assert_eq!(read_u16(os2, 62), 1 << 6);
+    assert_eq!((read_u16(os2, 64), read_u16(os2, 66)), (0x41, 0xE6));
+
+    let loca = tables[b"loca"];
+    let glyph_data = |gid: usize| {
+        let (start, end) = if long_loca {
+            (
+                read_u32(loca, 4 * gid) as usize,
+                read_u32(loca, 4 * gid + 4) as usize,
+            )
+        } else {
+            (
+                2 * read_u16(loca, 2 * gid) as usize,
+                2 * read_u16(loca, 2 * gid + 2) as usize,
+            )
+        };
+        &tables[b"glyf"][start..end]
+    };
+    /* Contours are reversed and start from the same point. */
+    let a = read_simple_glyph(glyph_data(1));
+    assert_eq!(a.len(), 2);
+    assert_eq!(
+        a[1],
+        [
+            (119, 200, true),
+            (119, 267, true),
+            (422, 267, true),
+            (422, 200, true)
+        ]
+    );
+    /* Quadratic curves are kept as they are, and implied on-curve points are omitted. */
+    let o = read_simple_glyph(glyph_data(3));
+    assert_eq!(
+        o,
+        [
+            vec![
+                (250, 0, true),
+                (50, 0, false),
+                (50, 500, true),
+                (250, 500, true),
+                (350, 500, false),
+                (450, 400, false),
+                (450, 100, false),
+                (350, 0, false),
+            ],
+            vec![
+                (100, 200, false),
+                (200, 200, false),
+                (200, 100, false),
+                (100, 100, false),
+            ],
+            vec![
+                (0, 0, true),
+                (30, 20, true),
+                (30, 10, false),
+                (20, 0, false),
+                (10, 0, true),
+            ],
+        ]
+    );
+    /* Agrave is a composite glyph. */
+    let agrave = glyph_data(5);
+    assert_eq!(read_i16(agrave, 0), -1);
+    let a_bounds = (2..10).step_by(2).map(|i| read_i16(glyph_data(1), i));
+    let agrave_bounds = (2..10).step_by(2).map(|i| read_i16(agrave, i));
+    assert!(a_bounds
+        .zip(agrave_bounds)
+        .zip([10, 20, 10, 20])
+        .all(|((a, agrave), offset)| a + offset == agrave));
+    /* flags, glyphIndex, argument1, argument2 */
+    assert_eq!(read_u16(agrave, 10) & 0x00A3, 0x0003);
+    assert_eq!(read_u16(agrave, 12), 1);
+    assert_eq!((read_i16(agrave, 14), read_i16(agrave, 16)), (10, 20));
+}
+
+#[test]
+fn test_sfnt_cff() {
+    let (info, glyphs) = test_font();
+    let data = compile(&info, &glyphs, OutputFormat::Otf).unwrap();
+    assert_eq!(&data[..4], b"OTTO");
+    let tables = read_tables(&data);
+    assert_eq!(
+        tables.keys().copied().collect::<Vec<Tag>>(),
+        [b"CFF ", b"OS/2", b"cmap", b"head", b"hhea", b"hmtx", b"maxp", b"name", b"post"]
+            .map(|t| *t)
+    );
+    assert_eq!(read_u32(tables[b"maxp"], 0), 0x0000_5000);
+    assert_eq!(read_u32(tables[b"post"], 0), 0x0003_0000);
+
+    let cff = tables[b"CFF "];
+    let (names, pos) = read_cff_index(cff, cff[2] as usize);
+    assert_eq!(names, [b"TestFamily-Regular"]);
+    let (top_dicts, pos) = read_cff_index(cff, pos);
+    let (strings, _) = read_cff_index(cff, pos);
+    let mut top_dict = BTreeMap::default();
+    read_cff_ops(top_dicts[0], |op, operands| {
+        top_dict.insert(op, operands.clone());
+    });
+    let string = |sid: f64| std::str::from_utf8(strings[sid as usize - 391]).unwrap();
+    assert_eq!(string(top_dict[&3][0]), "Test Family");
+    assert_eq!(string(top_dict[&0][0]), "1.002");
+    let (charstrings, _) = read_cff_index(cff, top_dict[&17][0] as usize);
+    assert_eq!(charstrings.len(), glyphs.len() + 1);
+    let charset = top_dict[&15][0] as usize;
+    assert_eq!(cff[charset], 0);
+    let names = (1..charstrings.len())
+        .map(|gid| string(read_u16(cff, charset + 1 + 2 * (gid - 1)).into()))
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["A", "b", "o.quadratic", "ae", "Agrave"]);
+
+    /* Decode the outline of A: cubic curves are kept as they are. */
+    let mut width = None;
+    let mut contours: Vec<Vec<(f64, f64)>> = vec![];
+    let mut current = (0.0, 0.0);
+    read_cff_ops(charstrings[1], |op, args| {
+        let expected = match op {
+            21 => 2,
+            5 | 8 => args.len() - args.len() % 2,
+            _ => 0,
+        };
+        if width.is_none() {
+            width = Some(if args.len() > expected {
+                args.remove(0)
+            } else {
+                0.0
+            });
+        }
+        if op == 21 {
+            contours.push(vec![]);
+        }
+        for d in args.chunks(2) {
+            current = (current.0 + d[0], current.1 + d[1]);
+            contours.last_mut().unwrap().push(current);
+        }
+    });
+    assert_eq!(width, Some(544.0));
+    assert_eq!(contours.len(), 2);
+    assert_eq!(
+        contours[0][..6],
+        [
+            (3.0, 0.0),
+            (88.0, 0.0),
+            (203.0, 367.0),
+            (227.0, 440.0),
+            (248.0, 512.0),
+            (268.0, 588.0)
+        ]
+    );
+    assert_eq!(
+        contours[1],
+        [
+            (119.0, 200.0),
+            (422.0, 200.0),
+            (422.0, 267.0),
+            (119.0, 267.0)
+        ]
+    );
+
+    let hmtx = tables[b"hmtx"];
+    /* Left side bearing of A */
+    assert_eq!(read_i16(hmtx, 6), 3);
+}
+
+#[test]
+fn test_sfnt_helpers() {
+    use glyf::cubic_to_quadratics;
+
+    let cubic: [Point; 4] =
+        [(194.0, -12.0), (257.0, -12.0), (327.0, 19.0), (392.0, 79.0)].map(Point::from);
+    for tolerance in [1.0, 0.1, 0.01] {
+        let quadratics = cubic_to_quadratics(cubic, tolerance);
+        assert_eq!(quadratics.last().unwrap().1, cubic[3]);
+        let n = quadratics.len();
+        let mut start = cubic[0];
+        for (i, (control, end)) in quadratics.into_iter().enumerate() {
+            for k in 0..=8 {
+                let t = k as f64 / 8.0;
+                let mt = 1.0 - t;
+                let q = (mt * mt) * start + (2.0 * mt * t) * control + (t * t) * end;
+                let c = cubic_point(cubic, (i as f64 + t) / n as f64);
+                assert!(q.distance(c) <= tolerance, "{tolerance} {n} {i} {t}");
+            }
+            start = end;
+        }
+    }
+    assert_eq!(cubic_to_quadratics(cubic, 1.0).len(), 1);
+
+    assert_eq!(
+        parse_datetime("1970/01/01 00:00:00"),
+        Some(MAC_EPOCH_OFFSET)
+    );
+    assert_eq!(
+        parse_datetime("2000/03/01 12:30:15"),
+        Some(951_868_800 + 12 * 3600 + 30 * 60 + 15 + MAC_EPOCH_OFFSET)
+    );
+    assert_eq!(parse_datetime("2000-03-01"), None);
+
+    assert_eq!(search_params(10, 16), (128, 3, 32));
+    assert_eq!(
+        cmap_format_12(&[(0x41, 1), (0x42, 2), (0x1F600, 3)].into_iter().collect())[12..16],
+        2u32.to_be_bytes()
+    );
+}
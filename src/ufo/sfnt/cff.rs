@@ -0,0 +1,487 @@
+/*
+ * gerb
+ *
+ * Copyright 2022 - Manos Pitsidianakis
+ *
+ * This file is part of gerb.
+ *
+ * gerb is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * gerb is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with gerb. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! PostScript outlines: the `CFF ` table.
+//!
+//! Writes a single font CFF version 1 table with Type 2 charstrings. Quadratic curves are
+//! converted to cubic curves exactly; outlines are not hinted and no subroutines are used.
+
+use super::*;
+
+/* Type 2 charstring operators */
+const RLINETO: u8 = 5;
+const RRCURVETO: u8 = 8;
+const ENDCHAR: u8 = 14;
+const RMOVETO: u8 = 21;
+
+/* DICT operators; two byte operators are prefixed with 12. */
+const VERSION: &[u8] = &[0];
+const NOTICE: &[u8] = &[1];
+const FULL_NAME: &[u8] = &[2];
+const FAMILY_NAME: &[u8] = &[3];
+const WEIGHT: &[u8] = &[4];
+const FONT_BBOX: &[u8] = &[5];
+const CHARSET: &[u8] = &[15];
+const CHAR_STRINGS: &[u8] = &[17];
+const PRIVATE: &[u8] = &[18];
+const COPYRIGHT: &[u8] = &[12, 0];
+const IS_FIXED_PITCH: &[u8] = &[12, 1];
+const ITALIC_ANGLE: &[u8] = &[12, 2];
+const UNDERLINE_POSITION: &[u8] = &[12, 3];
+const UNDERLINE_THICKNESS: &[u8] = &[12, 4];
+const FONT_MATRIX: &[u8] = &[12, 7];
+const BLUE_VALUES: &[u8] = &[6];
+const OTHER_BLUES: &[u8] = &[7];
+const FAMILY_BLUES: &[u8] = &[8];
+const FAMILY_OTHER_BLUES: &[u8] = &[9];
+const STD_HW: &[u8] = &[10];
+const STD_VW: &[u8] = &[11];
+const BLUE_SCALE: &[u8] = &[12, 9];
+const BLUE_SHIFT: &[u8] = &[12, 10];
+const BLUE_FUZZ: &[u8] = &[12, 11];
+const STEM_SNAP_H: &[u8] = &[12, 12];
+const STEM_SNAP_V: &[u8] = &[12, 13];
+const FORCE_BOLD: &[u8] = &[12, 14];
+
+/// Number of standard strings; custom string ids start after them.
+const N_STD_STRINGS: usize = 391;
+
+pub(super) struct Cff {
+    pub data: Vec<u8>,
+    pub bounds: Vec<Option<Bounds>>,
+}
+
+pub(super) fn compile(font: &Font<'_>) -> Result<Cff, Box<dyn std::error::Error>> {
+    let info = font.info;
+    let mut strings = Strings::default();
+    let mut charstrings = Vec::with_capacity(font.glyphs.len());
+    let mut bounds = Vec::with_capacity(font.glyphs.len());
+    let mut charset = vec![0];
+    for (gid, glyph) in font.glyphs.iter().enumerate() {
+        let (charstring, b) = charstring(glyph)?;
+        charstrings.push(charstring);
+        bounds.push(b);
+        if gid > 0 {
+            charset.put_u16(strings.sid(&glyph.name));
+        }
+    }
+    let total = Bounds::union(&bounds).unwrap_or_default();
+    let (major, minor) = font.version();
+    let (underline_position, underline_thickness) = font.underline();
+
+    let mut top_dict = Dict::default();
+    top_dict.op(
+        &[strings.sid(&format!("{major}.{minor:03}")).into()],
+        VERSION,
+    );
+    if !info.trademark.is_empty() {
+        top_dict.op(&[strings.sid(&info.trademark).into()], NOTICE);
+    }
+    if !info.copyright.is_empty() {
+        top_dict.op(&[strings.sid(&info.copyright).into()], COPYRIGHT);
+    }
+    let full_name = info
+        .postscript_full_name
+        .clone()
+        .unwrap_or_else(|| format!("{} {}", family_name(info), style_name(info)));
+    top_dict.op(&[strings.sid(&full_name).into()], FULL_NAME);
+    top_dict.op(&[strings.sid(&family_name(info)).into()], FAMILY_NAME);
+    if let Some(weight) = info.postscript_weight_name.as_deref() {
+        top_dict.op(&[strings.sid(weight).into()], WEIGHT);
+    }
+    if info.postscript_is_fixed_pitch == Some(true) {
+        top_dict.op(&[Operand::Int(1)], IS_FIXED_PITCH);
+    }
+    if font.italic_angle() != 0.0 {
+        top_dict.op(&[Operand::Real(font.italic_angle())], ITALIC_ANGLE);
+    }
+    top_dict.op(&[Operand::Real(underline_position)], UNDERLINE_POSITION);
+    top_dict.op(&[Operand::Real(underline_thickness)], UNDERLINE_THICKNESS);
+    if font.units_per_em != 1000.0 {
+        let scale = 1.0 / font.units_per_em;
+        top_dict.op(
+            &[
+                Operand::Real(scale),
+                Operand::Int(0),
+                Operand::Int(0),
+                Operand::Real(scale),
+                Operand::Int(0),
+                Operand::Int(0),
+            ],
+            FONT_MATRIX,
+        );
+    }
+    top_dict.op(
+        &[total.x_min, total.y_min, total.x_max, total.y_max].map(|v| Operand::Int(v.into())),
+        FONT_BBOX,
+    );
+
+    let private = private_dict(info);
+    let name_index = index(&[postscript_name(info).into_bytes()]);
+    let string_index = index(&strings.0);
+    let global_subrs = index(&[]);
+    let charstrings = index(&charstrings);
+
+    /* Offsets are written last with a fixed size encoding, so that the size of the top DICT
+     * doesn't depend on them. */
+    let private_len = i32::try_from(private.len())?;
+    let top_dict_index = |charset_offset: i32, charstrings_offset: i32, private_offset: i32| {
+        let mut dict = top_dict.clone();
+        dict.op(&[Operand::Offset(charset_offset)], CHARSET);
+        dict.op(&[Operand::Offset(charstrings_offset)], CHAR_STRINGS);
+        dict.op(
+            &[
+                Operand::Offset(private_len),
+                Operand::Offset(private_offset),
+            ],
+            PRIVATE,
+        );
+        index(&[dict.0])
+    };
+    let charset_offset = 4
+        + name_index.len()
+        + top_dict_index(0, 0, 0).len()
+        + string_index.len()
+        + global_subrs.len();
+    let charstrings_offset = charset_offset + charset.len();
+    let private_offset = charstrings_offset + charstrings.len();
+    let (charset_offset, charstrings_offset, private_offset) = match (
+        i32::try_from(charset_offset),
+        i32::try_from(charstrings_offset),
+        i32::try_from(private_offset),
+    ) {
+        (Ok(a), Ok(b), Ok(c)) => (a, b, c),
+        _ => return Err("CFF table is too large.".into()),
+    };
+
+    /* Header: major, minor, header size, absolute offset size. */
+    let mut data = vec![1, 0, 4, 4];
+    data.extend(name_index);
+    data.extend(top_dict_index(
+        charset_offset,
+        charstrings_offset,
+        private_offset,
+    ));
+    data.extend(string_index);
+    data.extend(global_subrs);
+    data.extend(charset);
+    data.extend(charstrings);
+    data.extend(private);
+    Ok(Cff { data, bounds })
+}
+
+fn private_dict(info: &FontInfo) -> Vec<u8> {
+    /// Blue zones and stem snaps are stored as deltas from the previous value.
+    fn deltas(values: &[f64]) -> Vec<Operand> {
+        let mut prev = 0.0;
+        values
+            .iter()
+            .map(|v| {
+                let d = Operand::Real(v - prev);
+                prev = *v;
+                d
+            })
+            .collect()
+    }
+
+    let mut dict = Dict::default();
+    for (values, op) in [
+        (&info.postscript_blue_values, BLUE_VALUES),
+        (&info.postscript_other_blues, OTHER_BLUES),
+        (&info.postscript_family_blues, FAMILY_BLUES),
+        (&info.postscript_family_other_blues, FAMILY_OTHER_BLUES),
+    ] {
+        if let Some(values) = values.as_deref().filter(|v| !v.is_empty()) {
+            dict.op(&deltas(values), op);
+        }
+    }
+    for (values, std_op, snap_op) in [
+        (&info.postscript_stem_snap_h, STD_HW, STEM_SNAP_H),
+        (&info.postscript_stem_snap_v, STD_VW, STEM_SNAP_V),
+    ] {
+        if let Some(values) = values.as_deref().filter(|v| !v.is_empty()) {
+            dict.op(&[Operand::Real(values[0])], std_op);
+            dict.op(&deltas(values), snap_op);
+        }
+    }
+    for (value, op) in [
+        (info.postscript_blue_scale, BLUE_SCALE),
+        (info.postscript_blue_shift, BLUE_SHIFT),
+        (info.postscript_blue_fuzz, BLUE_FUZZ),
+    ] {
+        if let Some(value) = value {
+            dict.op(&[Operand::Real(value)], op);
+        }
+    }
+    if info.postscript_force_bold == Some(true) {
+        dict.op(&[Operand::Int(1)], FORCE_BOLD);
+    }
+    dict.0
+}
+
+/// Encode the glyph's outline as a Type 2 charstring, and compute its bounds.
+///
+/// The advance width is written as a delta from the default `nominalWidthX` of 0.
+fn charstring(glyph: &FontGlyph) -> Result<(Vec<u8>, Option<Bounds>), Box<dyn std::error::Error>> {
+    let mut ret = vec![];
+    let mut args: Vec<i32> = vec![];
+    let advance = glyph.advance.round() as i32;
+    if advance != 0 {
+        args.push(advance);
+    }
+    let mut current = (0, 0);
+    let mut extrema = vec![];
+    let round = |p: Point| (p.x.round() as i32, p.y.round() as i32);
+    for path in glyph.decomposed.iter() {
+        let start = round(path.start);
+        args.extend([start.0 - current.0, start.1 - current.1]);
+        push_op(&mut ret, &mut args, RMOVETO)?;
+        current = start;
+        extrema.push(path.start);
+        for (i, segment) in path.segments.iter().enumerate() {
+            let (c1, c2, end) = match *segment {
+                Segment::Line(p) => {
+                    let end = round(p);
+                    /* Contours are closed implicitly. */
+                    if i + 1 == path.segments.len() && end == start {
+                        break;
+                    }
+                    args.extend([end.0 - current.0, end.1 - current.1]);
+                    push_op(&mut ret, &mut args, RLINETO)?;
+                    current = end;
+                    extrema.push(p);
+                    continue;
+                }
+                Segment::Quad(c, p) => {
+                    let p0 = Point::from((current.0.into(), current.1.into()));
+                    (
+                        round(p0 + (2.0 / 3.0) * (c - p0)),
+                        round(p + (2.0 / 3.0) * (c - p)),
+                        round(p),
+                    )
+                }
+                Segment::Cubic(c1, c2, p) => (round(c1), round(c2), round(p)),
+            };
+            args.extend([
+                c1.0 - current.0,
+                c1.1 - current.1,
+                c2.0 - c1.0,
+                c2.1 - c1.1,
+                end.0 - c2.0,
+                end.1 - c2.1,
+            ]);
+            push_op(&mut ret, &mut args, RRCURVETO)?;
+            let point = |p: (i32, i32)| Point::from((p.0.into(), p.1.into()));
+            extrema.extend(cubic_extrema([
+                point(current),
+                point(c1),
+                point(c2),
+                point(end),
+            ]));
+            current = end;
+        }
+    }
+    push_op(&mut ret, &mut args, ENDCHAR)?;
+    Ok((ret, Bounds::from_points(extrema)))
+}
+
+fn push_op(
+    charstring: &mut Vec<u8>,
+    args: &mut Vec<i32>,
+    op: u8,
+) -> Result<(), Box<dyn std::error::Error>> {
+    for arg in args.drain(..) {
+        if !put_int(charstring, arg) {
+            return Err(format!("Coordinate {arg} is out of range.").into());
+        }
+    }
+    charstring.put_u8(op);
+    Ok(())
+}
+
+/// Encode an integer operand in the shortest form shared by DICTs and charstrings.
+///
+/// Returns `false` if `v` doesn't fit in 16 bits.
+fn put_int(out: &mut Vec<u8>, v: i32) -> bool {
+    match v {
+        -107..=107 => out.put_u8((v + 139) as u8),
+        108..=1131 => {
+            let v = v - 108;
+            out.put_u8((v / 256 + 247) as u8);
+            out.put_u8((v % 256) as u8);
+        }
+        -1131..=-108 => {
+            let v = -v - 108;
+            out.put_u8((v / 256 + 251) as u8);
+            out.put_u8((v % 256) as u8);
+        }
+        -32768..=32767 => {
+            out.put_u8(28);
+            out.put_i16(v as i16);
+        }
+        _ => return false,
+    }
+    true
+}
+
+/// End points and extrema of a cubic curve.
+fn cubic_extrema(p: [Point; 4]) -> Vec<Point> {
+    let mut ret = vec![p[0], p[3]];
+    let coord = |p: Point, axis: usize| if axis == 0 { p.x } else { p.y };
+    for axis in 0..2 {
+        let [p0, p1, p2, p3] = p.map(|p| coord(p, axis));
+        /* Roots of the derivative a t^2 + b t + c. */
+        let a = 3.0 * 3.0f64.mul_add(p1 - p2, p3 - p0);
+        let b = 6.0 * 2.0f64.mul_add(-p1, p0 + p2);
+        let c = 3.0 * (p1 - p0);
+        let roots = if a.abs() < 1e-12 {
+            if b.abs() < 1e-12 {
+                vec![]
+            } else {
+                vec![-c / b]
+            }
+        } else {
+            let discriminant = b * b - 4.0 * a * c;
+            if discriminant < 0.0 {
+                vec![]
+            } else {
+                let sqrt = discriminant.sqrt();
+                vec![(-b + sqrt) / (2.0 * a), (-b - sqrt) / (2.0 * a)]
+            }
+        };
+        ret.extend(
+            roots
+                .into_iter()
+                .filter(|t| *t > 0.0 && *t < 1.0)
+                .map(|t| cubic_point(p, t)),
+        );
+    }
+    ret
+}
+
+/// The custom strings of the String INDEX.
+#[derive(Default)]
+struct Strings(Vec<Vec<u8>>);
+
+impl Strings {
+    /// String id of `s`, adding it if it doesn't exist.
+    fn sid(&mut self, s: &str) -> u16 {
+        let pos = match self.0.iter().position(|e| e == s.as_bytes()) {
+            Some(pos) => pos,
+            None => {
+                self.0.push(s.as_bytes().to_vec());
+                self.0.len() - 1
+            }
+        };
+        (N_STD_STRINGS + pos) as u16
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Operand {
+    Int(i32),
+    Real(f64),
+    /// An integer always encoded in five bytes.
+    Offset(i32),
+}
+
+impl From<u16> for Operand {
+    fn from(v: u16) -> Self {
+        Self::Int(v.into())
+    }
+}
+
+#[derive(Clone, Default)]
+struct Dict(Vec<u8>);
+
+impl Dict {
+    fn op(&mut self, operands: &[Operand], op: &[u8]) {
+        for operand in operands {
+            match *operand {
+                Operand::Real(v) if v.fract() == 0.0 && v.abs() < f64::from(i32::MAX) => {
+                    self.int(v as i32)
+                }
+                Operand::Int(v) => self.int(v),
+                Operand::Offset(v) => {
+                    self.0.put_u8(29);
+                    self.0.put_i32(v);
+                }
+                Operand::Real(v) => self.real(v),
+            }
+        }
+        self.0.extend_from_slice(op);
+    }
+
+    fn int(&mut self, v: i32) {
+        if !put_int(&mut self.0, v) {
+            self.0.put_u8(29);
+            self.0.put_i32(v);
+        }
+    }
+
+    /// Real numbers are encoded as a nibble string of their decimal representation.
+    fn real(&mut self, v: f64) {
+        let mut nibbles = vec![];
+        for c in format!("{v}").chars() {
+            nibbles.push(match c {
+                '0'..='9' => c as u8 - b'0',
+                '.' => 0xa,
+                '-' => 0xe,
+                _ => continue,
+            });
+        }
+        nibbles.push(0xf);
+        if nibbles.len() % 2 == 1 {
+            nibbles.push(0xf);
+        }
+        self.0.put_u8(30);
+        for pair in nibbles.chunks(2) {
+            self.0.put_u8(pair[0] << 4 | pair[1]);
+        }
+    }
+}
+
+/// A CFF INDEX: a list of variable length objects.
+fn index(objects: &[Vec<u8>]) -> Vec<u8> {
+    let mut ret = vec![];
+    ret.put_u16(objects.len() as u16);
+    if objects.is_empty() {
+        return ret;
+    }
+    let data_len = objects.iter().map(Vec::len).sum::<usize>();
+    let off_size = match data_len + 1 {
+        0..=0xFF => 1,
+        0x100..=0xFFFF => 2,
+        0x1_0000..=0xFF_FFFF => 3,
+        _ => 4,
+    };
+    ret.put_u8(off_size as u8);
+    let mut offset = 1usize;
+    ret.extend_from_slice(&(offset as u32).to_be_bytes()[4 - off_size..]);
+    for object in objects {
+        offset += object.len();
+        ret.extend_from_slice(&(offset as u32).to_be_bytes()[4 - off_size..]);
+    }
+    for object in objects {
+        ret.extend_from_slice(object);
+    }
+    ret
+}
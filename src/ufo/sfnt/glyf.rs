@@ -0,0 +1,449 @@
+/*
+ * gerb
+ *
+ * Copyright 2022 - Manos Pitsidianakis
+ *
+ * This file is part of gerb.
+ *
+ * gerb is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * gerb is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with gerb. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! TrueType outlines: the `glyf`, `loca` and `maxp` tables.
+//!
+//! Cubic curves are approximated with quadratic curves, and contours are reversed to follow
+//! the TrueType (clockwise) direction convention. Glyphs that consist only of components are
+//! written as composite glyphs if their transformations can be represented; otherwise they are
+//! decomposed.
+
+use super::*;
+
+const ON_CURVE_POINT: u8 = 0x01;
+const X_SHORT_VECTOR: u8 = 0x02;
+const Y_SHORT_VECTOR: u8 = 0x04;
+const REPEAT_FLAG: u8 = 0x08;
+const X_IS_SAME_OR_POSITIVE_X_SHORT_VECTOR: u8 = 0x10;
+const Y_IS_SAME_OR_POSITIVE_Y_SHORT_VECTOR: u8 = 0x20;
+
+const ARG_1_AND_2_ARE_WORDS: u16 = 0x0001;
+const ARGS_ARE_XY_VALUES: u16 = 0x0002;
+const ROUND_XY_TO_GRID: u16 = 0x0004;
+const WE_HAVE_A_SCALE: u16 = 0x0008;
+const MORE_COMPONENTS: u16 = 0x0020;
+const WE_HAVE_AN_X_AND_Y_SCALE: u16 = 0x0040;
+const WE_HAVE_A_TWO_BY_TWO: u16 = 0x0080;
+
+/// Maximum number of quadratic curves a cubic curve is split into.
+const MAX_QUADRATIC_SEGMENTS: usize = 16;
+
+/// A point of a TrueType contour: coordinates and whether it is on the curve.
+type TtPoint = (i16, i16, bool);
+
+pub(super) struct Glyf {
+    pub glyf: Vec<u8>,
+    pub loca: Vec<u8>,
+    pub long_loca: bool,
+    pub bounds: Vec<Option<Bounds>>,
+    pub maxp: Vec<u8>,
+}
+
+enum Outline {
+    Simple(Vec<Vec<TtPoint>>),
+    /// Base glyph ids and transformations.
+    Composite(Vec<(u16, Matrix)>),
+}
+
+#[derive(Default)]
+struct MaxpStats {
+    max_points: usize,
+    max_contours: usize,
+    max_composite_points: usize,
+    max_composite_contours: usize,
+    max_component_elements: usize,
+    max_component_depth: usize,
+}
+
+pub(super) fn compile(font: &Font<'_>) -> Result<Glyf, Box<dyn std::error::Error>> {
+    /* Same default maximum approximation error as ufo2ft: 0.001 em. */
+    let tolerance = font.units_per_em / 1000.0;
+    let outlines = font
+        .glyphs
+        .iter()
+        .map(|g| outline(font, g, tolerance))
+        .collect::<Vec<Outline>>();
+
+    let mut glyf = vec![];
+    let mut offsets = vec![0];
+    let mut bounds = Vec::with_capacity(outlines.len());
+    let mut stats = MaxpStats::default();
+    for (gid, outline) in outlines.iter().enumerate() {
+        let b = match outline {
+            Outline::Simple(contours) => {
+                let b = Bounds::from_points(
+                    contours
+                        .iter()
+                        .flatten()
+                        .map(|&(x, y, _)| Point::from((x.into(), y.into()))),
+                );
+                let points: usize = contours.iter().map(Vec::len).sum();
+                let number_of_contours = i16::try_from(contours.len())
+                    .ok()
+                    .filter(|_| points <= usize::from(u16::MAX))
+                    .ok_or_else(|| {
+                        format!("Glyph {} has too many points.", font.glyphs[gid].name)
+                    })?;
+                stats.max_points = stats.max_points.max(points);
+                stats.max_contours = stats.max_contours.max(contours.len());
+                if let Some(b) = b {
+                    write_simple(&mut glyf, number_of_contours, contours, b);
+                }
+                b
+            }
+            Outline::Composite(components) => {
+                let mut points = vec![];
+                let (contours, depth) =
+                    resolve_composite(&outlines, gid, Matrix::identity(), 0, &mut points);
+                let b = Bounds::from_points(points.iter().copied());
+                stats.max_composite_points = stats.max_composite_points.max(points.len());
+                stats.max_composite_contours = stats.max_composite_contours.max(contours);
+                stats.max_component_elements = stats.max_component_elements.max(components.len());
+                stats.max_component_depth = stats.max_component_depth.max(depth);
+                write_composite(&mut glyf, components, b.unwrap_or_default());
+                b
+            }
+        };
+        bounds.push(b);
+        glyf.resize((glyf.len() + 3) & !3, 0);
+        offsets.push(glyf.len());
+    }
+
+    let long_loca = glyf.len() > 2 * u16::MAX as usize;
+    let mut loca = vec![];
+    for offset in offsets {
+        if long_loca {
+            loca.put_u32(offset as u32);
+        } else {
+            loca.put_u16((offset / 2) as u16);
+        }
+    }
+
+    let mut maxp = Vec::with_capacity(32);
+    maxp.put_u32(0x0001_0000);
+    maxp.put_u16(font.glyphs.len() as u16);
+    for v in [
+        stats.max_points,
+        stats.max_contours,
+        stats.max_composite_points,
+        stats.max_composite_contours,
+    ] {
+        maxp.put_u16(v.min(u16::MAX as usize) as u16);
+    }
+    /* maxZones: no instructions use the twilight zone. */
+    maxp.put_u16(2);
+    /* maxTwilightPoints, maxStorage, maxFunctionDefs, maxInstructionDefs, maxStackElements,
+     * maxSizeOfInstructions: glyphs are not hinted. */
+    for _ in 0..6 {
+        maxp.put_u16(0);
+    }
+    maxp.put_u16(stats.max_component_elements as u16);
+    maxp.put_u16(stats.max_component_depth as u16);
+
+    Ok(Glyf {
+        glyf,
+        loca,
+        long_loca,
+        bounds,
+        maxp,
+    })
+}
+
+fn outline(font: &Font<'_>, glyph: &FontGlyph, tolerance: f64) -> Outline {
+    fn f2dot14(v: f64) -> bool {
+        (-2.0..=(f64::from(i16::MAX) / 16384.0)).contains(&v)
+    }
+
+    if glyph.contours.is_empty() && !glyph.components.is_empty() {
+        let components = glyph
+            .components
+            .iter()
+            .map(|(base, m)| {
+                let gid = *font.glyph_ids.get(base)?;
+                let fits = [m.xx, m.yx, m.xy, m.yy].into_iter().all(f2dot14)
+                    && [m.x0, m.y0]
+                        .into_iter()
+                        .all(|v| (f64::from(i16::MIN)..=f64::from(i16::MAX)).contains(&v.round()));
+                let mut m = *m;
+                /* Offsets are rounded to the grid when rendered. */
+                m.x0 = m.x0.round();
+                m.y0 = m.y0.round();
+                Some((gid, m)).filter(|_| fits)
+            })
+            .collect::<Option<Vec<_>>>();
+        if let Some(components) = components {
+            return Outline::Composite(components);
+        }
+    }
+    Outline::Simple(
+        glyph
+            .decomposed
+            .iter()
+            .map(|path| quadratic_contour(path, tolerance))
+            .filter(|c| !c.is_empty())
+            .collect(),
+    )
+}
+
+/// Collect the points of a composite glyph's decomposed outline in `points`, and return its
+/// number of contours and nesting depth.
+fn resolve_composite(
+    outlines: &[Outline],
+    gid: usize,
+    m: Matrix,
+    depth: usize,
+    points: &mut Vec<Point>,
+) -> (usize, usize) {
+    /* Cycles are rejected before compilation; this is only a safeguard. */
+    const MAX_DEPTH: usize = 32;
+
+    match &outlines[gid] {
+        Outline::Simple(contours) => {
+            points.extend(
+                contours
+                    .iter()
+                    .flatten()
+                    .map(|&(x, y, _)| m * Point::from((x.into(), y.into()))),
+            );
+            (contours.len(), 0)
+        }
+        Outline::Composite(_) if depth > MAX_DEPTH => (0, 0),
+        Outline::Composite(components) => {
+            components
+                .iter()
+                .fold((0, 0), |(contours, max_depth), (base, cm)| {
+                    let (c, d) = resolve_composite(
+                        outlines,
+                        *base as usize,
+                        Matrix::multiply(cm, &m),
+                        depth + 1,
+                        points,
+                    );
+                    (contours + c, max_depth.max(d + 1))
+                })
+        }
+    }
+}
+
+fn write_simple(glyf: &mut Vec<u8>, number_of_contours: i16, contours: &[Vec<TtPoint>], b: Bounds) {
+    glyf.put_i16(number_of_contours);
+    glyf.put_i16(b.x_min);
+    glyf.put_i16(b.y_min);
+    glyf.put_i16(b.x_max);
+    glyf.put_i16(b.y_max);
+    let mut end = 0;
+    for contour in contours {
+        end += contour.len();
+        glyf.put_u16((end - 1) as u16);
+    }
+    /* instructionLength */
+    glyf.put_u16(0);
+
+    let mut flags = vec![];
+    let mut xs = vec![];
+    let mut ys = vec![];
+    let (mut prev_x, mut prev_y) = (0i16, 0i16);
+    for &(x, y, on_curve) in contours.iter().flatten() {
+        let mut flag = if on_curve { ON_CURVE_POINT } else { 0 };
+        for (delta, coords, short, same_or_positive) in [
+            (
+                x.wrapping_sub(prev_x),
+                &mut xs,
+                X_SHORT_VECTOR,
+                X_IS_SAME_OR_POSITIVE_X_SHORT_VECTOR,
+            ),
+            (
+                y.wrapping_sub(prev_y),
+                &mut ys,
+                Y_SHORT_VECTOR,
+                Y_IS_SAME_OR_POSITIVE_Y_SHORT_VECTOR,
+            ),
+        ] {
+            if delta == 0 {
+                flag |= same_or_positive;
+            } else if delta.unsigned_abs() <= u16::from(u8::MAX) {
+                flag |= short;
+                if delta > 0 {
+                    flag |= same_or_positive;
+                }
+                coords.put_u8(delta.unsigned_abs() as u8);
+            } else {
+                coords.put_i16(delta);
+            }
+        }
+        flags.push(flag);
+        (prev_x, prev_y) = (x, y);
+    }
+
+    let mut i = 0;
+    while i < flags.len() {
+        let flag = flags[i];
+        let repeats = flags[i + 1..]
+            .iter()
+            .take(u8::MAX as usize)
+            .take_while(|f| **f == flag)
+            .count();
+        if repeats > 0 {
+            glyf.put_u8(flag | REPEAT_FLAG);
+            glyf.put_u8(repeats as u8);
+        } else {
+            glyf.put_u8(flag);
+        }
+        i += repeats + 1;
+    }
+    glyf.extend(xs);
+    glyf.extend(ys);
+}
+
+fn write_composite(glyf: &mut Vec<u8>, components: &[(u16, Matrix)], b: Bounds) {
+    let f2dot14 = |v: f64| (v * 16384.0).round() as i16;
+
+    glyf.put_i16(-1);
+    glyf.put_i16(b.x_min);
+    glyf.put_i16(b.y_min);
+    glyf.put_i16(b.x_max);
+    glyf.put_i16(b.y_max);
+    for (i, (gid, m)) in components.iter().enumerate() {
+        let mut flags = ARG_1_AND_2_ARE_WORDS | ARGS_ARE_XY_VALUES | ROUND_XY_TO_GRID;
+        if i + 1 < components.len() {
+            flags |= MORE_COMPONENTS;
+        }
+        /* Matrix fields are named after cairo's convention: `yx` is the glif `xyScale`. */
+        let transform = if m.yx != 0.0 || m.xy != 0.0 {
+            flags |= WE_HAVE_A_TWO_BY_TWO;
+            vec![m.xx, m.yx, m.xy, m.yy]
+        } else if m.xx != m.yy {
+            flags |= WE_HAVE_AN_X_AND_Y_SCALE;
+            vec![m.xx, m.yy]
+        } else if m.xx != 1.0 {
+            flags |= WE_HAVE_A_SCALE;
+            vec![m.xx]
+        } else {
+            vec![]
+        };
+        glyf.put_u16(flags);
+        glyf.put_u16(*gid);
+        glyf.put_i16(m.x0 as i16);
+        glyf.put_i16(m.y0 as i16);
+        for v in transform {
+            glyf.put_i16(f2dot14(v));
+        }
+    }
+}
+
+/// Convert a path to a TrueType contour.
+///
+/// The closing point is omitted, the direction is reversed, and on-curve points that lie
+/// exactly between two off-curve points are left implied.
+fn quadratic_contour(path: &Path, tolerance: f64) -> Vec<TtPoint> {
+    let mut points = vec![(path.start, true)];
+    let mut current = path.start;
+    for segment in path.segments.iter() {
+        match *segment {
+            Segment::Line(p) => points.push((p, true)),
+            Segment::Quad(c, p) => points.extend([(c, false), (p, true)]),
+            Segment::Cubic(c1, c2, p) => {
+                for (c, p) in cubic_to_quadratics([current, c1, c2, p], tolerance) {
+                    points.extend([(c, false), (p, true)]);
+                }
+            }
+        }
+        current = segment.end();
+    }
+    let mut points = points
+        .into_iter()
+        .map(|(p, on_curve)| (fword(p.x), fword(p.y), on_curve))
+        .collect::<Vec<TtPoint>>();
+    if points.len() > 1 && points.first() == points.last() {
+        points.pop();
+    }
+    points[1..].reverse();
+
+    let n = points.len();
+    if n < 3 {
+        return points;
+    }
+    (0..n)
+        .filter(|&i| {
+            let (x, y, on_curve) = points[i];
+            let (px, py, prev_on_curve) = points[(i + n - 1) % n];
+            let (nx, ny, next_on_curve) = points[(i + 1) % n];
+            let implied = on_curve
+                && !prev_on_curve
+                && !next_on_curve
+                && 2 * i32::from(x) == i32::from(px) + i32::from(nx)
+                && 2 * i32::from(y) == i32::from(py) + i32::from(ny);
+            !implied
+        })
+        .map(|i| points[i])
+        .collect()
+}
+
+/// Split a cubic curve in two at `t` with de Casteljau's algorithm.
+fn split_cubic(p: [Point; 4], t: f64) -> ([Point; 4], [Point; 4]) {
+    let lerp = |a: Point, b: Point| a + t * (b - a);
+    let (ab, bc, cd) = (lerp(p[0], p[1]), lerp(p[1], p[2]), lerp(p[2], p[3]));
+    let (abc, bcd) = (lerp(ab, bc), lerp(bc, cd));
+    let mid = lerp(abc, bcd);
+    ([p[0], ab, abc, mid], [mid, bcd, cd, p[3]])
+}
+
+fn quadratic_point(p: [Point; 3], t: f64) -> Point {
+    let mt = 1.0 - t;
+    (mt * mt) * p[0] + (2.0 * mt * t) * p[1] + (t * t) * p[2]
+}
+
+/// Approximate a cubic curve with as few quadratic curves as possible, each within
+/// `tolerance` units of the corresponding part of the cubic curve.
+///
+/// The cubic curve is split into parts of equal parameter length; returns the control and end
+/// point of each quadratic curve.
+pub(super) fn cubic_to_quadratics(p: [Point; 4], tolerance: f64) -> Vec<(Point, Point)> {
+    const SAMPLES: usize = 16;
+
+    let mut ret = Vec::with_capacity(MAX_QUADRATIC_SEGMENTS);
+    for n in 1..=MAX_QUADRATIC_SEGMENTS {
+        ret.clear();
+        let mut rest = p;
+        let mut within_tolerance = true;
+        for i in 0..n {
+            /* Split off the next 1/n of the original curve from what remains. */
+            let part = if i + 1 == n {
+                rest
+            } else {
+                let (part, r) = split_cubic(rest, 1.0 / (n - i) as f64);
+                rest = r;
+                part
+            };
+            let control = 0.75 * (part[1] + part[2]) - 0.25 * (part[0] + part[3]);
+            within_tolerance &= (1..SAMPLES).all(|k| {
+                let t = k as f64 / SAMPLES as f64;
+                cubic_point(part, t).distance(quadratic_point([part[0], control, part[3]], t))
+                    <= tolerance
+            });
+            ret.push((control, part[3]));
+        }
+        if within_tolerance {
+            break;
+        }
+    }
+    ret
+}
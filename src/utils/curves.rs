@@ -89,9 +89,15 @@ impl Bezier {
 #[derive(Default)]
 pub struct BezierInner {
     pub smooth: Cell<bool>,
+    /// The first point is an implied on-curve point of a TrueType quadratic curve, i.e. it was
+    /// not present in the source. If it is still the midpoint of its neighbouring off-curve points
+    /// when saving, it is omitted again.
+    pub implied_start: Cell<bool>,
     points: Rc<RefCell<Vec<CurvePoint>>>,
     pub lut: Rc<RefCell<Vec<Point>>>,
     pub emptiest_t: Cell<Option<(f64, Point, bool)>>,
+    /// See [`Bezier::revision`].
+    revision: Cell<u64>,
     pub continuity_in: Cell<Option<Continuity>>,
     pub continuity_out: Cell<Option<Continuity>>,
 }
@@ -108,6 +114,7 @@ impl std::fmt::Debug for BezierInner {
                 }
             })
             .field("smooth", &self.smooth.get())
+            .field("implied_start", &self.implied_start.get())
             .field("points", &self.points)
             .field("lut entries", &self.lut.borrow().len())
             .field("incoming continuity", &self.continuity_in)
@@ -426,6 +433,69 @@ impl Bezier {
         todo!()
     }
 
+    /// Parameters in `(0, 1)` where the curve has a horizontal or vertical tangent, in ascending
+    /// order. They are the roots of the derivative of each coordinate, which is at most
+    /// quadratic for the quadratic and cubic curves of fonts.
+    pub fn extrema(&self) -> Vec<f64> {
+        /* Parameters this close to the ends or to each other are the same extremum. */
+        const EPSILON: f64 = 1e-6;
+
+        let points = self
+            .points()
+            .iter()
+            .map(|cp| cp.position)
+            .collect::<Vec<Point>>();
+        let mut ret: Vec<f64> = vec![];
+        for coordinate in [|p: &Point| p.x, |p: &Point| p.y] {
+            /* Coefficients of `a t² + b t + c`, the derivative up to a constant factor. */
+            let (a, b, c) = match points.iter().map(coordinate).collect::<Vec<f64>>()[..] {
+                [p0, p1, p2] => (0.0, 2.0f64.mul_add(-p1, p0) + p2, p1 - p0),
+                [p0, p1, p2, p3] => (
+                    3.0f64.mul_add(p1 - p2, p3 - p0),
+                    2.0 * (2.0f64.mul_add(-p1, p0) + p2),
+                    p1 - p0,
+                ),
+                _ => return vec![],
+            };
+            for t in quadratic_roots(a, b, c) {
+                if t > EPSILON && t < 1.0 - EPSILON && !ret.iter().any(|r| (r - t).abs() < EPSILON)
+                {
+                    ret.push(t);
+                }
+            }
+        }
+        ret.sort_by(f64::total_cmp);
+        ret
+    }
+
+    /// Split the curve at `t` with de Casteljau's algorithm, without changing its shape. The
+    /// second curve is smooth at the split point.
+    pub fn split(&self, t: f64) -> (Self, Self) {
+        let mut points = self
+            .points()
+            .iter()
+            .map(|cp| cp.position)
+            .collect::<Vec<Point>>();
+        let (mut left, mut right) = (vec![], vec![]);
+        while let (Some(&first), Some(&last)) = (points.first(), points.last()) {
+            left.push(first);
+            right.push(last);
+            points = points
+                .windows(2)
+                .map(|w| w[0] + t * (w[1] - w[0]))
+                .collect();
+        }
+        right.reverse();
+        if let (Some(l), Some(r)) = (left.last_mut(), right.first_mut()) {
+            let on_curve = self.compute(t);
+            (*l, *r) = (on_curve, on_curve);
+        }
+        let (left, right) = (Self::new(left), Self::new(right));
+        left.set_property(Self::SMOOTH, self.property::<bool>(Self::SMOOTH));
+        right.set_property(Self::SMOOTH, true);
+        (left, right)
+    }
+
     pub fn approx_length(&self) -> f64 {
         let lut = self.get_lut(None);
         let mut ret = 0.0;
@@ -462,7 +532,7 @@ impl Bezier {
             Some(3) => {
                 let mut pts = self.imp().points.borrow_mut();
                 if pts[0].position == pts[1].position && pts[2].position == pts[3].position {
-                    self.imp().lut.borrow_mut().clear();
+                    self.set_modified();
                     // Make quadratic
                     let a = pts[0].clone();
                     let b = pts[3].clone();
@@ -474,7 +544,7 @@ impl Bezier {
             Some(2) => {
                 let mut pts = self.imp().points.borrow_mut();
                 if pts[0].position == pts[1].position || pts[1].position == pts[2].position {
-                    self.imp().lut.borrow_mut().clear();
+                    self.set_modified();
                     // Make quadratic
                     let a = pts[0].clone();
                     let b = pts[2].clone();
@@ -559,10 +629,271 @@ impl Bezier {
         (curr_t, curr_p)
     }
 
+    /// Intersections with the line segment from `start` to `end`, see
+    /// [`curve_line_intersections`].
+    pub fn line_intersections(&self, line: (Point, Point)) -> Vec<(f64, f64)> {
+        let points = self
+            .points()
+            .iter()
+            .map(|cp| cp.position)
+            .collect::<Vec<Point>>();
+        curve_line_intersections(&points, line)
+    }
+
+    /// Changes whenever the points of the curve change, so that results computed from them can
+    /// be cached. See also [`Contour::revision`](crate::glyphs::Contour::revision).
+    pub fn revision(&self) -> u64 {
+        self.imp().revision.get()
+    }
+
     pub fn set_modified(&self) {
         if let Some((distance, point, _)) = self.imp().emptiest_t.get() {
             self.imp().emptiest_t.set(Some((distance, point, true)));
         }
         self.imp().lut.borrow_mut().clear();
+        self.imp()
+            .revision
+            .set(self.imp().revision.get().wrapping_add(1));
+    }
+}
+
+/// Real roots of `a x² + b x + c`, or of `b x + c` if `a` is zero. A constant has no roots, even
+/// if it is zero.
+fn quadratic_roots(a: f64, b: f64, c: f64) -> Vec<f64> {
+    if a == 0.0 {
+        return if b == 0.0 { vec![] } else { vec![-c / b] };
+    }
+    let discriminant = b.mul_add(b, -4.0 * a * c);
+    if discriminant < 0.0 {
+        return vec![];
+    }
+    /* Avoid cancellation between `b` and the square root. */
+    let q = -0.5 * b.signum().mul_add(discriminant.sqrt(), b);
+    if q == 0.0 {
+        vec![0.0]
+    } else {
+        vec![q / a, c / q]
+    }
+}
+
+/// Evaluate the Bernstein polynomial with `coefficients` at `t` with de Casteljau's algorithm.
+fn bernstein(coefficients: &[f64], t: f64) -> f64 {
+    let mut values = coefficients.to_vec();
+    for n in (1..values.len()).rev() {
+        for i in 0..n {
+            values[i] = t.mul_add(values[i + 1] - values[i], values[i]);
+        }
+    }
+    values.first().copied().unwrap_or(0.0)
+}
+
+/// Roots in `(0, 1)` of the Bernstein polynomial with `coefficients`, if it is at most
+/// quadratic.
+fn bernstein_roots(coefficients: &[f64]) -> Option<Vec<f64>> {
+    const EPSILON: f64 = 1e-12;
+
+    /* Power basis coefficients, from the highest degree. */
+    let (a, b, c) = match *coefficients {
+        [] | [_] => return Some(vec![]),
+        [p0, p1] => (0.0, p1 - p0, p0),
+        [p0, p1, p2] => ((-2.0f64).mul_add(p1, p0) + p2, 2.0 * (p1 - p0), p0),
+        _ => return None,
+    };
+    let roots = if a.abs() <= EPSILON {
+        if b.abs() <= EPSILON {
+            vec![]
+        } else {
+            vec![-c / b]
+        }
+    } else {
+        let discriminant = b.mul_add(b, -4.0 * a * c);
+        if discriminant < 0.0 {
+            vec![]
+        } else {
+            let root = discriminant.sqrt();
+            vec![(-b - root) / (2.0 * a), (-b + root) / (2.0 * a)]
+        }
+    };
+    Some(roots.into_iter().filter(|t| *t > 0.0 && *t < 1.0).collect())
+}
+
+/// Intersections of the curve with `control` points and the line segment from `start` to
+/// `end`, as pairs of parameters of the curve and the segment in ascending order.
+///
+/// The signed distances of the curve from the line form a polynomial of the same degree. The
+/// roots of its derivative split the curve in monotonic intervals, each of which contains at
+/// most one root that is found with bisection, so no crossing is missed however close it is to
+/// another. Points where the curve only touches the line are found if they lie exactly on it.
+/// A curve that lies on the line has no intersections.
+pub fn curve_line_intersections(
+    control: &[Point],
+    (start, end): (Point, Point),
+) -> Vec<(f64, f64)> {
+    /* Monotonic intervals for curves of higher degree. */
+    const STEPS: u32 = 64;
+    const EPSILON: f64 = 1e-9;
+
+    let direction = end - start;
+    let length = direction.norm();
+    if length == 0.0 || control.len() < 2 {
+        return vec![];
+    }
+    let mut distances = control
+        .iter()
+        .map(|&p| {
+            let v = p - start;
+            direction.x.mul_add(v.y, -(direction.y * v.x)) / length
+        })
+        .collect::<Vec<f64>>();
+    let scale = distances.iter().map(|d| d.abs()).fold(0.0, f64::max);
+    if scale == 0.0 {
+        return vec![];
+    }
+    for d in distances.iter_mut() {
+        *d /= scale;
+    }
+    let value = |t: f64| {
+        let v = bernstein(&distances, t);
+        if v.abs() <= EPSILON {
+            0.0
+        } else {
+            v
+        }
+    };
+    let derivative = distances
+        .windows(2)
+        .map(|w| w[1] - w[0])
+        .collect::<Vec<f64>>();
+    let mut breaks = vec![0.0];
+    breaks.extend(bernstein_roots(&derivative).unwrap_or_else(|| {
+        (1..STEPS)
+            .map(|i| f64::from(i) / f64::from(STEPS))
+            .collect()
+    }));
+    breaks.push(1.0);
+    breaks.sort_by(f64::total_cmp);
+    let mut roots: Vec<f64> = vec![];
+    for w in breaks.windows(2) {
+        let (mut low, mut high) = (w[0], w[1]);
+        let (low_value, high_value) = (value(low), value(high));
+        let root = if low_value == 0.0 {
+            low
+        } else if high_value == 0.0 {
+            high
+        } else if low_value.signum() == high_value.signum() {
+            continue;
+        } else {
+            for _ in 0..60 {
+                let middle = (low + high) / 2.0;
+                if bernstein(&distances, middle).signum() == low_value.signum() {
+                    low = middle;
+                } else {
+                    high = middle;
+                }
+            }
+            (low + high) / 2.0
+        };
+        if roots.last().is_none_or(|r| root - r > EPSILON) {
+            roots.push(root);
+        }
+    }
+    let point_at = |t: f64| {
+        let mut points = control.to_vec();
+        while points.len() > 1 {
+            points = points
+                .windows(2)
+                .map(|w| w[0] + t * (w[1] - w[0]))
+                .collect();
+        }
+        points[0]
+    };
+    roots
+        .into_iter()
+        .filter_map(|t| {
+            let s = (point_at(t) - start).dot(direction) / (length * length);
+            (-EPSILON..=1.0 + EPSILON)
+                .contains(&s)
+                .then(|| (t, s.clamp(0.0, 1.0)))
+        })
+        .collect()
+}
+
+#[test]
+fn test_curve_line_intersections() {
+    fn point_at(control: &[Point], t: f64) -> Point {
+        let mut points = control.to_vec();
+        while points.len() > 1 {
+            points = points
+                .windows(2)
+                .map(|w| w[0] + t * (w[1] - w[0]))
+                .collect();
+        }
+        points[0]
     }
+
+    /* An S curve crosses the horizontal axis three times. */
+    let s_curve = [(0.0, -100.0), (0.0, 300.0), (100.0, -300.0), (100.0, 100.0)].map(Point::from);
+    let line = (Point::from((-10.0, 0.0)), Point::from((110.0, 0.0)));
+    let found = curve_line_intersections(&s_curve, line);
+    assert_eq!(found.len(), 3, "{found:?}");
+    for &(t, s) in &found {
+        let on_curve = point_at(&s_curve, t);
+        let on_line = line.0 + s * (line.1 - line.0);
+        assert!(
+            on_curve.distance(on_line) < 1e-6,
+            "{on_curve:?} {on_line:?}"
+        );
+    }
+    assert!(found.windows(2).all(|w| w[0].0 < w[1].0));
+
+    /* Crossings beyond the ends of the line segment are not intersections. */
+    let short = (Point::from((-10.0, 0.0)), Point::from((60.0, 0.0)));
+    assert_eq!(curve_line_intersections(&s_curve, short).len(), 2);
+
+    /* Two crossings very close to each other. */
+    let arch = [(0.0, 0.0), (1.0, 4.0), (2.0, 0.0)].map(Point::from);
+    let near_top = (Point::from((-1.0, 1.999)), Point::from((3.0, 1.999)));
+    assert_eq!(curve_line_intersections(&arch, near_top).len(), 2);
+    let above = (Point::from((-1.0, 2.001)), Point::from((3.0, 2.001)));
+    assert!(curve_line_intersections(&arch, above).is_empty());
+
+    /* Lines, endpoints on the line and curves lying on it. */
+    let segment = [(0.0, 0.0), (10.0, 10.0)].map(Point::from);
+    let diagonal = (Point::from((0.0, 10.0)), Point::from((10.0, 0.0)));
+    let found = curve_line_intersections(&segment, diagonal);
+    assert_eq!(found.len(), 1);
+    assert!((found[0].0 - 0.5).abs() < 1e-9 && (found[0].1 - 0.5).abs() < 1e-9);
+    let through_end = (Point::from((10.0, 0.0)), Point::from((10.0, 20.0)));
+    assert_eq!(
+        curve_line_intersections(&segment, through_end),
+        vec![(1.0, 0.5)]
+    );
+    assert!(curve_line_intersections(&segment, (segment[0], segment[1])).is_empty());
+}
+
+#[test]
+fn test_quadratic_roots() {
+    /* The derivative of the y coordinate of an S curve has two roots, at 1/2 ∓ √3/6. */
+    let (p0, p1, p2, p3) = (0.0, 100.0, -100.0, 0.0);
+    let mut roots = quadratic_roots(
+        3.0f64.mul_add(p1 - p2, p3 - p0),
+        2.0 * (2.0f64.mul_add(-p1, p0) + p2),
+        p1 - p0,
+    );
+    roots.sort_by(f64::total_cmp);
+    let expected = [0.5 - 3.0f64.sqrt() / 6.0, 0.5 + 3.0f64.sqrt() / 6.0];
+    assert_eq!(roots.len(), 2, "{roots:?}");
+    assert!(roots
+        .iter()
+        .zip(expected)
+        .all(|(r, e)| (r - e).abs() < 1e-12));
+
+    /* Small leading coefficients don't lose the root near zero. */
+    let roots = quadratic_roots(1e-9, 1.0, -0.25);
+    assert!(roots.iter().any(|r| (r - 0.25).abs() < 1e-9), "{roots:?}");
+
+    assert_eq!(quadratic_roots(0.0, 2.0, -1.0), vec![0.5]);
+    assert!(quadratic_roots(0.0, 0.0, 0.0).is_empty());
+    assert!(quadratic_roots(1.0, 0.0, 1.0).is_empty());
+    assert_eq!(quadratic_roots(1.0, 0.0, 0.0), vec![0.0]);
 }
@@ -21,8 +21,14 @@
 
 pub mod canvas;
 pub mod collection;
+pub mod features;
+pub mod kerning;
+pub mod lint;
 pub mod overlay;
 
 pub use canvas::{Canvas, Transformation, UnitPoint, ViewPoint};
 pub use collection::*;
+pub use features::FeatureEditor;
+pub use kerning::KerningSpace;
+pub use lint::LintView;
 pub use overlay::Overlay;
@@ -26,7 +26,8 @@ use gtk::cairo::{Context, FontSlant, FontWeight};
 use once_cell::unsync::OnceCell;
 use std::collections::HashMap;
 
-use crate::glyphs::{Glyph, GlyphDrawingOptions, GlyphKind};
+use crate::designspace::Compatibility;
+use crate::glyphs::{Contour, Glyph, GlyphDrawingOptions, GlyphKind};
 use crate::prelude::*;
 use crate::unicode::blocks::*;
 
@@ -39,6 +40,8 @@ const GLYPH_BOX_HEIGHT_I32: i32 = 140;
 pub struct CollectionInner {
     app: OnceCell<Application>,
     project: OnceCell<Project>,
+    /// Set if the project is a master of a designspace.
+    designspace: RefCell<Option<Designspace>>,
     flow_box: gtk::FlowBox,
     tree: gtk::TreeView,
     tree_store: OnceCell<gtk::TreeStore>,
@@ -68,6 +71,8 @@ impl ObjectImpl for CollectionInner {
         self.flow_box.set_can_focus(true);
         self.flow_box.set_column_spacing(0);
         self.flow_box.set_row_spacing(0);
+        self.flow_box
+            .set_selection_mode(gtk::SelectionMode::Multiple);
 
         let overlay = gtk::Overlay::builder()
             .expand(true)
@@ -313,6 +318,26 @@ impl ObjectImpl for CollectionInner {
                         }
                     }
                 })
+            ).add_button_cb(
+                "Remove overlaps",
+                clone!(@weak obj => move |_| {
+                    obj.remove_overlaps();
+                })
+            ).add_button_cb(
+                "Add points at extrema",
+                clone!(@weak obj => move |_| {
+                    obj.add_extrema();
+                })
+            ).add_button_cb(
+                "Clean up points",
+                clone!(@weak obj => move |_| {
+                    obj.remove_collinear_points();
+                })
+            ).add_button_cb(
+                "Correct direction",
+                clone!(@weak obj => move |_| {
+                    obj.correct_directions();
+                })
             );
             context_menu.popup(0);
         }));
@@ -481,6 +506,7 @@ impl ObjectImpl for CollectionInner {
                     obj.bind_property(Collection::ZOOM_FACTOR, &glyph_box, GlyphBox::ZOOM_FACTOR)
                         .flags(glib::BindingFlags::SYNC_CREATE | glib::BindingFlags::DEFAULT)
                         .build();
+                    *glyph_box.imp().designspace.borrow_mut() = obj.imp().designspace.borrow().clone();
                     obj.imp().flow_box.add(&glyph_box);
                     obj.imp().widgets.borrow_mut().push(glyph_box);
                 }
@@ -621,6 +647,177 @@ impl Collection {
         ret
     }
 
+    /// Show whether the masters of each glyph in `designspace` are compatible.
+    pub fn set_designspace(&self, designspace: &Designspace) {
+        *self.imp().designspace.borrow_mut() = Some(designspace.clone());
+        for glyph_box in self.imp().widgets.borrow().iter() {
+            *glyph_box.imp().designspace.borrow_mut() = Some(designspace.clone());
+            glyph_box.imp().compatibility.take();
+            glyph_box.queue_draw();
+        }
+        /* Glyphs of other masters affect compatibility too, and their modifications reach the
+         * designspace through their projects. */
+        designspace.connect_notify_local(
+            Some(Designspace::MODIFIED),
+            clone!(@weak self as obj => move |_, _| {
+                for glyph_box in obj.imp().widgets.borrow().iter() {
+                    glyph_box.imp().compatibility.take();
+                }
+            }),
+        );
+    }
+
+    /// Names of the selected glyphs. Glyphs are selected with Ctrl+click.
+    pub fn selected_glyphs(&self) -> Vec<String> {
+        self.imp()
+            .flow_box
+            .selected_children()
+            .into_iter()
+            .filter_map(|c| c.child()?.downcast::<GlyphBox>().ok())
+            .map(|b| b.imp().glyph.get().unwrap().borrow().name().to_string())
+            .collect()
+    }
+
+    /// Remove overlapping contours of the selected glyphs, or of all glyphs if there is no
+    /// selection, as one undoable action and report which glyphs changed.
+    pub fn remove_overlaps(&self) {
+        let selected = self.selected_glyphs();
+        let report = self
+            .project()
+            .default_layer
+            .remove_overlaps(Some(selected.as_slice()).filter(|s| !s.is_empty()));
+        let changed = glyph_names(&report.changed);
+        self.app()
+            .window
+            .replace_glyph_contours("remove overlaps", report.changed);
+        let mut text = if changed.is_empty() {
+            "No glyph had overlapping contours.".to_string()
+        } else {
+            format!(
+                "Removed overlaps of {} glyph(s): {}",
+                changed.len(),
+                list_names(changed, ", ")
+            )
+        };
+        if !report.failed.is_empty() {
+            text.push_str(&format!(
+                "\n\nFailed in {} glyph(s):\n{}",
+                report.failed.len(),
+                list_names(
+                    report
+                        .failed
+                        .iter()
+                        .map(|(name, err)| format!("{name}: {err}"))
+                        .collect(),
+                    "\n"
+                )
+            ));
+        }
+        self.report_dialog(
+            "Remove overlaps",
+            &text,
+            if report.failed.is_empty() {
+                gtk::MessageType::Info
+            } else {
+                gtk::MessageType::Warning
+            },
+        );
+    }
+
+    /// Add on-curve points at the extrema of the selected glyphs, or of all glyphs if there is
+    /// no selection, as one undoable action.
+    pub fn add_extrema(&self) {
+        let selected = self.selected_glyphs();
+        let changes = self.project().default_layer.map_contours(
+            Some(selected.as_slice()).filter(|s| !s.is_empty()),
+            Contour::with_extrema,
+        );
+        let changed = glyph_names(&changes);
+        self.app()
+            .window
+            .replace_glyph_contours("add extrema", changes);
+        let text = if changed.is_empty() {
+            "All glyphs have points at their extrema.".to_string()
+        } else {
+            format!(
+                "Added points at extrema of {} glyph(s): {}",
+                changed.len(),
+                list_names(changed, ", ")
+            )
+        };
+        self.report_dialog("Add points at extrema", &text, gtk::MessageType::Info);
+    }
+
+    /// Remove collinear on-curve points of the selected glyphs, or of all glyphs if there is no
+    /// selection, as one undoable action.
+    pub fn remove_collinear_points(&self) {
+        let selected = self.selected_glyphs();
+        let changes = self.project().default_layer.map_contours(
+            Some(selected.as_slice()).filter(|s| !s.is_empty()),
+            Contour::without_collinear_points,
+        );
+        let changed = glyph_names(&changes);
+        self.app()
+            .window
+            .replace_glyph_contours("clean up points", changes);
+        let text = if changed.is_empty() {
+            "No glyph had collinear points.".to_string()
+        } else {
+            format!(
+                "Removed collinear points of {} glyph(s): {}",
+                changed.len(),
+                list_names(changed, ", ")
+            )
+        };
+        self.report_dialog("Clean up points", &text, gtk::MessageType::Info);
+    }
+
+    /// Reverse the contours of the selected glyphs, or of all glyphs if there is no selection,
+    /// that run against the contour direction convention of the settings, as one undoable action.
+    pub fn correct_directions(&self) {
+        let selected = self.selected_glyphs();
+        let convention = self
+            .app()
+            .settings
+            .borrow()
+            .property::<ContourDirection>(Settings::CONTOUR_DIRECTION);
+        let changes = self.project().default_layer.correct_directions(
+            Some(selected.as_slice()).filter(|s| !s.is_empty()),
+            convention,
+        );
+        let changed = glyph_names(&changes);
+        self.app()
+            .window
+            .replace_glyph_contours("correct direction", changes);
+        let text = if changed.is_empty() {
+            "All contours run in the right direction.".to_string()
+        } else {
+            format!(
+                "Corrected contour direction of {} glyph(s): {}",
+                changed.len(),
+                list_names(changed, ", ")
+            )
+        };
+        self.report_dialog("Correct direction", &text, gtk::MessageType::Info);
+    }
+
+    /// Show the result of a batch operation on glyphs.
+    fn report_dialog(&self, title: &str, text: &str, message_type: gtk::MessageType) {
+        let dialog = gtk::MessageDialog::builder()
+            .attached_to(&self.app().window)
+            .transient_for(&self.app().window)
+            .destroy_with_parent(true)
+            .modal(true)
+            .buttons(gtk::ButtonsType::Close)
+            .message_type(message_type)
+            .text(title)
+            .secondary_text(text)
+            .build();
+        dialog.run();
+        dialog.emit_close();
+        self.imp().flow_box.queue_draw();
+    }
+
     fn update_tree_store(&self) {
         let tree_store = self.imp().tree_store.get().unwrap();
         let mut show_blocks = self.imp().show_blocks.borrow_mut();
@@ -749,6 +946,9 @@ pub struct GlyphBoxInner {
     pub app: OnceCell<Application>,
     pub project: OnceCell<Project>,
     pub glyph: OnceCell<Rc<RefCell<Glyph>>>,
+    pub designspace: RefCell<Option<Designspace>>,
+    /// Compatibility of the glyph's masters in `designspace`, cleared when a glyph is modified.
+    compatibility: RefCell<Option<Compatibility>>,
     pub focused: Cell<bool>,
     modified: Cell<bool>,
     mark_color: Cell<Color>,
@@ -804,6 +1004,11 @@ impl ObjectImpl for GlyphBoxInner {
                                 .add_button("Export SVG");
                             context_menu.popup(event.time());
                         }
+                        gtk::gdk::BUTTON_PRIMARY
+                            if event.state().contains(gtk::gdk::ModifierType::CONTROL_MASK) =>
+                        {
+                            obj.toggle_selected();
+                        }
                         gtk::gdk::BUTTON_PRIMARY => {
                             obj.emit_open_glyph_edit();
                         }
@@ -819,7 +1024,7 @@ impl ObjectImpl for GlyphBoxInner {
         self.drawing_area.connect_query_tooltip(
             clone!(@weak obj => @default-return false, move |_self, _x: i32, _y: i32, _by_keyboard: bool, tooltip| {
                 let glyph = obj.imp().glyph.get().unwrap().borrow();
-                if let GlyphKind::Char(c) = glyph.kinds().0 {
+                let mut text = if let GlyphKind::Char(c) = glyph.kinds().0 {
                     let block_name = if let Some(idx) = c.char_block() {
                         UNICODE_BLOCKS[idx].1
                     } else {
@@ -827,10 +1032,14 @@ impl ObjectImpl for GlyphBoxInner {
                     };
                     let unicode = format!("U+{:04X}", c as u32);
 
-                    tooltip.set_text(Some(&format!("Name: {}\nUnicode: {}\nBlock: {}", glyph.name(), unicode, block_name)));
+                    format!("Name: {}\nUnicode: {}\nBlock: {}", glyph.name(), unicode, block_name)
                 } else {
-                    tooltip.set_text(Some(&format!("Name: {}\nComponent", glyph.name())));
+                    format!("Name: {}\nComponent", glyph.name())
+                };
+                if let Some(compatibility) = obj.compatibility() {
+                    text.push_str(&format!("\nMasters: {compatibility}"));
                 }
+                tooltip.set_text(Some(&text));
                 true
             }));
         self.drawing_area.connect_draw(clone!(@weak obj => @default-return Inhibit(false), move |viewport: &gtk::DrawingArea, mut ctx: &Context| {
@@ -855,6 +1064,8 @@ impl ObjectImpl for GlyphBoxInner {
             let glyph_width = glyph.width().unwrap_or(units_per_em) * (width * 0.8) / units_per_em;
             if is_focused {
                 cr.set_source_rgb(1.0, 250.0 / 255.0, 141.0 / 255.0);
+            } else if obj.is_selected() {
+                cr.set_source_rgb(200.0 / 255.0, 225.0 / 255.0, 1.0);
             } else {
                 cr.set_source_rgb(1.0, 1.0, 1.0);
             }
@@ -895,6 +1106,18 @@ impl ObjectImpl for GlyphBoxInner {
                     },
                 }
             }
+            if let Some(compatibility) = obj.compatibility() {
+                /* Draw master compatibility indicator. */
+                let color = match compatibility {
+                    Compatibility::Compatible => Color::new(76, 175, 80), // [ref:hardcoded_color_value]
+                    Compatibility::Missing(_) => Color::new(255, 152, 0), // [ref:hardcoded_color_value]
+                    Compatibility::Incompatible { .. } => Color::new(229, 57, 53), // [ref:hardcoded_color_value]
+                };
+                let cr1 = cr.push();
+                cr1.set_source_color(color);
+                cr1.arc(width / 10.0, width / 10.0, width / 25.0, 0.0, 2.0 * std::f64::consts::PI);
+                cr1.fill().unwrap();
+            }
             cr.set_source_rgba(0.0, 0.0, 0.0, 0.4);
             // View height.
             let vh = f64::from(viewport.allocated_height());
@@ -1062,6 +1285,7 @@ impl ObjectImpl for GlyphBoxInner {
             }
             GlyphMetadata::MODIFIED => {
                 self.modified.set(value.get().unwrap());
+                self.compatibility.take();
             }
             _ => unimplemented!("{}", pspec.name()),
         }
@@ -1080,6 +1304,42 @@ impl GlyphBox {
     pub const MODIFIED: &str = GlyphMetadata::MODIFIED;
     pub const MARK_COLOR: &str = GlyphMetadata::MARK_COLOR;
 
+    fn flow_box_child(&self) -> Option<gtk::FlowBoxChild> {
+        self.parent()?.downcast::<gtk::FlowBoxChild>().ok()
+    }
+
+    pub fn is_selected(&self) -> bool {
+        self.flow_box_child().is_some_and(|c| c.is_selected())
+    }
+
+    fn toggle_selected(&self) {
+        let Some(child) = self.flow_box_child() else {
+            return;
+        };
+        let Some(flow_box) = child
+            .parent()
+            .and_then(|p| p.downcast::<gtk::FlowBox>().ok())
+        else {
+            return;
+        };
+        if child.is_selected() {
+            flow_box.unselect_child(&child);
+        } else {
+            flow_box.select_child(&child);
+        }
+        self.imp().drawing_area.queue_draw();
+    }
+
+    /// Compatibility of the glyph's masters, if the project is a master of a designspace.
+    fn compatibility(&self) -> Option<Compatibility> {
+        let designspace = self.imp().designspace.borrow().clone()?;
+        let mut cache = self.imp().compatibility.borrow_mut();
+        let compatibility = cache.get_or_insert_with(|| {
+            designspace.compatibility(self.imp().glyph.get().unwrap().borrow().name().as_str())
+        });
+        Some(compatibility.clone())
+    }
+
     fn emit_open_glyph_edit(&self) {
         self.imp()
             .app
@@ -1114,3 +1374,26 @@ impl GlyphBox {
 }
 
 impl_modified!(GlyphBox);
+
+/// Names of the glyphs changed by a batch operation.
+fn glyph_names(changes: &crate::glyphs::ContourChanges) -> Vec<String> {
+    changes
+        .iter()
+        .map(|(glyph, _)| glyph.borrow().name().to_string())
+        .collect()
+}
+
+/// Join `items` with `separator`, without listing too many of them.
+fn list_names(items: Vec<String>, separator: &str) -> String {
+    const MAX_NAMES: usize = 30;
+    let count = items.len();
+    let mut ret = items
+        .into_iter()
+        .take(MAX_NAMES)
+        .collect::<Vec<String>>()
+        .join(separator);
+    if count > MAX_NAMES {
+        ret.push_str(&format!("{separator}and {} more", count - MAX_NAMES));
+    }
+    ret
+}
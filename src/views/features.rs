@@ -0,0 +1,568 @@
+/*
+ * gerb
+ *
+ * Copyright 2022 - Manos Pitsidianakis
+ *
+ * This file is part of gerb.
+ *
+ * gerb is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * gerb is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with gerb. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Feature file editor: `features.fea` with syntax highlighting, glyph and class name completion
+//! and inline diagnostics.
+
+use glib::clone;
+use once_cell::unsync::OnceCell;
+
+use crate::prelude::*;
+use crate::ufo::features::{self, Diagnostic, DiagnosticKind, TokenKind};
+
+/// Maximum number of entries in the completion popover.
+const MAX_COMPLETIONS: usize = 50;
+
+#[derive(Debug, Default)]
+pub struct FeatureEditorInner {
+    app: OnceCell<Application>,
+    project: OnceCell<Project>,
+    view: gtk::TextView,
+    buffer: OnceCell<gtk::TextBuffer>,
+    diagnostics: RefCell<Vec<Diagnostic>>,
+    diagnostics_list: gtk::ListBox,
+    status: gtk::Label,
+    completion: gtk::Popover,
+    completion_list: gtk::ListBox,
+    /// Text of the completion entries, in the order of `completion_list`.
+    completions: RefCell<Vec<String>>,
+    /// Set when the user typed a name character, so that the completion popover can be shown
+    /// once the buffer has been updated.
+    typing: Cell<bool>,
+    update_queued: Cell<bool>,
+    title: RefCell<String>,
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for FeatureEditorInner {
+    const NAME: &'static str = "FeatureEditor";
+    type Type = FeatureEditor;
+    type ParentType = gtk::EventBox;
+}
+
+impl ObjectImpl for FeatureEditorInner {
+    fn constructed(&self, obj: &Self::Type) {
+        self.parent_constructed(obj);
+        *self.title.borrow_mut() = "features".to_string();
+
+        let tag_table = gtk::TextTagTable::new();
+        for tag in [
+            gtk::TextTag::builder()
+                .name("keyword")
+                .foreground("#1565c0")
+                .weight(700)
+                .build(),
+            gtk::TextTag::builder()
+                .name("class")
+                .foreground("#8e24aa")
+                .build(),
+            gtk::TextTag::builder()
+                .name("number")
+                .foreground("#ef6c00")
+                .build(),
+            gtk::TextTag::builder()
+                .name("string")
+                .foreground("#2e7d32")
+                .build(),
+            gtk::TextTag::builder()
+                .name("comment")
+                .foreground("#757575")
+                .style(gtk::pango::Style::Italic)
+                .build(),
+            gtk::TextTag::builder()
+                .name("error")
+                .underline(gtk::pango::Underline::Error)
+                .underline_rgba(&gdk::RGBA::new(0.898, 0.224, 0.208, 1.0))
+                .build(),
+            gtk::TextTag::builder()
+                .name("unknown-glyph")
+                .underline(gtk::pango::Underline::Error)
+                .underline_rgba(&gdk::RGBA::new(1.0, 0.596, 0.0, 1.0))
+                .build(),
+        ] {
+            tag_table.add(&tag);
+        }
+        let buffer = gtk::TextBuffer::new(Some(&tag_table));
+        buffer.connect_changed(clone!(@weak obj => move |buffer| {
+            let (start, end) = buffer.bounds();
+            let text = buffer.text(&start, &end, true).as_deref().unwrap_or_default().to_string();
+            let project = obj.project();
+            project.features.borrow_mut().set(text);
+            if project.features.borrow().modified() {
+                project.set_property(Project::MODIFIED, true);
+            }
+            obj.queue_update();
+        }));
+
+        self.view.set_buffer(Some(&buffer));
+        self.view.set_monospace(true);
+        self.view.set_wrap_mode(gtk::WrapMode::None);
+        self.view.set_left_margin(5);
+        self.view.set_top_margin(5);
+        self.view.set_expand(true);
+        self.view.set_visible(true);
+        self.view.set_has_tooltip(true);
+        self.view.connect_query_tooltip(
+            clone!(@weak obj => @default-return false, move |view, x, y, _keyboard_mode, tooltip| {
+                let (x, y) = view.window_to_buffer_coords(gtk::TextWindowType::Widget, x, y);
+                let Some(iter) = view.iter_at_location(x, y) else {
+                    return false;
+                };
+                let messages = obj
+                    .diagnostics
+                    .borrow()
+                    .iter()
+                    .filter(|d| d.contains(&iter))
+                    .map(|d| d.message.clone())
+                    .collect::<Vec<String>>();
+                if messages.is_empty() {
+                    return false;
+                }
+                tooltip.set_text(Some(&messages.join("\n")));
+                true
+            }),
+        );
+        self.view.connect_key_press_event(
+            clone!(@weak obj => @default-return Inhibit(false), move |_view, event| {
+                use gtk::gdk::keys::constants as keys;
+
+                let keyval = event.keyval();
+                if event.state().contains(gdk::ModifierType::CONTROL_MASK) {
+                    if keyval == keys::space {
+                        obj.show_completion(true);
+                        return Inhibit(true);
+                    }
+                    return Inhibit(false);
+                }
+                if obj.completion.is_visible() {
+                    match keyval {
+                        keys::Escape => {
+                            obj.completion.popdown();
+                            return Inhibit(true);
+                        }
+                        keys::Return | keys::KP_Enter | keys::Tab => {
+                            if let Some(row) = obj.completion_list.selected_row() {
+                                obj.complete(row.index());
+                            }
+                            return Inhibit(true);
+                        }
+                        keys::Up | keys::Down => {
+                            obj.move_completion_selection(keyval == keys::Down);
+                            return Inhibit(true);
+                        }
+                        _ => {}
+                    }
+                }
+                obj.typing.set(
+                    keyval
+                        .to_unicode()
+                        .is_some_and(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '@')),
+                );
+                Inhibit(false)
+            }),
+        );
+
+        self.completion_list.set_visible(true);
+        self.completion_list
+            .set_selection_mode(gtk::SelectionMode::Browse);
+        self.completion_list
+            .connect_row_activated(clone!(@weak obj => move |_, row| {
+                obj.complete(row.index());
+            }));
+        let completion_scrolled_window = gtk::ScrolledWindow::builder()
+            .visible(true)
+            .max_content_height(200)
+            .propagate_natural_height(true)
+            .propagate_natural_width(true)
+            .build();
+        completion_scrolled_window.set_child(Some(&self.completion_list));
+        self.completion.set_child(Some(&completion_scrolled_window));
+        self.completion.set_relative_to(Some(&self.view));
+        self.completion.set_position(gtk::PositionType::Bottom);
+        self.completion.set_modal(false);
+
+        let text_scrolled_window = gtk::ScrolledWindow::builder()
+            .expand(true)
+            .visible(true)
+            .build();
+        text_scrolled_window.set_child(Some(&self.view));
+
+        self.diagnostics_list.set_visible(true);
+        self.diagnostics_list
+            .set_selection_mode(gtk::SelectionMode::Single);
+        self.diagnostics_list
+            .connect_row_activated(clone!(@weak obj => move |_, row| {
+                let diagnostic = usize::try_from(row.index())
+                    .ok()
+                    .and_then(|i| obj.diagnostics.borrow().get(i).cloned());
+                if let Some(d) = diagnostic {
+                    obj.go_to(&d);
+                }
+            }));
+        let diagnostics_scrolled_window = gtk::ScrolledWindow::builder()
+            .expand(true)
+            .visible(true)
+            .build();
+        diagnostics_scrolled_window.set_child(Some(&self.diagnostics_list));
+        self.status.set_visible(true);
+        self.status.set_halign(gtk::Align::Start);
+        let diagnostics_box = gtk::Box::builder()
+            .orientation(gtk::Orientation::Vertical)
+            .spacing(5)
+            .margin(5)
+            .visible(true)
+            .build();
+        diagnostics_box.pack_start(&self.status, false, false, 0);
+        diagnostics_box.pack_start(&diagnostics_scrolled_window, true, true, 0);
+
+        let paned = gtk::Paned::builder()
+            .orientation(gtk::Orientation::Vertical)
+            .wide_handle(true)
+            .position(450)
+            .expand(true)
+            .visible(true)
+            .build();
+        paned.pack1(&text_scrolled_window, true, false);
+        paned.pack2(&diagnostics_box, false, false);
+        obj.set_child(Some(&paned));
+        obj.set_visible(true);
+        obj.set_expand(true);
+        self.buffer.set(buffer).unwrap();
+    }
+
+    fn properties() -> &'static [ParamSpec] {
+        static PROPERTIES: once_cell::sync::Lazy<Vec<ParamSpec>> =
+            once_cell::sync::Lazy::new(|| {
+                vec![
+                    ParamSpecString::new(
+                        FeatureEditor::TITLE,
+                        FeatureEditor::TITLE,
+                        FeatureEditor::TITLE,
+                        Some("features"),
+                        ParamFlags::READWRITE,
+                    ),
+                    ParamSpecBoolean::new(
+                        FeatureEditor::CLOSEABLE,
+                        FeatureEditor::CLOSEABLE,
+                        FeatureEditor::CLOSEABLE,
+                        false,
+                        ParamFlags::READABLE,
+                    ),
+                ]
+            });
+        PROPERTIES.as_ref()
+    }
+
+    fn property(&self, _obj: &Self::Type, _id: usize, pspec: &ParamSpec) -> Value {
+        match pspec.name() {
+            FeatureEditor::TITLE => self.title.borrow().to_value(),
+            FeatureEditor::CLOSEABLE => false.to_value(),
+            _ => unimplemented!("{}", pspec.name()),
+        }
+    }
+
+    fn set_property(&self, _obj: &Self::Type, _id: usize, value: &Value, pspec: &ParamSpec) {
+        match pspec.name() {
+            FeatureEditor::TITLE => {
+                *self.title.borrow_mut() = value.get().unwrap();
+            }
+            _ => unimplemented!("{}", pspec.name()),
+        }
+    }
+}
+
+impl WidgetImpl for FeatureEditorInner {}
+impl ContainerImpl for FeatureEditorInner {}
+impl BinImpl for FeatureEditorInner {}
+impl EventBoxImpl for FeatureEditorInner {}
+
+impl FeatureEditorInner {
+    pub fn app(&self) -> &Application {
+        self.app.get().unwrap()
+    }
+
+    pub fn project(&self) -> &Project {
+        self.project.get().unwrap()
+    }
+
+    fn buffer(&self) -> &gtk::TextBuffer {
+        self.buffer.get().unwrap()
+    }
+}
+
+impl std::ops::Deref for FeatureEditor {
+    type Target = FeatureEditorInner;
+
+    fn deref(&self) -> &Self::Target {
+        self.imp()
+    }
+}
+
+glib::wrapper! {
+    pub struct FeatureEditor(ObjectSubclass<FeatureEditorInner>)
+        @extends gtk::Widget, gtk::Container, gtk::EventBox;
+}
+
+/// Buffer position of byte `column` in `line`.
+fn iter_at(buffer: &gtk::TextBuffer, line: usize, column: usize) -> gtk::TextIter {
+    buffer.iter_at_line_index(
+        i32::try_from(line).unwrap_or(i32::MAX),
+        i32::try_from(column).unwrap_or(i32::MAX),
+    )
+}
+
+/// Buffer range of `text` starting at byte `column` in `line`.
+fn range_at(
+    buffer: &gtk::TextBuffer,
+    line: usize,
+    column: usize,
+    text: &str,
+) -> (gtk::TextIter, gtk::TextIter) {
+    let start = iter_at(buffer, line, column);
+    let mut end = start;
+    end.forward_chars(i32::try_from(text.chars().count()).unwrap_or(i32::MAX));
+    (start, end)
+}
+
+impl Diagnostic {
+    /// Whether buffer position `iter` is inside the diagnostic's span.
+    fn contains(&self, iter: &gtk::TextIter) -> bool {
+        let (Ok(line), Ok(column)) = (
+            usize::try_from(iter.line()),
+            usize::try_from(iter.line_index()),
+        ) else {
+            return false;
+        };
+        line == self.line && (self.column..self.column + (self.end - self.start)).contains(&column)
+    }
+}
+
+impl FeatureEditor {
+    pub const TITLE: &str = Workspace::TITLE;
+    pub const CLOSEABLE: &str = Workspace::CLOSEABLE;
+
+    pub fn new(app: Application, project: Project) -> Self {
+        let ret: Self = glib::Object::new(&[]).expect("Failed to create FeatureEditor");
+        ret.app.set(app).unwrap();
+        let source = project.features.borrow().source.clone();
+        ret.project.set(project).unwrap();
+        ret.buffer().set_text(&source);
+        ret.buffer().place_cursor(&ret.buffer().start_iter());
+        ret
+    }
+
+    /// Highlight and check the buffer when idle, so that it happens once for a burst of changes.
+    fn queue_update(&self) {
+        if self.update_queued.replace(true) {
+            return;
+        }
+        glib::idle_add_local_once(clone!(@weak self as obj => move || {
+            obj.update_queued.set(false);
+            obj.update();
+            if obj.typing.replace(false) || obj.completion.is_visible() {
+                obj.show_completion(false);
+            }
+        }));
+    }
+
+    /// Re-apply syntax highlighting and diagnostics.
+    fn update(&self) {
+        let buffer = self.buffer();
+        let (start, end) = buffer.bounds();
+        buffer.remove_all_tags(&start, &end);
+        let source = buffer
+            .text(&start, &end, true)
+            .as_deref()
+            .unwrap_or_default()
+            .to_string();
+        for token in features::tokenize(&source) {
+            let tag = match token.kind {
+                TokenKind::Keyword => "keyword",
+                TokenKind::Class => "class",
+                TokenKind::Number | TokenKind::Cid => "number",
+                TokenKind::String => "string",
+                TokenKind::Comment => "comment",
+                TokenKind::Name | TokenKind::Punct(_) | TokenKind::Invalid => continue,
+            };
+            let (start, end) = range_at(buffer, token.line, token.column, token.text);
+            buffer.apply_tag_by_name(tag, &start, &end);
+        }
+        let diagnostics = {
+            let glyphs = self.project().default_layer.glyphs();
+            features::check(&source, |name| glyphs.contains_key(name))
+        };
+        for d in diagnostics.iter() {
+            let (start, end) = range_at(buffer, d.line, d.column, &source[d.start..d.end]);
+            buffer.apply_tag_by_name(
+                match d.kind {
+                    DiagnosticKind::Syntax => "error",
+                    DiagnosticKind::UnknownGlyph => "unknown-glyph",
+                },
+                &start,
+                &end,
+            );
+        }
+        for row in self.diagnostics_list.children() {
+            self.diagnostics_list.remove(&row);
+        }
+        for d in diagnostics.iter() {
+            let label = gtk::Label::builder()
+                .label(&d.to_string())
+                .halign(gtk::Align::Start)
+                .visible(true)
+                .build();
+            label.style_monospace();
+            self.diagnostics_list.add(&label);
+        }
+        self.status.set_text(&match diagnostics.len() {
+            0 => "No problems found.".to_string(),
+            1 => "1 problem:".to_string(),
+            n => format!("{n} problems:"),
+        });
+        *self.diagnostics.borrow_mut() = diagnostics;
+    }
+
+    /// Move the cursor to `diagnostic` and scroll to it.
+    fn go_to(&self, diagnostic: &Diagnostic) {
+        let buffer = self.buffer();
+        let mut iter = iter_at(buffer, diagnostic.line, diagnostic.column);
+        buffer.place_cursor(&iter);
+        self.view.scroll_to_iter(&mut iter, 0.1, false, 0.0, 0.0);
+        self.view.grab_focus();
+    }
+
+    /// The start of the glyph or class name being typed at the cursor, and its text up to the
+    /// cursor.
+    fn word_at_cursor(&self) -> (gtk::TextIter, String) {
+        let buffer = self.buffer();
+        let cursor = buffer.iter_at_offset(buffer.cursor_position());
+        let mut start = cursor;
+        while start.backward_char() {
+            let Some(c) = start.char() else {
+                break;
+            };
+            if !(c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-' | '@' | '\\')) {
+                start.forward_char();
+                break;
+            }
+        }
+        let word = buffer
+            .text(&start, &cursor, false)
+            .as_deref()
+            .unwrap_or_default()
+            .to_string();
+        (start, word)
+    }
+
+    /// Show glyph or class names that start with the word at the cursor. Unless `force` is
+    /// set, the popover is only shown after a couple of characters have been typed.
+    fn show_completion(&self, force: bool) {
+        let (start, word) = self.word_at_cursor();
+        let prefix = word.trim_start_matches('\\');
+        if !force && prefix.len() < 2 {
+            self.completion.popdown();
+            return;
+        }
+        let candidates: Vec<String> = if prefix.starts_with('@') {
+            let buffer = self.buffer();
+            let (start, end) = buffer.bounds();
+            let source = buffer
+                .text(&start, &end, true)
+                .as_deref()
+                .unwrap_or_default()
+                .to_string();
+            features::class_names(&features::tokenize(&source))
+                .into_iter()
+                .filter(|name| name.starts_with(prefix) && *name != prefix)
+                .map(str::to_string)
+                .collect()
+        } else {
+            self.project()
+                .default_layer
+                .glyphs()
+                .keys()
+                .filter(|name| name.starts_with(prefix) && name.as_str() != prefix)
+                .cloned()
+                .collect()
+        };
+        for row in self.completion_list.children() {
+            self.completion_list.remove(&row);
+        }
+        if candidates.is_empty() {
+            self.completion.popdown();
+            return;
+        }
+        for name in candidates.iter().take(MAX_COMPLETIONS) {
+            let label = gtk::Label::builder()
+                .label(name)
+                .halign(gtk::Align::Start)
+                .visible(true)
+                .build();
+            self.completion_list.add(&label);
+        }
+        self.completion_list
+            .select_row(self.completion_list.row_at_index(0).as_ref());
+        *self.completions.borrow_mut() = candidates.into_iter().take(MAX_COMPLETIONS).collect();
+        let rect = self.view.iter_location(&start);
+        let (x, y) =
+            self.view
+                .buffer_to_window_coords(gtk::TextWindowType::Widget, rect.x(), rect.y());
+        self.completion.set_pointing_to(&gdk::Rectangle::new(
+            x,
+            y,
+            rect.width().max(1),
+            rect.height(),
+        ));
+        self.completion.popup();
+    }
+
+    fn move_completion_selection(&self, down: bool) {
+        let index = self
+            .completion_list
+            .selected_row()
+            .map_or(0, |row| row.index() + if down { 1 } else { -1 });
+        if let Some(row) = self.completion_list.row_at_index(index) {
+            self.completion_list.select_row(Some(&row));
+            row.grab_focus();
+            self.view.grab_focus();
+        }
+    }
+
+    /// Replace the word at the cursor with completion entry `index`.
+    fn complete(&self, index: i32) {
+        self.completion.popdown();
+        let Some(name) = usize::try_from(index)
+            .ok()
+            .and_then(|i| self.completions.borrow().get(i).cloned())
+        else {
+            return;
+        };
+        let buffer = self.buffer();
+        let (mut start, word) = self.word_at_cursor();
+        let mut cursor = buffer.iter_at_offset(buffer.cursor_position());
+        if word.starts_with('\\') {
+            start.forward_char();
+        }
+        buffer.delete(&mut start, &mut cursor);
+        buffer.insert(&mut start, &name);
+        self.view.grab_focus();
+    }
+}
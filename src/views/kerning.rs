@@ -0,0 +1,807 @@
+/*
+ * gerb
+ *
+ * Copyright 2022 - Manos Pitsidianakis
+ *
+ * This file is part of gerb.
+ *
+ * gerb is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * gerb is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with gerb. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Kerning editor: a list of all kerning pairs and a live preview of the selected pair.
+
+use glib::clone;
+use gtk::cairo::{Context, FontSlant, FontWeight, Matrix};
+use once_cell::unsync::OnceCell;
+use std::collections::HashMap;
+
+use crate::glyphs::{Glyph, GlyphDrawingOptions, GlyphKind};
+use crate::prelude::*;
+
+#[derive(Debug, Default)]
+pub struct KerningSpaceInner {
+    app: OnceCell<Application>,
+    project: OnceCell<Project>,
+    tree: gtk::TreeView,
+    list_store: OnceCell<gtk::ListStore>,
+    filter_model: OnceCell<gtk::TreeModelFilter>,
+    preview: gtk::DrawingArea,
+    filter_input: RefCell<Option<String>>,
+    context_input: RefCell<String>,
+    /// Currently selected `(first, second)` pair.
+    selected: RefCell<Option<(String, String)>>,
+    /// Horizontal extents of the second glyph of the selected pair in the preview, in view
+    /// coordinates.
+    second_extents: Cell<Option<(f64, f64)>>,
+    /// Units per view pixel of the last preview draw.
+    preview_scale: Cell<f64>,
+    /// Pointer x position and kerning value when dragging started.
+    drag_start: Cell<Option<(f64, f64)>>,
+    title: RefCell<String>,
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for KerningSpaceInner {
+    const NAME: &'static str = "KerningSpace";
+    type Type = KerningSpace;
+    type ParentType = gtk::EventBox;
+}
+
+impl ObjectImpl for KerningSpaceInner {
+    fn constructed(&self, obj: &Self::Type) {
+        self.parent_constructed(obj);
+        *self.title.borrow_mut() = "kerning".to_string();
+        self.preview_scale.set(1.0);
+
+        let store = gtk::ListStore::new(&[
+            String::static_type(),
+            String::static_type(),
+            f64::static_type(),
+        ]);
+        let filter_model = gtk::TreeModelFilter::new(&store, None);
+        filter_model.set_visible_func(
+            clone!(@weak obj => @default-return true, move |model, iter| {
+                let filter_input = obj.imp().filter_input.borrow();
+                let Some(filter) = filter_input.as_ref() else {
+                    return true;
+                };
+                let first = model.value(iter, 0).get::<String>().unwrap_or_default();
+                let second = model.value(iter, 1).get::<String>().unwrap_or_default();
+                let Some(project) = obj.imp().project.get() else {
+                    return true;
+                };
+                let groups = project.groups.borrow();
+                [first, second].iter().any(|side| {
+                    side.contains(filter.as_str())
+                        || groups
+                            .get(side)
+                            .map(|members| members.iter().any(|m| m.contains(filter.as_str())))
+                            .unwrap_or(false)
+                })
+            }),
+        );
+
+        self.tree.set_visible(true);
+        self.tree.set_expand(true);
+        self.tree.set_headers_visible(true);
+        self.tree.set_enable_search(false);
+        self.tree.set_grid_lines(gtk::TreeViewGridLines::Both);
+        for (title, col) in [("first", 0), ("second", 1)] {
+            let column = gtk::TreeViewColumn::new();
+            column.set_title(title);
+            column.set_expand(true);
+            let cell = gtk::CellRendererText::new();
+            column.pack_start(&cell, true);
+            column.add_attribute(&cell, "text", col);
+            self.tree.append_column(&column);
+        }
+        {
+            let column = gtk::TreeViewColumn::new();
+            column.set_title("value");
+            let cell = gtk::CellRendererText::new();
+            cell.set_editable(true);
+            cell.set_xalign(1.0);
+            column.pack_start(&cell, true);
+            CellLayoutExt::set_cell_data_func(
+                &column,
+                &cell,
+                Some(Box::new(|_column, cell, model, iter| {
+                    let value = model.value(iter, 2).get::<f64>().unwrap_or_default();
+                    cell.set_property("text", value.to_string());
+                })),
+            );
+            cell.connect_edited(clone!(@weak obj => move |_cell, path, new_text| {
+                let Some(filter_model) = obj.imp().filter_model.get() else {
+                    return;
+                };
+                let Some(iter) = filter_model.iter(&path) else {
+                    return;
+                };
+                let first = filter_model.value(&iter, 0).get::<String>().unwrap();
+                let second = filter_model.value(&iter, 1).get::<String>().unwrap();
+                if let Ok(value) = new_text.trim().parse::<f64>() {
+                    obj.set_pair(&first, &second, Some(value), false);
+                }
+            }));
+            self.tree.append_column(&column);
+        }
+        self.tree.set_model(Some(&filter_model));
+        self.tree
+            .selection()
+            .connect_changed(clone!(@weak obj => move |selection| {
+                let selected = selection.selected().map(|(model, iter)| {
+                    (
+                        model.value(&iter, 0).get::<String>().unwrap(),
+                        model.value(&iter, 1).get::<String>().unwrap(),
+                    )
+                });
+                *obj.imp().selected.borrow_mut() = selected;
+                obj.imp().preview.queue_draw();
+            }));
+
+        let search_entry = gtk::SearchEntry::builder()
+            .visible(true)
+            .placeholder_text("Filter by glyph or group name")
+            .build();
+        search_entry.connect_changed(clone!(@weak obj => move |_self| {
+            let text = _self.text();
+            *obj.imp().filter_input.borrow_mut() = if text.is_empty() {
+                None
+            } else {
+                Some(text.to_string())
+            };
+            if let Some(filter_model) = obj.imp().filter_model.get() {
+                filter_model.refilter();
+            }
+        }));
+
+        let tree_scrolled_window = gtk::ScrolledWindow::builder()
+            .expand(true)
+            .visible(true)
+            .can_focus(true)
+            .build();
+        tree_scrolled_window.set_child(Some(&self.tree));
+
+        let add_button = gtk::Button::builder()
+            .label("Add pair")
+            .visible(true)
+            .build();
+        add_button.connect_clicked(clone!(@weak obj => move |_| {
+            obj.add_pair_dialog();
+        }));
+        let remove_button = gtk::Button::builder()
+            .label("Remove pair")
+            .visible(true)
+            .build();
+        remove_button.connect_clicked(clone!(@weak obj => move |_| {
+            let selected = obj.imp().selected.borrow().clone();
+            if let Some((first, second)) = selected {
+                obj.set_pair(&first, &second, None, false);
+            }
+        }));
+        let buttons_box = gtk::Box::builder()
+            .orientation(gtk::Orientation::Horizontal)
+            .spacing(5)
+            .visible(true)
+            .build();
+        buttons_box.pack_start(&add_button, false, false, 0);
+        buttons_box.pack_start(&remove_button, false, false, 0);
+
+        let list_box = gtk::Box::builder()
+            .orientation(gtk::Orientation::Vertical)
+            .spacing(5)
+            .margin(5)
+            .visible(true)
+            .build();
+        list_box.pack_start(&search_entry, false, false, 0);
+        list_box.pack_start(&tree_scrolled_window, true, true, 0);
+        list_box.pack_start(&buttons_box, false, false, 0);
+
+        let context_entry = gtk::Entry::builder()
+            .visible(true)
+            .placeholder_text("Preview context, e.g. HOH")
+            .build();
+        context_entry.connect_changed(clone!(@weak obj => move |_self| {
+            *obj.imp().context_input.borrow_mut() = _self.text().to_string();
+            obj.imp().preview.queue_draw();
+        }));
+
+        self.preview.set_expand(true);
+        self.preview.set_visible(true);
+        self.preview.set_can_focus(true);
+        self.preview.set_tooltip_text(Some(
+            "Drag the right glyph or use the arrow keys to change the selected pair's value. Hold Shift for steps of 10.",
+        ));
+        self.preview.add_events(
+            gdk::EventMask::BUTTON_PRESS_MASK
+                | gdk::EventMask::BUTTON_RELEASE_MASK
+                | gdk::EventMask::POINTER_MOTION_MASK
+                | gdk::EventMask::KEY_PRESS_MASK,
+        );
+        self.preview.connect_draw(
+            clone!(@weak obj => @default-return Inhibit(false), move |viewport, ctx| {
+                obj.draw_preview(viewport, ctx);
+                Inhibit(false)
+            }),
+        );
+        self.preview.connect_button_press_event(
+            clone!(@weak obj => @default-return Inhibit(false), move |viewport, event| {
+                viewport.grab_focus();
+                if event.button() != gtk::gdk::BUTTON_PRIMARY {
+                    return Inhibit(false);
+                }
+                let (x, _) = event.position();
+                let Some((start, end)) = obj.imp().second_extents.get() else {
+                    return Inhibit(false);
+                };
+                if !(start..=end).contains(&x) {
+                    return Inhibit(false);
+                }
+                let Some(value) = obj.selected_value() else {
+                    return Inhibit(false);
+                };
+                obj.imp().drag_start.set(Some((x, value)));
+                Inhibit(true)
+            }),
+        );
+        self.preview.connect_motion_notify_event(
+            clone!(@weak obj => @default-return Inhibit(false), move |viewport, event| {
+                let (x, _) = event.position();
+                let Some((start_x, start_value)) = obj.imp().drag_start.get() else {
+                    let hovering = obj
+                        .imp()
+                        .second_extents
+                        .get()
+                        .map(|(start, end)| (start..=end).contains(&x))
+                        .unwrap_or(false);
+                    if let Some(window) = viewport.window() {
+                        let display = window.display();
+                        window.set_cursor(
+                            gtk::gdk::Cursor::from_name(
+                                &display,
+                                if hovering { "ew-resize" } else { "default" },
+                            )
+                            .as_ref(),
+                        );
+                    }
+                    return Inhibit(false);
+                };
+                let selected = obj.imp().selected.borrow().clone();
+                if let Some((first, second)) = selected {
+                    let value = (x - start_x)
+                        .mul_add(obj.imp().preview_scale.get(), start_value)
+                        .round();
+                    obj.set_pair(&first, &second, Some(value), true);
+                }
+                Inhibit(true)
+            }),
+        );
+        self.preview.connect_button_release_event(
+            clone!(@weak obj => @default-return Inhibit(false), move |_viewport, event| {
+                if event.button() != gtk::gdk::BUTTON_PRIMARY || obj.imp().drag_start.get().is_none() {
+                    return Inhibit(false);
+                }
+                obj.imp().drag_start.set(None);
+                Inhibit(true)
+            }),
+        );
+        self.preview.connect_key_press_event(
+            clone!(@weak obj => @default-return Inhibit(false), move |_viewport, event| {
+                use gtk::gdk::keys::constants as keys;
+
+                let step = if event.state().contains(gdk::ModifierType::SHIFT_MASK) {
+                    10.0
+                } else {
+                    1.0
+                };
+                let delta = match event.keyval() {
+                    keys::Left => -step,
+                    keys::Right => step,
+                    _ => return Inhibit(false),
+                };
+                let selected = obj.imp().selected.borrow().clone();
+                if let (Some((first, second)), Some(value)) = (selected, obj.selected_value()) {
+                    obj.set_pair(&first, &second, Some(value + delta), false);
+                }
+                Inhibit(true)
+            }),
+        );
+
+        let preview_box = gtk::Box::builder()
+            .orientation(gtk::Orientation::Vertical)
+            .spacing(5)
+            .margin(5)
+            .visible(true)
+            .build();
+        preview_box.pack_start(&context_entry, false, false, 0);
+        preview_box.pack_start(&self.preview, true, true, 0);
+
+        let paned = gtk::Paned::builder()
+            .orientation(gtk::Orientation::Horizontal)
+            .wide_handle(true)
+            .position(350)
+            .expand(true)
+            .visible(true)
+            .build();
+        paned.pack1(&list_box, false, false);
+        paned.pack2(&preview_box, true, false);
+        obj.set_child(Some(&paned));
+        obj.set_visible(true);
+        obj.set_expand(true);
+        self.list_store.set(store).unwrap();
+        self.filter_model.set(filter_model).unwrap();
+    }
+
+    fn properties() -> &'static [ParamSpec] {
+        static PROPERTIES: once_cell::sync::Lazy<Vec<ParamSpec>> =
+            once_cell::sync::Lazy::new(|| {
+                vec![
+                    ParamSpecString::new(
+                        KerningSpace::TITLE,
+                        KerningSpace::TITLE,
+                        KerningSpace::TITLE,
+                        Some("kerning"),
+                        ParamFlags::READWRITE,
+                    ),
+                    ParamSpecBoolean::new(
+                        KerningSpace::CLOSEABLE,
+                        KerningSpace::CLOSEABLE,
+                        KerningSpace::CLOSEABLE,
+                        false,
+                        ParamFlags::READABLE,
+                    ),
+                ]
+            });
+        PROPERTIES.as_ref()
+    }
+
+    fn property(&self, _obj: &Self::Type, _id: usize, pspec: &ParamSpec) -> Value {
+        match pspec.name() {
+            KerningSpace::TITLE => self.title.borrow().to_value(),
+            KerningSpace::CLOSEABLE => false.to_value(),
+            _ => unimplemented!("{}", pspec.name()),
+        }
+    }
+
+    fn set_property(&self, _obj: &Self::Type, _id: usize, value: &Value, pspec: &ParamSpec) {
+        match pspec.name() {
+            KerningSpace::TITLE => {
+                *self.title.borrow_mut() = value.get().unwrap();
+            }
+            _ => unimplemented!("{}", pspec.name()),
+        }
+    }
+}
+
+impl WidgetImpl for KerningSpaceInner {}
+impl ContainerImpl for KerningSpaceInner {}
+impl BinImpl for KerningSpaceInner {}
+impl EventBoxImpl for KerningSpaceInner {}
+
+impl KerningSpaceInner {
+    pub fn app(&self) -> &Application {
+        self.app.get().unwrap()
+    }
+
+    pub fn project(&self) -> &Project {
+        self.project.get().unwrap()
+    }
+}
+
+impl std::ops::Deref for KerningSpace {
+    type Target = KerningSpaceInner;
+
+    fn deref(&self) -> &Self::Target {
+        self.imp()
+    }
+}
+
+glib::wrapper! {
+    pub struct KerningSpace(ObjectSubclass<KerningSpaceInner>)
+        @extends gtk::Widget, gtk::Container, gtk::EventBox;
+}
+
+impl KerningSpace {
+    pub const TITLE: &str = Workspace::TITLE;
+    pub const CLOSEABLE: &str = Workspace::CLOSEABLE;
+
+    pub fn new(app: Application, project: Project) -> Self {
+        let ret: Self = glib::Object::new(&[]).expect("Failed to create KerningSpace");
+        ret.app.set(app).unwrap();
+        ret.project.set(project).unwrap();
+        ret.update_list_store();
+        /* Kerning and groups can change while the tab is hidden, e.g. by renaming or deleting
+         * glyphs and groups, so rebuild the list whenever it is shown again. */
+        ret.connect_map(|self_| {
+            self_.update_list_store();
+        });
+        ret
+    }
+
+    /// Rebuild the list from the project's kerning, keeping the selected pair if it still
+    /// exists.
+    fn update_list_store(&self) {
+        let store = self.list_store.get().unwrap();
+        let selected = self.selected.borrow().clone();
+        store.clear();
+        for (first, second, value) in self.project().kerning.borrow().iter() {
+            let iter = store.insert_with_values(None, &[(0, &first), (1, &second), (2, &value)]);
+            if selected
+                .as_ref()
+                .map(|(f, s)| f == first && s == second)
+                .unwrap_or(false)
+            {
+                if let Some(iter) = self
+                    .filter_model
+                    .get()
+                    .and_then(|f| f.convert_child_iter_to_iter(&iter))
+                {
+                    self.tree.selection().select_iter(&iter);
+                }
+            }
+        }
+        self.preview.queue_draw();
+    }
+
+    /// Update, insert or remove the list row of pair `(first, second)`.
+    fn sync_row(&self, first: &str, second: &str, value: Option<f64>) {
+        let store = self.list_store.get().unwrap();
+        let mut row = None;
+        if let Some(iter) = store.iter_first() {
+            loop {
+                if store
+                    .value(&iter, 0)
+                    .get::<String>()
+                    .map(|v| v == first)
+                    .unwrap_or(false)
+                    && store
+                        .value(&iter, 1)
+                        .get::<String>()
+                        .map(|v| v == second)
+                        .unwrap_or(false)
+                {
+                    row = Some(iter);
+                    break;
+                }
+                if !store.iter_next(&iter) {
+                    break;
+                }
+            }
+        }
+        match (row, value) {
+            (Some(iter), Some(value)) => {
+                store.set_value(&iter, 2, &value.to_value());
+            }
+            (Some(iter), None) => {
+                store.remove(&iter);
+            }
+            (None, Some(value)) => {
+                let iter =
+                    store.insert_with_values(None, &[(0, &first), (1, &second), (2, &value)]);
+                if let Some(iter) = self
+                    .filter_model
+                    .get()
+                    .and_then(|f| f.convert_child_iter_to_iter(&iter))
+                {
+                    self.tree.selection().select_iter(&iter);
+                }
+            }
+            (None, None) => {}
+        }
+    }
+
+    fn selected_value(&self) -> Option<f64> {
+        let selected = self.selected.borrow();
+        let (first, second) = selected.as_ref()?;
+        self.project().kerning.borrow().get(first, second)
+    }
+
+    /// Set the value of pair `(first, second)`, or remove it if `value` is `None`. The change is
+    /// recorded in the undo database.
+    pub fn set_pair(&self, first: &str, second: &str, value: Option<f64>, compress: bool) {
+        let old_value = self.project().kerning.borrow().get(first, second);
+        if old_value == value {
+            return;
+        }
+        let (first, second) = (first.to_string(), second.to_string());
+        let mut action = Action {
+            stamp: EventStamp {
+                t: std::any::TypeId::of::<Self>(),
+                property: "kerning",
+                id: format!("{first}\0{second}").into_bytes().into_boxed_slice(),
+            },
+            compress,
+            redo: Box::new(
+                clone!(@weak self as obj, @strong first, @strong second => move || {
+                    obj.apply_pair(&first, &second, value);
+                }),
+            ),
+            undo: Box::new(clone!(@weak self as obj => move || {
+                obj.apply_pair(&first, &second, old_value);
+            })),
+        };
+        (action.redo)();
+        self.app().undo_db.borrow().event(action);
+    }
+
+    fn apply_pair(&self, first: &str, second: &str, value: Option<f64>) {
+        let project = self.project();
+        {
+            let mut kerning = project.kerning.borrow_mut();
+            if let Some(value) = value {
+                kerning.set(first, second, value);
+            } else {
+                kerning.remove(first, second);
+            }
+        }
+        project.set_property(Project::MODIFIED, true);
+        self.sync_row(first, second, value);
+        self.preview.queue_draw();
+    }
+
+    fn add_pair_dialog(&self) {
+        let dialog = gtk::Dialog::builder()
+            .attached_to(&self.app().window)
+            .application(self.app())
+            .border_width(10)
+            .destroy_with_parent(true)
+            .modal(true)
+            .title("Add kerning pair")
+            .build();
+        dialog.add_button("Add", gtk::ResponseType::Accept);
+        dialog.add_button("Cancel", gtk::ResponseType::Close);
+        let grid = gtk::Grid::builder()
+            .row_spacing(5)
+            .column_spacing(5)
+            .visible(true)
+            .build();
+        let first_entry = gtk::Entry::builder()
+            .visible(true)
+            .placeholder_text("glyph or public.kern1.* group")
+            .build();
+        let second_entry = gtk::Entry::builder()
+            .visible(true)
+            .placeholder_text("glyph or public.kern2.* group")
+            .build();
+        let value_entry = gtk::SpinButton::with_range(-10000.0, 10000.0, 1.0);
+        value_entry.set_visible(true);
+        value_entry.set_value(0.0);
+        for (row, (label, widget)) in (0..).zip([
+            ("First", first_entry.upcast_ref::<gtk::Widget>()),
+            ("Second", second_entry.upcast_ref::<gtk::Widget>()),
+            ("Value", value_entry.upcast_ref::<gtk::Widget>()),
+        ]) {
+            grid.attach(
+                &gtk::Label::builder()
+                    .label(label)
+                    .halign(gtk::Align::End)
+                    .visible(true)
+                    .build(),
+                0,
+                row,
+                1,
+                1,
+            );
+            grid.attach(widget, 1, row, 1, 1);
+        }
+        dialog.content_area().pack_start(&grid, true, true, 5);
+        loop {
+            match dialog.run() {
+                gtk::ResponseType::Accept => {
+                    let first = first_entry.text().trim().to_string();
+                    let second = second_entry.text().trim().to_string();
+                    let msg = match self.validate_side(&first, true) {
+                        Ok(()) => self.validate_side(&second, false),
+                        err => err,
+                    };
+                    if let Err(msg) = msg {
+                        let err_dialog = crate::utils::widgets::new_simple_error_dialog(
+                            Some("Error: Could not add kerning pair."),
+                            &msg,
+                            None,
+                            dialog.upcast_ref(),
+                        );
+                        err_dialog.run();
+                        err_dialog.emit_close();
+                        continue;
+                    }
+                    self.set_pair(&first, &second, Some(value_entry.value()), false);
+                    dialog.emit_close();
+                    break;
+                }
+                _ => {
+                    dialog.emit_close();
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Check that `side` is an existing glyph or a kerning group of the correct side.
+    fn validate_side(&self, side: &str, first: bool) -> Result<(), String> {
+        let project = self.project();
+        if side.is_empty() {
+            return Err("Pair sides cannot be empty.".to_string());
+        }
+        let (prefix, other) = if first {
+            (ufo::KERN1_PREFIX, ufo::KERN2_PREFIX)
+        } else {
+            (ufo::KERN2_PREFIX, ufo::KERN1_PREFIX)
+        };
+        if side.starts_with(other) {
+            return Err(format!(
+                "Group <tt>{side}</tt> cannot be used on this side of a pair; expected a group starting with <tt>{prefix}</tt>."
+            ));
+        }
+        if side.starts_with(prefix) {
+            if project.groups.borrow().get(side).is_none() {
+                return Err(format!("Group <tt>{side}</tt> does not exist."));
+            }
+        } else if !project.default_layer.glyphs().contains_key(side) {
+            return Err(format!("Glyph <tt>{side}</tt> does not exist."));
+        }
+        Ok(())
+    }
+
+    /// Glyph name used to preview `side` of a pair: either the glyph itself or the first member
+    /// of a kerning group.
+    fn representative_glyph(&self, side: &str) -> Option<String> {
+        if side.starts_with(ufo::KERN1_PREFIX) || side.starts_with(ufo::KERN2_PREFIX) {
+            self.project()
+                .groups
+                .borrow()
+                .get(side)
+                .and_then(|members| members.first().cloned())
+        } else {
+            Some(side.to_string())
+        }
+    }
+
+    /// The glyph names to preview and whether each is part of the selected pair.
+    fn preview_sequence(
+        &self,
+        glyphs: &IndexMap<String, Rc<RefCell<Glyph>>>,
+    ) -> Vec<(String, bool)> {
+        let mut chars: HashMap<char, String> = HashMap::default();
+        for (name, g) in glyphs.iter() {
+            if let GlyphKind::Char(c) = g.borrow().kinds().0 {
+                chars.insert(c, name.clone());
+            }
+        }
+        let context = self
+            .context_input
+            .borrow()
+            .chars()
+            .filter_map(|c| chars.get(&c).cloned())
+            .map(|n| (n, false))
+            .collect::<Vec<(String, bool)>>();
+        let mut ret = context.clone();
+        if let Some((first, second)) = self.selected.borrow().as_ref() {
+            if let (Some(first), Some(second)) = (
+                self.representative_glyph(first),
+                self.representative_glyph(second),
+            ) {
+                ret.push((first, true));
+                ret.push((second, true));
+                ret.extend(context);
+            }
+        }
+        ret
+    }
+
+    fn draw_preview(&self, viewport: &gtk::DrawingArea, mut ctx: &Context) {
+        let project = self.project();
+        let mut cr = ctx.push();
+        cr.set_source_rgb(1.0, 1.0, 1.0);
+        cr.paint().expect("Invalid cairo surface state");
+        self.second_extents.set(None);
+
+        let width = f64::from(viewport.allocated_width());
+        let height = f64::from(viewport.allocated_height());
+        let units_per_em: f64 = project.property(Project::UNITS_PER_EM);
+        let descender: f64 = project.property(Project::DESCENDER);
+        let glyphs = project.default_layer.glyphs();
+        let sequence = self.preview_sequence(&glyphs);
+        if sequence.is_empty() {
+            cr.set_source_rgba(0.0, 0.0, 0.0, 0.5);
+            cr.select_font_face("Sans", FontSlant::Normal, FontWeight::Normal);
+            cr.set_font_size(14.0);
+            let label = "Select a kerning pair to preview it.";
+            let extents = cr.text_extents(label).expect("Invalid cairo surface state");
+            cr.move_to((width - extents.width) / 2.0, height / 2.0);
+            cr.show_text(label).expect("Invalid cairo surface state");
+            return;
+        }
+        let kerning = project.kerning.borrow();
+        let groups = project.groups.borrow();
+        /* Compute pen positions of each glyph in units. */
+        let mut positions = Vec::with_capacity(sequence.len());
+        let mut pen = 0.0;
+        for (i, (name, _)) in sequence.iter().enumerate() {
+            if i > 0 {
+                pen += kerning
+                    .lookup(&groups, &sequence[i - 1].0, name)
+                    .unwrap_or(0.0);
+            }
+            let advance = glyphs
+                .get(name)
+                .and_then(|g| g.borrow().width())
+                .unwrap_or(units_per_em / 2.0);
+            positions.push((pen, advance));
+            pen += advance;
+        }
+        let scale = (0.5 * height / units_per_em).min(0.9 * width / pen.max(1.0));
+        self.preview_scale.set(1.0 / scale);
+        let x_offset = (width - pen * scale) / 2.0;
+        let baseline = 0.5f64
+            .mul_add(units_per_em, descender)
+            .mul_add(scale, height / 2.0);
+
+        cr.set_line_width(1.0);
+        cr.set_source_rgba(0.0, 0.0, 0.0, 0.2);
+        cr.move_to(0.0, baseline);
+        cr.line_to(width, baseline);
+        cr.stroke().expect("Invalid cairo surface state");
+
+        let mut second_glyph = false;
+        for ((name, in_pair), (position, advance)) in sequence.iter().zip(positions.iter()) {
+            let x = x_offset + position * scale;
+            if *in_pair {
+                if second_glyph {
+                    self.second_extents.set(Some((x, x + advance * scale)));
+                }
+                second_glyph = true;
+            }
+            let Some(glyph) = glyphs.get(name) else {
+                continue;
+            };
+            let matrix = Matrix::new(scale, 0.0, 0.0, -scale, x, baseline);
+            let fill = if *in_pair {
+                Color::new(0, 0, 0) // [ref:hardcoded_color_value]
+            } else {
+                Color::new(89, 89, 89) // [ref:hardcoded_color_value]
+            };
+            let options = GlyphDrawingOptions {
+                outline: (Color::new_alpha(0, 0, 0, 0), 1.5).into(),
+                inner_fill: Some((fill, 1.5).into()),
+                highlight: None,
+                matrix,
+                units_per_em,
+                ..Default::default()
+            };
+            glyph.borrow().draw(cr.push(), options);
+        }
+
+        /* Draw the value of the selected pair under it. */
+        if let (Some((start, end)), Some(value)) =
+            (self.second_extents.get(), self.selected_value())
+        {
+            cr.set_source_rgb(0.0, 0.0, 0.0);
+            cr.select_font_face("Sans", FontSlant::Normal, FontWeight::Bold);
+            cr.set_font_size(14.0);
+            let label = value.to_string();
+            let extents = cr
+                .text_extents(&label)
+                .expect("Invalid cairo surface state");
+            cr.move_to(
+                start - extents.width / 2.0,
+                2.0f64.mul_add(extents.height, descender.mul_add(-scale, baseline)),
+            );
+            cr.show_text(&label).expect("Invalid cairo surface state");
+            cr.set_source_rgba(0.0, 0.0, 1.0, 0.3); // [ref:hardcoded_color_value]
+            cr.rectangle(start, baseline + 4.0, end - start, 2.0);
+            cr.fill().expect("Invalid cairo surface state");
+        }
+    }
+}
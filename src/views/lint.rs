@@ -0,0 +1,250 @@
+/*
+ * gerb
+ *
+ * Copyright 2022 - Manos Pitsidianakis
+ *
+ * This file is part of gerb.
+ *
+ * gerb is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * gerb is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with gerb. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! List of the problems [`lint`](crate::lint) finds in a project. Activating a problem opens its
+//! glyph with the offending point selected.
+
+use glib::clone;
+use once_cell::unsync::OnceCell;
+
+use crate::lint::{self, Check, Problem};
+use crate::prelude::*;
+
+#[derive(Debug, Default)]
+pub struct LintViewInner {
+    app: OnceCell<Application>,
+    project: OnceCell<Project>,
+    /// Problems of the last check.
+    problems: RefCell<Vec<Problem>>,
+    /// Indices in `problems` of the rows of `list`.
+    shown: RefCell<Vec<usize>>,
+    list: gtk::ListBox,
+    status: gtk::Label,
+    filter: gtk::ComboBoxText,
+    title: RefCell<String>,
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for LintViewInner {
+    const NAME: &'static str = "LintView";
+    type Type = LintView;
+    type ParentType = gtk::EventBox;
+}
+
+impl ObjectImpl for LintViewInner {
+    fn constructed(&self, obj: &Self::Type) {
+        self.parent_constructed(obj);
+        *self.title.borrow_mut() = "lint".to_string();
+
+        self.filter.append(None, "All checks");
+        for check in Check::ALL {
+            self.filter.append(None, &check.to_string());
+        }
+        self.filter.set_active(Some(0));
+        self.filter.set_visible(true);
+        self.filter
+            .connect_changed(clone!(@weak obj => move |_| obj.update()));
+        let refresh = gtk::Button::builder()
+            .label("Check again")
+            .visible(true)
+            .build();
+        refresh.connect_clicked(clone!(@weak obj => move |_| obj.check()));
+        self.status.set_visible(true);
+        self.status.set_halign(gtk::Align::Start);
+        let header = gtk::Box::builder()
+            .orientation(gtk::Orientation::Horizontal)
+            .spacing(5)
+            .visible(true)
+            .build();
+        header.pack_start(&self.status, true, true, 0);
+        header.pack_end(&refresh, false, false, 0);
+        header.pack_end(&self.filter, false, false, 0);
+
+        self.list.set_visible(true);
+        self.list.set_selection_mode(gtk::SelectionMode::Single);
+        self.list
+            .connect_row_activated(clone!(@weak obj => move |_, row| {
+                let problem = usize::try_from(row.index())
+                    .ok()
+                    .and_then(|i| obj.shown.borrow().get(i).copied())
+                    .and_then(|i| obj.problems.borrow().get(i).cloned());
+                if let Some(p) = problem {
+                    obj.go_to(&p);
+                }
+            }));
+        let scrolled_window = gtk::ScrolledWindow::builder()
+            .expand(true)
+            .visible(true)
+            .build();
+        scrolled_window.set_child(Some(&self.list));
+        let vbox = gtk::Box::builder()
+            .orientation(gtk::Orientation::Vertical)
+            .spacing(5)
+            .margin(5)
+            .expand(true)
+            .visible(true)
+            .build();
+        vbox.pack_start(&header, false, false, 0);
+        vbox.pack_start(&scrolled_window, true, true, 0);
+        obj.set_child(Some(&vbox));
+        obj.set_visible(true);
+        obj.set_expand(true);
+    }
+
+    fn properties() -> &'static [ParamSpec] {
+        static PROPERTIES: once_cell::sync::Lazy<Vec<ParamSpec>> =
+            once_cell::sync::Lazy::new(|| {
+                vec![
+                    ParamSpecString::new(
+                        LintView::TITLE,
+                        LintView::TITLE,
+                        LintView::TITLE,
+                        Some("lint"),
+                        ParamFlags::READWRITE,
+                    ),
+                    ParamSpecBoolean::new(
+                        LintView::CLOSEABLE,
+                        LintView::CLOSEABLE,
+                        LintView::CLOSEABLE,
+                        true,
+                        ParamFlags::READABLE,
+                    ),
+                ]
+            });
+        PROPERTIES.as_ref()
+    }
+
+    fn property(&self, _obj: &Self::Type, _id: usize, pspec: &ParamSpec) -> Value {
+        match pspec.name() {
+            LintView::TITLE => self.title.borrow().to_value(),
+            LintView::CLOSEABLE => true.to_value(),
+            _ => unimplemented!("{}", pspec.name()),
+        }
+    }
+
+    fn set_property(&self, _obj: &Self::Type, _id: usize, value: &Value, pspec: &ParamSpec) {
+        match pspec.name() {
+            LintView::TITLE => {
+                *self.title.borrow_mut() = value.get().unwrap();
+            }
+            _ => unimplemented!("{}", pspec.name()),
+        }
+    }
+}
+
+impl WidgetImpl for LintViewInner {}
+impl ContainerImpl for LintViewInner {}
+impl BinImpl for LintViewInner {}
+impl EventBoxImpl for LintViewInner {}
+
+impl LintViewInner {
+    pub fn app(&self) -> &Application {
+        self.app.get().unwrap()
+    }
+
+    pub fn project(&self) -> &Project {
+        self.project.get().unwrap()
+    }
+}
+
+impl std::ops::Deref for LintView {
+    type Target = LintViewInner;
+
+    fn deref(&self) -> &Self::Target {
+        self.imp()
+    }
+}
+
+glib::wrapper! {
+    pub struct LintView(ObjectSubclass<LintViewInner>)
+        @extends gtk::Widget, gtk::Container, gtk::EventBox;
+}
+
+impl LintView {
+    pub const TITLE: &str = Workspace::TITLE;
+    pub const CLOSEABLE: &str = Workspace::CLOSEABLE;
+
+    pub fn new(app: Application, project: Project) -> Self {
+        let ret: Self = glib::Object::new(&[]).expect("Failed to create LintView");
+        ret.app.set(app).unwrap();
+        ret.project.set(project).unwrap();
+        ret.check();
+        ret
+    }
+
+    /// Check the project again and show the results.
+    pub fn check(&self) {
+        *self.problems.borrow_mut() = lint::check_project(self.project());
+        self.update();
+    }
+
+    /// The check selected in the filter, or `None` for all of them.
+    fn selected_check(&self) -> Option<Check> {
+        let active = usize::try_from(self.filter.active()?).ok()?;
+        active
+            .checked_sub(1)
+            .and_then(|i| Check::ALL.get(i).copied())
+    }
+
+    /// Show the problems that pass the filter.
+    fn update(&self) {
+        for row in self.list.children() {
+            self.list.remove(&row);
+        }
+        let selected = self.selected_check();
+        let problems = self.problems.borrow();
+        let shown = (0..problems.len())
+            .filter(|&i| selected.is_none_or(|c| c == problems[i].check))
+            .collect::<Vec<usize>>();
+        for &i in shown.iter() {
+            let label = gtk::Label::builder()
+                .label(&format!("[{}] {}", problems[i].check, problems[i]))
+                .halign(gtk::Align::Start)
+                .visible(true)
+                .build();
+            label.style_monospace();
+            self.list.add(&label);
+        }
+        self.status.set_text(&match shown.len() {
+            0 => "No problems found.".to_string(),
+            1 => "1 problem:".to_string(),
+            n => format!("{n} problems:"),
+        });
+        *self.shown.borrow_mut() = shown;
+    }
+
+    /// Open the glyph of `problem` and select its point.
+    fn go_to(&self, problem: &Problem) {
+        let Some(glyph) = self
+            .project()
+            .default_layer
+            .glyphs()
+            .get(&problem.glyph)
+            .cloned()
+        else {
+            return;
+        };
+        let editor = self.app().window.edit_glyph(&glyph);
+        if let Some(point) = problem.point {
+            editor.set_selection(&[point], SelectionModifier::Replace);
+        }
+    }
+}
@@ -32,6 +32,7 @@ pub struct WindowInner {
     pub root_box: gtk::Box,
     pub welcome_banner: gtk::Box,
     pub project: RefCell<Project>,
+    pub designspace: RefCell<Option<Designspace>>,
     pub headerbar: gtk::HeaderBar,
     pub statusbar: gtk::Statusbar,
     pub notebook: gtk::Notebook,
@@ -129,9 +130,18 @@ impl ObjectImpl for WindowInner {
         }));
 
         obj.connect_local("open-project", false, clone!(@weak obj => @default-return Some(false.to_value()), move |v: &[gtk::glib::Value]| {
-            match v[1].get::<String>().map_err(|err| err.into()).and_then(Project::from_path) {
-                Ok(project) => {
-                    obj.load_project(project);
+            let result = v[1]
+                .get::<String>()
+                .map_err(|err| err.into())
+                .and_then(|path| {
+                    if path.ends_with(".designspace") {
+                        Designspace::from_path(&path).map(|designspace| obj.load_designspace(designspace))
+                    } else {
+                        Project::from_path(&path).map(|project| obj.load_project(project))
+                    }
+                });
+            match result {
+                Ok(()) => {
                     obj.queue_draw();
                 }
                 Err(err) => {
@@ -263,6 +273,53 @@ impl WindowInner {
     }
 
     pub fn load_project(&self, project: Project) {
+        *self.designspace.borrow_mut() = None;
+        self.load_project_tabs(project);
+    }
+
+    /// Load all masters of a designspace; the default master's project gets the usual tabs, and
+    /// every other master gets its own glyph collection tab.
+    pub fn load_designspace(&self, designspace: Designspace) {
+        let default_project = designspace.default_master().project;
+        let collection = self.load_project_tabs(default_project.clone());
+        collection.set_designspace(&designspace);
+        for project in designspace.projects.borrow().iter() {
+            if *project == default_project {
+                continue;
+            }
+            let collection = Collection::new(self.application(), project.clone());
+            collection.set_designspace(&designspace);
+            add_tab(
+                &self.notebook,
+                Workspace::new(collection.upcast_ref::<gtk::Widget>()).upcast_ref::<gtk::Widget>(),
+                true,
+                false,
+            );
+        }
+        self.notebook.show_all();
+        self.notebook.set_page(0);
+        designspace
+            .bind_property(Designspace::MODIFIED, &self.instance(), "title")
+            .transform_to(|_b, v| {
+                if v.get::<bool>().ok()? {
+                    Some(format!("{APPLICATION_NAME}*").to_value())
+                } else {
+                    Some(APPLICATION_NAME.to_value())
+                }
+            })
+            .flags(glib::BindingFlags::SYNC_CREATE)
+            .build();
+        let name = designspace.property::<String>(Designspace::NAME);
+        self.headerbar
+            .set_subtitle(Some(&format!("Loaded designspace: {name}")));
+        self.statusbar.push(
+            self.statusbar.context_id("main"),
+            &format!("Loaded designspace: {name}"),
+        );
+        *self.designspace.borrow_mut() = Some(designspace);
+    }
+
+    fn load_project_tabs(&self, project: Project) -> Collection {
         project
             .bind_property(Project::MODIFIED, &self.instance(), "title")
             .transform_to(|_b, v| {
@@ -314,29 +371,197 @@ impl WindowInner {
             self.notebook.remove(tab);
         });
 
-        let collection = Collection::new(self.application(), project);
+        let collection = Collection::new(self.application(), project.clone());
         add_tab(
             &self.notebook,
             Workspace::new(collection.upcast_ref::<gtk::Widget>()).upcast_ref::<gtk::Widget>(),
             false,
             false,
         );
+        let kerning = crate::views::KerningSpace::new(self.application(), project.clone());
+        add_tab(
+            &self.notebook,
+            Workspace::new(kerning.upcast_ref::<gtk::Widget>()).upcast_ref::<gtk::Widget>(),
+            false,
+            false,
+        );
+        let features = crate::views::FeatureEditor::new(self.application(), project.clone());
+        add_tab(
+            &self.notebook,
+            Workspace::new(features.upcast_ref::<gtk::Widget>()).upcast_ref::<gtk::Widget>(),
+            false,
+            false,
+        );
         self.notebook.show_all();
         self.notebook.queue_draw();
+        let load_report = project.load_report.borrow();
+        if !load_report.is_empty() {
+            self.show_load_report(&load_report);
+        }
+        collection
     }
 
-    pub fn edit_glyph(&self, glyph: &Rc<RefCell<crate::glyphs::Glyph>>) {
-        let edit_view = Editor::new(
-            self.application(),
-            self.project.borrow().clone(),
-            glyph.clone(),
+    /// Show the errors of glyphs that could not be loaded with a project, and the warnings about
+    /// data that was loaded differently from how it is on disk.
+    fn show_load_report(&self, report: &crate::project::LoadReport) {
+        let obj = self.instance();
+        let errors = report.errors.len();
+        let dialog = if errors == 0 {
+            crate::utils::widgets::new_simple_error_dialog(
+                Some("Warning: Some files were not loaded as they are"),
+                "Some project files had problems that were worked around while loading.",
+                None,
+                obj.upcast_ref(),
+            )
+        } else {
+            crate::utils::widgets::new_simple_error_dialog(
+                Some("Warning: Some glyphs could not be loaded"),
+                &format!(
+                    "{} glyph{} could not be loaded and {} not shown.",
+                    errors,
+                    if errors == 1 { "" } else { "s" },
+                    if errors == 1 { "is" } else { "are" },
+                ),
+                Some("Their files are left untouched when saving the project."),
+                obj.upcast_ref(),
+            )
+        };
+        dialog.set_message_type(gtk::MessageType::Warning);
+        let buffer = gtk::TextBuffer::new(None::<&gtk::TextTagTable>);
+        buffer.set_text(
+            &report
+                .errors
+                .iter()
+                .map(ToString::to_string)
+                .chain(report.warnings.iter().cloned())
+                .collect::<Vec<String>>()
+                .join("\n"),
         );
+        let text_view = gtk::TextView::builder()
+            .buffer(&buffer)
+            .editable(false)
+            .monospace(true)
+            .wrap_mode(gtk::WrapMode::WordChar)
+            .build();
+        let scrolled_window = gtk::ScrolledWindow::builder()
+            .child(&text_view)
+            .min_content_height(150)
+            .min_content_width(500)
+            .expand(true)
+            .build();
+        if let Ok(message_area) = dialog.message_area().downcast::<gtk::Box>() {
+            message_area.pack_start(&scrolled_window, true, true, 0);
+        }
+        dialog.set_resizable(true);
+        dialog.show_all();
+        dialog.run();
+        dialog.emit_close();
+    }
+
+    pub fn edit_glyph(&self, glyph: &Rc<RefCell<crate::glyphs::Glyph>>) -> Editor {
+        let project = self
+            .designspace
+            .borrow()
+            .as_ref()
+            .and_then(|designspace| designspace.glyph_project(glyph))
+            .unwrap_or_else(|| self.project.borrow().clone());
+        let edit_view = Editor::new(self.application(), project, glyph.clone());
+        if let Some(designspace) = self.designspace.borrow().as_ref() {
+            edit_view.set_designspace(designspace);
+        }
         add_tab(
             &self.notebook,
             Workspace::new(edit_view.upcast_ref::<gtk::Widget>()).upcast_ref::<gtk::Widget>(),
             true,
             true,
         );
+        edit_view
+    }
+
+    /// Editors of the open tabs that edit `glyph`.
+    pub fn glyph_editors(&self, glyph: &Rc<RefCell<crate::glyphs::Glyph>>) -> Vec<Editor> {
+        self.notebook
+            .children()
+            .into_iter()
+            .filter_map(|tab| {
+                tab.downcast_ref::<Workspace>()
+                    .map(|w| w.property::<gtk::Widget>(Workspace::CHILD))
+                    .and_then(|child| child.downcast::<Editor>().ok())
+            })
+            .filter(|editor| Rc::ptr_eq(editor.glyph(), glyph))
+            .collect()
+    }
+
+    /// Apply the result of a batch operation on glyphs as one undoable action.
+    ///
+    /// Glyphs that are open in an editor are changed through [`State::replace_contours`], after
+    /// clearing the editor's selection, so that its point index stays valid.
+    pub fn replace_glyph_contours(
+        &self,
+        property: &'static str,
+        changes: crate::glyphs::ContourChanges,
+    ) {
+        if changes.is_empty() {
+            return;
+        }
+        let mut actions = vec![];
+        for (glyph, contours) in changes {
+            let editors = self.glyph_editors(&glyph);
+            if editors.is_empty() {
+                actions.push(replace_contours_action(glyph, contours));
+                continue;
+            }
+            for editor in editors {
+                editor.set_selection(&[], SelectionModifier::Replace);
+                actions.push(editor.state().borrow().replace_contours(contours.clone()));
+            }
+        }
+        let redraw = clone!(@weak self.notebook as notebook => move || {
+            notebook.queue_draw();
+        });
+        actions.push(Action {
+            stamp: EventStamp {
+                t: std::any::TypeId::of::<Self>(),
+                property,
+                id: Box::new([]),
+            },
+            compress: false,
+            redo: Box::new(redraw.clone()),
+            undo: Box::new(redraw),
+        });
+        let mut action = Action::compound(
+            EventStamp {
+                t: std::any::TypeId::of::<Self>(),
+                property,
+                id: Box::new([]),
+            },
+            actions,
+        );
+        (action.redo)();
+        self.application().undo_db.borrow().event(action);
+    }
+
+    /// Check the project for problems and show them in a tab, reusing the tab if it is open.
+    pub fn show_lint(&self) {
+        for (i, tab) in self.notebook.children().into_iter().enumerate() {
+            let Some(view) = tab
+                .downcast_ref::<Workspace>()
+                .map(|w| w.property::<gtk::Widget>(Workspace::CHILD))
+                .and_then(|child| child.downcast::<crate::views::LintView>().ok())
+            else {
+                continue;
+            };
+            view.check();
+            self.notebook.set_current_page(u32::try_from(i).ok());
+            return;
+        }
+        let lint = crate::views::LintView::new(self.application(), self.project.borrow().clone());
+        add_tab(
+            &self.notebook,
+            Workspace::new(lint.upcast_ref::<gtk::Widget>()).upcast_ref::<gtk::Widget>(),
+            true,
+            true,
+        );
     }
 
     pub fn unload_project(&self) {
@@ -359,6 +584,7 @@ impl WindowInner {
         */
         self.notebook.queue_draw();
         *self.project.borrow_mut() = Project::new();
+        *self.designspace.borrow_mut() = None;
     }
 
     pub fn application(&self) -> Application {
@@ -369,6 +595,31 @@ impl WindowInner {
     }
 }
 
+/// Replace the contours of `glyph`, which is not open in an editor, with `contours`.
+fn replace_contours_action(
+    glyph: Rc<RefCell<crate::glyphs::Glyph>>,
+    contours: Vec<crate::glyphs::Contour>,
+) -> Action {
+    let old = glyph.borrow().contours.clone();
+    let swap = |contours: Vec<crate::glyphs::Contour>| {
+        Box::new(clone!(@weak glyph => move || {
+            let mut glyph = glyph.borrow_mut();
+            glyph.contours = contours.clone();
+            glyph.metadata.set_property(GlyphMetadata::MODIFIED, true);
+        }))
+    };
+    Action {
+        stamp: EventStamp {
+            t: std::any::TypeId::of::<WindowInner>(),
+            property: "replace contours",
+            id: Box::new([]),
+        },
+        compress: false,
+        redo: swap(contours),
+        undo: swap(old),
+    }
+}
+
 impl WidgetImpl for WindowInner {}
 impl ContainerImpl for WindowInner {}
 impl BinImpl for WindowInner {}